@@ -0,0 +1,208 @@
+use cosmic_text::{Cursor, Edit, Editor};
+
+use crate::get_cosmic_text;
+
+/// Edits kept for undo in every editor.
+const MAX_UNDO: usize = 100;
+/// Typing or deleting after a longer pause starts a new undo step.
+const COALESCE_SECONDS: f64 = 1.;
+
+/// Kind of a text change. Consecutive insertions, or deletions, are undone together while
+/// the cursor stays where the previous one left it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditKind {
+    Insert,
+    Delete,
+    NewLine,
+    Paste,
+    Cut,
+}
+
+/// Text of an editor with its cursor and selection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditSnapshot {
+    pub text: String,
+    pub cursor: Cursor,
+    pub select: Option<Cursor>,
+}
+
+impl EditSnapshot {
+    pub fn of(editor: &Editor) -> Self {
+        Self {
+            text: get_cosmic_text(editor),
+            cursor: editor.cursor(),
+            select: editor.select_opt(),
+        }
+    }
+}
+
+/// Undo and redo stacks of an editor, with the state before every undo step.
+#[derive(Default)]
+pub struct EditHistory {
+    undo: Vec<EditSnapshot>,
+    redo: Vec<EditSnapshot>,
+    /// Kind, time and resulting cursor of the last edit.
+    last: Option<(EditKind, f64, Cursor)>,
+}
+
+impl EditHistory {
+    /// Adds an edit from `before` to `after`, merged into the previous undo step when it continues
+    /// the same burst of typing or deleting.
+    pub fn record(&mut self, kind: EditKind, before: EditSnapshot, after: &EditSnapshot, now: f64) {
+        if before.text == after.text {
+            return;
+        }
+        let continues = match self.last {
+            Some((last_kind, time, cursor)) => {
+                matches!(kind, EditKind::Insert | EditKind::Delete)
+                    && last_kind == kind
+                    && before.cursor == cursor
+                    && before.select.is_none()
+                    && now - time < COALESCE_SECONDS
+            }
+            None => false,
+        };
+        if !continues {
+            self.push_undo(before);
+        }
+        self.redo.clear();
+        self.last = Some((kind, now, after.cursor));
+    }
+
+    /// State to restore to undo the last step, `current` is kept for redo.
+    pub fn undo(&mut self, current: EditSnapshot) -> Option<EditSnapshot> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        self.last = None;
+        Some(previous)
+    }
+
+    /// State to restore to redo the last undone step, `current` is kept for undo.
+    pub fn redo(&mut self, current: EditSnapshot) -> Option<EditSnapshot> {
+        let next = self.redo.pop()?;
+        self.push_undo(current);
+        self.last = None;
+        Some(next)
+    }
+
+    fn push_undo(&mut self, snapshot: EditSnapshot) {
+        self.undo.push(snapshot);
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(text: &str) -> EditSnapshot {
+        EditSnapshot {
+            text: text.to_string(),
+            cursor: Cursor::new(0, text.len()),
+            select: None,
+        }
+    }
+
+    fn type_text(history: &mut EditHistory, from: &str, text: &str, now: f64) -> String {
+        let mut current = from.to_string();
+        for c in text.chars() {
+            let before = snapshot(&current);
+            current.push(c);
+            history.record(EditKind::Insert, before, &snapshot(&current), now);
+        }
+        current
+    }
+
+    #[test]
+    fn test_typing_is_coalesced() {
+        let mut history = EditHistory::default();
+        let text = type_text(&mut history, "", "hello", 0.);
+        // a pause starts a new step
+        let text = type_text(&mut history, &text, " world", 2.);
+        assert_eq!(history.undo(snapshot(&text)), Some(snapshot("hello")));
+        assert_eq!(history.undo(snapshot("hello")), Some(snapshot("")));
+        assert_eq!(history.undo(snapshot("")), None);
+        assert_eq!(history.redo(snapshot("")), Some(snapshot("hello")));
+        assert_eq!(history.redo(snapshot("hello")), Some(snapshot(&text)));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_edit_kinds_are_separate_steps() {
+        let mut history = EditHistory::default();
+        let text = type_text(&mut history, "", "ab", 0.);
+        history.record(EditKind::Delete, snapshot(&text), &snapshot("a"), 0.1);
+        history.record(EditKind::Paste, snapshot("a"), &snapshot("a b"), 0.2);
+        history.record(EditKind::Paste, snapshot("a b"), &snapshot("a b b"), 0.3);
+        // moving the cursor ends the burst
+        let mut moved = snapshot("a b b");
+        moved.cursor = Cursor::new(0, 0);
+        history.record(EditKind::Insert, moved.clone(), &snapshot("xa b b"), 0.4);
+        history.record(EditKind::Insert, snapshot("x"), &snapshot("x"), 0.5);
+
+        let mut current = snapshot("xa b b");
+        let mut undone = vec![];
+        while let Some(previous) = history.undo(current) {
+            undone.push(previous.text.clone());
+            current = previous;
+        }
+        assert_eq!(undone, vec!["a b b", "a b", "a", "ab", ""]);
+        assert_eq!(history.undo(snapshot("")), None);
+        assert_eq!(history.redo(snapshot("")).unwrap().text, "ab");
+    }
+
+    #[test]
+    fn test_selection_is_restored() {
+        let mut history = EditHistory::default();
+        let mut before = snapshot("hello");
+        before.cursor = Cursor::new(0, 0);
+        before.select = Some(Cursor::new(0, 5));
+        history.record(EditKind::Cut, before.clone(), &snapshot(""), 0.);
+        assert_eq!(history.undo(snapshot("")), Some(before));
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let mut history = EditHistory::default();
+        let text = type_text(&mut history, "", "a", 0.);
+        history.undo(snapshot(&text));
+        assert!(history.can_redo());
+        type_text(&mut history, "", "b", 1.);
+        assert!(!history.can_redo());
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn test_undo_is_capped() {
+        let mut history = EditHistory::default();
+        let mut text = String::new();
+        for i in 0..MAX_UNDO + 10 {
+            text = type_text(&mut history, &text, "a", i as f64 * 2.);
+        }
+        let mut current = snapshot(&text);
+        while let Some(previous) = history.undo(current.clone()) {
+            current = previous;
+        }
+        assert_eq!(current.text.len(), 10);
+        // redoing everything pushes the steps back, still within the cap
+        while let Some(next) = history.redo(current.clone()) {
+            current = next;
+        }
+        assert_eq!(current.text, text);
+        assert_eq!(history.undo.len(), MAX_UNDO);
+    }
+}
@@ -0,0 +1,230 @@
+use bevy::{
+    prelude::*,
+    window::{Ime, PrimaryWindow},
+};
+use cosmic_text::{Action, Cursor, Edit, Editor, FontSystem};
+
+use crate::{
+    cursor_x, get_scaled_y_offset, get_x_offset, scaled_layout_runs, ActiveEditor, CosmicEdit,
+    CosmicFont, CosmicLineScales, CosmicTextPos, EditKind, EditSnapshot,
+};
+
+/// Text being composed with an input method, e.g. CJK or a dead key, before it is committed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Preedit {
+    pub text: String,
+    /// Byte range of the cursor in `text`, `None` if the input method hides it.
+    pub cursor: Option<(usize, usize)>,
+}
+
+/// Composition shown in the buffer while it is drawn.
+pub(crate) struct ShownPreedit {
+    start: Cursor,
+    end: Cursor,
+    cursor: Cursor,
+    select: Option<Cursor>,
+}
+
+/// Inserts the preedit text at the cursor, so it is laid out and drawn with the rest of the
+/// line. It has to be removed with [`hide_preedit`] once drawn.
+pub(crate) fn show_preedit(
+    editor: &mut Editor,
+    font_system: &mut FontSystem,
+    preedit: &Preedit,
+) -> ShownPreedit {
+    let cursor = editor.cursor();
+    let select = editor.select_opt();
+    editor.set_select_opt(None);
+    editor.insert_string(&preedit.text, None);
+    let end = editor.cursor();
+    let preedit_cursor = preedit
+        .cursor
+        .map_or(preedit.text.len(), |(start, _)| start);
+    editor.set_cursor(Cursor {
+        index: cursor.index + preedit_cursor,
+        ..cursor
+    });
+    editor.shape_as_needed(font_system);
+    ShownPreedit {
+        start: cursor,
+        end,
+        cursor,
+        select,
+    }
+}
+
+pub(crate) fn hide_preedit(editor: &mut Editor, font_system: &mut FontSystem, shown: ShownPreedit) {
+    editor.set_cursor(shown.end);
+    editor.set_select_opt(Some(shown.start));
+    editor.delete_selection();
+    editor.set_cursor(shown.cursor);
+    editor.set_select_opt(shown.select);
+    editor.shape_as_needed(font_system);
+}
+
+/// Underlines of the shown preedit text, one `(x, y, width)` per layout run in buffer pixels.
+pub(crate) fn preedit_underlines(
+    editor: &Editor,
+    line_scales: &[f32],
+    shown: &ShownPreedit,
+) -> Vec<(i32, i32, u32)> {
+    let descent = editor.buffer().metrics().font_size / 6.;
+    scaled_layout_runs(editor.buffer(), line_scales)
+        .filter(|(run, _)| run.line_i == shown.start.line)
+        .filter_map(|(run, placement)| {
+            let glyphs = run
+                .glyphs
+                .iter()
+                .filter(|glyph| glyph.start >= shown.start.index && glyph.end <= shown.end.index);
+            let (start, end) = glyphs.fold((f32::MAX, f32::MIN), |(start, end), glyph| {
+                (start.min(glyph.x), end.max(glyph.x + glyph.w))
+            });
+            (start < end).then_some((
+                start as i32,
+                (placement.baseline + descent) as i32,
+                (end - start) as u32,
+            ))
+        })
+        .collect()
+}
+
+/// Position of the bottom of the cursor in buffer pixels, before the text is offset to be
+/// centered.
+pub fn get_cursor_position(editor: &Editor, line_scales: &[f32]) -> Option<(f32, f32)> {
+    let cursor = editor.cursor();
+    scaled_layout_runs(editor.buffer(), line_scales).find_map(|(run, placement)| {
+        cursor_x(&run, cursor).map(|x| (x, placement.top + placement.height))
+    })
+}
+
+/// Enables the input method while an editable editor is active, keeps track of the composed
+/// text, commits it into the editor and places the candidate window at the cursor.
+pub fn cosmic_edit_ime_events(
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    active_editor: Res<ActiveEditor>,
+    mut ime_evr: EventReader<Ime>,
+    mut cosmic_edit_query: Query<
+        (&mut CosmicEdit, &GlobalTransform, Option<&CosmicLineScales>),
+        With<CosmicEdit>,
+    >,
+    mut font_system_assets: ResMut<Assets<CosmicFont>>,
+    time: Res<Time>,
+) {
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+    let active = active_editor
+        .entity
+        .and_then(|entity| cosmic_edit_query.get_mut(entity).ok())
+        .filter(|(cosmic_edit, _, _)| !cosmic_edit.readonly);
+    let Some((mut cosmic_edit, node_transform, line_scales)) = active else {
+        if window.ime_enabled {
+            window.ime_enabled = false;
+        }
+        for (mut cosmic_edit, _, _) in cosmic_edit_query.iter_mut() {
+            if cosmic_edit.preedit.is_some() {
+                cosmic_edit.preedit = None;
+                cosmic_edit.editor.buffer_mut().set_redraw(true);
+            }
+        }
+        ime_evr.clear();
+        return;
+    };
+    if !window.ime_enabled {
+        window.ime_enabled = true;
+    }
+    let Some(font_system) = font_system_assets.get_mut(&cosmic_edit.font_system) else {
+        return;
+    };
+    for ev in ime_evr.iter() {
+        match ev {
+            Ime::Preedit { value, cursor, .. } => {
+                let preedit = (!value.is_empty()).then(|| Preedit {
+                    text: value.clone(),
+                    cursor: *cursor,
+                });
+                if cosmic_edit.preedit != preedit {
+                    cosmic_edit.preedit = preedit;
+                    cosmic_edit.editor.buffer_mut().set_redraw(true);
+                }
+            }
+            Ime::Commit { value, .. } => {
+                cosmic_edit.preedit = None;
+                let before = EditSnapshot::of(&cosmic_edit.editor);
+                for c in value.chars() {
+                    cosmic_edit
+                        .editor
+                        .action(&mut font_system.0, Action::Insert(c));
+                }
+                cosmic_edit.record_edit(EditKind::Insert, before, time.elapsed_seconds_f64());
+                cosmic_edit.editor.buffer_mut().set_redraw(true);
+            }
+            Ime::Disabled { .. } => {
+                cosmic_edit.preedit = None;
+                cosmic_edit.editor.buffer_mut().set_redraw(true);
+            }
+            Ime::Enabled { .. } => {}
+        }
+    }
+    // sprites are placed by a camera, the candidate window is only moved for ui nodes
+    if !cosmic_edit.is_ui_node {
+        return;
+    }
+    let line_scales = line_scales.map_or(&[][..], |line_scales| &line_scales.0[..]);
+    let (Some(size), Some((x, y))) = (
+        cosmic_edit.size,
+        get_cursor_position(&cosmic_edit.editor, line_scales),
+    ) else {
+        return;
+    };
+    let (offset_y, offset_x) = match cosmic_edit.text_pos {
+        CosmicTextPos::Center => (
+            get_scaled_y_offset(&cosmic_edit.editor, line_scales),
+            get_x_offset(&cosmic_edit.editor),
+        ),
+        CosmicTextPos::TopLeft => (0, 0),
+    };
+    let scale_factor = window.scale_factor() as f32;
+    let translation = node_transform.affine().translation;
+    let position = Vec2::new(
+        translation.x - size.0 / 2. + (x + offset_x as f32) / scale_factor,
+        translation.y - size.1 / 2. + (y + offset_y as f32) / scale_factor,
+    );
+    if window.ime_position != position {
+        window.ime_position = position;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmic_text::{Attrs, AttrsOwned, Buffer, Metrics};
+
+    use super::*;
+    use crate::{cosmic_edit_set_text, create_cosmic_font_system, get_cosmic_text, CosmicText};
+
+    #[test]
+    fn test_preedit_is_not_kept_in_text() {
+        let mut font_system = create_cosmic_font_system(Default::default());
+        let buffer = Buffer::new(&mut font_system, Metrics::new(14., 18.));
+        let mut editor = Editor::new(buffer);
+        editor.buffer_mut().set_size(&mut font_system, 200., 100.);
+        cosmic_edit_set_text(
+            CosmicText::OneStyle("ab".to_string()),
+            AttrsOwned::new(Attrs::new()),
+            &mut editor,
+            &mut font_system,
+        );
+        editor.set_cursor(Cursor::new(0, 1));
+        let preedit = Preedit {
+            text: "にほ".to_string(),
+            cursor: Some((3, 3)),
+        };
+        let shown = show_preedit(&mut editor, &mut font_system, &preedit);
+        assert_eq!(get_cosmic_text(&editor), "aにほb");
+        assert_eq!(editor.cursor().index, 4);
+        hide_preedit(&mut editor, &mut font_system, shown);
+        assert_eq!(get_cosmic_text(&editor), "ab");
+        assert_eq!(editor.cursor().index, 1);
+        assert_eq!(editor.select_opt(), None);
+    }
+}
@@ -0,0 +1,400 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use bevy::prelude::*;
+
+/// Modifier keys that have to be held for a [`KeyChord`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// Cmd on macOS, Win/Super elsewhere.
+    pub logo: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        ctrl: false,
+        shift: false,
+        alt: false,
+        logo: false,
+    };
+
+    /// Shortcut modifier of the current platform: Cmd on macOS, Ctrl elsewhere.
+    pub fn primary() -> Self {
+        if cfg!(target_os = "macos") {
+            Modifiers {
+                logo: true,
+                ..Modifiers::NONE
+            }
+        } else {
+            Modifiers {
+                ctrl: true,
+                ..Modifiers::NONE
+            }
+        }
+    }
+
+    pub fn with_shift(self) -> Self {
+        Modifiers {
+            shift: true,
+            ..self
+        }
+    }
+
+    pub fn with_alt(self) -> Self {
+        Modifiers { alt: true, ..self }
+    }
+
+    /// Modifiers currently held down.
+    pub fn pressed(keys: &Input<KeyCode>) -> Self {
+        Modifiers {
+            ctrl: keys.any_pressed([KeyCode::LControl, KeyCode::RControl]),
+            shift: keys.any_pressed([KeyCode::LShift, KeyCode::RShift]),
+            alt: keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]),
+            logo: keys.any_pressed([KeyCode::LWin, KeyCode::RWin]),
+        }
+    }
+}
+
+/// Key combination like `Ctrl+Shift+S`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub modifiers: Modifiers,
+    pub key: KeyCode,
+}
+
+impl KeyChord {
+    pub fn new(modifiers: Modifiers, key: KeyCode) -> Self {
+        Self { modifiers, key }
+    }
+
+    /// Returns true if the key was just pressed with exactly the chord modifiers held,
+    /// so that `Ctrl+S` doesn't fire for `Ctrl+Shift+S`.
+    pub fn just_pressed(&self, keys: &Input<KeyCode>) -> bool {
+        keys.just_pressed(self.key) && Modifiers::pressed(keys) == self.modifiers
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    /// Parses chords like `Ctrl+S`, `Cmd+Shift+S` or `Primary+L`, where `Primary`
+    /// is Cmd on macOS and Ctrl elsewhere. Names are case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('+').map(|part| part.trim()).collect();
+        let (key, modifier_names) = parts.split_last().unwrap();
+        let mut modifiers = Modifiers::NONE;
+        for name in modifier_names {
+            match name.to_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" | "option" => modifiers.alt = true,
+                "cmd" | "command" | "super" | "win" | "meta" | "logo" => modifiers.logo = true,
+                "primary" | "cmdorctrl" => {
+                    let primary = Modifiers::primary();
+                    modifiers.ctrl |= primary.ctrl;
+                    modifiers.logo |= primary.logo;
+                }
+                _ => return Err(format!("unknown modifier \"{}\" in \"{}\"", name, s)),
+            }
+        }
+        let key =
+            parse_key_code(key).ok_or_else(|| format!("unknown key \"{}\" in \"{}\"", key, s))?;
+        Ok(KeyChord { modifiers, key })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.logo {
+            let name = if cfg!(target_os = "macos") {
+                "Cmd"
+            } else {
+                "Super"
+            };
+            write!(f, "{}+", name)?;
+        }
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", key_code_name(self.key))
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 20] = [
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Escape", KeyCode::Escape),
+    ("Enter", KeyCode::Return),
+    ("Tab", KeyCode::Tab),
+    ("Space", KeyCode::Space),
+    ("Backspace", KeyCode::Back),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Comma", KeyCode::Comma),
+    ("Period", KeyCode::Period),
+    ("Slash", KeyCode::Slash),
+    ("Minus", KeyCode::Minus),
+    ("Equals", KeyCode::Equals),
+];
+
+const LETTER_KEYS: [KeyCode; 26] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+];
+
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+const FUNCTION_KEYS: [KeyCode; 12] = [
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+];
+
+fn parse_key_code(name: &str) -> Option<KeyCode> {
+    let upper = name.to_uppercase();
+    let mut chars = upper.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_uppercase() {
+            return Some(LETTER_KEYS[(c as u8 - b'A') as usize]);
+        }
+        if c.is_ascii_digit() {
+            return Some(DIGIT_KEYS[(c as u8 - b'0') as usize]);
+        }
+    }
+    if let Some(number) = upper
+        .strip_prefix('F')
+        .and_then(|n| n.parse::<usize>().ok())
+    {
+        return FUNCTION_KEYS.get(number.wrapping_sub(1)).copied();
+    }
+    match upper.as_str() {
+        "ESC" => return Some(KeyCode::Escape),
+        "RETURN" => return Some(KeyCode::Return),
+        "BACK" => return Some(KeyCode::Back),
+        "DEL" => return Some(KeyCode::Delete),
+        _ => {}
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(key_name, _)| key_name.to_uppercase() == upper)
+        .map(|(_, key)| *key)
+}
+
+fn key_code_name(key: KeyCode) -> String {
+    if let Some(index) = LETTER_KEYS.iter().position(|k| *k == key) {
+        return ((b'A' + index as u8) as char).to_string();
+    }
+    if let Some(index) = DIGIT_KEYS.iter().position(|k| *k == key) {
+        return index.to_string();
+    }
+    if let Some(index) = FUNCTION_KEYS.iter().position(|k| *k == key) {
+        return format!("F{}", index + 1);
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(_, k)| *k == key)
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_else(|| format!("{:?}", key))
+}
+
+/// Editor actions that can be rebound with [`CosmicKeymap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EditorAction {
+    Copy,
+    Cut,
+    Paste,
+    SelectAll,
+    PreviousWord,
+    NextWord,
+    /// Undo the last text edit, unlike the app `undo` that restores a checkpoint of the tab.
+    UndoEdit,
+    RedoEdit,
+}
+
+impl EditorAction {
+    pub const ALL: [EditorAction; 8] = [
+        EditorAction::Copy,
+        EditorAction::Cut,
+        EditorAction::Paste,
+        EditorAction::SelectAll,
+        EditorAction::PreviousWord,
+        EditorAction::NextWord,
+        EditorAction::UndoEdit,
+        EditorAction::RedoEdit,
+    ];
+
+    /// Name used in config files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EditorAction::Copy => "copy",
+            EditorAction::Cut => "cut",
+            EditorAction::Paste => "paste",
+            EditorAction::SelectAll => "select_all",
+            EditorAction::PreviousWord => "previous_word",
+            EditorAction::NextWord => "next_word",
+            EditorAction::UndoEdit => "undo_edit",
+            EditorAction::RedoEdit => "redo_edit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        EditorAction::ALL
+            .iter()
+            .find(|action| action.name() == name)
+            .copied()
+    }
+}
+
+/// Key bindings of the editor shortcuts.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct CosmicKeymap {
+    pub bindings: HashMap<EditorAction, KeyChord>,
+}
+
+impl Default for CosmicKeymap {
+    fn default() -> Self {
+        let primary = Modifiers::primary();
+        // word navigation is Option+Arrow on macOS and Ctrl+Arrow elsewhere
+        let word = if cfg!(target_os = "macos") {
+            Modifiers::NONE.with_alt()
+        } else {
+            primary
+        };
+        let bindings = HashMap::from([
+            (EditorAction::Copy, KeyChord::new(primary, KeyCode::C)),
+            (EditorAction::Cut, KeyChord::new(primary, KeyCode::X)),
+            (EditorAction::Paste, KeyChord::new(primary, KeyCode::V)),
+            (EditorAction::SelectAll, KeyChord::new(primary, KeyCode::A)),
+            (
+                EditorAction::PreviousWord,
+                KeyChord::new(word, KeyCode::Left),
+            ),
+            (EditorAction::NextWord, KeyChord::new(word, KeyCode::Right)),
+            (EditorAction::UndoEdit, KeyChord::new(primary, KeyCode::Z)),
+            (
+                EditorAction::RedoEdit,
+                KeyChord::new(primary.with_shift(), KeyCode::Z),
+            ),
+        ]);
+        Self { bindings }
+    }
+}
+
+impl CosmicKeymap {
+    pub fn just_pressed(&self, action: EditorAction, keys: &Input<KeyCode>) -> bool {
+        self.bindings
+            .get(&action)
+            .map_or(false, |chord| chord.just_pressed(keys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_chord() {
+        let chord: KeyChord = "Ctrl+Shift+S".parse().unwrap();
+        assert_eq!(
+            chord,
+            KeyChord::new(
+                Modifiers {
+                    ctrl: true,
+                    shift: true,
+                    ..Modifiers::NONE
+                },
+                KeyCode::S
+            )
+        );
+        assert_eq!(chord.to_string(), "Ctrl+Shift+S");
+        assert_eq!(
+            "cmd + alt + left".parse::<KeyChord>().unwrap(),
+            KeyChord::new(
+                Modifiers {
+                    logo: true,
+                    alt: true,
+                    ..Modifiers::NONE
+                },
+                KeyCode::Left
+            )
+        );
+        assert_eq!(
+            "Primary+1".parse::<KeyChord>().unwrap(),
+            KeyChord::new(Modifiers::primary(), KeyCode::Key1)
+        );
+        assert_eq!("F12".parse::<KeyChord>().unwrap().key, KeyCode::F12);
+        assert_eq!("Esc".parse::<KeyChord>().unwrap().key, KeyCode::Escape);
+        assert!("Hyper+S".parse::<KeyChord>().is_err());
+        assert!("Ctrl+F13".parse::<KeyChord>().is_err());
+        assert!("Ctrl+".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn test_key_chord_just_pressed() {
+        let chord: KeyChord = "Ctrl+S".parse().unwrap();
+        let mut keys = Input::<KeyCode>::default();
+        keys.press(KeyCode::LControl);
+        keys.press(KeyCode::S);
+        assert!(chord.just_pressed(&keys));
+        keys.press(KeyCode::LShift);
+        assert!(!chord.just_pressed(&keys));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{cmp, path::PathBuf};
+use std::{cmp, collections::HashSet, path::PathBuf};
 
 use bevy::{
     asset::HandleId,
@@ -9,11 +9,18 @@ use bevy::{
     window::{PrimaryWindow, WindowScaleFactorChanged},
 };
 use cosmic_text::{
-    Action, AttrsList, AttrsOwned, Buffer, BufferLine, Cursor, Edit, Editor, FontSystem, Metrics,
-    Shaping, SwashCache,
+    Action, AttrsList, AttrsOwned, Buffer, BufferLine, Cursor, Edit, Editor, FontSystem, LayoutRun,
+    Metrics, Shaping, SwashCache,
 };
 use image::{imageops::FilterType, GenericImageView};
 
+mod history;
+mod ime;
+mod keymap;
+pub use history::*;
+pub use ime::*;
+pub use keymap::*;
+
 #[derive(Clone)]
 pub struct CosmicEditUi;
 
@@ -74,6 +81,98 @@ pub struct CosmicEdit {
     pub readonly: bool,
     pub attrs: cosmic_text::AttrsOwned,
     pub is_ui_node: bool,
+    /// Text edits made with the keyboard, for undo and redo.
+    pub history: EditHistory,
+    /// Text composed with an input method, drawn at the cursor until it is committed.
+    pub preedit: Option<Preedit>,
+}
+
+impl CosmicEdit {
+    /// Adds the change from `before` to the current text to the undo history.
+    pub fn record_edit(&mut self, kind: EditKind, before: EditSnapshot, now: f64) {
+        let after = EditSnapshot::of(&self.editor);
+        self.history.record(kind, before, &after, now);
+    }
+
+    /// Replaces the text from outside of the editor, the undo history no longer applies to it.
+    pub fn set_text(&mut self, text: CosmicText, font_system: &mut FontSystem) {
+        cosmic_edit_set_text(text, self.attrs.clone(), &mut self.editor, font_system);
+        self.history.clear();
+    }
+
+    /// Restores the text, cursor and selection before the last edit, returns false if there is
+    /// nothing to undo.
+    pub fn undo(&mut self, font_system: &mut FontSystem) -> bool {
+        let current = EditSnapshot::of(&self.editor);
+        match self.history.undo(current) {
+            Some(snapshot) => {
+                self.restore(font_system, snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self, font_system: &mut FontSystem) -> bool {
+        let current = EditSnapshot::of(&self.editor);
+        match self.history.redo(current) {
+            Some(snapshot) => {
+                self.restore(font_system, snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn restore(&mut self, font_system: &mut FontSystem, snapshot: EditSnapshot) {
+        cosmic_edit_set_text(
+            CosmicText::OneStyle(snapshot.text),
+            self.attrs.clone(),
+            &mut self.editor,
+            font_system,
+        );
+        self.editor.set_cursor(snapshot.cursor);
+        self.editor.set_select_opt(snapshot.select);
+        self.editor.buffer_mut().set_redraw(true);
+    }
+}
+
+/// Font size of every buffer line relative to the buffer metrics, e.g. `2.0` for a heading twice
+/// as large as body text. Lines without a scale use the buffer font size.
+///
+/// Scaled lines are laid out at their own size and push the following lines down.
+#[derive(Component, Default)]
+pub struct CosmicLineScales(pub Vec<f32>);
+
+/// Image drawn in place of a buffer line, scaled to the buffer width.
+pub struct CosmicImage {
+    pub line: usize,
+    pub image: Handle<Image>,
+}
+
+/// Images shown between the lines of the buffer. The height of each loaded image is reserved by
+/// setting the scale of its line in [`CosmicLineScales`].
+#[derive(Component, Default)]
+pub struct CosmicImages(pub Vec<CosmicImage>);
+
+/// Attrs metadata of the spans drawn struck through, cosmic-text has no strikethrough attribute.
+#[derive(Component, Default)]
+pub struct CosmicStrikethrough(pub HashSet<usize>);
+
+/// What is drawn over and between the glyphs of a buffer.
+#[derive(Clone, Copy)]
+struct Decorations<'a> {
+    line_scales: &'a [f32],
+    images: &'a [CosmicImage],
+    strikethrough: Option<&'a HashSet<usize>>,
+}
+
+/// Vertical placement of a layout run in a buffer with scaled lines, relative to the top of the
+/// buffer.
+pub struct RunPlacement {
+    pub top: f32,
+    pub height: f32,
+    pub baseline: f32,
 }
 
 #[derive(TypeUuid)]
@@ -87,7 +186,11 @@ impl Plugin for CosmicEditPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems((
             cosmic_edit_bevy_events,
+            cosmic_edit_ime_events.after(cosmic_edit_bevy_events),
             cosmic_edit_set_redraw,
+            cosmic_edit_image_scales.before(cosmic_edit_line_scales_changed),
+            cosmic_edit_line_scales_changed.before(cosmic_edit_redraw_buffer_ui),
+            cosmic_edit_strikethrough_changed.before(cosmic_edit_redraw_buffer_ui),
             scale_factor_changed,
             cosmic_edit_redraw_buffer_ui
                 .before(cosmic_edit_set_redraw)
@@ -95,6 +198,7 @@ impl Plugin for CosmicEditPlugin {
             cosmic_edit_redraw_buffer.before(scale_factor_changed),
         ))
         .init_resource::<ActiveEditor>()
+        .init_resource::<CosmicKeymap>()
         .add_asset::<CosmicFont>()
         .insert_resource(SwashCacheState {
             swash_cache: SwashCache::new(),
@@ -255,6 +359,73 @@ pub fn get_y_offset(editor: &Editor) -> i32 {
     ((editor.buffer().size().1 - text_height) / 2.0) as i32
 }
 
+/// Same as [`get_y_offset`] for a buffer with [`CosmicLineScales`].
+pub fn get_scaled_y_offset(editor: &Editor, line_scales: &[f32]) -> i32 {
+    if line_scales.is_empty() {
+        return get_y_offset(editor);
+    }
+    let text_height: f32 = scaled_layout_runs(editor.buffer(), line_scales)
+        .map(|(_, placement)| placement.height)
+        .sum();
+    ((editor.buffer().size().1 - text_height).max(0.) / 2.0) as i32
+}
+
+fn line_scale(line_scales: &[f32], line_i: usize) -> f32 {
+    line_scales.get(line_i).copied().unwrap_or(1.)
+}
+
+/// Layout runs of the buffer with their placement when lines are scaled.
+pub fn scaled_layout_runs<'a>(
+    buffer: &'a Buffer,
+    line_scales: &'a [f32],
+) -> impl Iterator<Item = (LayoutRun<'a>, RunPlacement)> + 'a {
+    let line_height = buffer.metrics().line_height;
+    let mut top = 0.;
+    buffer.layout_runs().enumerate().map(move |(i, run)| {
+        let scale = line_scale(line_scales, run.line_i);
+        // the buffer places every run one line height below the previous one
+        let baseline_offset = run.line_y - i as f32 * line_height;
+        let placement = RunPlacement {
+            top,
+            height: line_height * scale,
+            baseline: top + baseline_offset * scale,
+        };
+        top += placement.height;
+        (run, placement)
+    })
+}
+
+/// Lays out scaled lines at their own font size. The buffer lays out lines again at its font
+/// size when it is resized, so this runs before every redraw.
+fn layout_scaled_lines(buffer: &mut Buffer, font_system: &mut FontSystem, line_scales: &[f32]) {
+    let font_size = buffer.metrics().font_size;
+    let width = buffer.size().0;
+    let wrap = buffer.wrap();
+    for (line, scale) in buffer.lines.iter_mut().zip(line_scales) {
+        if *scale != 1. {
+            line.reset_layout();
+            line.layout(font_system, font_size * scale, width, wrap);
+        }
+    }
+}
+
+/// Maps a y position in a buffer with scaled lines to the middle of the same run in the buffer
+/// layout, where every run is one line height tall, so clicks and drags hit the right line.
+fn scaled_hit_y(editor: &Editor, line_scales: &[f32], y: i32) -> i32 {
+    if line_scales.is_empty() {
+        return y;
+    }
+    let line_height = editor.buffer().metrics().line_height;
+    let mut runs = 0;
+    for (i, (_, placement)) in scaled_layout_runs(editor.buffer(), line_scales).enumerate() {
+        if (y as f32) < placement.top + placement.height {
+            return (i as f32 * line_height + line_height / 2.) as i32;
+        }
+        runs = i + 1;
+    }
+    (runs as f32 * line_height) as i32
+}
+
 pub fn get_x_offset(editor: &Editor) -> i32 {
     let mut max_line_width = 0.;
     for line in editor.buffer().lines.iter() {
@@ -275,19 +446,52 @@ pub fn cosmic_edit_bevy_events(
     windows: Query<&Window, With<PrimaryWindow>>,
     active_editor: Res<ActiveEditor>,
     keys: Res<Input<KeyCode>>,
+    keymap: Res<CosmicKeymap>,
     mut char_evr: EventReader<ReceivedCharacter>,
     buttons: Res<Input<MouseButton>>,
-    mut cosmic_edit_query: Query<(&mut CosmicEdit, &GlobalTransform, Entity), With<CosmicEdit>>,
+    mut cosmic_edit_query: Query<
+        (
+            &mut CosmicEdit,
+            &GlobalTransform,
+            Entity,
+            Option<&CosmicLineScales>,
+        ),
+        With<CosmicEdit>,
+    >,
     mut is_deleting: Local<bool>,
     mut font_system_assets: ResMut<Assets<CosmicFont>>,
     mut scroll_evr: EventReader<MouseWheel>,
+    time: Res<Time>,
 ) {
     let window = windows.single();
-    for (mut cosmic_edit, node_transform, entity) in &mut cosmic_edit_query.iter_mut() {
+    let now = time.elapsed_seconds_f64();
+    for (mut cosmic_edit, node_transform, entity, line_scales) in &mut cosmic_edit_query.iter_mut()
+    {
+        let line_scales = line_scales.map_or(&[][..], |line_scales| &line_scales.0[..]);
         if active_editor.entity == Some(entity) {
             if let Some(font_system) = font_system_assets.get_mut(&cosmic_edit.font_system) {
-                let command = keys.any_pressed([KeyCode::RWin, KeyCode::LWin]);
-                let option = keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]);
+                // keys belong to the input method while text is being composed
+                if cosmic_edit.preedit.is_some() {
+                    char_evr.clear();
+                    // RETURN
+                    return;
+                }
+                // word navigation is usually bound to modified arrows,
+                // so it has to be checked before plain arrow keys
+                if keymap.just_pressed(EditorAction::PreviousWord, &keys) {
+                    cosmic_edit
+                        .editor
+                        .action(&mut font_system.0, Action::PreviousWord);
+                    // RETURN
+                    return;
+                }
+                if keymap.just_pressed(EditorAction::NextWord, &keys) {
+                    cosmic_edit
+                        .editor
+                        .action(&mut font_system.0, Action::NextWord);
+                    // RETURN
+                    return;
+                }
                 if keys.just_pressed(KeyCode::Left) {
                     cosmic_edit.editor.action(&mut font_system.0, Action::Left);
                 }
@@ -303,24 +507,32 @@ pub fn cosmic_edit_bevy_events(
                 if !cosmic_edit.readonly && keys.just_pressed(KeyCode::Back) {
                     // there is ReceivedCharacter event for backspace on wasm
                     #[cfg(target_arch = "wasm32")]
-                    cosmic_edit
-                        .editor
-                        .action(&mut font_system.0, Action::Backspace);
+                    {
+                        let before = EditSnapshot::of(&cosmic_edit.editor);
+                        cosmic_edit
+                            .editor
+                            .action(&mut font_system.0, Action::Backspace);
+                        cosmic_edit.record_edit(EditKind::Delete, before, now);
+                    }
                     *is_deleting = true;
                 }
                 if !cosmic_edit.readonly && keys.just_released(KeyCode::Back) {
                     *is_deleting = false;
                 }
                 if !cosmic_edit.readonly && keys.just_pressed(KeyCode::Delete) {
+                    let before = EditSnapshot::of(&cosmic_edit.editor);
                     cosmic_edit
                         .editor
                         .action(&mut font_system.0, Action::Delete);
+                    cosmic_edit.record_edit(EditKind::Delete, before, now);
                 }
                 if !cosmic_edit.readonly && keys.just_pressed(KeyCode::Return) {
                     // to have new line on wasm rather than E
+                    let before = EditSnapshot::of(&cosmic_edit.editor);
                     cosmic_edit
                         .editor
                         .action(&mut font_system.0, Action::Insert('\n'));
+                    cosmic_edit.record_edit(EditKind::NewLine, before, now);
                     // RETURN
                     return;
                 }
@@ -329,53 +541,54 @@ pub fn cosmic_edit_bevy_events(
                         .editor
                         .action(&mut font_system.0, Action::Escape);
                 }
-                if command && keys.just_pressed(KeyCode::A) {
-                    cosmic_edit
-                        .editor
-                        .action(&mut font_system.0, Action::BufferEnd);
-                    cosmic_edit.editor.set_select_opt(Some(Cursor::default()));
+                if !cosmic_edit.readonly && keymap.just_pressed(EditorAction::UndoEdit, &keys) {
+                    cosmic_edit.undo(&mut font_system.0);
                     // RETURN
                     return;
                 }
-                if command && option && keys.just_pressed(KeyCode::Left) {
-                    cosmic_edit
-                        .editor
-                        .action(&mut font_system.0, Action::PreviousWord);
+                if !cosmic_edit.readonly && keymap.just_pressed(EditorAction::RedoEdit, &keys) {
+                    cosmic_edit.redo(&mut font_system.0);
                     // RETURN
                     return;
                 }
-                if command && option && keys.just_pressed(KeyCode::Right) {
+                if keymap.just_pressed(EditorAction::SelectAll, &keys) {
                     cosmic_edit
                         .editor
-                        .action(&mut font_system.0, Action::NextWord);
+                        .action(&mut font_system.0, Action::BufferEnd);
+                    cosmic_edit.editor.set_select_opt(Some(Cursor::default()));
                     // RETURN
                     return;
                 }
                 #[cfg(not(target_arch = "wasm32"))]
                 {
                     if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                        if command && keys.just_pressed(KeyCode::C) {
+                        if keymap.just_pressed(EditorAction::Copy, &keys) {
                             if let Some(text) = cosmic_edit.editor.copy_selection() {
                                 clipboard.set_text(text).unwrap();
                             }
                             // RETURN
                             return;
                         }
-                        if !cosmic_edit.readonly && command && keys.just_pressed(KeyCode::X) {
+                        if !cosmic_edit.readonly && keymap.just_pressed(EditorAction::Cut, &keys) {
                             if let Some(text) = cosmic_edit.editor.copy_selection() {
                                 clipboard.set_text(text).unwrap();
+                                let before = EditSnapshot::of(&cosmic_edit.editor);
                                 cosmic_edit.editor.delete_selection();
+                                cosmic_edit.record_edit(EditKind::Cut, before, now);
                             }
                             // RETURN
                             return;
                         }
-                        if !cosmic_edit.readonly && command && keys.just_pressed(KeyCode::V) {
+                        if !cosmic_edit.readonly && keymap.just_pressed(EditorAction::Paste, &keys)
+                        {
                             if let Ok(text) = clipboard.get_text() {
+                                let before = EditSnapshot::of(&cosmic_edit.editor);
                                 for c in text.chars() {
                                     cosmic_edit
                                         .editor
                                         .action(&mut font_system.0, Action::Insert(c));
                                 }
+                                cosmic_edit.record_edit(EditKind::Paste, before, now);
                             }
                             // RETURN
                             return;
@@ -384,7 +597,7 @@ pub fn cosmic_edit_bevy_events(
                 }
                 let (offset_y, offset_x) = match cosmic_edit.text_pos {
                     CosmicTextPos::Center => (
-                        get_y_offset(&cosmic_edit.editor),
+                        get_scaled_y_offset(&cosmic_edit.editor, line_scales),
                         get_x_offset(&cosmic_edit.editor),
                     ),
                     CosmicTextPos::TopLeft => (0, 0),
@@ -396,13 +609,17 @@ pub fn cosmic_edit_bevy_events(
                         cosmic_edit.size.unwrap(),
                         cosmic_edit.is_ui_node,
                     ) {
+                        let y = scaled_hit_y(
+                            &cosmic_edit.editor,
+                            line_scales,
+                            (node_cursor_pos.1 * window.scale_factor() as f32) as i32 - offset_y,
+                        );
                         cosmic_edit.editor.action(
                             &mut font_system.0,
                             Action::Click {
                                 x: (node_cursor_pos.0 * window.scale_factor() as f32) as i32
                                     - offset_x,
-                                y: (node_cursor_pos.1 * window.scale_factor() as f32) as i32
-                                    - offset_y,
+                                y,
                             },
                         );
                     }
@@ -416,13 +633,17 @@ pub fn cosmic_edit_bevy_events(
                         cosmic_edit.size.unwrap(),
                         cosmic_edit.is_ui_node,
                     ) {
+                        let y = scaled_hit_y(
+                            &cosmic_edit.editor,
+                            line_scales,
+                            (node_cursor_pos.1 * window.scale_factor() as f32) as i32 - offset_y,
+                        );
                         cosmic_edit.editor.action(
                             &mut font_system.0,
                             Action::Drag {
                                 x: (node_cursor_pos.0 * window.scale_factor() as f32) as i32
                                     - offset_x,
-                                y: (node_cursor_pos.1 * window.scale_factor() as f32) as i32
-                                    - offset_y,
+                                y,
                             },
                         );
                     }
@@ -455,14 +676,18 @@ pub fn cosmic_edit_bevy_events(
                     return;
                 }
                 for char_ev in char_evr.iter() {
+                    let before = EditSnapshot::of(&cosmic_edit.editor);
                     if *is_deleting {
                         cosmic_edit
                             .editor
                             .action(&mut font_system.0, Action::Backspace);
-                    } else {
+                        cosmic_edit.record_edit(EditKind::Delete, before, now);
+                    } else if !char_ev.char.is_control() || char_ev.char == '\t' {
+                        // Ctrl shortcuts on Linux/Windows also produce control characters
                         cosmic_edit
                             .editor
                             .action(&mut font_system.0, Action::Insert(char_ev.char));
+                        cosmic_edit.record_edit(EditKind::Insert, before, now);
                     }
                 }
             }
@@ -476,18 +701,72 @@ fn cosmic_edit_set_redraw(mut cosmic_edit_query: Query<&mut CosmicEdit, Added<Co
     }
 }
 
+fn cosmic_edit_line_scales_changed(
+    mut cosmic_edit_query: Query<&mut CosmicEdit, Changed<CosmicLineScales>>,
+) {
+    for mut cosmic_edit in cosmic_edit_query.iter_mut() {
+        cosmic_edit.editor.buffer_mut().set_redraw(true);
+    }
+}
+
+fn cosmic_edit_strikethrough_changed(
+    mut cosmic_edit_query: Query<&mut CosmicEdit, Changed<CosmicStrikethrough>>,
+) {
+    for mut cosmic_edit in cosmic_edit_query.iter_mut() {
+        cosmic_edit.editor.buffer_mut().set_redraw(true);
+    }
+}
+
+fn cosmic_edit_image_scales(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    images: Res<Assets<Image>>,
+    mut cosmic_edit_query: Query<(&CosmicEdit, &CosmicImages, &mut CosmicLineScales)>,
+) {
+    let window = windows.single();
+    for (cosmic_edit, cosmic_images, mut line_scales) in cosmic_edit_query.iter_mut() {
+        let width = match cosmic_edit.size {
+            Some(size) => size.0 * window.scale_factor() as f32,
+            None => continue,
+        };
+        let line_height = cosmic_edit.editor.buffer().metrics().line_height;
+        for cosmic_image in cosmic_images.0.iter() {
+            let image = match images.get(&cosmic_image.image) {
+                Some(image) => image,
+                None => continue,
+            };
+            let scale = image.size().y * width / image.size().x / line_height;
+            match line_scales.0.get(cosmic_image.line) {
+                Some(current) if (current - scale).abs() > 0.01 => {
+                    line_scales.0[cosmic_image.line] = scale;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
 fn redraw_buffer_common(
     window: &Window,
     images: &mut ResMut<Assets<Image>>,
     swash_cache_state: &mut ResMut<SwashCacheState>,
     cosmic_edit: &mut CosmicEdit,
+    decorations: Decorations,
     img_handle: &mut Handle<Image>,
     font_system_assets: &mut ResMut<Assets<CosmicFont>>,
 ) {
+    let Decorations {
+        line_scales,
+        images: cosmic_images,
+        strikethrough,
+    } = decorations;
     let swash_cache = &mut swash_cache_state.swash_cache;
     if let Some(font_system) = font_system_assets.get_mut(&cosmic_edit.font_system) {
         cosmic_edit.editor.shape_as_needed(&mut font_system.0);
         if cosmic_edit.editor.buffer().redraw() {
+            let preedit = cosmic_edit
+                .preedit
+                .clone()
+                .map(|preedit| show_preedit(&mut cosmic_edit.editor, &mut font_system.0, &preedit));
             let size = cosmic_edit.size.unwrap();
             let width = cmp::max((size.0 * window.scale_factor() as f32) as i32, 1) as f32;
             let height = cmp::max((size.1 * window.scale_factor() as f32) as i32, 1) as f32;
@@ -495,6 +774,13 @@ fn redraw_buffer_common(
                 .editor
                 .buffer_mut()
                 .set_size(&mut font_system.0, width, height);
+            if !line_scales.is_empty() {
+                layout_scaled_lines(
+                    cosmic_edit.editor.buffer_mut(),
+                    &mut font_system.0,
+                    line_scales,
+                );
+            }
             let font_color = cosmic_text::Color::rgb(0, 0, 0);
 
             let mut pixels = vec![0; width as usize * height as usize * 4];
@@ -529,30 +815,64 @@ fn redraw_buffer_common(
 
             let (offset_y, offset_x) = match cosmic_edit.text_pos {
                 CosmicTextPos::Center => (
-                    get_y_offset(&cosmic_edit.editor),
+                    get_scaled_y_offset(&cosmic_edit.editor, line_scales),
                     get_x_offset(&cosmic_edit.editor),
                 ),
                 CosmicTextPos::TopLeft => (0, 0),
             };
-            cosmic_edit.editor.draw(
-                &mut font_system.0,
-                swash_cache,
-                font_color,
-                |x, y, w, h, color| {
-                    for row in 0..h as i32 {
-                        for col in 0..w as i32 {
-                            draw_pixel(
-                                &mut pixels,
-                                width as i32,
-                                height as i32,
-                                x + col + offset_x,
-                                y + row + offset_y,
-                                color,
-                            );
-                        }
+            if !cosmic_images.is_empty() {
+                draw_line_images(
+                    &mut pixels,
+                    (width as i32, height as i32),
+                    offset_y,
+                    &cosmic_edit.editor,
+                    line_scales,
+                    cosmic_images,
+                    images,
+                );
+            }
+            let mut draw = |x: i32, y: i32, w: u32, h: u32, color| {
+                for row in 0..h as i32 {
+                    for col in 0..w as i32 {
+                        draw_pixel(
+                            &mut pixels,
+                            width as i32,
+                            height as i32,
+                            x + col + offset_x,
+                            y + row + offset_y,
+                            color,
+                        );
                     }
-                },
-            );
+                }
+            };
+            if line_scales.is_empty() {
+                cosmic_edit
+                    .editor
+                    .draw(&mut font_system.0, swash_cache, font_color, &mut draw);
+            } else {
+                draw_scaled_lines(
+                    &cosmic_edit.editor,
+                    &mut font_system.0,
+                    swash_cache,
+                    font_color,
+                    line_scales,
+                    &mut draw,
+                );
+            }
+            let thickness = cmp::max(window.scale_factor() as u32, 1);
+            if let Some(struck) = strikethrough.filter(|struck| !struck.is_empty()) {
+                for (x, y, w, color) in
+                    strikethrough_lines(&cosmic_edit.editor, line_scales, struck, font_color)
+                {
+                    draw(x, y, w, thickness, color);
+                }
+            }
+            if let Some(preedit) = preedit {
+                for (x, y, w) in preedit_underlines(&cosmic_edit.editor, line_scales, &preedit) {
+                    draw(x, y, w, thickness, font_color);
+                }
+                hide_preedit(&mut cosmic_edit.editor, &mut font_system.0, preedit);
+            }
             cosmic_edit.editor.buffer_mut().set_redraw(false);
 
             if let Some(prev_image) = images.get_mut(img_handle) {
@@ -587,17 +907,34 @@ fn cosmic_edit_redraw_buffer_ui(
     windows: Query<&Window, With<PrimaryWindow>>,
     mut images: ResMut<Assets<Image>>,
     mut swash_cache_state: ResMut<SwashCacheState>,
-    mut cosmic_edit_query: Query<(&mut CosmicEdit, &mut UiImage, &Node), With<CosmicEdit>>,
+    mut cosmic_edit_query: Query<
+        (
+            &mut CosmicEdit,
+            &mut UiImage,
+            &Node,
+            Option<&CosmicLineScales>,
+            Option<&CosmicImages>,
+            Option<&CosmicStrikethrough>,
+        ),
+        With<CosmicEdit>,
+    >,
     mut font_system_assets: ResMut<Assets<CosmicFont>>,
 ) {
     let window = windows.single();
-    for (mut cosmic_edit, mut img, node) in &mut cosmic_edit_query.iter_mut() {
+    for (mut cosmic_edit, mut img, node, line_scales, cosmic_images, strikethrough) in
+        &mut cosmic_edit_query.iter_mut()
+    {
         cosmic_edit.size = Some((node.size().x, node.size().y));
         redraw_buffer_common(
             window,
             &mut images,
             &mut swash_cache_state,
             &mut cosmic_edit,
+            Decorations {
+                line_scales: line_scales.map_or(&[][..], |line_scales| &line_scales.0[..]),
+                images: cosmic_images.map_or(&[][..], |cosmic_images| &cosmic_images.0[..]),
+                strikethrough: strikethrough.map(|strikethrough| &strikethrough.0),
+            },
             &mut img.texture,
             &mut font_system_assets,
         );
@@ -608,16 +945,32 @@ fn cosmic_edit_redraw_buffer(
     windows: Query<&Window, With<PrimaryWindow>>,
     mut images: ResMut<Assets<Image>>,
     mut swash_cache_state: ResMut<SwashCacheState>,
-    mut cosmic_edit_query: Query<(&mut CosmicEdit, &mut Handle<Image>), With<CosmicEdit>>,
+    mut cosmic_edit_query: Query<
+        (
+            &mut CosmicEdit,
+            &mut Handle<Image>,
+            Option<&CosmicLineScales>,
+            Option<&CosmicImages>,
+            Option<&CosmicStrikethrough>,
+        ),
+        With<CosmicEdit>,
+    >,
     mut font_system_assets: ResMut<Assets<CosmicFont>>,
 ) {
     let window = windows.single();
-    for (mut cosmic_edit, mut handle) in &mut cosmic_edit_query.iter_mut() {
+    for (mut cosmic_edit, mut handle, line_scales, cosmic_images, strikethrough) in
+        &mut cosmic_edit_query.iter_mut()
+    {
         redraw_buffer_common(
             window,
             &mut images,
             &mut swash_cache_state,
             &mut cosmic_edit,
+            Decorations {
+                line_scales: line_scales.map_or(&[][..], |line_scales| &line_scales.0[..]),
+                images: cosmic_images.map_or(&[][..], |cosmic_images| &cosmic_images.0[..]),
+                strikethrough: strikethrough.map(|strikethrough| &strikethrough.0),
+            },
             &mut handle,
             &mut font_system_assets,
         );
@@ -708,6 +1061,8 @@ pub fn spawn_cosmic_edit(
         readonly: cosmic_edit_meta.readonly,
         attrs: cosmic_edit_meta.attrs.clone(),
         bg_image: cosmic_edit_meta.bg_image,
+        history: EditHistory::default(),
+        preedit: None,
     };
     match cosmic_edit_meta.node {
         CosmicNode::Ui => {
@@ -736,6 +1091,156 @@ pub fn spawn_cosmic_edit(
     }
 }
 
+/// X position of the cursor in a layout run, `None` if the cursor isn't in the run.
+pub(crate) fn cursor_x(run: &LayoutRun, cursor: Cursor) -> Option<f32> {
+    if run.line_i != cursor.line {
+        return None;
+    }
+    if run.glyphs.is_empty() {
+        return Some(0.);
+    }
+    run.glyphs.iter().find_map(|glyph| {
+        if (glyph.start..glyph.end).contains(&cursor.index) {
+            Some(glyph.x)
+        } else if glyph.end == cursor.index
+            && run.glyphs.last().map(|last| last.end) == Some(cursor.index)
+        {
+            Some(glyph.x + glyph.w)
+        } else {
+            None
+        }
+    })
+}
+
+/// Draws the glyphs, selection and cursor of a buffer with [`CosmicLineScales`] at the height
+/// of each scaled line.
+fn draw_scaled_lines<F>(
+    editor: &Editor,
+    font_system: &mut FontSystem,
+    swash_cache: &mut SwashCache,
+    color: cosmic_text::Color,
+    line_scales: &[f32],
+    mut f: F,
+) where
+    F: FnMut(i32, i32, u32, u32, cosmic_text::Color),
+{
+    let cursor = editor.cursor();
+    let selection = editor.select_opt().map(|select| {
+        if (select.line, select.index) < (cursor.line, cursor.index) {
+            ((select.line, select.index), (cursor.line, cursor.index))
+        } else {
+            ((cursor.line, cursor.index), (select.line, select.index))
+        }
+    });
+    let selection_color = cosmic_text::Color::rgba(color.r(), color.g(), color.b(), 0x33);
+    for (run, placement) in scaled_layout_runs(editor.buffer(), line_scales) {
+        let top = placement.top as i32;
+        let height = placement.height as u32;
+        let baseline = placement.baseline as i32;
+        if let Some((start, end)) = selection {
+            for glyph in run.glyphs.iter() {
+                if (run.line_i, glyph.start) >= start && (run.line_i, glyph.end) <= end {
+                    f(glyph.x as i32, top, glyph.w as u32, height, selection_color);
+                }
+            }
+        }
+        for glyph in run.glyphs.iter() {
+            let glyph_color = glyph.color_opt.unwrap_or(color);
+            swash_cache.with_pixels(font_system, glyph.cache_key, glyph_color, |x, y, color| {
+                f(glyph.x_int + x, baseline + glyph.y_int + y, 1, 1, color);
+            });
+        }
+        if let Some(x) = cursor_x(&run, cursor) {
+            f(x as i32, top, 1, height, cursor.color.unwrap_or(color));
+        }
+    }
+}
+
+/// Lines through the glyphs of struck spans, one `(x, y, width, color)` per stretch of
+/// consecutive glyphs in buffer pixels.
+fn strikethrough_lines(
+    editor: &Editor,
+    line_scales: &[f32],
+    struck: &HashSet<usize>,
+    color: cosmic_text::Color,
+) -> Vec<(i32, i32, u32, cosmic_text::Color)> {
+    let font_size = editor.buffer().metrics().font_size;
+    let mut lines = vec![];
+    for (run, placement) in scaled_layout_runs(editor.buffer(), line_scales) {
+        // about the middle of lowercase letters
+        let y = (placement.baseline - font_size * line_scale(line_scales, run.line_i) * 0.3) as i32;
+        let mut current: Option<(f32, f32, cosmic_text::Color)> = None;
+        for glyph in run.glyphs.iter() {
+            let glyph_color = glyph.color_opt.unwrap_or(color);
+            if !struck.contains(&glyph.metadata) {
+                if let Some((start, end, color)) = current.take() {
+                    lines.push((start as i32, y, (end - start) as u32, color));
+                }
+                continue;
+            }
+            current = match current {
+                Some((start, _, color)) if color == glyph_color => {
+                    Some((start, glyph.x + glyph.w, color))
+                }
+                Some((start, end, color)) => {
+                    lines.push((start as i32, y, (end - start) as u32, color));
+                    Some((glyph.x, glyph.x + glyph.w, glyph_color))
+                }
+                None => Some((glyph.x, glyph.x + glyph.w, glyph_color)),
+            };
+        }
+        if let Some((start, end, color)) = current {
+            lines.push((start as i32, y, (end - start) as u32, color));
+        }
+    }
+    lines
+}
+
+/// Draws every loaded image over the first run of its line, stretched to the buffer width and
+/// the height reserved for the line.
+fn draw_line_images(
+    pixels: &mut [u8],
+    (width, height): (i32, i32),
+    offset_y: i32,
+    editor: &Editor,
+    line_scales: &[f32],
+    cosmic_images: &[CosmicImage],
+    images: &Assets<Image>,
+) {
+    let mut last_line = None;
+    for (run, placement) in scaled_layout_runs(editor.buffer(), line_scales) {
+        if last_line == Some(run.line_i) {
+            continue;
+        }
+        last_line = Some(run.line_i);
+        let image = match cosmic_images
+            .iter()
+            .find(|cosmic_image| cosmic_image.line == run.line_i)
+            .and_then(|cosmic_image| images.get(&cosmic_image.image))
+            .and_then(|image| image.clone().try_into_dynamic().ok())
+        {
+            Some(image) => image,
+            None => continue,
+        };
+        let image_height = placement.height as u32;
+        if image_height == 0 {
+            continue;
+        }
+        let image = image.resize_exact(width as u32, image_height, FilterType::Triangle);
+        let top = placement.top as i32 + offset_y;
+        for (x, y, rgba) in image.pixels() {
+            draw_pixel(
+                pixels,
+                width,
+                height,
+                x as i32,
+                top + y as i32,
+                cosmic_text::Color::rgba(rgba[0], rgba[1], rgba[2], rgba[3]),
+            );
+        }
+    }
+}
+
 fn draw_pixel(
     buffer: &mut [u8],
     width: i32,
@@ -0,0 +1,93 @@
+//! Renders a tab worth of code-heavy nodes the way the app did before the syntax sets were
+//! shared, with shared sets, and from the render cache.
+//!
+//! `cargo bench -p bevy_markdown`
+
+use std::sync::Mutex;
+
+use bevy_markdown::{
+    generate_markdown_lines, generate_markdown_lines_cached, BevyMarkdown, BevyMarkdownTheme,
+    MarkdownCache,
+};
+use cosmic_text::{Attrs, AttrsOwned, Color};
+use criterion::{criterion_group, criterion_main, Criterion};
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+
+const NODES: usize = 200;
+
+fn node_text(i: usize) -> String {
+    format!(
+        "# Node {i}
+
+Some **bold** and *italic* text with a [link](https://example.com).
+
+```rust
+fn node_{i}(values: &[u32]) -> u32 {{
+    values.iter().filter(|v| *v % 2 == 0).map(|v| v * {i}).sum()
+}}
+```
+
+- first
+- second
+"
+    )
+}
+
+fn markdown(text: &str) -> BevyMarkdown {
+    BevyMarkdown {
+        markdown_theme: BevyMarkdownTheme {
+            code_theme: "base16-ocean.dark".to_string(),
+            code_default_lang: "rs".to_string(),
+            link: Color::rgb(10, 10, 10),
+            inline_code: Color::rgb(100, 100, 100),
+            blockquote: Color::rgb(120, 120, 120),
+        },
+        text: text.to_string(),
+        attrs: AttrsOwned::new(Attrs::new()),
+        font_size: 15.0,
+    }
+}
+
+fn render(c: &mut Criterion) {
+    let texts: Vec<String> = (0..NODES).map(node_text).collect();
+    let mut group = c.benchmark_group("render tab");
+    group.sample_size(10);
+
+    group.bench_function("load sets for every node", |b| {
+        b.iter(|| {
+            for text in texts.iter() {
+                let _ = SyntaxSet::load_defaults_newlines();
+                let _ = ThemeSet::load_defaults();
+                generate_markdown_lines(markdown(text));
+            }
+        })
+    });
+
+    // the first render loads the shared sets
+    generate_markdown_lines(markdown(""));
+    group.bench_function("shared sets", |b| {
+        b.iter(|| {
+            for text in texts.iter() {
+                generate_markdown_lines(markdown(text));
+            }
+        })
+    });
+
+    let cache = Mutex::new(MarkdownCache::default());
+    for text in texts.iter() {
+        generate_markdown_lines_cached(markdown(text), &cache);
+    }
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            for text in texts.iter() {
+                generate_markdown_lines_cached(markdown(text), &cache);
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
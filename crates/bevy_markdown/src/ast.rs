@@ -0,0 +1,476 @@
+//! Renderer independent model of the markdown: blocks of styled spans with links and code
+//! highlighting resolved, turned into cosmic-text lines or HTML by the backends.
+
+use cosmic_text::Color;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::FontStyle;
+use syntect::util::LinesWithEndings;
+
+use crate::{default, BevyMarkdownError, BevyMarkdownTheme, MarkdownImage, SYNTAX_SET, THEME_SET};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontWeight {
+    SemiBold,
+    Bold,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpanStyle {
+    pub weight: Option<FontWeight>,
+    pub italic: bool,
+    pub strikethrough: bool,
+    /// Inline `code`.
+    pub code: bool,
+    /// Color given by code highlighting, other colors come from the theme of the backend.
+    pub color: Option<Color>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StyledSpan {
+    pub text: String,
+    pub style: SpanStyle,
+    pub link: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarkdownInline {
+    Span(StyledSpan),
+    /// Hard line break.
+    Break,
+    Image(MarkdownImage),
+    /// Syntax that isn't supported, shown as its markdown source.
+    Source(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TableAlign {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListItem {
+    /// `Some` for task list items.
+    pub checked: Option<bool>,
+    /// Byte offset of the `[ ]` marker of task list items in the text.
+    pub checkbox: Option<usize>,
+    pub children: Vec<MarkdownBlock>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarkdownBlock {
+    Heading {
+        depth: u8,
+        content: Vec<MarkdownInline>,
+    },
+    Paragraph(Vec<MarkdownInline>),
+    /// Highlighted with the code theme, spans keep their line endings.
+    Code {
+        lang: String,
+        spans: Vec<StyledSpan>,
+    },
+    List {
+        ordered: bool,
+        start: Option<u32>,
+        items: Vec<ListItem>,
+    },
+    Blockquote(Vec<MarkdownBlock>),
+    /// Rows of cells, the first row is the header.
+    Table {
+        align: Vec<TableAlign>,
+        rows: Vec<Vec<Vec<MarkdownInline>>>,
+    },
+    Rule,
+    /// Syntax that isn't supported, shown as its markdown source.
+    Source(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MarkdownDocument {
+    pub blocks: Vec<MarkdownBlock>,
+    /// Parts of the text that couldn't be parsed or highlighted, they are still in `blocks`.
+    pub diagnostics: Vec<BevyMarkdownError>,
+}
+
+fn transform_error(
+    position: Option<&markdown::unist::Position>,
+    info: String,
+) -> BevyMarkdownError {
+    let info = match position {
+        Some(position) => format!("line {}: {}", position.start.line, info),
+        None => info,
+    };
+    BevyMarkdownError::Transform { info }
+}
+
+/// Reports a node that can't be rendered and returns its markdown source.
+fn handle_unsupported(
+    node: &markdown::mdast::Node,
+    text: &str,
+    errors: &mut Vec<BevyMarkdownError>,
+) -> Option<String> {
+    // the debug output starts with the name of the node type
+    let debug = format!("{:?}", node);
+    let name = debug
+        .split(|ch| ch == '(' || ch == ' ')
+        .next()
+        .unwrap_or_default();
+    let position = node.position();
+    errors.push(transform_error(
+        position,
+        format!("{} is not supported, shown as text", name),
+    ));
+    position
+        .and_then(|position| text.get(position.start.offset..position.end.offset))
+        .map(|source| source.to_string())
+}
+
+/// Parses the text, never fails: syntax that isn't supported is kept as `Source` and reported
+/// in `MarkdownDocument::diagnostics`.
+pub fn parse_markdown(text: &str, markdown_theme: &BevyMarkdownTheme) -> MarkdownDocument {
+    let mut blocks = Vec::new();
+    let mut errors = Vec::new();
+
+    match markdown::to_mdast(text, &markdown::ParseOptions::gfm()) {
+        Ok(markdown::mdast::Node::Root(root)) => {
+            root.children.iter().for_each(|child| {
+                handle_block(child, text, markdown_theme, &mut blocks, &mut errors);
+            });
+        }
+        Ok(node) => {
+            if let Some(source) = handle_unsupported(&node, text, &mut errors) {
+                blocks.push(MarkdownBlock::Source(source));
+            }
+        }
+        Err(info) => {
+            errors.push(BevyMarkdownError::Parsing { info });
+            blocks.push(MarkdownBlock::Source(text.to_string()));
+        }
+    }
+
+    MarkdownDocument {
+        blocks,
+        diagnostics: errors,
+    }
+}
+
+/// Parses a block that can appear at the root of the document or inside a blockquote.
+fn handle_block(
+    node: &markdown::mdast::Node,
+    text: &str,
+    markdown_theme: &BevyMarkdownTheme,
+    blocks: &mut Vec<MarkdownBlock>,
+    errors: &mut Vec<BevyMarkdownError>,
+) {
+    match node {
+        markdown::mdast::Node::Code(code) => {
+            if let Some(block) = handle_code(code, markdown_theme, errors) {
+                blocks.push(block);
+            }
+        }
+        markdown::mdast::Node::Heading(heading) => {
+            let mut content = Vec::new();
+            heading.children.iter().for_each(|child| {
+                handle_inline(child, text, &default(), None, &mut content, errors);
+            });
+            blocks.push(MarkdownBlock::Heading {
+                depth: heading.depth,
+                content,
+            });
+        }
+        markdown::mdast::Node::Paragraph(paragraph) => {
+            let mut content = Vec::new();
+            paragraph.children.iter().for_each(|child| match child {
+                markdown::mdast::Node::Break(_)
+                | markdown::mdast::Node::Text(_)
+                | markdown::mdast::Node::Strong(_)
+                | markdown::mdast::Node::Emphasis(_)
+                | markdown::mdast::Node::InlineCode(_)
+                | markdown::mdast::Node::Delete(_)
+                | markdown::mdast::Node::Image(_)
+                | markdown::mdast::Node::Link(_) => {
+                    handle_inline(child, text, &default(), None, &mut content, errors);
+                }
+                node => {
+                    if let Some(source) = handle_unsupported(node, text, errors) {
+                        content.push(MarkdownInline::Source(source));
+                    }
+                }
+            });
+            blocks.push(MarkdownBlock::Paragraph(content));
+        }
+        markdown::mdast::Node::List(list) => blocks.push(handle_list(list, text, errors)),
+        markdown::mdast::Node::Blockquote(blockquote) => {
+            let mut quoted = Vec::new();
+            blockquote.children.iter().for_each(|child| {
+                handle_block(child, text, markdown_theme, &mut quoted, errors);
+            });
+            blocks.push(MarkdownBlock::Blockquote(quoted));
+        }
+        markdown::mdast::Node::Table(table) => blocks.push(handle_table(table, text, errors)),
+        markdown::mdast::Node::ThematicBreak(_) => blocks.push(MarkdownBlock::Rule),
+        node => {
+            if let Some(source) = handle_unsupported(node, text, errors) {
+                blocks.push(MarkdownBlock::Source(source));
+            }
+        }
+    }
+}
+
+fn handle_inline(
+    node: &markdown::mdast::Node,
+    text: &str,
+    style: &SpanStyle,
+    link: Option<&str>,
+    content: &mut Vec<MarkdownInline>,
+    errors: &mut Vec<BevyMarkdownError>,
+) {
+    match node {
+        markdown::mdast::Node::Break(_) => content.push(MarkdownInline::Break),
+        markdown::mdast::Node::InlineCode(code) => content.push(MarkdownInline::Span(StyledSpan {
+            text: code.value.clone(),
+            style: SpanStyle {
+                code: true,
+                ..default()
+            },
+            link: link.map(|link| link.to_string()),
+        })),
+        markdown::mdast::Node::Emphasis(emphasis) => {
+            let style = SpanStyle {
+                italic: true,
+                ..style.clone()
+            };
+            emphasis.children.iter().for_each(|child| {
+                handle_inline(child, text, &style, link, content, errors);
+            });
+        }
+        markdown::mdast::Node::Strong(strong) => {
+            let style = SpanStyle {
+                weight: Some(FontWeight::Bold),
+                ..style.clone()
+            };
+            strong.children.iter().for_each(|child| {
+                handle_inline(child, text, &style, link, content, errors);
+            });
+        }
+        markdown::mdast::Node::Delete(delete) => {
+            let style = SpanStyle {
+                strikethrough: true,
+                ..style.clone()
+            };
+            delete.children.iter().for_each(|child| {
+                handle_inline(child, text, &style, link, content, errors);
+            });
+        }
+        markdown::mdast::Node::Text(text_node) => content.push(MarkdownInline::Span(StyledSpan {
+            text: text_node.value.clone(),
+            style: style.clone(),
+            link: link.map(|link| link.to_string()),
+        })),
+        markdown::mdast::Node::Image(image) => content.push(MarkdownInline::Image(MarkdownImage {
+            src: image.url.clone(),
+            alt: image.alt.clone(),
+        })),
+        markdown::mdast::Node::Link(link_node) => link_node.children.iter().for_each(|child| {
+            handle_inline(
+                child,
+                text,
+                style,
+                Some(link_node.url.as_str()),
+                content,
+                errors,
+            );
+        }),
+        node => {
+            if let Some(source) = handle_unsupported(node, text, errors) {
+                content.push(MarkdownInline::Source(source));
+            }
+        }
+    }
+}
+
+fn handle_code(
+    code: &markdown::mdast::Code,
+    markdown_theme: &BevyMarkdownTheme,
+    errors: &mut Vec<BevyMarkdownError>,
+) -> Option<MarkdownBlock> {
+    let ps = &*SYNTAX_SET;
+    let ts = &*THEME_SET;
+    let lang = code
+        .lang
+        .clone()
+        .unwrap_or_else(|| markdown_theme.code_default_lang.clone());
+    let syntax = ps
+        .find_syntax_by_name(lang.as_str())
+        .or_else(|| ps.find_syntax_by_extension(lang.as_str()))
+        .unwrap_or_else(|| {
+            errors.push(transform_error(
+                code.position.as_ref(),
+                format!("unknown code language \"{}\", shown as plain text", lang),
+            ));
+            ps.find_syntax_plain_text()
+        });
+    let code_theme = &markdown_theme.code_theme;
+    let theme = match ts.themes.get(code_theme) {
+        Some(theme) => theme,
+        None => {
+            errors.push(transform_error(
+                code.position.as_ref(),
+                format!(
+                    "unknown code theme \"{}\", using the default theme",
+                    code_theme
+                ),
+            ));
+            ts.themes.values().next()?
+        }
+    };
+    let mut h = HighlightLines::new(syntax, theme);
+    let mut spans = Vec::new();
+    for line in LinesWithEndings::from(code.value.as_str()) {
+        let ranges: Vec<(syntect::highlighting::Style, &str)> = match h.highlight_line(line, ps) {
+            Ok(ranges) => ranges,
+            Err(e) => {
+                errors.push(transform_error(code.position.as_ref(), e.to_string()));
+                spans.push(StyledSpan {
+                    text: line.to_string(),
+                    style: default(),
+                    link: None,
+                });
+                continue;
+            }
+        };
+
+        for &(style, text) in ranges.iter() {
+            let (weight, italic) = match style.font_style {
+                FontStyle::BOLD => (Some(FontWeight::Bold), false),
+                FontStyle::ITALIC => (None, true),
+                FontStyle::UNDERLINE => (Some(FontWeight::Bold), true),
+                _ => (Some(FontWeight::SemiBold), false),
+            };
+            let color = style.foreground;
+            spans.push(StyledSpan {
+                text: text.to_string(),
+                style: SpanStyle {
+                    weight,
+                    italic,
+                    color: Some(Color::rgb(color.r, color.g, color.b)),
+                    ..default()
+                },
+                link: None,
+            });
+        }
+    }
+    Some(MarkdownBlock::Code { lang, spans })
+}
+
+fn handle_list(
+    list: &markdown::mdast::List,
+    text: &str,
+    errors: &mut Vec<BevyMarkdownError>,
+) -> MarkdownBlock {
+    let mut items = Vec::new();
+    for node in list.children.iter() {
+        match node {
+            markdown::mdast::Node::ListItem(item) => {
+                let mut children = Vec::new();
+                for child in item.children.iter() {
+                    match child {
+                        markdown::mdast::Node::Paragraph(paragraph) => {
+                            let mut content = Vec::new();
+                            paragraph.children.iter().for_each(|child| {
+                                handle_inline(child, text, &default(), None, &mut content, errors);
+                            });
+                            children.push(MarkdownBlock::Paragraph(content));
+                        }
+                        markdown::mdast::Node::List(inner_list) => {
+                            children.push(handle_list(inner_list, text, errors));
+                        }
+                        node => {
+                            if let Some(source) = handle_unsupported(node, text, errors) {
+                                children.push(MarkdownBlock::Source(source));
+                            }
+                        }
+                    }
+                }
+                let checkbox = item
+                    .checked
+                    .and(item.position.as_ref())
+                    .and_then(|position| {
+                        let source = text.get(position.start.offset..position.end.offset)?;
+                        source.find('[').map(|i| position.start.offset + i)
+                    });
+                items.push(ListItem {
+                    checked: item.checked,
+                    checkbox,
+                    children,
+                });
+            }
+            node => {
+                if let Some(source) = handle_unsupported(node, text, errors) {
+                    items.push(ListItem {
+                        checked: None,
+                        checkbox: None,
+                        children: vec![MarkdownBlock::Source(source)],
+                    });
+                }
+            }
+        }
+    }
+    MarkdownBlock::List {
+        ordered: list.ordered,
+        start: list.start,
+        items,
+    }
+}
+
+fn handle_table(
+    table: &markdown::mdast::Table,
+    text: &str,
+    errors: &mut Vec<BevyMarkdownError>,
+) -> MarkdownBlock {
+    let mut rows = Vec::new();
+    for row in table.children.iter() {
+        match row {
+            markdown::mdast::Node::TableRow(row) => {
+                let mut cells = Vec::new();
+                for cell in row.children.iter() {
+                    let mut content = Vec::new();
+                    match cell {
+                        markdown::mdast::Node::TableCell(cell) => {
+                            cell.children.iter().for_each(|child| {
+                                handle_inline(child, text, &default(), None, &mut content, errors);
+                            });
+                        }
+                        cell => {
+                            if let Some(source) = handle_unsupported(cell, text, errors) {
+                                content.push(MarkdownInline::Source(source));
+                            }
+                        }
+                    }
+                    cells.push(content);
+                }
+                rows.push(cells);
+            }
+            row => {
+                let mut content = Vec::new();
+                if let Some(source) = handle_unsupported(row, text, errors) {
+                    content.push(MarkdownInline::Source(source));
+                }
+                rows.push(vec![content]);
+            }
+        }
+    }
+    let align = table
+        .align
+        .iter()
+        .map(|align| match align {
+            markdown::mdast::AlignKind::Left => TableAlign::Left,
+            markdown::mdast::AlignKind::Center => TableAlign::Center,
+            markdown::mdast::AlignKind::Right => TableAlign::Right,
+            markdown::mdast::AlignKind::None => TableAlign::None,
+        })
+        .collect();
+    MarkdownBlock::Table { align, rows }
+}
@@ -0,0 +1,499 @@
+//! cosmic-text backend, lays the document out as lines of attributed spans for
+//! `bevy_cosmic_edit`.
+
+use cosmic_text::{AttrsOwned, Family, Weight};
+
+use crate::ast::{
+    FontWeight, ListItem, MarkdownBlock, MarkdownDocument, MarkdownInline, StyledSpan, TableAlign,
+};
+use crate::{default, BevyMarkdownError, BevyMarkdownTheme, MarkdownImage, TextSpanMetadata};
+
+/// Number of characters in a horizontal rule.
+const RULE_WIDTH: usize = 16;
+const QUOTE_PREFIX: &str = "▎ ";
+const TABLE_COLUMN_SEPARATOR: &str = " │ ";
+const TABLE_HEADER_SEPARATOR: &str = "─┼─";
+/// Text of the line reserved for an image, the editor draws the image over it.
+const IMAGE_PLACEHOLDER: &str = " ";
+
+pub fn get_header_font_size(val: u8) -> f32 {
+    match val {
+        1 => 30.0,
+        2 => 27.0,
+        3 => 24.0,
+        4 => 21.0,
+        5 => 18.0,
+        6 => 15.0,
+        _ => 15.0,
+    }
+}
+
+pub fn get_bullet_for_indentation_level(level: u8) -> &'static str {
+    let level = level % 3;
+    if level == 0 {
+        " • "
+    } else if level == 1 {
+        " ◦ "
+    } else {
+        " ▪ "
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TextSpan {
+    pub text: String,
+    pub font_size: Option<f32>,
+    pub weigth: Option<Weight>,
+    pub style: Option<cosmic_text::Style>,
+    pub color: Option<cosmic_text::Color>,
+    pub family: Option<Family<'static>>,
+    pub metadata: Option<TextSpanMetadata>,
+    pub image: Option<MarkdownImage>,
+}
+
+fn line_break() -> TextSpan {
+    TextSpan {
+        text: "\n".to_string(),
+        ..default()
+    }
+}
+
+/// Number of characters shown for the spans.
+fn spans_width(text_spans: &[TextSpan]) -> usize {
+    text_spans.iter().flat_map(|span| span.text.chars()).count()
+}
+
+fn cosmic_weight(weight: FontWeight) -> Weight {
+    match weight {
+        FontWeight::SemiBold => Weight::SEMIBOLD,
+        FontWeight::Bold => Weight::BOLD,
+    }
+}
+
+/// Headings are bold and sized by their depth.
+fn handle_inline(
+    inline: &MarkdownInline,
+    markdown_theme: &BevyMarkdownTheme,
+    heading: Option<u8>,
+    text_spans: &mut Vec<TextSpan>,
+) {
+    match inline {
+        MarkdownInline::Span(span) => {
+            let mut text_span = TextSpan {
+                text: span.text.clone(),
+                font_size: heading.map(get_header_font_size),
+                metadata: span.link.as_ref().map(|link| TextSpanMetadata {
+                    link: Some(link.clone()),
+                    ..default()
+                }),
+                ..default()
+            };
+            if span.style.code {
+                text_span.color = Some(markdown_theme.inline_code);
+            } else {
+                if span.link.is_some() {
+                    text_span.color = Some(markdown_theme.link);
+                }
+                if heading.is_some() {
+                    text_span.weigth = Some(Weight::BOLD);
+                } else {
+                    text_span.weigth = span.style.weight.map(cosmic_weight);
+                }
+                if span.style.italic {
+                    text_span.style = Some(cosmic_text::Style::Italic);
+                }
+                if span.style.strikethrough {
+                    text_span
+                        .metadata
+                        .get_or_insert_with(TextSpanMetadata::default)
+                        .strikethrough = true;
+                }
+            }
+            text_spans.push(text_span);
+        }
+        MarkdownInline::Break => text_spans.push(line_break()),
+        MarkdownInline::Image(image) => {
+            // images take a line of their own
+            if text_spans
+                .last()
+                .map_or(false, |span| !span.text.ends_with('\n'))
+            {
+                text_spans.push(line_break());
+            }
+            text_spans.push(TextSpan {
+                text: IMAGE_PLACEHOLDER.to_string(),
+                image: Some(image.clone()),
+                ..default()
+            });
+            text_spans.push(line_break());
+        }
+        MarkdownInline::Source(source) => text_spans.push(TextSpan {
+            text: source.clone(),
+            ..default()
+        }),
+    }
+}
+
+fn handle_block(
+    block: &MarkdownBlock,
+    markdown_theme: &BevyMarkdownTheme,
+    text_spans: &mut Vec<TextSpan>,
+) {
+    match block {
+        MarkdownBlock::Heading { depth, content } => {
+            text_spans.push(line_break());
+            for inline in content.iter() {
+                handle_inline(inline, markdown_theme, Some(*depth), text_spans);
+            }
+            text_spans.push(line_break());
+        }
+        MarkdownBlock::Paragraph(content) => {
+            for inline in content.iter() {
+                handle_inline(inline, markdown_theme, None, text_spans);
+            }
+        }
+        MarkdownBlock::Code { spans, .. } => {
+            text_spans.push(TextSpan {
+                text: "\n\n".to_string(),
+                ..default()
+            });
+            for span in spans.iter() {
+                text_spans.push(code_span(span));
+            }
+            text_spans.push(line_break());
+        }
+        MarkdownBlock::List {
+            ordered,
+            start,
+            items,
+        } => handle_list(*ordered, *start, items, markdown_theme, text_spans, 0),
+        MarkdownBlock::Blockquote(blocks) => handle_blockquote(blocks, markdown_theme, text_spans),
+        MarkdownBlock::Table { align, rows } => {
+            handle_table(align, rows, markdown_theme, text_spans)
+        }
+        MarkdownBlock::Rule => {
+            text_spans.push(line_break());
+            text_spans.push(TextSpan {
+                text: "─".repeat(RULE_WIDTH),
+                color: Some(markdown_theme.blockquote),
+                ..default()
+            });
+            text_spans.push(line_break());
+        }
+        MarkdownBlock::Source(source) => text_spans.push(TextSpan {
+            text: source.clone(),
+            ..default()
+        }),
+    }
+}
+
+fn code_span(span: &StyledSpan) -> TextSpan {
+    TextSpan {
+        text: span.text.clone(),
+        weigth: span.style.weight.map(cosmic_weight),
+        style: span.style.italic.then_some(cosmic_text::Style::Italic),
+        color: span.style.color,
+        ..default()
+    }
+}
+
+fn handle_list(
+    ordered: bool,
+    start: Option<u32>,
+    items: &[ListItem],
+    markdown_theme: &BevyMarkdownTheme,
+    text_spans: &mut Vec<TextSpan>,
+    indentation_level: u8,
+) {
+    text_spans.push(line_break());
+
+    let mut list_index = start.unwrap_or(1);
+    for item in items.iter() {
+        for _ in 0..indentation_level {
+            text_spans.push(TextSpan {
+                text: "    ".to_string(),
+                ..default()
+            });
+        }
+
+        let indent_char = if ordered {
+            let index = list_index;
+            list_index += 1;
+            Some(format!(" {}. ", index))
+        } else if item.checked.is_none() {
+            Some(get_bullet_for_indentation_level(indentation_level).to_string())
+        } else {
+            // the checkbox replaces the bullet of task list items
+            None
+        };
+
+        if let Some(indent_char) = indent_char {
+            text_spans.push(TextSpan {
+                text: indent_char,
+                ..default()
+            });
+        }
+        if let Some(checked) = item.checked {
+            // the app toggles the marker when the checkbox is clicked
+            text_spans.push(TextSpan {
+                text: if checked { " ☑ " } else { " ☐ " }.to_string(),
+                metadata: item.checkbox.map(|offset| TextSpanMetadata {
+                    checkbox: Some(offset),
+                    ..default()
+                }),
+                ..default()
+            });
+        }
+
+        for child in item.children.iter() {
+            match child {
+                MarkdownBlock::List {
+                    ordered,
+                    start,
+                    items,
+                } => handle_list(
+                    *ordered,
+                    *start,
+                    items,
+                    markdown_theme,
+                    text_spans,
+                    indentation_level + 1,
+                ),
+                block => handle_block(block, markdown_theme, text_spans),
+            }
+        }
+
+        text_spans.push(line_break());
+    }
+}
+
+/// Quoted blocks are tinted and every line starts with a quote bar, nested quotes get one bar
+/// per level.
+fn handle_blockquote(
+    blocks: &[MarkdownBlock],
+    markdown_theme: &BevyMarkdownTheme,
+    text_spans: &mut Vec<TextSpan>,
+) {
+    let mut quote_spans: Vec<TextSpan> = Vec::new();
+    for block in blocks.iter() {
+        if let Some(last) = quote_spans.last() {
+            if !last.text.ends_with('\n') {
+                quote_spans.push(line_break());
+            }
+        }
+        handle_block(block, markdown_theme, &mut quote_spans);
+    }
+    // blocks start and end with line breaks, which would add empty quoted lines
+    while let Some(first) = quote_spans.first_mut() {
+        first.text = first.text.trim_start_matches('\n').to_string();
+        if !first.text.is_empty() {
+            break;
+        }
+        quote_spans.remove(0);
+    }
+    while let Some(last) = quote_spans.last_mut() {
+        last.text = last.text.trim_end_matches('\n').to_string();
+        if !last.text.is_empty() {
+            break;
+        }
+        quote_spans.pop();
+    }
+
+    let prefix = TextSpan {
+        text: QUOTE_PREFIX.to_string(),
+        color: Some(markdown_theme.blockquote),
+        ..default()
+    };
+    text_spans.push(line_break());
+    text_spans.push(prefix.clone());
+    for mut span in quote_spans {
+        if span.color.is_none() {
+            span.color = Some(markdown_theme.blockquote);
+        }
+        let text = span.text.clone();
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                text_spans.push(line_break());
+                text_spans.push(prefix.clone());
+            }
+            if !part.is_empty() {
+                text_spans.push(TextSpan {
+                    text: part.to_string(),
+                    ..span.clone()
+                });
+            }
+        }
+    }
+    text_spans.push(line_break());
+}
+
+/// Tables are laid out as a monospace grid, cells are padded to the widest cell of their
+/// column according to the column alignment.
+fn handle_table(
+    align: &[TableAlign],
+    rows: &[Vec<Vec<MarkdownInline>>],
+    markdown_theme: &BevyMarkdownTheme,
+    text_spans: &mut Vec<TextSpan>,
+) {
+    let rows: Vec<Vec<Vec<TextSpan>>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| {
+                    let mut cell_spans = Vec::new();
+                    for inline in cell.iter() {
+                        handle_inline(inline, markdown_theme, None, &mut cell_spans);
+                    }
+                    cell_spans
+                })
+                .collect()
+        })
+        .collect();
+
+    let columns = rows
+        .iter()
+        .map(|row| row.len())
+        .max()
+        .unwrap_or(0)
+        .max(align.len());
+    let mut widths = vec![0; columns];
+    for row in rows.iter() {
+        for (column, cell) in row.iter().enumerate() {
+            widths[column] = widths[column].max(spans_width(cell));
+        }
+    }
+
+    let monospace = |text: String| TextSpan {
+        text,
+        family: Some(Family::Monospace),
+        ..default()
+    };
+    text_spans.push(line_break());
+    for (row_index, row) in rows.into_iter().enumerate() {
+        let mut cells = row.into_iter();
+        for (column, width) in widths.iter().enumerate() {
+            if column > 0 {
+                text_spans.push(monospace(TABLE_COLUMN_SEPARATOR.to_string()));
+            }
+            let cell = cells.next().unwrap_or_default();
+            let padding = width - spans_width(&cell);
+            let (left, right) = match align.get(column) {
+                Some(TableAlign::Right) => (padding, 0),
+                Some(TableAlign::Center) => (padding / 2, padding - padding / 2),
+                _ => (0, padding),
+            };
+            if left > 0 {
+                text_spans.push(monospace(" ".repeat(left)));
+            }
+            for span in cell {
+                text_spans.push(TextSpan {
+                    family: Some(Family::Monospace),
+                    ..span
+                });
+            }
+            if right > 0 {
+                text_spans.push(monospace(" ".repeat(right)));
+            }
+        }
+        text_spans.push(line_break());
+        if row_index == 0 {
+            let separator: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+            text_spans.push(monospace(separator.join(TABLE_HEADER_SEPARATOR)));
+            text_spans.push(line_break());
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BevyMarkdownLines {
+    pub lines: Vec<Vec<(String, cosmic_text::AttrsOwned)>>,
+    /// Font size of every line relative to the editor font size, `2.0` for a line twice as
+    /// large as body text.
+    pub line_scales: Vec<f32>,
+    pub span_metadata: Vec<TextSpanMetadata>,
+    /// Images with the index of the line they replace.
+    pub images: Vec<(usize, MarkdownImage)>,
+    /// Parts of the text that couldn't be rendered as markdown.
+    pub diagnostics: Vec<BevyMarkdownError>,
+}
+
+/// Lays out a parsed document, spans take the color and font of `attrs` unless the markdown
+/// styles them. Heading line scales are relative to `font_size`, the font size of body text.
+pub fn generate_cosmic_lines(
+    document: MarkdownDocument,
+    markdown_theme: &BevyMarkdownTheme,
+    attrs: &AttrsOwned,
+    font_size: f32,
+) -> BevyMarkdownLines {
+    let mut text_spans = Vec::new();
+    for block in document.blocks.iter() {
+        handle_block(block, markdown_theme, &mut text_spans);
+    }
+
+    let mut spans_meta = vec![];
+    let mut lines: Vec<Vec<(String, AttrsOwned)>> = vec![vec![]];
+    let mut line_scales: Vec<f32> = vec![1.0];
+    let mut images = vec![];
+
+    for (i, span) in text_spans.iter().enumerate() {
+        let mut attrs = attrs.as_attrs();
+        if let Some(color) = span.color {
+            attrs = attrs.color(color)
+        }
+        if let Some(style) = span.style {
+            attrs = attrs.style(style)
+        }
+        if let Some(weight) = span.weigth {
+            attrs = attrs.weight(weight)
+        }
+        if let Some(family) = span.family {
+            attrs = attrs.family(family)
+        }
+        attrs = attrs.metadata(i);
+        let scale = span.font_size.map_or(1.0, |size| size / font_size);
+        if let Some(metadata) = span.metadata.clone() {
+            spans_meta.push(metadata);
+        } else {
+            spans_meta.push(TextSpanMetadata::default());
+        };
+
+        let mut temp = String::new();
+
+        for ch in span.text.chars() {
+            if ch == '\n' {
+                if !temp.is_empty() {
+                    lines
+                        .last_mut()
+                        .unwrap()
+                        .push((temp.clone(), AttrsOwned::new(attrs)));
+                    let line_scale = line_scales.last_mut().unwrap();
+                    *line_scale = line_scale.max(scale);
+                    temp.clear();
+                }
+                lines.push(Vec::new());
+                line_scales.push(1.0);
+            } else {
+                temp.push(ch);
+            }
+        }
+
+        if !temp.is_empty() {
+            if let Some(image) = span.image.clone() {
+                images.push((lines.len() - 1, image));
+            }
+            lines
+                .last_mut()
+                .unwrap()
+                .push((temp, AttrsOwned::new(attrs)));
+            let line_scale = line_scales.last_mut().unwrap();
+            *line_scale = line_scale.max(scale);
+        }
+    }
+    BevyMarkdownLines {
+        lines,
+        line_scales,
+        span_metadata: spans_meta,
+        images,
+        diagnostics: document.diagnostics,
+    }
+}
@@ -0,0 +1,367 @@
+//! HTML backend, code keeps its highlighting colors as inline styles.
+
+use std::fmt::Write;
+
+use crate::ast::{
+    FontWeight, ListItem, MarkdownBlock, MarkdownDocument, MarkdownInline, StyledSpan, TableAlign,
+};
+
+fn escape(text: &str, html: &mut String) {
+    for ch in text.chars() {
+        match ch {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            ch => html.push(ch),
+        }
+    }
+}
+
+/// Only web and mail links and relative URLs are written, other schemes like `javascript:` could
+/// run in the page the fragment is shown in.
+fn is_safe_url(url: &str) -> bool {
+    // browsers skip these before reading the scheme
+    let url: String = url
+        .trim_start_matches(|ch: char| ch <= ' ')
+        .chars()
+        .filter(|ch| !matches!(ch, '\t' | '\n' | '\r'))
+        .collect();
+    match url.find(|ch| matches!(ch, ':' | '/' | '?' | '#')) {
+        Some(i) if url[i..].starts_with(':') => {
+            let scheme = url[..i].to_lowercase();
+            scheme == "http" || scheme == "https" || scheme == "mailto"
+        }
+        _ => true,
+    }
+}
+
+fn handle_span(span: &StyledSpan, html: &mut String) {
+    let mut tags = vec![];
+    if span.style.code {
+        tags.push("code");
+    }
+    if span.style.weight.is_some() {
+        tags.push("strong");
+    }
+    if span.style.italic {
+        tags.push("em");
+    }
+    if span.style.strikethrough {
+        tags.push("del");
+    }
+    for tag in tags.iter() {
+        let _ = write!(html, "<{}>", tag);
+    }
+    escape(&span.text, html);
+    for tag in tags.iter().rev() {
+        let _ = write!(html, "</{}>", tag);
+    }
+}
+
+fn link_of(inline: &MarkdownInline) -> Option<&str> {
+    match inline {
+        MarkdownInline::Span(span) => span.link.as_deref(),
+        _ => None,
+    }
+}
+
+fn handle_inline(inline: &MarkdownInline, html: &mut String) {
+    match inline {
+        MarkdownInline::Span(span) => handle_span(span, html),
+        MarkdownInline::Break => html.push_str("<br>\n"),
+        // images with other sources are shown as their alt text
+        MarkdownInline::Image(image) if is_safe_url(&image.src) => {
+            html.push_str("<img src=\"");
+            escape(&image.src, html);
+            html.push_str("\" alt=\"");
+            escape(&image.alt, html);
+            html.push_str("\">");
+        }
+        MarkdownInline::Image(image) => escape(&image.alt, html),
+        MarkdownInline::Source(source) => escape(source, html),
+    }
+}
+
+/// Writes the inlines, the styled spans of a link share a single `<a>`. Links with other URLs
+/// than [`is_safe_url`] allows are written as text.
+fn handle_inlines(content: &[MarkdownInline], html: &mut String) {
+    let mut i = 0;
+    while i < content.len() {
+        let link = link_of(&content[i]);
+        let end = i + content[i..]
+            .iter()
+            .take_while(|inline| link.is_some() && link_of(inline) == link)
+            .count()
+            .max(1);
+        match link.filter(|link| is_safe_url(link)) {
+            Some(link) => {
+                html.push_str("<a href=\"");
+                escape(link, html);
+                html.push_str("\">");
+                content[i..end]
+                    .iter()
+                    .for_each(|inline| handle_inline(inline, html));
+                html.push_str("</a>");
+            }
+            None => content[i..end]
+                .iter()
+                .for_each(|inline| handle_inline(inline, html)),
+        }
+        i = end;
+    }
+}
+
+fn handle_code_span(span: &StyledSpan, html: &mut String) {
+    let mut styles = vec![];
+    if let Some(color) = span.style.color {
+        styles.push(format!(
+            "color: #{:02x}{:02x}{:02x}",
+            color.r(),
+            color.g(),
+            color.b()
+        ));
+    }
+    match span.style.weight {
+        Some(FontWeight::SemiBold) => styles.push("font-weight: 600".to_string()),
+        Some(FontWeight::Bold) => styles.push("font-weight: bold".to_string()),
+        None => {}
+    }
+    if span.style.italic {
+        styles.push("font-style: italic".to_string());
+    }
+    if styles.is_empty() {
+        escape(&span.text, html);
+    } else {
+        let _ = write!(html, "<span style=\"{}\">", styles.join("; "));
+        escape(&span.text, html);
+        html.push_str("</span>");
+    }
+}
+
+fn handle_list_item(item: &ListItem, html: &mut String) {
+    html.push_str("<li>");
+    match item.checked {
+        Some(true) => html.push_str("<input type=\"checkbox\" disabled checked> "),
+        Some(false) => html.push_str("<input type=\"checkbox\" disabled> "),
+        None => {}
+    }
+    for child in item.children.iter() {
+        match child {
+            // list items are tight, their text isn't wrapped in paragraphs
+            MarkdownBlock::Paragraph(content) => handle_inlines(content, html),
+            block => handle_block(block, html),
+        }
+    }
+    html.push_str("</li>\n");
+}
+
+fn table_cell(
+    tag: &str,
+    align: Option<&TableAlign>,
+    content: &[MarkdownInline],
+    html: &mut String,
+) {
+    match align {
+        Some(TableAlign::Left) => {
+            let _ = write!(html, "<{} style=\"text-align: left\">", tag);
+        }
+        Some(TableAlign::Center) => {
+            let _ = write!(html, "<{} style=\"text-align: center\">", tag);
+        }
+        Some(TableAlign::Right) => {
+            let _ = write!(html, "<{} style=\"text-align: right\">", tag);
+        }
+        _ => {
+            let _ = write!(html, "<{}>", tag);
+        }
+    }
+    handle_inlines(content, html);
+    let _ = write!(html, "</{}>", tag);
+}
+
+fn handle_block(block: &MarkdownBlock, html: &mut String) {
+    match block {
+        MarkdownBlock::Heading { depth, content } => {
+            let _ = write!(html, "<h{}>", depth);
+            handle_inlines(content, html);
+            let _ = writeln!(html, "</h{}>", depth);
+        }
+        MarkdownBlock::Paragraph(content) => {
+            html.push_str("<p>");
+            handle_inlines(content, html);
+            html.push_str("</p>\n");
+        }
+        MarkdownBlock::Code { lang, spans } => {
+            html.push_str("<pre><code class=\"language-");
+            escape(lang, html);
+            html.push_str("\">");
+            spans.iter().for_each(|span| handle_code_span(span, html));
+            html.push_str("</code></pre>\n");
+        }
+        MarkdownBlock::List {
+            ordered,
+            start,
+            items,
+        } => {
+            let tag = if *ordered { "ol" } else { "ul" };
+            match start {
+                Some(start) if *ordered && *start != 1 => {
+                    let _ = writeln!(html, "<ol start=\"{}\">", start);
+                }
+                _ => {
+                    let _ = writeln!(html, "<{}>", tag);
+                }
+            }
+            items.iter().for_each(|item| handle_list_item(item, html));
+            let _ = writeln!(html, "</{}>", tag);
+        }
+        MarkdownBlock::Blockquote(blocks) => {
+            html.push_str("<blockquote>\n");
+            blocks.iter().for_each(|block| handle_block(block, html));
+            html.push_str("</blockquote>\n");
+        }
+        MarkdownBlock::Table { align, rows } => {
+            html.push_str("<table>\n");
+            for (row_index, row) in rows.iter().enumerate() {
+                if row_index == 0 {
+                    html.push_str("<thead>\n");
+                } else if row_index == 1 {
+                    html.push_str("<tbody>\n");
+                }
+                let tag = if row_index == 0 { "th" } else { "td" };
+                html.push_str("<tr>");
+                for (column, cell) in row.iter().enumerate() {
+                    table_cell(tag, align.get(column), cell, html);
+                }
+                html.push_str("</tr>\n");
+                if row_index == 0 {
+                    html.push_str("</thead>\n");
+                }
+            }
+            if rows.len() > 1 {
+                html.push_str("</tbody>\n");
+            }
+            html.push_str("</table>\n");
+        }
+        MarkdownBlock::Rule => html.push_str("<hr>\n"),
+        MarkdownBlock::Source(source) => {
+            html.push_str("<p>");
+            escape(source, html);
+            html.push_str("</p>\n");
+        }
+    }
+}
+
+/// Writes the document as an HTML fragment, syntax that isn't supported is escaped and shown as
+/// text.
+pub fn generate_html(document: &MarkdownDocument) -> String {
+    let mut html = String::new();
+    document
+        .blocks
+        .iter()
+        .for_each(|block| handle_block(block, &mut html));
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_theme;
+    use crate::*;
+
+    fn test_html(input: &str, test_name: &str) {
+        let html = generate_html(&parse_markdown(input, &test_theme()));
+        insta::assert_snapshot!(test_name, html);
+    }
+
+    #[test]
+    pub fn test_html_text_style() {
+        let input = "# Title
+
+Some **bold**, *italic* and ~~deleted~~ text with `code` and a [link](https://example.com).
+";
+        test_html(input, "test_html_text_style")
+    }
+
+    #[test]
+    pub fn test_html_lists() {
+        let input = "1. first
+2. second
+   - nested
+
+- [x] done
+- [ ] todo
+";
+        test_html(input, "test_html_lists")
+    }
+
+    #[test]
+    pub fn test_html_breaks() {
+        let input = "- a  
+  b
+- *c  
+  d*
+";
+        let document = parse_markdown(input, &test_theme());
+        assert!(
+            document.diagnostics.is_empty(),
+            "{:?}",
+            document.diagnostics
+        );
+        insta::assert_snapshot!("test_html_breaks", generate_html(&document));
+    }
+
+    #[test]
+    pub fn test_html_quote_and_code() {
+        let input = "> quoted *text*
+
+```txt
+let x = 1;
+```
+
+---
+";
+        test_html(input, "test_html_quote_and_code")
+    }
+
+    #[test]
+    pub fn test_html_table_and_image() {
+        let input = "| Name | Qty |
+| :-- | --: |
+| apple | 3 |
+
+![logo](images/logo.png) & <b>
+";
+        test_html(input, "test_html_table_and_image")
+    }
+
+    #[test]
+    pub fn test_html_links() {
+        let input = "[a **b** c](https://example.com) and [mail](mailto:me@example.com)
+
+[x](javascript:alert(1)) [y](JaVaScRiPt:alert(1)) ![pixel](data:image/png;base64,AAAA)
+";
+        let html = generate_html(&parse_markdown(input, &test_theme()));
+        assert!(html.contains("<a href=\"https://example.com\">a <strong>b</strong> c</a> and "));
+        assert!(html.contains("<a href=\"mailto:me@example.com\">mail</a>"));
+        assert_eq!(html.matches("<a ").count(), 2);
+        assert!(!html.to_lowercase().contains("javascript"));
+        assert!(!html.contains("data:"));
+        assert!(html.contains("pixel"));
+    }
+
+    #[test]
+    pub fn test_safe_url() {
+        use super::is_safe_url;
+        assert!(is_safe_url("https://example.com/a:b"));
+        assert!(is_safe_url("HTTP://example.com"));
+        assert!(is_safe_url("mailto:me@example.com"));
+        assert!(is_safe_url("images/logo.png"));
+        assert!(is_safe_url("../page.html#top:1"));
+        assert!(is_safe_url("//example.com/logo.png"));
+        assert!(!is_safe_url("javascript:alert(1)"));
+        assert!(!is_safe_url(" java\tscript:alert(1)"));
+        assert!(!is_safe_url("data:text/html,<b>"));
+        assert!(!is_safe_url("image:1234"));
+    }
+}
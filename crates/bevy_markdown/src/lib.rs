@@ -1,21 +1,58 @@
-use std::vec;
-
-use cosmic_text::{AttrsOwned, Color, Weight};
-use syntect::easy::HighlightLines;
-use syntect::highlighting::{FontStyle, ThemeSet};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use cosmic_text::AttrsOwned;
+use once_cell::sync::Lazy;
+use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
-use syntect::util::LinesWithEndings;
 
+mod ast;
+mod cosmic;
+mod html;
+
+pub use ast::*;
+pub use cosmic::*;
+pub use html::*;
+
+#[derive(Clone, Hash, PartialEq)]
 pub struct BevyMarkdownTheme {
     pub code_theme: String,
     pub code_default_lang: String,
     pub link: cosmic_text::Color,
     pub inline_code: cosmic_text::Color,
+    /// Color of blockquote text, quote bars and horizontal rules.
+    pub blockquote: cosmic_text::Color,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct TextSpanMetadata {
     pub link: Option<String>,
+    /// Byte offset of the `[ ]` marker of a task list item in the markdown text.
+    pub checkbox: Option<usize>,
+    /// cosmic-text has no strikethrough attribute, the line is drawn by the editor.
+    pub strikethrough: bool,
+}
+
+/// Checks or unchecks the task list item whose marker starts at `offset`, returns `false` if
+/// there is no marker there.
+pub fn toggle_task_checkbox(text: &mut String, offset: usize) -> bool {
+    let marker = match text.get(offset..offset + 3) {
+        Some("[ ]") => "[x]",
+        Some("[x]") | Some("[X]") => "[ ]",
+        _ => return false,
+    };
+    text.replace_range(offset..offset + 3, marker);
+    true
+}
+
+/// Image referenced with `![alt](src)`, the source is a URL, a file path or an id understood by
+/// the app.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkdownImage {
+    pub src: String,
+    pub alt: String,
 }
 
 #[inline]
@@ -23,500 +60,195 @@ pub fn default<T: Default>() -> T {
     std::default::Default::default()
 }
 
-#[derive(Default)]
-pub struct TextSpan {
-    pub text: String,
-    pub font_size: Option<f32>,
-    pub weigth: Option<Weight>,
-    pub style: Option<cosmic_text::Style>,
-    pub color: Option<cosmic_text::Color>,
-    pub metadata: Option<TextSpanMetadata>,
-}
-
+#[derive(Clone, PartialEq)]
 pub struct BevyMarkdown {
     pub markdown_theme: BevyMarkdownTheme,
     pub text: String,
     pub attrs: AttrsOwned,
+    /// Font size of body text in the editor, heading line scales are relative to it.
+    pub font_size: f32,
 }
 
-#[repr(u8)]
-#[derive(Clone)]
-enum InlineStyleType {
-    Strong = 0x01,
-    Emphasis = 0x02,
-    StrongEmphasis = 0x03,
-    // StrikeThrough = 0x04,
-    // StrikeBold = 0x05,
-    // StrikeItalic = 0x06,
-    // StrikeBoldItalic = 0x07,
-    None = 0x00,
-}
-
-pub fn get_header_font_size(val: u8) -> f32 {
-    match val {
-        1 => 30.0,
-        2 => 27.0,
-        3 => 24.0,
-        4 => 21.0,
-        5 => 18.0,
-        6 => 15.0,
-        _ => 15.0,
+impl Hash for BevyMarkdown {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.markdown_theme.hash(state);
+        self.text.hash(state);
+        self.attrs.hash(state);
+        self.font_size.to_bits().hash(state);
     }
 }
 
-impl InlineStyleType {
-    #[inline]
-    pub fn from_u8(style_code: u8) -> Self {
-        match style_code {
-            0x01 => InlineStyleType::Strong,
-            0x02 => InlineStyleType::Emphasis,
-            0x03 => InlineStyleType::StrongEmphasis,
-            // 0x04 => InlineStyleType::StrikeThrough,
-            // 0x05 => InlineStyleType::StrikeBold,
-            // 0x06 => InlineStyleType::StrikeItalic,
-            // 0x07 => InlineStyleType::StrikeBoldItalic,
-            _ => InlineStyleType::None,
-        }
-    }
+/// Loading the bundled syntaxes and themes takes longer than rendering most texts, they are
+/// loaded on first use and shared by every render.
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+/// Returns names of the syntax highlighting themes bundled with syntect,
+/// these are the valid values for `BevyMarkdownTheme::code_theme`.
+pub fn get_code_theme_names() -> Vec<String> {
+    THEME_SET.themes.keys().cloned().collect()
 }
 
-#[derive(Debug)]
+/// Problems found while rendering, the affected part of the text is still shown.
+#[derive(Clone, Debug, PartialEq)]
 pub enum BevyMarkdownError {
     Transform { info: String },
     Parsing { info: String },
 }
 
-pub fn get_bullet_for_indentation_level(level: u8) -> &'static str {
-    let level = level % 3;
-    if level == 0 {
-        " • "
-    } else if level == 1 {
-        " ◦ "
-    } else {
-        " ▪ "
+impl std::fmt::Display for BevyMarkdownError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BevyMarkdownError::Transform { info } => write!(f, "{}", info),
+            BevyMarkdownError::Parsing { info } => write!(f, "failed to parse markdown: {}", info),
+        }
     }
 }
 
-pub fn handle_block_styling(
-    node: &markdown::mdast::Node,
-    bevy_markdown: &BevyMarkdown,
-    text_spans: &mut Vec<TextSpan>,
-    errors: &mut Vec<BevyMarkdownError>,
-) -> Result<(), Vec<BevyMarkdownError>> {
-    match node {
-        markdown::mdast::Node::Heading(header) => {
-            text_spans.push(TextSpan {
-                text: "\n".to_string(),
-                ..default()
-            });
-            header.children.iter().for_each(|child| {
-                let _ = handle_inline_styling(
-                    child,
-                    bevy_markdown,
-                    text_spans,
-                    errors,
-                    InlineStyleType::Strong as u8,
-                    None,
-                    Some(get_header_font_size(header.depth)),
-                    &None,
-                );
-            });
-            text_spans.push(TextSpan {
-                text: "\n".to_string(),
-                ..default()
-            });
-        }
-        markdown::mdast::Node::Paragraph(paragraph) => {
-            paragraph.children.iter().for_each(|child| match child {
-                markdown::mdast::Node::Break(_) => {
-                    text_spans.push(TextSpan {
-                        text: "\n".to_string(),
-                        ..default()
-                    });
-                }
-                markdown::mdast::Node::Text(text) => {
-                    text_spans.push(TextSpan {
-                        text: text.value.to_string(),
-                        ..default()
-                    });
-                }
-                markdown::mdast::Node::Strong(_)
-                | markdown::mdast::Node::Emphasis(_)
-                | markdown::mdast::Node::InlineCode(_)
-                | markdown::mdast::Node::Delete(_)
-                | markdown::mdast::Node::Link(_) => {
-                    let _ = handle_inline_styling(
-                        child,
-                        bevy_markdown,
-                        text_spans,
-                        errors,
-                        InlineStyleType::None as u8,
-                        None,
-                        None,
-                        &None,
-                    );
-                }
-                node => errors.push(BevyMarkdownError::Transform {
-                    info: format!("{:?} node is not implemented for paragraph", node),
-                }),
-            });
-        }
-        _ => errors.push(BevyMarkdownError::Transform {
-            info: "nesting is not implemented".to_string(),
-        }),
+/// Renders the text, never fails: parts that can't be rendered are shown as their markdown
+/// source and reported in `BevyMarkdownLines::diagnostics`.
+pub fn generate_markdown_lines(bevy_markdown: BevyMarkdown) -> BevyMarkdownLines {
+    let document = parse_markdown(&bevy_markdown.text, &bevy_markdown.markdown_theme);
+    generate_cosmic_lines(
+        document,
+        &bevy_markdown.markdown_theme,
+        &bevy_markdown.attrs,
+        bevy_markdown.font_size,
+    )
+}
+
+struct CacheEntry {
+    /// Compared on lookup, texts with the same hash are rendered again.
+    source: BevyMarkdown,
+    lines: BevyMarkdownLines,
+    last_used: u64,
+}
+
+/// Rendered texts by hash of the text, theme and attributes, shared between threads rendering
+/// in the background.
+pub struct MarkdownCache {
+    lines: HashMap<u64, CacheEntry>,
+    capacity: usize,
+    /// Incremented on every lookup and insert, orders entries by last use.
+    clock: u64,
+}
+
+impl Default for MarkdownCache {
+    fn default() -> Self {
+        Self::with_capacity(1024)
     }
-    Ok(())
 }
 
-pub fn handle_inline_styling(
-    node: &markdown::mdast::Node,
-    bevy_markdown: &BevyMarkdown,
-    text_spans: &mut Vec<TextSpan>,
-    errors: &mut Vec<BevyMarkdownError>,
-    applied_style: u8,
-    force_color: Option<Color>,
-    force_size: Option<f32>,
-    force_data: &Option<String>,
-) -> Result<(), Vec<BevyMarkdownError>> {
-    match node {
-        markdown::mdast::Node::InlineCode(code) => {
-            let mut text_span = TextSpan {
-                text: code.value.clone(),
-                color: Some(bevy_markdown.markdown_theme.inline_code),
-                font_size: force_size,
-                ..default()
-            };
-            if let Some(link) = force_data {
-                text_span.metadata = Some(TextSpanMetadata {
-                    link: Some(link.clone()),
-                })
-            }
-            text_spans.push(text_span);
+impl MarkdownCache {
+    /// The least recently used text is dropped when a text is added to a cache holding
+    /// `capacity` texts.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            lines: HashMap::new(),
+            capacity,
+            clock: 0,
         }
-        markdown::mdast::Node::Emphasis(emphasis) => emphasis.children.iter().for_each(|child| {
-            let _ = handle_inline_styling(
-                child,
-                bevy_markdown,
-                text_spans,
-                errors,
-                applied_style | InlineStyleType::Emphasis as u8,
-                force_color,
-                force_size,
-                force_data,
-            );
-        }),
-        markdown::mdast::Node::Strong(strong) => strong.children.iter().for_each(|child| {
-            let _ = handle_inline_styling(
-                child,
-                bevy_markdown,
-                text_spans,
-                errors,
-                applied_style | InlineStyleType::Strong as u8,
-                force_color,
-                force_size,
-                force_data,
-            );
-        }),
-        markdown::mdast::Node::Text(text) => {
-            let mut text_span = TextSpan {
-                text: text.value.clone(),
-                font_size: force_size,
-                ..default()
-            };
-            if let Some(color) = force_color {
-                text_span.color = Some(color)
-            }
-            if let Some(link) = force_data {
-                text_span.metadata = Some(TextSpanMetadata {
-                    link: Some(link.clone()),
-                })
-            }
-            match InlineStyleType::from_u8(applied_style) {
-                InlineStyleType::Strong => {
-                    text_span.weigth = Some(Weight::BOLD);
-                }
-                InlineStyleType::Emphasis => {
-                    text_span.style = Some(cosmic_text::Style::Italic);
-                }
-                InlineStyleType::StrongEmphasis => {
-                    text_span.weigth = Some(Weight::BOLD);
-                    text_span.style = Some(cosmic_text::Style::Italic);
-                }
-                _ => {}
+    }
+
+    pub fn key(bevy_markdown: &BevyMarkdown) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        bevy_markdown.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn get(&mut self, bevy_markdown: &BevyMarkdown) -> Option<&BevyMarkdownLines> {
+        self.clock += 1;
+        let entry = self
+            .lines
+            .get_mut(&Self::key(bevy_markdown))
+            .filter(|entry| entry.source == *bevy_markdown)?;
+        entry.last_used = self.clock;
+        Some(&entry.lines)
+    }
+
+    pub fn insert(&mut self, bevy_markdown: BevyMarkdown, lines: BevyMarkdownLines) {
+        self.clock += 1;
+        let key = Self::key(&bevy_markdown);
+        if self.lines.len() >= self.capacity && !self.lines.contains_key(&key) {
+            let oldest = self
+                .lines
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                self.lines.remove(&oldest);
             }
-            text_spans.push(text_span);
-        }
-        markdown::mdast::Node::Link(link) => link.children.iter().for_each(|child| {
-            let _ = handle_inline_styling(
-                child,
-                bevy_markdown,
-                text_spans,
-                errors,
-                applied_style,
-                Some(bevy_markdown.markdown_theme.link),
-                force_size,
-                &Some(link.url.clone()),
-            );
-        }),
-        _ => {
-            errors.push(BevyMarkdownError::Transform {
-                info: "nesting is not implemented".to_string(),
-            });
         }
+        self.lines.insert(
+            key,
+            CacheEntry {
+                source: bevy_markdown,
+                lines,
+                last_used: self.clock,
+            },
+        );
     }
-    Ok(())
-}
 
-fn handle_list_recursive(
-    list: &markdown::mdast::List,
-    bevy_markdown: &BevyMarkdown,
-    text_spans: &mut Vec<TextSpan>,
-    errors: &mut Vec<BevyMarkdownError>,
-    indentation_level: u8,
-) -> Result<(), Vec<BevyMarkdownError>> {
-    text_spans.push(TextSpan {
-        text: "\n".to_string(),
-        ..default()
-    });
-
-    let mut list_index = list.start;
-    list.children
-        .clone()
-        .into_iter()
-        .for_each(|node| match node {
-            markdown::mdast::Node::ListItem(item) => {
-                for _ in 0..indentation_level {
-                    text_spans.push(TextSpan {
-                        text: "    ".to_string(),
-                        ..default()
-                    });
-                }
-
-                let indent_char = if list.ordered {
-                    let index = list_index.unwrap();
-                    list_index = Some(index + 1);
-                    format!(" {}. ", index)
-                } else {
-                    get_bullet_for_indentation_level(indentation_level).to_string()
-                };
-
-                text_spans.push(TextSpan {
-                    text: indent_char,
-                    ..default()
-                });
-
-                item.children.into_iter().for_each(|child| match child {
-                    markdown::mdast::Node::Paragraph(paragraph) => {
-                        paragraph.children.iter().for_each(|child| {
-                            let _ = handle_inline_styling(
-                                child,
-                                bevy_markdown,
-                                text_spans,
-                                errors,
-                                InlineStyleType::None as u8,
-                                None,
-                                None,
-                                &None,
-                            );
-                        })
-                    }
-                    markdown::mdast::Node::List(inner_list) => {
-                        let _ = handle_list_recursive(
-                            &inner_list,
-                            bevy_markdown,
-                            text_spans,
-                            errors,
-                            indentation_level + 1,
-                        );
-                    }
-                    node => errors.push(BevyMarkdownError::Transform {
-                        info: format!("{:?} node is not implemented for list item", node),
-                    }),
-                });
-
-                text_spans.push(TextSpan {
-                    text: "\n".to_string(),
-                    ..default()
-                });
-            }
-            _ => {
-                errors.push(BevyMarkdownError::Transform {
-                    info: "invalid list children".to_string(),
-                });
-            }
-        });
-    Ok(())
-}
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
 
-#[derive(Debug)]
-pub struct BevyMarkdownLines {
-    pub lines: Vec<Vec<(String, cosmic_text::AttrsOwned)>>,
-    pub span_metadata: Vec<TextSpanMetadata>,
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
 }
 
-pub fn generate_markdown_lines(
+/// Same as `generate_markdown_lines`, a text rendered before with the same theme and attributes
+/// is taken from the cache. The cache is not locked while rendering.
+pub fn generate_markdown_lines_cached(
     bevy_markdown: BevyMarkdown,
-) -> Result<BevyMarkdownLines, Vec<BevyMarkdownError>> {
-    let node = markdown::to_mdast(bevy_markdown.text.as_str(), &markdown::ParseOptions::gfm());
-    let ps = SyntaxSet::load_defaults_newlines();
-    let ts = ThemeSet::load_defaults();
-
-    match node {
-        Ok(node) => {
-            let mut text_spans = Vec::new();
-            let mut errors = Vec::new();
-            match node {
-                markdown::mdast::Node::Root(root) => {
-                    root.children.iter().for_each(|child| match child {
-                        markdown::mdast::Node::Code(code) => {
-                            let default_lang =
-                                bevy_markdown.markdown_theme.code_default_lang.clone();
-                            let lang = code.lang.as_ref().unwrap_or(&default_lang);
-                            let syntax = vec![
-                                ps.find_syntax_by_name(lang.as_str()),
-                                ps.find_syntax_by_extension(lang.as_str()),
-                            ]
-                            .iter()
-                            .find(|&o| o.is_some())
-                            .unwrap()
-                            .unwrap();
-                            let mut h = HighlightLines::new(
-                                syntax,
-                                &ts.themes[&bevy_markdown.markdown_theme.code_theme.clone()],
-                            );
-                            text_spans.push(TextSpan {
-                                text: "\n\n".to_string(),
-                                ..default()
-                            });
-                            for line in LinesWithEndings::from(code.value.as_str()) {
-                                let ranges: Vec<(syntect::highlighting::Style, &str)> =
-                                    h.highlight_line(line, &ps).unwrap();
-
-                                for &(style, text) in ranges.iter() {
-                                    let mut text_span = TextSpan {
-                                        text: text.to_string(),
-                                        ..default()
-                                    };
-                                    match style.font_style {
-                                        FontStyle::BOLD => text_span.weigth = Some(Weight::BOLD),
-                                        FontStyle::ITALIC => {
-                                            text_span.style = Some(cosmic_text::Style::Italic)
-                                        }
-                                        FontStyle::UNDERLINE => {
-                                            text_span.weigth = Some(Weight::BOLD);
-                                            text_span.style = Some(cosmic_text::Style::Italic);
-                                        }
-                                        _ => text_span.weigth = Some(Weight::SEMIBOLD),
-                                    };
-                                    let color = style.foreground;
-                                    text_span.color =
-                                        Some(cosmic_text::Color::rgb(color.r, color.g, color.b));
-                                    text_spans.push(text_span);
-                                }
-                            }
-                            text_spans.push(TextSpan {
-                                text: "\n".to_string(),
-                                ..default()
-                            });
-                        }
-                        markdown::mdast::Node::Heading(_) | markdown::mdast::Node::Paragraph(_) => {
-                            let _ = handle_block_styling(
-                                child,
-                                &bevy_markdown,
-                                &mut text_spans,
-                                &mut errors,
-                            );
-                        }
-                        markdown::mdast::Node::List(list) => {
-                            let _ = handle_list_recursive(
-                                list,
-                                &bevy_markdown,
-                                &mut text_spans,
-                                &mut errors,
-                                0,
-                            );
-                        }
-                        node => errors.push(BevyMarkdownError::Transform {
-                            info: format!("{:?} node is not implemented for root", node),
-                        }),
-                    });
-                }
-                node => errors.push(BevyMarkdownError::Transform {
-                    info: format!("unexpected node: {:?}", node),
-                }),
-            }
-            if !errors.is_empty() {
-                Err(errors)
-            } else {
-                let mut spans_meta = vec![];
-                let mut lines: Vec<Vec<(String, AttrsOwned)>> = vec![vec![]];
-
-                for (i, span) in text_spans.iter().enumerate() {
-                    let mut attrs = bevy_markdown.attrs.as_attrs();
-                    // if cosmic-text implements attrs.size add it here
-                    if let Some(color) = span.color {
-                        attrs = attrs.color(color)
-                    }
-                    if let Some(style) = span.style {
-                        attrs = attrs.style(style)
-                    }
-                    if let Some(weight) = span.weigth {
-                        attrs = attrs.weight(weight)
-                    }
-                    attrs = attrs.metadata(i);
-                    if let Some(metadata) = span.metadata.clone() {
-                        spans_meta.push(metadata);
-                    } else {
-                        spans_meta.push(TextSpanMetadata { link: None });
-                    };
-
-                    let mut temp = String::new();
-
-                    for ch in span.text.chars() {
-                        if ch == '\n' {
-                            if !temp.is_empty() {
-                                lines
-                                    .last_mut()
-                                    .unwrap()
-                                    .push((temp.clone(), AttrsOwned::new(attrs)));
-                                temp.clear();
-                            }
-                            lines.push(Vec::new());
-                        } else {
-                            temp.push(ch);
-                        }
-                    }
-
-                    if !temp.is_empty() {
-                        lines
-                            .last_mut()
-                            .unwrap()
-                            .push((temp, AttrsOwned::new(attrs)));
-                    }
-                }
-                Ok(BevyMarkdownLines {
-                    lines,
-                    span_metadata: spans_meta,
-                })
-            }
-        }
-        Err(e) => Err(vec![BevyMarkdownError::Parsing { info: e }]),
+    cache: &Mutex<MarkdownCache>,
+) -> BevyMarkdownLines {
+    if let Some(lines) = cache
+        .lock()
+        .ok()
+        .and_then(|mut cache| cache.get(&bevy_markdown).cloned())
+    {
+        return lines;
     }
+    let lines = generate_markdown_lines(bevy_markdown.clone());
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(bevy_markdown, lines.clone());
+    }
+    lines
 }
 
 #[cfg(test)]
 mod tests {
-    use cosmic_text::Attrs;
+    use cosmic_text::{Attrs, Color};
 
     use crate::*;
 
-    fn test_bevymarkdown(input: String, test_name: String) {
-        let markdown_theme = BevyMarkdownTheme {
+    pub(crate) fn test_theme() -> BevyMarkdownTheme {
+        BevyMarkdownTheme {
             code_theme: "Solarized (light)".to_string(),
             code_default_lang: "rs".to_string(),
             link: Color::rgb(10, 10, 10),
             inline_code: Color::rgb(100, 100, 100),
-        };
+            blockquote: Color::rgb(120, 120, 120),
+        }
+    }
+
+    fn rendered_text(lines: &BevyMarkdownLines) -> String {
+        lines
+            .lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|(text, _)| text.as_str())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn test_bevymarkdown(input: String, test_name: String) {
+        let markdown_theme = test_theme();
 
         insta::assert_debug_snapshot!(
             test_name.clone(),
@@ -524,6 +256,7 @@ mod tests {
                 markdown_theme,
                 text: input.clone(),
                 attrs: AttrsOwned::new(Attrs::new()),
+                font_size: 15.0,
             })
         );
     }
@@ -651,4 +384,204 @@ hello world
         .to_string();
         test_bevymarkdown(input, "test_render_nested_ordered_list".to_string())
     }
+
+    #[test]
+    pub fn test_render_strikethrough() {
+        let input = "~~gone~~ and **~~bold gone~~**".to_string();
+        test_bevymarkdown(input, "test_render_strikethrough".to_string())
+    }
+
+    #[test]
+    pub fn test_render_blockquote() {
+        let input = "> quoted *text*
+>
+> > nested
+"
+        .to_string();
+        test_bevymarkdown(input, "test_render_blockquote".to_string())
+    }
+
+    #[test]
+    pub fn test_render_thematic_break() {
+        let input = "above
+
+---
+
+below
+"
+        .to_string();
+        test_bevymarkdown(input, "test_render_thematic_break".to_string())
+    }
+
+    #[test]
+    pub fn test_unknown_code_language() {
+        let lines = generate_markdown_lines(BevyMarkdown {
+            markdown_theme: test_theme(),
+            text: "```nosuchlang\nlet x = 1;\n```".to_string(),
+            attrs: AttrsOwned::new(Attrs::new()),
+            font_size: 15.0,
+        });
+        assert!(rendered_text(&lines).contains("let x = 1;"));
+        assert_eq!(
+            lines.diagnostics,
+            vec![BevyMarkdownError::Transform {
+                info: "line 1: unknown code language \"nosuchlang\", shown as plain text"
+                    .to_string()
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_unknown_code_theme() {
+        let lines = generate_markdown_lines(BevyMarkdown {
+            markdown_theme: BevyMarkdownTheme {
+                code_theme: "no such theme".to_string(),
+                ..test_theme()
+            },
+            text: "```rs\nlet x = 1;\n```".to_string(),
+            attrs: AttrsOwned::new(Attrs::new()),
+            font_size: 15.0,
+        });
+        assert!(rendered_text(&lines).contains("let x = 1;"));
+        assert_eq!(lines.diagnostics.len(), 1);
+    }
+
+    #[test]
+    pub fn test_unsupported_node_shown_as_source() {
+        let lines = generate_markdown_lines(BevyMarkdown {
+            markdown_theme: test_theme(),
+            text: "text\n\n<div>html</div>\n\nmore text".to_string(),
+            attrs: AttrsOwned::new(Attrs::new()),
+            font_size: 15.0,
+        });
+        let text = rendered_text(&lines);
+        assert!(text.contains("<div>html</div>"));
+        assert!(text.contains("more text"));
+        assert_eq!(
+            lines.diagnostics,
+            vec![BevyMarkdownError::Transform {
+                info: "line 3: Html is not supported, shown as text".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_render_image() {
+        let input = "Before
+
+![diagram](https://example.com/a.png)
+
+See ![logo](image:1234) inline"
+            .to_string();
+        test_bevymarkdown(input, "test_render_image".to_string())
+    }
+
+    #[test]
+    pub fn test_render_task_list() {
+        let input = "- [ ] todo
+- [x] done
+"
+        .to_string();
+        test_bevymarkdown(input, "test_render_task_list".to_string())
+    }
+
+    #[test]
+    pub fn test_toggle_task_checkbox() {
+        let mut text = "- [ ] todo\n- [X] done\n".to_string();
+        assert!(toggle_task_checkbox(&mut text, 2));
+        assert!(toggle_task_checkbox(&mut text, 13));
+        assert_eq!(text, "- [x] todo\n- [ ] done\n");
+        assert!(!toggle_task_checkbox(&mut text, 0));
+        assert!(!toggle_task_checkbox(&mut text, 21));
+        assert_eq!(text, "- [x] todo\n- [ ] done\n");
+    }
+
+    #[test]
+    pub fn test_render_table() {
+        let input = "| Name | Qty |
+| :-- | --: |
+| apple | 3 |
+| kiwi | 12 |
+"
+        .to_string();
+        test_bevymarkdown(input, "test_render_table".to_string())
+    }
+
+    #[test]
+    pub fn test_cache_key() {
+        let markdown = |text: &str, code_theme: &str| BevyMarkdown {
+            markdown_theme: BevyMarkdownTheme {
+                code_theme: code_theme.to_string(),
+                ..test_theme()
+            },
+            text: text.to_string(),
+            attrs: AttrsOwned::new(Attrs::new()),
+            font_size: 15.0,
+        };
+        let key = MarkdownCache::key(&markdown("# title", "Solarized (light)"));
+        assert_eq!(
+            key,
+            MarkdownCache::key(&markdown("# title", "Solarized (light)"))
+        );
+        assert_ne!(
+            key,
+            MarkdownCache::key(&markdown("# title!", "Solarized (light)"))
+        );
+        assert_ne!(
+            key,
+            MarkdownCache::key(&markdown("# title", "Solarized (dark)"))
+        );
+    }
+
+    #[test]
+    pub fn test_cached_render() {
+        let cache = Mutex::new(MarkdownCache::with_capacity(2));
+        let markdown = |text: &str| BevyMarkdown {
+            markdown_theme: test_theme(),
+            text: text.to_string(),
+            attrs: AttrsOwned::new(Attrs::new()),
+            font_size: 15.0,
+        };
+        let input = "```rust\nfn main() {}\n```";
+        let rendered = generate_markdown_lines(markdown(input));
+        let cached = generate_markdown_lines_cached(markdown(input), &cache);
+        assert_eq!(rendered_text(&cached), rendered_text(&rendered));
+        assert_eq!(cache.lock().unwrap().len(), 1);
+
+        let cached = generate_markdown_lines_cached(markdown(input), &cache);
+        assert_eq!(rendered_text(&cached), rendered_text(&rendered));
+        assert_eq!(cache.lock().unwrap().len(), 1);
+
+        // the least recently used text is dropped
+        generate_markdown_lines_cached(markdown("a"), &cache);
+        generate_markdown_lines_cached(markdown(input), &cache);
+        generate_markdown_lines_cached(markdown("b"), &cache);
+        let mut cache = cache.lock().unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&markdown(input)).is_some());
+        assert!(cache.get(&markdown("a")).is_none());
+        assert!(cache.get(&markdown("b")).is_some());
+    }
+
+    #[test]
+    pub fn test_cache_compares_source() {
+        let markdown = |text: &str| BevyMarkdown {
+            markdown_theme: test_theme(),
+            text: text.to_string(),
+            attrs: AttrsOwned::new(Attrs::new()),
+            font_size: 15.0,
+        };
+        let mut cache = MarkdownCache::default();
+        let lines = generate_markdown_lines(markdown("b"));
+        // another text stored under the key of "a", as if their hashes collided
+        cache.lines.insert(
+            MarkdownCache::key(&markdown("a")),
+            CacheEntry {
+                source: markdown("b"),
+                lines,
+                last_used: 0,
+            },
+        );
+        assert!(cache.get(&markdown("a")).is_none());
+    }
 }
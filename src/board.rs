@@ -0,0 +1,522 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::ReflectableUuid;
+
+/// Size of the board cells, a board without lanes has a single lane.
+pub const COLUMN_WIDTH: f32 = 220.;
+pub const LANE_HEIGHT: f32 = 420.;
+pub const HEADER_HEIGHT: f32 = 32.;
+/// Width of the lane names, only shown when the board has lanes.
+pub const LANE_LABEL_WIDTH: f32 = 100.;
+const CARD_GAP: f32 = 10.;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoardColumn {
+    pub name: String,
+    /// Cards allowed in the column before it's highlighted.
+    #[serde(default)]
+    pub wip_limit: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoardCard {
+    pub id: ReflectableUuid,
+    pub column: usize,
+    pub lane: usize,
+}
+
+/// Columns and lanes of a tab laid out as a kanban board, saved in the tab checkpoint.
+/// Cards are nodes snapped into a cell, in order from the top of the cell.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Board {
+    pub columns: Vec<BoardColumn>,
+    #[serde(default)]
+    pub lanes: Vec<String>,
+    /// Bottom left corner on the canvas.
+    pub left: f32,
+    pub bottom: f32,
+    #[serde(default)]
+    pub cards: Vec<BoardCard>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoardRect {
+    pub left: f32,
+    pub bottom: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Board {
+    pub fn new(columns: Vec<BoardColumn>, lanes: Vec<String>, left: f32, bottom: f32) -> Self {
+        Self {
+            columns,
+            lanes,
+            left,
+            bottom,
+            cards: vec![],
+        }
+    }
+
+    pub fn lane_count(&self) -> usize {
+        self.lanes.len().max(1)
+    }
+
+    fn labels_width(&self) -> f32 {
+        if self.lanes.is_empty() {
+            0.
+        } else {
+            LANE_LABEL_WIDTH
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.labels_width() + COLUMN_WIDTH * self.columns.len() as f32
+    }
+
+    pub fn height(&self) -> f32 {
+        HEADER_HEIGHT + LANE_HEIGHT * self.lane_count() as f32
+    }
+
+    fn top(&self) -> f32 {
+        self.bottom + self.height()
+    }
+
+    /// The whole column, its header included.
+    pub fn column_rect(&self, column: usize) -> BoardRect {
+        BoardRect {
+            left: self.left + self.labels_width() + COLUMN_WIDTH * column as f32,
+            bottom: self.bottom,
+            width: COLUMN_WIDTH,
+            height: self.height(),
+        }
+    }
+
+    /// The whole lane, its name included.
+    pub fn lane_rect(&self, lane: usize) -> BoardRect {
+        BoardRect {
+            left: self.left,
+            bottom: self.top() - HEADER_HEIGHT - LANE_HEIGHT * (lane + 1) as f32,
+            width: self.width(),
+            height: LANE_HEIGHT,
+        }
+    }
+
+    /// Column and lane of the cell at the point, the header belongs to the first lane.
+    pub fn cell_at(&self, x: f32, y: f32) -> Option<(usize, usize)> {
+        let x = x - self.left - self.labels_width();
+        // distance below the header
+        let depth = self.top() - HEADER_HEIGHT - y;
+        if x < 0. || y < self.bottom || depth < -HEADER_HEIGHT {
+            return None;
+        }
+        let column = (x / COLUMN_WIDTH) as usize;
+        let lane = (depth.max(0.) / LANE_HEIGHT) as usize;
+        (column < self.columns.len() && lane < self.lane_count()).then_some((column, lane))
+    }
+
+    pub fn cards_in(&self, column: usize, lane: usize) -> impl Iterator<Item = &BoardCard> {
+        self.cards
+            .iter()
+            .filter(move |card| card.column == column && card.lane == lane)
+    }
+
+    pub fn column_count(&self, column: usize) -> usize {
+        self.cards
+            .iter()
+            .filter(|card| card.column == column)
+            .count()
+    }
+
+    pub fn wip_exceeded(&self, column: usize) -> bool {
+        match self.columns[column].wip_limit {
+            Some(limit) => self.column_count(column) > limit,
+            None => false,
+        }
+    }
+
+    /// Snaps the card dropped with its center at `(x, y)` into the cell there, above the cards of
+    /// the cell that are lower. Returns false, and removes the card, when dropped outside.
+    pub fn place(
+        &mut self,
+        id: ReflectableUuid,
+        (x, y): (f32, f32),
+        centers: &HashMap<ReflectableUuid, (f32, f32)>,
+    ) -> bool {
+        self.cards.retain(|card| card.id != id);
+        let (column, lane) = match self.cell_at(x, y) {
+            Some(cell) => cell,
+            None => return false,
+        };
+        let index = self
+            .cards
+            .iter()
+            .position(|card| {
+                card.column == column
+                    && card.lane == lane
+                    && matches!(centers.get(&card.id), Some(center) if center.1 < y)
+            })
+            .unwrap_or(self.cards.len());
+        self.cards.insert(index, BoardCard { id, column, lane });
+        true
+    }
+
+    /// Removes the cards of nodes that no longer exist.
+    pub fn retain(&mut self, exists: impl Fn(&ReflectableUuid) -> bool) {
+        self.cards.retain(|card| exists(&card.id));
+    }
+
+    /// Positions of the cards, stacked from the top of their cells and centered in the column.
+    pub fn layout(
+        &self,
+        sizes: &HashMap<ReflectableUuid, (f32, f32)>,
+    ) -> HashMap<ReflectableUuid, (f32, f32)> {
+        let mut tops: HashMap<(usize, usize), f32> = HashMap::new();
+        let mut layout = HashMap::new();
+        for card in self.cards.iter() {
+            let (width, height) = match sizes.get(&card.id) {
+                Some(size) => *size,
+                None => continue,
+            };
+            let column = self.column_rect(card.column);
+            let top = tops
+                .entry((card.column, card.lane))
+                .or_insert_with(|| self.top() - HEADER_HEIGHT - LANE_HEIGHT * card.lane as f32);
+            *top -= CARD_GAP + height;
+            layout.insert(card.id, (column.left + (COLUMN_WIDTH - width) / 2., *top));
+        }
+        layout
+    }
+
+    /// Columns and lanes as edited in the board modal, e.g. `To do, Doing:3, Done | Web, Mobile`.
+    pub fn spec(&self) -> String {
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| match column.wip_limit {
+                Some(limit) => format!("{}:{}", column.name, limit),
+                None => column.name.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.lanes.is_empty() {
+            columns
+        } else {
+            format!("{} | {}", columns, self.lanes.join(", "))
+        }
+    }
+
+    /// Changes the columns and lanes, cards stay in the columns and lanes with the same name and
+    /// the others move to the first ones.
+    pub fn set_spec(&mut self, columns: Vec<BoardColumn>, lanes: Vec<String>) {
+        let find = |names: Vec<&String>, name: Option<&String>| {
+            name.and_then(|name| names.iter().position(|other| *other == name))
+                .unwrap_or(0)
+        };
+        for card in self.cards.iter_mut() {
+            card.column = find(
+                columns.iter().map(|column| &column.name).collect(),
+                self.columns.get(card.column).map(|column| &column.name),
+            );
+            card.lane = find(lanes.iter().collect(), self.lanes.get(card.lane));
+        }
+        self.columns = columns;
+        self.lanes = lanes;
+    }
+
+    /// One CSV column per board column with the first line of its cards, lane by lane with the
+    /// lane name first when the board has lanes.
+    pub fn to_csv(&self, texts: &HashMap<ReflectableUuid, String>) -> String {
+        let has_lanes = !self.lanes.is_empty();
+        let mut rows: Vec<Vec<String>> = vec![];
+        let mut header: Vec<String> = vec![];
+        if has_lanes {
+            header.push("Lane".to_string());
+        }
+        header.extend(self.columns.iter().map(|column| column.name.clone()));
+        rows.push(header);
+        for lane in 0..self.lane_count() {
+            let cells: Vec<Vec<String>> = (0..self.columns.len())
+                .map(|column| {
+                    self.cards_in(column, lane)
+                        .map(|card| {
+                            texts
+                                .get(&card.id)
+                                .and_then(|text| text.lines().find(|line| !line.trim().is_empty()))
+                                .unwrap_or_default()
+                                .trim()
+                                .to_string()
+                        })
+                        .collect()
+                })
+                .collect();
+            let count = cells.iter().map(|cards| cards.len()).max().unwrap_or(0);
+            for index in 0..count {
+                let mut row = vec![];
+                if has_lanes {
+                    row.push(self.lanes[lane].clone());
+                }
+                row.extend(
+                    cells
+                        .iter()
+                        .map(|cards| cards.get(index).cloned().unwrap_or_default()),
+                );
+                rows.push(row);
+            }
+        }
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| csv_field(value))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .map(|row| row + "\n")
+            .collect()
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    // spreadsheets would run cells starting like a formula
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Parses the columns, with an optional WIP limit after a final `:`, and the lanes after `|`,
+/// e.g. `To do, Doing:3, Done | Web, Mobile`. Other colons are part of the column name.
+pub fn parse_board_spec(spec: &str) -> Result<(Vec<BoardColumn>, Vec<String>), String> {
+    let (columns, lanes) = match spec.split_once('|') {
+        Some((columns, lanes)) => (columns, lanes),
+        None => (spec, ""),
+    };
+    let names = |list: &str| -> Vec<String> {
+        list.split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect()
+    };
+    let mut board_columns: Vec<BoardColumn> = vec![];
+    for column in names(columns) {
+        let limit = column.rsplit_once(':').filter(|(_, limit)| {
+            let limit = limit.trim();
+            !limit.is_empty() && limit.chars().all(|c| c.is_ascii_digit())
+        });
+        let (name, wip_limit) = match limit {
+            Some((name, limit)) => match limit.trim().parse::<usize>() {
+                Ok(limit) if limit > 0 => (name.trim().to_string(), Some(limit)),
+                _ => {
+                    return Err(format!(
+                        "WIP limit of \"{}\" must be a positive number",
+                        name.trim()
+                    ))
+                }
+            },
+            None => (column, None),
+        };
+        if name.is_empty() {
+            return Err("Column names can't be empty".to_string());
+        }
+        if board_columns.iter().any(|other| other.name == name) {
+            return Err(format!("Column \"{}\" is listed twice", name));
+        }
+        board_columns.push(BoardColumn { name, wip_limit });
+    }
+    if board_columns.is_empty() {
+        return Err("A board needs at least one column".to_string());
+    }
+    let lanes = names(lanes);
+    if let Some(lane) = lanes
+        .iter()
+        .enumerate()
+        .find(|(index, lane)| lanes[..*index].contains(lane))
+        .map(|(_, lane)| lane)
+    {
+        return Err(format!("Lane \"{}\" is listed twice", lane));
+    }
+    Ok((board_columns, lanes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, wip_limit: Option<usize>) -> BoardColumn {
+        BoardColumn {
+            name: name.to_string(),
+            wip_limit,
+        }
+    }
+
+    #[test]
+    fn test_parse_board_spec() {
+        let (columns, lanes) = parse_board_spec("To do, Doing: 2 ,Done | Web, Mobile").unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                column("To do", None),
+                column("Doing", Some(2)),
+                column("Done", None)
+            ]
+        );
+        assert_eq!(lanes, vec!["Web".to_string(), "Mobile".to_string()]);
+        let board = Board::new(columns, lanes, 0., 0.);
+        assert_eq!(board.spec(), "To do, Doing:2, Done | Web, Mobile");
+        assert_eq!(parse_board_spec(&board.spec()).unwrap().1.len(), 2);
+
+        assert!(parse_board_spec(" , |").is_err());
+        assert!(parse_board_spec("To do, Doing:0").is_err());
+        assert!(parse_board_spec("To do, Doing:99999999999999999999999").is_err());
+        // only a number after the last colon is a WIP limit
+        let (columns, _) = parse_board_spec("Review: design, Q3:2, Done: 1").unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                column("Review: design", None),
+                column("Q3", Some(2)),
+                column("Done", Some(1))
+            ]
+        );
+        assert!(parse_board_spec("To do, To do").is_err());
+        assert!(parse_board_spec("To do | Web, Web").is_err());
+    }
+
+    #[test]
+    fn test_board_cells() {
+        let board = Board::new(
+            vec![column("To do", None), column("Done", None)],
+            vec!["Web".to_string(), "Mobile".to_string()],
+            100.,
+            50.,
+        );
+        assert_eq!(board.height(), HEADER_HEIGHT + 2. * LANE_HEIGHT);
+        let first = board.column_rect(0);
+        assert_eq!(first.left, 100. + LANE_LABEL_WIDTH);
+        let top = 50. + board.height();
+        assert_eq!(board.cell_at(first.left + 1., top - 1.), Some((0, 0)));
+        assert_eq!(
+            board.cell_at(first.left + COLUMN_WIDTH + 1., 51.),
+            Some((1, 1))
+        );
+        assert_eq!(board.cell_at(first.left + 1., 49.), None);
+        assert_eq!(board.cell_at(first.left + 1., top + 1.), None);
+        assert_eq!(board.cell_at(first.left - 1., 100.), None);
+        assert_eq!(
+            board.cell_at(first.left + 2. * COLUMN_WIDTH + 1., 100.),
+            None
+        );
+    }
+
+    #[test]
+    fn test_board_place_and_layout() {
+        let mut board = Board::new(
+            vec![column("To do", None), column("Doing", Some(1))],
+            vec![],
+            0.,
+            0.,
+        );
+        let ids: Vec<ReflectableUuid> = (0..3).map(|_| ReflectableUuid::generate()).collect();
+        let sizes: HashMap<ReflectableUuid, (f32, f32)> =
+            ids.iter().map(|id| (*id, (100., 50.))).collect();
+        let top = board.height() - HEADER_HEIGHT;
+        assert!(board.place(ids[0], (10., 100.), &HashMap::new()));
+        assert!(board.place(ids[1], (10., 50.), &HashMap::new()));
+        let layout = board.layout(&sizes);
+        assert_eq!(layout[&ids[0]], (60., top - CARD_GAP - 50.));
+        assert_eq!(layout[&ids[1]], (60., top - 2. * (CARD_GAP + 50.)));
+
+        // dropped above the first card of the column
+        let centers: HashMap<ReflectableUuid, (f32, f32)> = layout
+            .iter()
+            .map(|(id, (left, bottom))| (*id, (left + 50., bottom + 25.)))
+            .collect();
+        assert!(board.place(ids[2], (20., top), &centers));
+        assert_eq!(board.cards_in(0, 0).next().unwrap().id, ids[2]);
+
+        // moved to the other column
+        assert!(board.place(ids[0], (COLUMN_WIDTH + 10., 100.), &centers));
+        assert!(board.place(ids[1], (COLUMN_WIDTH + 10., 10.), &centers));
+        assert_eq!(board.column_count(1), 2);
+        assert!(board.wip_exceeded(1));
+        assert!(!board.wip_exceeded(0));
+        assert_eq!(board.layout(&sizes)[&ids[0]].0, COLUMN_WIDTH + 60.);
+
+        // dropped outside the board
+        assert!(!board.place(ids[1], (-10., 10.), &centers));
+        assert_eq!(board.cards.len(), 2);
+        board.retain(|id| *id != ids[0]);
+        assert_eq!(board.cards.len(), 1);
+    }
+
+    #[test]
+    fn test_board_set_spec() {
+        let mut board = Board::new(
+            vec![column("To do", None), column("Done", None)],
+            vec!["Web".to_string()],
+            0.,
+            0.,
+        );
+        let ids: Vec<ReflectableUuid> = (0..2).map(|_| ReflectableUuid::generate()).collect();
+        board.cards = vec![
+            BoardCard {
+                id: ids[0],
+                column: 0,
+                lane: 0,
+            },
+            BoardCard {
+                id: ids[1],
+                column: 1,
+                lane: 0,
+            },
+        ];
+        board.set_spec(
+            vec![column("Doing", None), column("Done", Some(3))],
+            vec!["Mobile".to_string(), "Web".to_string()],
+        );
+        assert_eq!((board.cards[0].column, board.cards[0].lane), (0, 1));
+        assert_eq!((board.cards[1].column, board.cards[1].lane), (1, 1));
+    }
+
+    #[test]
+    fn test_board_to_csv() {
+        let mut board = Board::new(
+            vec![column("To do", None), column("Done, really", None)],
+            vec!["Web".to_string(), "Mobile".to_string()],
+            0.,
+            0.,
+        );
+        let ids: Vec<ReflectableUuid> = (0..4).map(|_| ReflectableUuid::generate()).collect();
+        let cells = [(0, 0), (0, 0), (1, 0), (1, 1)];
+        for (id, (column, lane)) in ids.iter().zip(cells) {
+            board.cards.push(BoardCard {
+                id: *id,
+                column,
+                lane,
+            });
+        }
+        let texts = HashMap::from([
+            (ids[0], "\n# Login\nmore details".to_string()),
+            (ids[1], "Say \"hi\"".to_string()),
+            (ids[2], "Signup".to_string()),
+            (ids[3], "=HYPERLINK(\"x\")".to_string()),
+        ]);
+        assert_eq!(
+            board.to_csv(&texts),
+            "Lane,To do,\"Done, really\"\n\
+             Web,# Login,Signup\n\
+             Web,\"Say \"\"hi\"\"\",\n\
+             Mobile,,\"'=HYPERLINK(\"\"x\"\")\"\n"
+        );
+        assert_eq!(csv_field("-1"), "'-1");
+        assert_eq!(csv_field("a-b"), "a-b");
+    }
+}
@@ -1,13 +1,16 @@
 use crate::utils::ReflectableUuid;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 #[derive(Component)]
 pub struct MainCamera;
 
 #[derive(Component)]
 pub struct EffectsCamera;
 
+#[derive(Component)]
+pub struct CanvasBackground;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Tab {
     pub is_active: bool,
@@ -22,4 +25,32 @@ pub struct Doc {
     pub id: ReflectableUuid,
     pub name: String,
     pub tags: Vec<String>,
+    /// Where the document was last shared, updated in place when it is shared again.
+    #[serde(default, alias = "gist")]
+    pub shared: Option<SharedDoc>,
+    /// Base64 encoded images shown inline in node text by source, stored once for all
+    /// checkpoints of the tabs.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub inline_images: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SharedDoc {
+    /// Name of the share provider that published the document.
+    #[serde(default = "default_share_provider")]
+    pub provider: String,
+    pub id: String,
+    /// URL the document can be loaded from.
+    #[serde(alias = "raw_url")]
+    pub url: String,
+    #[serde(default)]
+    pub public: bool,
+    /// HTTP method the document was created with by the `http` provider, `None` for `PUT`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+}
+
+// documents shared before there were other providers
+fn default_share_provider() -> String {
+    "gist".to_string()
 }
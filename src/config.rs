@@ -0,0 +1,566 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::path::PathBuf;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::utils::line_of_key;
+
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Application configuration, read from `config.toml` in the config directory
+/// (e.g. `~/.config/velo/config.toml` on Linux) or the legacy `~/.velo.toml`.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Theme used until another one is picked in the app.
+    pub theme: Option<String>,
+    /// Directory with additional fonts for node text.
+    pub fonts_dir: Option<PathBuf>,
+    /// Seconds between automatic saves of the current document, disabled if not set.
+    pub autosave_interval: Option<u64>,
+    /// Nodes snap to a grid of this size (px) while dragged, disabled if not set.
+    pub grid_size: Option<f32>,
+    /// Number of checkpoints kept for every tab.
+    pub checkpoint_depth: usize,
+    /// Number of documents kept in memory before the least recent ones are dropped.
+    pub max_docs_in_memory: usize,
+    pub search: SearchConfig,
+    pub import: ImportConfig,
+    pub history: HistoryConfig,
+    pub share: ShareConfig,
+    /// Shortcut overrides, action name -> key chord like `"Ctrl+Shift+S"`.
+    pub keymap: BTreeMap<String, String>,
+    /// Kept for compatibility with old config files, prefer `share.github.access_token`.
+    pub github_access_token: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: None,
+            fonts_dir: None,
+            autosave_interval: None,
+            grid_size: None,
+            checkpoint_depth: 7,
+            max_docs_in_memory: 7,
+            search: SearchConfig::default(),
+            import: ImportConfig::default(),
+            history: HistoryConfig::default(),
+            share: ShareConfig::default(),
+            keymap: BTreeMap::new(),
+            github_access_token: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn github_access_token(&self) -> Option<String> {
+        self.share
+            .github
+            .as_ref()
+            .map(|github| github.access_token.clone())
+            .or_else(|| self.github_access_token.clone())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    /// Maximum number of nodes returned by a search.
+    pub max_results: usize,
+    /// Maximum edit distance of fuzzy matches (0-2).
+    pub fuzzy_distance: u8,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            max_results: 1000,
+            fuzzy_distance: 2,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportConfig {
+    /// Largest document (bytes) loaded from a file or URL.
+    pub max_size: usize,
+    /// Seconds to wait for a document loaded from a URL.
+    pub timeout: u64,
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10 * 1024 * 1024,
+            timeout: 30,
+        }
+    }
+}
+
+/// Retention of document versions, named versions are never pruned.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// Minutes between automatic versions, taken when the document is saved. 0 disables them.
+    pub auto_interval: u64,
+    /// Automatic versions kept for every document.
+    pub max_auto_versions: usize,
+    /// Automatic versions older than this are pruned, 0 keeps them regardless of age.
+    pub max_age_days: u64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            auto_interval: 30,
+            max_auto_versions: 20,
+            max_age_days: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShareConfig {
+    /// Share provider: "gist", "http" or "folder". The first configured one is used if not set.
+    pub provider: Option<String>,
+    /// Link copied after sharing, `{url}` is replaced with the URL of the shared document.
+    pub viewer_url: String,
+    pub github: Option<GithubShareConfig>,
+    pub http: Option<HttpShareConfig>,
+    pub folder: Option<FolderShareConfig>,
+}
+
+impl Default for ShareConfig {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            viewer_url: "https://staffengineer.github.io/velo?document={url}".to_string(),
+            github: None,
+            http: None,
+            folder: None,
+        }
+    }
+}
+
+pub const SHARE_PROVIDERS: [&str; 3] = ["gist", "http", "folder"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GithubShareConfig {
+    /// GitHub access token with "gist" scope.
+    pub access_token: String,
+    #[serde(default = "default_github_api_url")]
+    pub api_url: String,
+    /// Visibility picked by default when sharing, public gists are listed on the profile of
+    /// the token owner and secret ones are only visible with the link.
+    #[serde(default = "default_github_public")]
+    pub public: bool,
+}
+
+fn default_github_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
+fn default_github_public() -> bool {
+    true
+}
+
+/// Generic HTTP endpoint, e.g. a self-hosted paste service.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HttpShareConfig {
+    pub url: String,
+    /// `put` stores documents at `<url>/<document id>.json`, `post` sends them to `url`
+    /// and expects the document URL in the `Location` header or the `url` field of a JSON response.
+    #[serde(default)]
+    pub method: HttpShareMethod,
+    /// Extra request headers, e.g. `Authorization`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpShareMethod {
+    #[default]
+    Put,
+    Post,
+}
+
+/// Folder shared with the team, e.g. a network drive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FolderShareConfig {
+    pub path: PathBuf,
+    /// URL the folder is served at, `file://` URLs without a viewer link are used if not set.
+    pub base_url: Option<String>,
+}
+
+/// Error found in a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+/// Config file locations, in lookup order.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_file_paths() -> Vec<PathBuf> {
+    let mut paths = vec![];
+    if let Some(dirs) = directories::ProjectDirs::from("", crate::ORG_NAME, crate::APP_NAME) {
+        paths.push(dirs.config_dir().join(CONFIG_FILE_NAME));
+    }
+    if let Some(dirs) = directories::BaseDirs::new() {
+        paths.push(dirs.home_dir().join(".velo.toml"));
+    }
+    paths
+}
+
+/// Reads the first existing config file, errors are logged and defaults are used instead.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_config() -> Config {
+    for path in config_file_paths() {
+        if !path.exists() {
+            continue;
+        }
+        return match load_config(&path) {
+            Ok(config) => {
+                info!("Loaded config from {}", path.display());
+                config
+            }
+            Err(errors) => {
+                for error in errors {
+                    error!("{}", error);
+                }
+                warn!("Using default config");
+                Config::default()
+            }
+        };
+    }
+    Config::default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_config(path: &Path) -> Result<Config, Vec<ConfigError>> {
+    let source = std::fs::read_to_string(path).map_err(|e| {
+        vec![ConfigError {
+            path: path.to_path_buf(),
+            line: None,
+            message: e.to_string(),
+        }]
+    })?;
+    parse_config(path, &source)
+}
+
+/// Parses and validates config file content, `path` is only used in errors.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn parse_config(path: &Path, source: &str) -> Result<Config, Vec<ConfigError>> {
+    let config: Config = toml::from_str(source).map_err(|e| {
+        let line = e
+            .span()
+            .map(|span| source[..span.start].matches('\n').count() + 1);
+        vec![ConfigError {
+            path: path.to_path_buf(),
+            line,
+            message: e.message().to_string(),
+        }]
+    })?;
+    let errors: Vec<ConfigError> = validate_config(&config)
+        .into_iter()
+        .map(|(key, message)| ConfigError {
+            path: path.to_path_buf(),
+            line: line_of_key(source, &key),
+            message: format!("`{}`: {}", key, message),
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(config)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn validate_config(config: &Config) -> Vec<(String, String)> {
+    let mut errors = vec![];
+    if config.checkpoint_depth == 0 {
+        errors.push((
+            "checkpoint_depth".to_string(),
+            "must be at least 1".to_string(),
+        ));
+    }
+    if config.max_docs_in_memory == 0 {
+        errors.push((
+            "max_docs_in_memory".to_string(),
+            "must be at least 1".to_string(),
+        ));
+    }
+    if config.autosave_interval == Some(0) {
+        errors.push((
+            "autosave_interval".to_string(),
+            "must be at least 1 second".to_string(),
+        ));
+    }
+    if let Some(grid_size) = config.grid_size {
+        if grid_size <= 0. || !grid_size.is_finite() {
+            errors.push((
+                "grid_size".to_string(),
+                "must be a positive number".to_string(),
+            ));
+        }
+    }
+    if config.search.max_results == 0 {
+        errors.push(("max_results".to_string(), "must be at least 1".to_string()));
+    }
+    if config.search.fuzzy_distance > 2 {
+        errors.push((
+            "fuzzy_distance".to_string(),
+            "must be between 0 and 2".to_string(),
+        ));
+    }
+    if let Some(theme) = &config.theme {
+        let theme_names = crate::themes::get_theme_names();
+        if !theme_names.contains(theme) {
+            errors.push((
+                "theme".to_string(),
+                format!(
+                    "unknown theme \"{}\", expected one of: {}",
+                    theme,
+                    theme_names.join(", ")
+                ),
+            ));
+        }
+    }
+    if config.import.max_size == 0 {
+        errors.push(("max_size".to_string(), "must be at least 1".to_string()));
+    }
+    if config.import.timeout == 0 {
+        errors.push((
+            "timeout".to_string(),
+            "must be at least 1 second".to_string(),
+        ));
+    }
+    if config.history.max_auto_versions == 0 {
+        errors.push((
+            "max_auto_versions".to_string(),
+            "must be at least 1".to_string(),
+        ));
+    }
+    if let Some(provider) = &config.share.provider {
+        let configured = match provider.as_str() {
+            "gist" => Some(config.github_access_token().is_some()),
+            "http" => Some(config.share.http.is_some()),
+            "folder" => Some(config.share.folder.is_some()),
+            _ => None,
+        };
+        match configured {
+            Some(true) => {}
+            Some(false) => errors.push((
+                "provider".to_string(),
+                format!("share provider \"{}\" is not configured", provider),
+            )),
+            None => errors.push((
+                "provider".to_string(),
+                format!(
+                    "unknown share provider \"{}\", expected one of: {}",
+                    provider,
+                    SHARE_PROVIDERS.join(", ")
+                ),
+            )),
+        }
+    }
+    if !config.share.viewer_url.contains("{url}") {
+        errors.push(("viewer_url".to_string(), "must contain {url}".to_string()));
+    }
+    let (_, _, keymap_errors) = crate::keymap::build_keymaps(&config.keymap);
+    errors.extend(keymap_errors);
+    errors
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<Config, Vec<ConfigError>> {
+        parse_config(Path::new("config.toml"), source)
+    }
+
+    #[test]
+    fn test_parse_config_defaults() {
+        assert_eq!(parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = parse(
+            r#"
+theme = "dark"
+fonts_dir = "/usr/share/fonts/velo"
+autosave_interval = 60
+grid_size = 20
+checkpoint_depth = 20
+max_docs_in_memory = 3
+
+[search]
+max_results = 10
+fuzzy_distance = 1
+
+[import]
+max_size = 1000
+timeout = 5
+
+[history]
+auto_interval = 0
+max_auto_versions = 5
+
+[share.github]
+access_token = "token"
+public = false
+
+[keymap]
+save_tab = "Ctrl+Alt+S"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.theme, Some("dark".to_string()));
+        assert_eq!(
+            config.fonts_dir,
+            Some(PathBuf::from("/usr/share/fonts/velo"))
+        );
+        assert_eq!(config.autosave_interval, Some(60));
+        assert_eq!(config.grid_size, Some(20.));
+        assert_eq!(config.checkpoint_depth, 20);
+        assert_eq!(config.max_docs_in_memory, 3);
+        assert_eq!(
+            config.search,
+            SearchConfig {
+                max_results: 10,
+                fuzzy_distance: 1
+            }
+        );
+        assert_eq!(
+            config.import,
+            ImportConfig {
+                max_size: 1000,
+                timeout: 5
+            }
+        );
+        assert_eq!(
+            config.history,
+            HistoryConfig {
+                auto_interval: 0,
+                max_auto_versions: 5,
+                max_age_days: 30
+            }
+        );
+        let github = config.share.github.as_ref().unwrap();
+        assert_eq!(github.api_url, "https://api.github.com");
+        assert_eq!(config.github_access_token(), Some("token".to_string()));
+        assert!(!github.public);
+        assert_eq!(config.keymap["save_tab"], "Ctrl+Alt+S");
+    }
+
+    #[test]
+    fn test_parse_share_config() {
+        let config = parse(
+            r#"
+[share]
+provider = "http"
+viewer_url = "https://velo.example.com/?document={url}"
+
+[share.http]
+url = "https://paste.example.com/velo"
+method = "post"
+headers = { Authorization = "Bearer token" }
+"#,
+        )
+        .unwrap();
+        let http = config.share.http.as_ref().unwrap();
+        assert_eq!(http.method, HttpShareMethod::Post);
+        assert_eq!(http.headers["Authorization"], "Bearer token");
+
+        let errors = parse("[share]\nprovider = \"folder\"\n").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, Some(2));
+        assert!(
+            errors[0].message.contains("is not configured"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn test_parse_legacy_config() {
+        let config = parse("github_access_token = \"token\"\n").unwrap();
+        assert_eq!(config.github_access_token(), Some("token".to_string()));
+    }
+
+    #[test]
+    fn test_parse_config_unknown_key() {
+        let errors = parse("checkpoint_depth = 3\n\n[search]\nmax_result = 10\n").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, Some(4));
+        assert!(
+            errors[0].message.contains("unknown field `max_result`"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn test_parse_config_type_error() {
+        let errors = parse("theme = \"dark\"\ncheckpoint_depth = \"ten\"\n").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, Some(2));
+        assert!(errors[0].message.contains("invalid type"), "{}", errors[0]);
+        assert!(errors[0].to_string().starts_with("config.toml:2: "));
+    }
+
+    #[test]
+    fn test_parse_config_validation() {
+        let errors =
+            parse("checkpoint_depth = 0\nautosave_interval = 0\n[search]\nfuzzy_distance = 3\n[keymap]\nundo = \"Ctrl+Hyper+Z\"\n")
+                .unwrap_err();
+        let errors: Vec<(Option<usize>, String)> =
+            errors.into_iter().map(|e| (e.line, e.message)).collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    Some(1),
+                    "`checkpoint_depth`: must be at least 1".to_string()
+                ),
+                (
+                    Some(2),
+                    "`autosave_interval`: must be at least 1 second".to_string()
+                ),
+                (
+                    Some(4),
+                    "`fuzzy_distance`: must be between 0 and 2".to_string()
+                ),
+                (
+                    Some(6),
+                    "`undo`: unknown modifier \"Hyper\" in \"Ctrl+Hyper+Z\"".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use bevy::prelude::*;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::canvas::arrow::components::ArrowMeta;
+use crate::components::Doc;
+use crate::ui_plugin::JsonNode;
+
+const MAX_LABEL_LEN: usize = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Moved,
+    Resized,
+    Recolored,
+    TextChanged,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Moved => write!(f, "moved"),
+            ChangeKind::Resized => write!(f, "resized"),
+            ChangeKind::Recolored => write!(f, "recolored"),
+            ChangeKind::TextChanged => write!(f, "text changed"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeChange {
+    pub id: Uuid,
+    pub changes: Vec<ChangeKind>,
+    /// Position and size in the newer snapshot, or in the older one for removed nodes.
+    pub position: (Val, Val),
+    pub size: (Val, Val),
+    pub label: String,
+}
+
+/// Arrows have no id, they are either added or removed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArrowChange {
+    pub change: ChangeKind,
+    pub arrow: ArrowMeta,
+    pub start_label: String,
+    pub end_label: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TabDiff {
+    pub nodes: Vec<NodeChange>,
+    pub arrows: Vec<ArrowChange>,
+}
+
+/// Nodes and arrows of a tab checkpoint.
+pub struct TabSnapshot {
+    pub nodes: Vec<JsonNode>,
+    pub arrows: Vec<ArrowMeta>,
+}
+
+impl TabSnapshot {
+    pub fn parse(checkpoint: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(checkpoint).map_err(|e| e.to_string())?;
+        let nodes = json["nodes"]
+            .as_array()
+            .ok_or("missing nodes")?
+            .iter()
+            .map(|node| serde_json::from_value(node.clone()).map_err(|e| e.to_string()))
+            .collect::<Result<_, _>>()?;
+        let arrows = json["arrows"]
+            .as_array()
+            .ok_or("missing arrows")?
+            .iter()
+            .map(|arrow| serde_json::from_value(arrow.clone()).map_err(|e| e.to_string()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { nodes, arrows })
+    }
+
+    /// Labels of the nodes, in the order they were saved.
+    pub fn labels(&self) -> Vec<String> {
+        self.nodes.iter().map(node_label).collect()
+    }
+
+    fn label(&self, id: Uuid) -> Option<String> {
+        self.nodes.iter().find(|node| node.id == id).map(node_label)
+    }
+}
+
+/// First line of the node text, or the start of its id for empty nodes.
+fn node_label(node: &JsonNode) -> String {
+    let line = node
+        .text
+        .text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty());
+    match line {
+        Some(line) if line.chars().count() > MAX_LABEL_LEN => {
+            format!(
+                "{}...",
+                line.chars().take(MAX_LABEL_LEN).collect::<String>()
+            )
+        }
+        Some(line) => line.to_string(),
+        None => node.id.to_string()[..8].to_string(),
+    }
+}
+
+fn node_change(node: &JsonNode, changes: Vec<ChangeKind>) -> NodeChange {
+    NodeChange {
+        id: node.id,
+        changes,
+        position: (node.left, node.bottom),
+        size: (node.width, node.height),
+        label: node_label(node),
+    }
+}
+
+pub fn diff_tabs(old: &TabSnapshot, new: &TabSnapshot) -> TabDiff {
+    let old_nodes: HashMap<Uuid, &JsonNode> =
+        old.nodes.iter().map(|node| (node.id, node)).collect();
+    let mut diff = TabDiff::default();
+    for node in new.nodes.iter() {
+        let old_node = match old_nodes.get(&node.id) {
+            Some(old_node) => old_node,
+            None => {
+                diff.nodes.push(node_change(node, vec![ChangeKind::Added]));
+                continue;
+            }
+        };
+        let mut changes = vec![];
+        if (old_node.left, old_node.bottom) != (node.left, node.bottom) {
+            changes.push(ChangeKind::Moved);
+        }
+        if (old_node.width, old_node.height) != (node.width, node.height) {
+            changes.push(ChangeKind::Resized);
+        }
+        if old_node.bg_color != node.bg_color {
+            changes.push(ChangeKind::Recolored);
+        }
+        if old_node.text.text != node.text.text {
+            changes.push(ChangeKind::TextChanged);
+        }
+        if !changes.is_empty() {
+            diff.nodes.push(node_change(node, changes));
+        }
+    }
+    for node in old.nodes.iter() {
+        if !new.nodes.iter().any(|new_node| new_node.id == node.id) {
+            diff.nodes
+                .push(node_change(node, vec![ChangeKind::Removed]));
+        }
+    }
+    let arrow_change = |arrow: &ArrowMeta, change, snapshot: &TabSnapshot| ArrowChange {
+        change,
+        arrow: *arrow,
+        start_label: snapshot.label(arrow.start.id.0).unwrap_or_default(),
+        end_label: snapshot.label(arrow.end.id.0).unwrap_or_default(),
+    };
+    for arrow in new
+        .arrows
+        .iter()
+        .filter(|arrow| !old.arrows.contains(arrow))
+    {
+        diff.arrows
+            .push(arrow_change(arrow, ChangeKind::Added, new));
+    }
+    for arrow in old
+        .arrows
+        .iter()
+        .filter(|arrow| !new.arrows.contains(arrow))
+    {
+        diff.arrows
+            .push(arrow_change(arrow, ChangeKind::Removed, old));
+    }
+    diff
+}
+
+pub fn diff_checkpoints(old: &str, new: &str) -> Result<TabDiff, String> {
+    Ok(diff_tabs(
+        &TabSnapshot::parse(old)?,
+        &TabSnapshot::parse(new)?,
+    ))
+}
+
+impl TabDiff {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.arrows.is_empty()
+    }
+
+    /// Number of changes of every kind, nodes changed in several ways are counted for each.
+    pub fn counts(&self) -> BTreeMap<ChangeKind, usize> {
+        let mut counts = BTreeMap::new();
+        for change in self.nodes.iter().flat_map(|node| node.changes.iter()) {
+            *counts.entry(*change).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Number of added and removed arrows, e.g. "2 arrows added".
+    pub fn arrow_counts(&self) -> Vec<(ChangeKind, String)> {
+        let mut counts = vec![];
+        for kind in [ChangeKind::Added, ChangeKind::Removed] {
+            let count = self
+                .arrows
+                .iter()
+                .filter(|arrow| arrow.change == kind)
+                .count();
+            if count > 0 {
+                let plural = if count == 1 { "" } else { "s" };
+                counts.push((kind, format!("{} arrow{} {}", count, plural, kind)));
+            }
+        }
+        counts
+    }
+
+    fn summary(&self) -> String {
+        let mut parts: Vec<String> = self
+            .counts()
+            .into_iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+        parts.extend(self.arrow_counts().into_iter().map(|(_, count)| count));
+        parts.join(", ")
+    }
+}
+
+impl fmt::Display for TabDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No changes");
+        }
+        let mut lines = vec![];
+        for node in self.nodes.iter() {
+            let changes: Vec<String> = node.changes.iter().map(|c| c.to_string()).collect();
+            lines.push(format!("\"{}\": {}", node.label, changes.join(", ")));
+        }
+        for arrow in self.arrows.iter() {
+            lines.push(format!(
+                "arrow \"{}\" -> \"{}\": {}",
+                arrow.start_label, arrow.end_label, arrow.change
+            ));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Differences between the latest checkpoints of two versions of a document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DocDiff {
+    pub added_tabs: Vec<String>,
+    pub removed_tabs: Vec<String>,
+    pub changed_tabs: Vec<(String, TabDiff)>,
+}
+
+impl DocDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_tabs.is_empty() && self.removed_tabs.is_empty() && self.changed_tabs.is_empty()
+    }
+}
+
+pub fn diff_docs(old: &Doc, new: &Doc) -> Result<DocDiff, String> {
+    let mut diff = DocDiff::default();
+    for tab in new.tabs.iter() {
+        let old_tab = match old.tabs.iter().find(|old_tab| old_tab.id == tab.id) {
+            Some(old_tab) => old_tab,
+            None => {
+                diff.added_tabs.push(tab.name.clone());
+                continue;
+            }
+        };
+        let tab_diff = match (old_tab.checkpoints.back(), tab.checkpoints.back()) {
+            (Some(old), Some(new)) => {
+                diff_checkpoints(old, new).map_err(|e| format!("tab \"{}\": {}", tab.name, e))?
+            }
+            (None, None) => TabDiff::default(),
+            (old, new) => diff_tabs(
+                &TabSnapshot::parse(old.map_or(EMPTY_CHECKPOINT, |c| c.as_str()))?,
+                &TabSnapshot::parse(new.map_or(EMPTY_CHECKPOINT, |c| c.as_str()))?,
+            ),
+        };
+        if !tab_diff.is_empty() {
+            diff.changed_tabs.push((tab.name.clone(), tab_diff));
+        }
+    }
+    for tab in old.tabs.iter() {
+        if !new.tabs.iter().any(|new_tab| new_tab.id == tab.id) {
+            diff.removed_tabs.push(tab.name.clone());
+        }
+    }
+    Ok(diff)
+}
+
+const EMPTY_CHECKPOINT: &str = r#"{"images": {}, "nodes": [], "arrows": []}"#;
+
+/// One line per changed tab, short enough for a modal.
+impl fmt::Display for DocDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No changes");
+        }
+        let mut lines = vec![];
+        for name in self.added_tabs.iter() {
+            lines.push(format!("\"{}\": new tab", name));
+        }
+        for name in self.removed_tabs.iter() {
+            lines.push(format!("\"{}\": tab removed", name));
+        }
+        for (name, tab_diff) in self.changed_tabs.iter() {
+            lines.push(format!("\"{}\": {}", name, tab_diff.summary()));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{checkpoint, node};
+    use serde_json::json;
+
+    const A: &str = "3f5c7a9e-1b2d-4c6e-8f0a-1b2c3d4e5f60";
+    const B: &str = "9a8b7c6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d";
+    const C: &str = "0b1c2d3e-4f5a-4b6c-8d7e-9f0a1b2c3d4e";
+
+    fn arrow(start: &str, end: &str) -> Value {
+        json!({
+            "arrow_type": "Line",
+            "start": { "id": start, "pos": "Right" },
+            "end": { "id": end, "pos": "Left" }
+        })
+    }
+
+    #[test]
+    fn test_diff_checkpoints() {
+        let old = checkpoint(
+            vec![node(A, "Start", 0.), node(B, "Middle\nmore", 200.)],
+            vec![arrow(A, B)],
+        );
+        let mut recolored = node(B, "Middle\nchanged", 200.);
+        recolored["bg_color"]["Rgba"]["red"] = json!(0.5);
+        let new = checkpoint(
+            vec![node(A, "Start", 50.), recolored, node(C, "", 400.)],
+            vec![arrow(B, C)],
+        );
+        let diff = diff_checkpoints(&old, &new).unwrap();
+        let changes: Vec<(String, Vec<ChangeKind>)> = diff
+            .nodes
+            .iter()
+            .map(|node| (node.label.clone(), node.changes.clone()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("Start".to_string(), vec![ChangeKind::Moved]),
+                (
+                    "Middle".to_string(),
+                    vec![ChangeKind::Recolored, ChangeKind::TextChanged]
+                ),
+                ("0b1c2d3e".to_string(), vec![ChangeKind::Added]),
+            ]
+        );
+        assert_eq!(diff.nodes[0].position, (Val::Px(50.), Val::Px(100.)));
+        assert_eq!(
+            diff.to_string(),
+            [
+                "\"Start\": moved",
+                "\"Middle\": recolored, text changed",
+                "\"0b1c2d3e\": added",
+                "arrow \"Middle\" -> \"0b1c2d3e\": added",
+                "arrow \"Start\" -> \"Middle\": removed",
+            ]
+            .join("\n")
+        );
+
+        let resized = {
+            let mut node = node(A, "Start", 0.);
+            node["width"] = json!({ "Px": 150.0 });
+            node
+        };
+        let diff = diff_checkpoints(&old, &checkpoint(vec![resized], vec![])).unwrap();
+        assert_eq!(
+            diff.to_string(),
+            [
+                "\"Start\": resized",
+                "\"Middle\": removed",
+                "arrow \"Start\" -> \"Middle\": removed",
+            ]
+            .join("\n")
+        );
+        assert_eq!(diff.nodes[1].position, (Val::Px(200.), Val::Px(100.)));
+
+        assert!(diff_checkpoints(&old, &old).unwrap().is_empty());
+        assert!(diff_checkpoints(&old, "{}").is_err());
+    }
+
+    #[test]
+    fn test_diff_docs() {
+        let tab = |id: &str, name: &str, checkpoint: String| crate::components::Tab {
+            is_active: false,
+            id: crate::utils::ReflectableUuid(Uuid::parse_str(id).unwrap()),
+            name: name.to_string(),
+            checkpoints: vec![checkpoint].into(),
+        };
+        let old = Doc {
+            tabs: vec![
+                tab(A, "Plan", checkpoint(vec![node(A, "Start", 0.)], vec![])),
+                tab(B, "Old", checkpoint(vec![], vec![])),
+            ],
+            ..Doc::default()
+        };
+        let new = Doc {
+            tabs: vec![
+                tab(
+                    A,
+                    "Plan",
+                    checkpoint(
+                        vec![node(A, "Start", 10.), node(B, "Next", 0.)],
+                        vec![arrow(A, B)],
+                    ),
+                ),
+                tab(C, "New", checkpoint(vec![], vec![])),
+            ],
+            ..Doc::default()
+        };
+        assert_eq!(
+            diff_docs(&old, &new).unwrap().to_string(),
+            [
+                "\"New\": new tab",
+                "\"Old\": tab removed",
+                "\"Plan\": 1 added, 1 moved, 1 arrow added",
+            ]
+            .join("\n")
+        );
+        assert!(diff_docs(&old, &old).unwrap().is_empty());
+    }
+}
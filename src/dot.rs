@@ -0,0 +1,1185 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::Write;
+
+use bevy::prelude::*;
+use serde_json::json;
+
+use crate::canvas::arrow::components::{ArrowConnect, ArrowConnectPos, ArrowMeta, ArrowType};
+use crate::diff::TabSnapshot;
+use crate::ui_plugin::{JsonNode, JsonNodeText, NodeType, TextPos};
+use crate::utils::ReflectableUuid;
+
+/// Size of imported nodes without a `width`/`height`, circles are as large as the default circle.
+const NODE_WIDTH: f32 = 160.;
+const NODE_HEIGHT: f32 = 80.;
+const CIRCLE_SIZE: f32 = 128.;
+/// Space between layers of the layout and between nodes of a layer.
+const LAYER_GAP: f32 = 80.;
+const NODE_GAP: f32 = 40.;
+const MARGIN: f32 = 20.;
+/// DOT sizes are in inches.
+const POINTS_PER_INCH: f32 = 72.;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DotError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid DOT file, line {}: {}", self.line, self.message)
+    }
+}
+
+pub type DotAttrs = HashMap<String, String>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DotNode {
+    pub id: String,
+    pub attrs: DotAttrs,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DotEdge {
+    pub from: String,
+    pub to: String,
+    pub attrs: DotAttrs,
+}
+
+/// Nodes and edges of a graph, subgraphs are flattened and attribute defaults applied.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DotGraph {
+    pub directed: bool,
+    pub name: Option<String>,
+    pub attrs: DotAttrs,
+    /// In the order they first appear.
+    pub nodes: Vec<DotNode>,
+    pub edges: Vec<DotEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id(String),
+    /// `->` (`true`) or `--`.
+    EdgeOp(bool),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Equals,
+    Semicolon,
+    Comma,
+    Colon,
+    Plus,
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, DotError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+    let mut line_start = true;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch == '\n' {
+            line += 1;
+            line_start = true;
+            i += 1;
+            continue;
+        }
+        if ch.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        // lines starting with `#` are C preprocessor output
+        if (ch == '#' && line_start) || (ch == '/' && chars.get(i + 1) == Some(&'/')) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        line_start = false;
+        if ch == '/' && chars.get(i + 1) == Some(&'*') {
+            let start_line = line;
+            i += 2;
+            loop {
+                match chars.get(i) {
+                    Some('*') if chars.get(i + 1) == Some(&'/') => {
+                        i += 2;
+                        break;
+                    }
+                    Some('\n') => line += 1,
+                    Some(_) => {}
+                    None => {
+                        return Err(DotError {
+                            line: start_line,
+                            message: "unterminated comment".to_string(),
+                        })
+                    }
+                }
+                i += 1;
+            }
+            continue;
+        }
+        let token = match ch {
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '=' => Token::Equals,
+            ';' => Token::Semicolon,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '+' => Token::Plus,
+            '-' if chars.get(i + 1) == Some(&'>') => {
+                i += 1;
+                Token::EdgeOp(true)
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                i += 1;
+                Token::EdgeOp(false)
+            }
+            '"' => {
+                let start_line = line;
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        Some('"') => break,
+                        Some('\\') if chars.get(i + 1) == Some(&'"') => {
+                            value.push('"');
+                            i += 1;
+                        }
+                        // escaped line break continues the string
+                        Some('\\') if chars.get(i + 1) == Some(&'\n') => {
+                            line += 1;
+                            i += 1;
+                        }
+                        // other escapes, e.g. `\n` in labels, are kept for the attribute
+                        Some('\\') if chars.get(i + 1).is_some() => {
+                            value.push('\\');
+                            value.push(chars[i + 1]);
+                            i += 1;
+                        }
+                        Some(ch) => {
+                            if *ch == '\n' {
+                                line += 1;
+                            }
+                            value.push(*ch);
+                        }
+                        None => {
+                            return Err(DotError {
+                                line: start_line,
+                                message: "unterminated string".to_string(),
+                            })
+                        }
+                    }
+                    i += 1;
+                }
+                Token::Id(value)
+            }
+            '<' => {
+                // HTML string, only its text is kept
+                let start_line = line;
+                let mut depth = 0;
+                let mut in_tag = false;
+                let mut value = String::new();
+                loop {
+                    match chars.get(i) {
+                        Some('<') => {
+                            if depth > 0 {
+                                in_tag = true;
+                            }
+                            depth += 1;
+                        }
+                        Some('>') => {
+                            depth -= 1;
+                            in_tag = false;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        Some(ch) => {
+                            if *ch == '\n' {
+                                line += 1;
+                            }
+                            if !in_tag {
+                                value.push(*ch);
+                            }
+                        }
+                        None => {
+                            return Err(DotError {
+                                line: start_line,
+                                message: "unterminated HTML string".to_string(),
+                            })
+                        }
+                    }
+                    i += 1;
+                }
+                Token::Id(value.trim().to_string())
+            }
+            ch if ch.is_alphanumeric() || ch == '_' || ch == '.' || ch == '-' => {
+                let start = i;
+                while i + 1 < chars.len()
+                    && (chars[i + 1].is_alphanumeric()
+                        || chars[i + 1] == '_'
+                        || chars[i + 1] == '.')
+                {
+                    i += 1;
+                }
+                Token::Id(chars[start..=i].iter().collect())
+            }
+            ch => {
+                return Err(DotError {
+                    line,
+                    message: format!("unexpected character '{}'", ch),
+                })
+            }
+        };
+        tokens.push((token, line));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+/// Attribute defaults of the current graph or subgraph.
+#[derive(Clone, Default)]
+struct Scope {
+    node: DotAttrs,
+    edge: DotAttrs,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    graph: DotGraph,
+    node_index: HashMap<String, usize>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.pos).or_else(|| self.tokens.last()) {
+            Some((_, line)) => *line,
+            None => 1,
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, DotError> {
+        Err(DotError {
+            line: self.line(),
+            message: message.into(),
+        })
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), DotError> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            self.error(format!("expected {}", what))
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Id(id)) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    /// An id, quoted strings can be joined with `+`.
+    fn id(&mut self) -> Result<String, DotError> {
+        let mut value = match self.next() {
+            Some(Token::Id(id)) => id,
+            _ => {
+                self.pos -= 1;
+                return self.error("expected an identifier");
+            }
+        };
+        while self.peek() == Some(&Token::Plus) {
+            self.pos += 1;
+            match self.next() {
+                Some(Token::Id(id)) => value.push_str(&id),
+                _ => {
+                    self.pos -= 1;
+                    return self.error("expected a string after '+'");
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    fn graph(&mut self) -> Result<(), DotError> {
+        if self.peek_keyword("strict") {
+            self.pos += 1;
+        }
+        if self.peek_keyword("digraph") {
+            self.graph.directed = true;
+        } else if !self.peek_keyword("graph") {
+            return self.error("expected 'graph' or 'digraph'");
+        }
+        self.pos += 1;
+        if let Some(Token::Id(_)) = self.peek() {
+            self.graph.name = Some(self.id()?);
+        }
+        self.expect(Token::LBrace, "'{'")?;
+        self.statements(&mut Scope::default(), true)?;
+        if self.pos < self.tokens.len() {
+            return self.error("unexpected content after the graph");
+        }
+        Ok(())
+    }
+
+    /// Statements up to the closing brace, returns the nodes they mention.
+    fn statements(&mut self, scope: &mut Scope, top_level: bool) -> Result<Vec<String>, DotError> {
+        let mut nodes = vec![];
+        loop {
+            match self.peek() {
+                Some(Token::RBrace) => {
+                    self.pos += 1;
+                    return Ok(nodes);
+                }
+                None => return self.error("expected '}'"),
+                Some(Token::Semicolon) => self.pos += 1,
+                _ => self.statement(scope, top_level, &mut nodes)?,
+            }
+        }
+    }
+
+    fn statement(
+        &mut self,
+        scope: &mut Scope,
+        top_level: bool,
+        nodes: &mut Vec<String>,
+    ) -> Result<(), DotError> {
+        let is_attr_stmt = self.tokens.get(self.pos + 1).map(|(token, _)| token)
+            == Some(&Token::LBracket)
+            && (self.peek_keyword("graph")
+                || self.peek_keyword("node")
+                || self.peek_keyword("edge"));
+        if is_attr_stmt {
+            let kind = self.id()?.to_lowercase();
+            let attrs = self.attr_lists()?;
+            match kind.as_str() {
+                "node" => scope.node.extend(attrs),
+                "edge" => scope.edge.extend(attrs),
+                _ if top_level => self.graph.attrs.extend(attrs),
+                _ => {}
+            }
+            return Ok(());
+        }
+        if let (Some(Token::Id(_)), Some((Token::Equals, _))) =
+            (self.peek(), self.tokens.get(self.pos + 1))
+        {
+            let key = self.id()?;
+            self.pos += 1;
+            let value = self.id()?;
+            if top_level {
+                self.graph.attrs.insert(key, value);
+            }
+            return Ok(());
+        }
+        let mut group = self.edge_operand(scope)?;
+        nodes.extend(group.iter().cloned());
+        let mut edges = vec![];
+        while let Some(Token::EdgeOp(directed)) = self.peek() {
+            if *directed != self.graph.directed {
+                return self.error(if self.graph.directed {
+                    "'--' in a digraph"
+                } else {
+                    "'->' in an undirected graph"
+                });
+            }
+            self.pos += 1;
+            let next = self.edge_operand(scope)?;
+            nodes.extend(next.iter().cloned());
+            for from in group.iter() {
+                for to in next.iter() {
+                    edges.push((from.clone(), to.clone()));
+                }
+            }
+            group = next;
+        }
+        let attrs = if self.peek() == Some(&Token::LBracket) {
+            self.attr_lists()?
+        } else {
+            DotAttrs::new()
+        };
+        if edges.is_empty() {
+            // node statement, or an anonymous subgraph
+            if group.len() == 1 && !attrs.is_empty() {
+                let index = self.node_index[&group[0]];
+                self.graph.nodes[index].attrs.extend(attrs);
+            }
+        } else {
+            for (from, to) in edges {
+                let mut edge_attrs = scope.edge.clone();
+                edge_attrs.extend(attrs.clone());
+                self.graph.edges.push(DotEdge {
+                    from,
+                    to,
+                    attrs: edge_attrs,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Node id or subgraph of an edge statement, returns the nodes it stands for.
+    fn edge_operand(&mut self, scope: &Scope) -> Result<Vec<String>, DotError> {
+        if self.peek_keyword("subgraph") || self.peek() == Some(&Token::LBrace) {
+            if self.peek_keyword("subgraph") {
+                self.pos += 1;
+                if let Some(Token::Id(_)) = self.peek() {
+                    self.id()?;
+                }
+            }
+            self.expect(Token::LBrace, "'{'")?;
+            let mut inner = scope.clone();
+            let mut nodes = self.statements(&mut inner, false)?;
+            let mut seen = HashSet::new();
+            nodes.retain(|node| seen.insert(node.clone()));
+            return Ok(nodes);
+        }
+        let id = self.id()?;
+        // ports aren't used
+        while self.eat(&Token::Colon) {
+            self.id()?;
+        }
+        if !self.node_index.contains_key(&id) {
+            self.node_index.insert(id.clone(), self.graph.nodes.len());
+            self.graph.nodes.push(DotNode {
+                id: id.clone(),
+                attrs: scope.node.clone(),
+            });
+        }
+        Ok(vec![id])
+    }
+
+    fn attr_lists(&mut self) -> Result<DotAttrs, DotError> {
+        let mut attrs = DotAttrs::new();
+        while self.eat(&Token::LBracket) {
+            while !self.eat(&Token::RBracket) {
+                let key = self.id()?;
+                let value = if self.eat(&Token::Equals) {
+                    self.id()?
+                } else {
+                    "true".to_string()
+                };
+                attrs.insert(key, value);
+                if !self.eat(&Token::Comma) {
+                    self.eat(&Token::Semicolon);
+                }
+            }
+        }
+        Ok(attrs)
+    }
+}
+
+pub fn parse_dot(text: &str) -> Result<DotGraph, DotError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        graph: DotGraph::default(),
+        node_index: HashMap::new(),
+    };
+    parser.graph()?;
+    Ok(parser.graph)
+}
+
+/// Label with the escapes DOT uses for line breaks and names replaced.
+fn node_label(graph: &DotGraph, node: &DotNode) -> String {
+    let label = match node.attrs.get("label") {
+        Some(label) => label,
+        None => return node.id.clone(),
+    };
+    let mut text = String::new();
+    let mut chars = label.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            text.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('l') | Some('r') => text.push('\n'),
+            Some('N') => text.push_str(&node.id),
+            Some('G') => text.push_str(graph.name.as_deref().unwrap_or_default()),
+            Some(ch) => text.push(ch),
+            None => {}
+        }
+    }
+    text.trim_end_matches('\n').to_string()
+}
+
+fn named_color(name: &str) -> Option<(u8, u8, u8)> {
+    let rgb = match name {
+        "black" => (0, 0, 0),
+        "white" => (255, 255, 255),
+        "red" => (255, 0, 0),
+        "green" => (0, 255, 0),
+        "blue" => (0, 0, 255),
+        "yellow" => (255, 255, 0),
+        "cyan" => (0, 255, 255),
+        "magenta" => (255, 0, 255),
+        "orange" => (255, 165, 0),
+        "purple" => (160, 32, 240),
+        "pink" => (255, 192, 203),
+        "brown" => (165, 42, 42),
+        "gold" => (255, 215, 0),
+        "gray" | "grey" => (192, 192, 192),
+        "darkgray" | "darkgrey" => (169, 169, 169),
+        "lightgray" | "lightgrey" => (211, 211, 211),
+        "lightblue" => (173, 216, 230),
+        "lightgreen" => (144, 238, 144),
+        "lightyellow" => (255, 255, 224),
+        "lightpink" => (255, 182, 193),
+        "lightcyan" => (224, 255, 255),
+        "palegreen" => (152, 251, 152),
+        "skyblue" => (135, 206, 235),
+        "salmon" => (250, 128, 114),
+        "khaki" => (240, 230, 140),
+        "lavender" => (230, 230, 250),
+        "beige" => (245, 245, 220),
+        "wheat" => (245, 222, 179),
+        "tomato" => (255, 99, 71),
+        "navy" => (0, 0, 128),
+        "darkgreen" => (0, 100, 0),
+        _ => return None,
+    };
+    Some(rgb)
+}
+
+/// `#rrggbb[aa]`, `h,s,v` or a color name, the first color of a list is used.
+pub fn parse_dot_color(value: &str) -> Option<Color> {
+    let value = value.split(':').next().unwrap_or_default().trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        return Color::hex(hex).ok();
+    }
+    let hsv: Vec<f32> = value
+        .split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse())
+        .collect::<Result<_, _>>()
+        .unwrap_or_default();
+    if let [h, s, v] = hsv[..] {
+        // HSV to HSL, both with components in 0..1
+        let lightness = v * (1. - s / 2.);
+        let saturation = if lightness == 0. || lightness == 1. {
+            0.
+        } else {
+            (v - lightness) / lightness.min(1. - lightness)
+        };
+        return Some(Color::hsl(h * 360., saturation, lightness));
+    }
+    named_color(&value.to_lowercase()).map(|(r, g, b)| Color::rgb_u8(r, g, b))
+}
+
+fn node_color(node: &DotNode) -> Option<Color> {
+    if let Some(color) = node.attrs.get("fillcolor").and_then(|c| parse_dot_color(c)) {
+        return Some(color);
+    }
+    let filled = matches!(node.attrs.get("style"), Some(style) if style.contains("filled"));
+    if filled {
+        node.attrs.get("color").and_then(|c| parse_dot_color(c))
+    } else {
+        None
+    }
+}
+
+fn node_type(node: &DotNode) -> NodeType {
+    match node.attrs.get("shape").map(|shape| shape.to_lowercase()) {
+        Some(shape)
+            if matches!(
+                shape.as_str(),
+                "circle" | "doublecircle" | "ellipse" | "oval"
+            ) =>
+        {
+            NodeType::Circle
+        }
+        _ => NodeType::Rect,
+    }
+}
+
+fn node_size(node: &DotNode, node_type: &NodeType) -> (f32, f32) {
+    let inches = |key: &str| -> Option<f32> {
+        node.attrs
+            .get(key)
+            .and_then(|value| value.parse::<f32>().ok())
+            .filter(|value| *value > 0.)
+            .map(|value| value * POINTS_PER_INCH)
+    };
+    match node_type {
+        NodeType::Circle => {
+            let size = inches("width")
+                .or_else(|| inches("height"))
+                .unwrap_or(CIRCLE_SIZE);
+            (size, size)
+        }
+        NodeType::Rect => (
+            inches("width").unwrap_or(NODE_WIDTH),
+            inches("height").unwrap_or(NODE_HEIGHT),
+        ),
+    }
+}
+
+/// Arrow type of the edge and whether it points from `to` to `from`.
+fn edge_arrow(graph: &DotGraph, edge: &DotEdge) -> (ArrowType, bool) {
+    let default_dir = if graph.directed { "forward" } else { "none" };
+    let dir = edge.attrs.get("dir").map_or(default_dir, String::as_str);
+    let head = edge.attrs.get("arrowhead").map(String::as_str) != Some("none");
+    let tail = edge.attrs.get("arrowtail").map(String::as_str) != Some("none");
+    let (arrow_type, reversed) = match dir {
+        "both" if head && tail => (ArrowType::DoubleArrow, false),
+        "both" if head => (ArrowType::Arrow, false),
+        "both" | "back" if tail => (ArrowType::Arrow, true),
+        "forward" if head => (ArrowType::Arrow, false),
+        _ => (ArrowType::Line, false),
+    };
+    // orthogonal edges are drawn with the parallel arrow types
+    let ortho = graph.attrs.get("splines").map(String::as_str) == Some("ortho");
+    let arrow_type = match arrow_type {
+        ArrowType::Line if ortho => ArrowType::ParallelLine,
+        ArrowType::Arrow if ortho => ArrowType::ParallelArrow,
+        ArrowType::DoubleArrow if ortho => ArrowType::ParallelDoubleArrow,
+        arrow_type => arrow_type,
+    };
+    (arrow_type, reversed)
+}
+
+/// Layers of a layered layout, edges closing a cycle are ignored.
+fn layout_layers(graph: &DotGraph, index: &HashMap<&str, usize>) -> Vec<Vec<usize>> {
+    let count = graph.nodes.len();
+    let mut successors = vec![vec![]; count];
+    for edge in graph.edges.iter() {
+        let (from, to) = (index[edge.from.as_str()], index[edge.to.as_str()]);
+        if from != to {
+            successors[from].push(to);
+        }
+    }
+    // depth first search from the nodes in order, dropping edges back to the current path
+    let mut state = vec![0u8; count];
+    let mut forward = vec![vec![]; count];
+    for root in 0..count {
+        if state[root] != 0 {
+            continue;
+        }
+        state[root] = 1;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, next)) = stack.pop() {
+            if next < successors[node].len() {
+                stack.push((node, next + 1));
+                let successor = successors[node][next];
+                match state[successor] {
+                    0 => {
+                        forward[node].push(successor);
+                        state[successor] = 1;
+                        stack.push((successor, 0));
+                    }
+                    2 => forward[node].push(successor),
+                    _ => {}
+                }
+            } else {
+                state[node] = 2;
+            }
+        }
+    }
+    // longest path layering
+    let mut incoming = vec![0; count];
+    for successors in forward.iter() {
+        for successor in successors.iter() {
+            incoming[*successor] += 1;
+        }
+    }
+    let mut layer = vec![0; count];
+    let mut ready: Vec<usize> = (0..count).filter(|node| incoming[*node] == 0).collect();
+    ready.reverse();
+    while let Some(node) = ready.pop() {
+        for successor in forward[node].iter() {
+            layer[*successor] = layer[*successor].max(layer[node] + 1);
+            incoming[*successor] -= 1;
+            if incoming[*successor] == 0 {
+                ready.push(*successor);
+            }
+        }
+    }
+    let mut layers = vec![vec![]; layer.iter().max().map_or(0, |max| max + 1)];
+    for node in 0..count {
+        layers[layer[node]].push(node);
+    }
+    // order nodes by the average position of their predecessors to reduce crossings
+    let mut predecessors = vec![vec![]; count];
+    for (node, successors) in forward.iter().enumerate() {
+        for successor in successors.iter() {
+            predecessors[*successor].push(node);
+        }
+    }
+    let mut position = vec![0.; count];
+    for layer in layers.iter_mut() {
+        let mut keys: Vec<(f32, usize)> = layer
+            .iter()
+            .enumerate()
+            .map(|(order, node)| {
+                let placed = &predecessors[*node];
+                let key = if placed.is_empty() {
+                    order as f32
+                } else {
+                    placed.iter().map(|p| position[*p]).sum::<f32>() / placed.len() as f32
+                };
+                (key, *node)
+            })
+            .collect();
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        *layer = keys.into_iter().map(|(_, node)| node).collect();
+        for (order, node) in layer.iter().enumerate() {
+            position[*node] = order as f32;
+        }
+    }
+    layers
+}
+
+/// Side of the nodes an arrow between them is attached to.
+fn connect_positions(start: (f32, f32), end: (f32, f32)) -> (ArrowConnectPos, ArrowConnectPos) {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    if dy.abs() >= dx.abs() {
+        // `bottom` grows upwards
+        if dy < 0. {
+            (ArrowConnectPos::Bottom, ArrowConnectPos::Top)
+        } else {
+            (ArrowConnectPos::Top, ArrowConnectPos::Bottom)
+        }
+    } else if dx > 0. {
+        (ArrowConnectPos::Right, ArrowConnectPos::Left)
+    } else {
+        (ArrowConnectPos::Left, ArrowConnectPos::Right)
+    }
+}
+
+/// Tab checkpoint with the graph laid out in layers, following `rankdir` (top to bottom by
+/// default). Nodes without a fill color use `default_color`, self loops are left out.
+pub fn dot_to_checkpoint(graph: &DotGraph, default_color: Color) -> String {
+    let index: HashMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.id.as_str(), i))
+        .collect();
+    let types: Vec<NodeType> = graph.nodes.iter().map(node_type).collect();
+    let sizes: Vec<(f32, f32)> = graph
+        .nodes
+        .iter()
+        .zip(types.iter())
+        .map(|(node, node_type)| node_size(node, node_type))
+        .collect();
+    let rankdir = graph
+        .attrs
+        .get("rankdir")
+        .map(|dir| dir.to_uppercase())
+        .unwrap_or_default();
+    let horizontal = rankdir == "LR" || rankdir == "RL";
+    // layers are rows, or columns when horizontal, `across` is the size along a layer
+    let across = |node: usize| {
+        if horizontal {
+            sizes[node].1
+        } else {
+            sizes[node].0
+        }
+    };
+    let along = |node: usize| {
+        if horizontal {
+            sizes[node].0
+        } else {
+            sizes[node].1
+        }
+    };
+    let layers = layout_layers(graph, &index);
+    let layer_lengths: Vec<f32> = layers
+        .iter()
+        .map(|layer| {
+            layer.iter().map(|node| across(*node)).sum::<f32>()
+                + NODE_GAP * layer.len().saturating_sub(1) as f32
+        })
+        .collect();
+    let longest = layer_lengths.iter().cloned().fold(0., f32::max);
+    let depths: Vec<f32> = layers
+        .iter()
+        .map(|layer| layer.iter().map(|node| along(*node)).fold(0., f32::max))
+        .collect();
+    let total_depth =
+        depths.iter().sum::<f32>() + LAYER_GAP * layers.len().saturating_sub(1) as f32;
+    let mut positions = vec![(0., 0.); graph.nodes.len()];
+    let mut depth_offset = 0.;
+    for (layer_index, layer) in layers.iter().enumerate() {
+        let mut offset = (longest - layer_lengths[layer_index]) / 2.;
+        for node in layer.iter() {
+            let (width, height) = sizes[*node];
+            // center of the node in layer coordinates, the first layer at 0
+            let depth = depth_offset + depths[layer_index] / 2.;
+            let cross = offset + across(*node) / 2.;
+            let (x, y) = match rankdir.as_str() {
+                "LR" => (depth, longest - cross),
+                "RL" => (total_depth - depth, longest - cross),
+                "BT" => (cross, depth),
+                _ => (cross, total_depth - depth),
+            };
+            positions[*node] = (MARGIN + x - width / 2., MARGIN + y - height / 2.);
+            offset += across(*node) + NODE_GAP;
+        }
+        depth_offset += depths[layer_index] + LAYER_GAP;
+    }
+
+    let ids: Vec<ReflectableUuid> = graph
+        .nodes
+        .iter()
+        .map(|_| ReflectableUuid::generate())
+        .collect();
+    let nodes: Vec<_> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            json!(JsonNode {
+                id: ids[i].0,
+                node_type: types[i].clone(),
+                left: Val::Px(positions[i].0),
+                bottom: Val::Px(positions[i].1),
+                width: Val::Px(sizes[i].0),
+                height: Val::Px(sizes[i].1),
+                text: JsonNodeText {
+                    text: node_label(graph, node),
+                    pos: TextPos::Center,
+                },
+                bg_color: node_color(node).unwrap_or(default_color),
+                z_index: 0,
+            })
+        })
+        .collect();
+    let center = |node: usize| {
+        (
+            positions[node].0 + sizes[node].0 / 2.,
+            positions[node].1 + sizes[node].1 / 2.,
+        )
+    };
+    let arrows: Vec<_> = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.from != edge.to)
+        .map(|edge| {
+            let (arrow_type, reversed) = edge_arrow(graph, edge);
+            let (mut start, mut end) = (index[edge.from.as_str()], index[edge.to.as_str()]);
+            if reversed {
+                std::mem::swap(&mut start, &mut end);
+            }
+            let (start_pos, end_pos) = connect_positions(center(start), center(end));
+            json!(ArrowMeta {
+                arrow_type,
+                start: ArrowConnect {
+                    id: ids[start],
+                    pos: start_pos,
+                },
+                end: ArrowConnect {
+                    id: ids[end],
+                    pos: end_pos,
+                },
+            })
+        })
+        .collect();
+    json!({
+        "images": {},
+        "nodes": nodes,
+        "arrows": arrows,
+    })
+    .to_string()
+}
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn rgb_u8(color: Color) -> (u8, u8, u8) {
+    let channel = |value: f32| (value * 255.).round() as u8;
+    (channel(color.r()), channel(color.g()), channel(color.b()))
+}
+
+fn px(value: &Val) -> Option<f32> {
+    match value {
+        Val::Px(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// Writes the nodes and arrows of a tab checkpoint as a digraph, node ids are the node UUIDs so
+/// exporting the same tab again gives the same ids.
+pub fn export_dot(name: &str, checkpoint: &str) -> Result<String, String> {
+    let snapshot = TabSnapshot::parse(checkpoint)?;
+    let mut dot = String::new();
+    let _ = writeln!(dot, "digraph {} {{", quote(name));
+    let is_parallel = |arrow: &ArrowMeta| {
+        matches!(
+            arrow.arrow_type,
+            ArrowType::ParallelLine | ArrowType::ParallelArrow | ArrowType::ParallelDoubleArrow
+        )
+    };
+    if !snapshot.arrows.is_empty() && snapshot.arrows.iter().all(is_parallel) {
+        let _ = writeln!(dot, "    splines=ortho;");
+    }
+    for node in snapshot.nodes.iter() {
+        let shape = match node.node_type {
+            NodeType::Rect => "box",
+            NodeType::Circle => "circle",
+        };
+        let (r, g, b) = rgb_u8(node.bg_color);
+        let mut attrs = vec![
+            format!("label={}", quote(&node.text.text)),
+            format!("shape={}", shape),
+            "style=filled".to_string(),
+            format!("fillcolor=\"#{:02x}{:02x}{:02x}\"", r, g, b),
+        ];
+        if let (Some(width), Some(height)) = (px(&node.width), px(&node.height)) {
+            attrs.push(format!("width={:.2}", width / POINTS_PER_INCH));
+            attrs.push(format!("height={:.2}", height / POINTS_PER_INCH));
+        }
+        let _ = writeln!(
+            dot,
+            "    {} [{}];",
+            quote(&node.id.to_string()),
+            attrs.join(", ")
+        );
+    }
+    let node_ids: HashSet<_> = snapshot.nodes.iter().map(|node| node.id).collect();
+    for arrow in snapshot.arrows.iter() {
+        if !node_ids.contains(&arrow.start.id.0) || !node_ids.contains(&arrow.end.id.0) {
+            continue;
+        }
+        let attrs = match arrow.arrow_type {
+            ArrowType::Line | ArrowType::ParallelLine => " [dir=none]",
+            ArrowType::Arrow | ArrowType::ParallelArrow => "",
+            ArrowType::DoubleArrow | ArrowType::ParallelDoubleArrow => " [dir=both]",
+        };
+        let _ = writeln!(
+            dot,
+            "    {} -> {}{};",
+            quote(&arrow.start.id.0.to_string()),
+            quote(&arrow.end.id.0.to_string()),
+            attrs
+        );
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(graph: &DotGraph, id: &str) -> DotAttrs {
+        graph
+            .nodes
+            .iter()
+            .find(|node| node.id == id)
+            .unwrap()
+            .attrs
+            .clone()
+    }
+
+    #[test]
+    fn test_parse_dot() {
+        let graph = parse_dot(
+            r##"
+            # generated
+            strict digraph deps {
+                rankdir = LR; // left to right
+                node [shape=box, style=filled];
+                app [label="Velo\napp" fillcolor="#ff8800"];
+                /* edges */
+                app -> { core; "ui plugin" } -> utils [arrowhead=none];
+                subgraph cluster_x { node [color=red]; x; }
+                "a" + "b":port:n -> app;
+            }
+            "##,
+        )
+        .unwrap();
+        assert!(graph.directed);
+        assert_eq!(graph.name.as_deref(), Some("deps"));
+        assert_eq!(graph.attrs["rankdir"], "LR");
+        let ids: Vec<&str> = graph.nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, vec!["app", "core", "ui plugin", "utils", "x", "ab"]);
+        assert_eq!(attrs(&graph, "app")["label"], "Velo\\napp");
+        assert_eq!(attrs(&graph, "app")["fillcolor"], "#ff8800");
+        assert_eq!(attrs(&graph, "core")["shape"], "box");
+        // defaults of a subgraph stay inside it
+        assert_eq!(attrs(&graph, "x")["color"], "red");
+        assert!(!attrs(&graph, "ab").contains_key("color"));
+        let edges: Vec<(&str, &str)> = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("app", "core"),
+                ("app", "ui plugin"),
+                ("core", "utils"),
+                ("ui plugin", "utils"),
+                ("ab", "app"),
+            ]
+        );
+        assert_eq!(graph.edges[0].attrs["arrowhead"], "none");
+    }
+
+    #[test]
+    fn test_parse_dot_errors() {
+        assert_eq!(
+            parse_dot("digraph {\n a -- b\n}").unwrap_err(),
+            DotError {
+                line: 2,
+                message: "'--' in a digraph".to_string()
+            }
+        );
+        assert_eq!(parse_dot("graph { a [label=\"x }").unwrap_err().line, 1);
+        assert!(parse_dot("digraph { a -> }").is_err());
+        assert!(parse_dot("flowchart { a }").is_err());
+        assert!(parse_dot("graph { a } b").is_err());
+    }
+
+    #[test]
+    fn test_parse_dot_color() {
+        assert_eq!(parse_dot_color("#ff0000"), Some(Color::rgb_u8(255, 0, 0)));
+        assert_eq!(
+            parse_dot_color("LightBlue:red"),
+            Some(Color::rgb_u8(173, 216, 230))
+        );
+        assert_eq!(rgb_u8(parse_dot_color("0.0 1.0 1.0").unwrap()), (255, 0, 0));
+        assert_eq!(parse_dot_color("/x11/unknown"), None);
+    }
+
+    #[test]
+    fn test_dot_to_checkpoint() {
+        let graph = parse_dot(
+            "digraph {
+                a [label=Start shape=circle];
+                b [style=filled color=red];
+                a -> b;
+                b -> c [dir=back];
+                c -> a [dir=both];
+                a -> a;
+                c -> d [arrowhead=none];
+            }",
+        )
+        .unwrap();
+        let snapshot = TabSnapshot::parse(&dot_to_checkpoint(&graph, Color::WHITE)).unwrap();
+        assert_eq!(snapshot.labels(), vec!["Start", "b", "c", "d"]);
+        assert_eq!(snapshot.nodes[0].node_type, NodeType::Circle);
+        assert_eq!(snapshot.nodes[1].bg_color, Color::rgb_u8(255, 0, 0));
+        assert_eq!(snapshot.nodes[2].bg_color, Color::WHITE);
+        // a, b, c and d are in separate layers from top to bottom, the cycle is broken
+        let bottoms: Vec<f32> = snapshot
+            .nodes
+            .iter()
+            .map(|node| px(&node.bottom).unwrap() + px(&node.height).unwrap() / 2.)
+            .collect();
+        assert!(bottoms.windows(2).all(|pair| pair[0] > pair[1]));
+        let ids: Vec<_> = snapshot.nodes.iter().map(|node| node.id).collect();
+        let arrows: Vec<_> = snapshot
+            .arrows
+            .iter()
+            .map(|arrow| {
+                let start = ids.iter().position(|id| *id == arrow.start.id.0).unwrap();
+                let end = ids.iter().position(|id| *id == arrow.end.id.0).unwrap();
+                (start, end, arrow.arrow_type)
+            })
+            .collect();
+        assert_eq!(
+            arrows,
+            vec![
+                (0, 1, ArrowType::Arrow),
+                (2, 1, ArrowType::Arrow),
+                (2, 0, ArrowType::DoubleArrow),
+                (2, 3, ArrowType::Line),
+            ]
+        );
+        assert_eq!(snapshot.arrows[0].start.pos, ArrowConnectPos::Bottom);
+        assert_eq!(snapshot.arrows[0].end.pos, ArrowConnectPos::Top);
+    }
+
+    #[test]
+    fn test_dot_layout_directions() {
+        let graph = parse_dot("graph { rankdir=LR; splines=ortho; a -- b; a -- c }").unwrap();
+        let snapshot = TabSnapshot::parse(&dot_to_checkpoint(&graph, Color::WHITE)).unwrap();
+        let left = |i: usize| px(&snapshot.nodes[i].left).unwrap();
+        let bottom = |i: usize| px(&snapshot.nodes[i].bottom).unwrap();
+        assert!(left(0) < left(1));
+        assert_eq!(left(1), left(2));
+        // the first node of a layer is at the top
+        assert!(bottom(1) > bottom(2));
+        assert_eq!(snapshot.arrows[0].arrow_type, ArrowType::ParallelLine);
+        assert_eq!(snapshot.arrows[0].start.pos, ArrowConnectPos::Right);
+        assert!(snapshot
+            .nodes
+            .iter()
+            .all(|node| px(&node.left).unwrap() >= MARGIN && px(&node.bottom).unwrap() >= MARGIN));
+    }
+
+    #[test]
+    fn test_export_dot() {
+        let graph = parse_dot(
+            r##"digraph {
+                a [label="Say \"hi\"\nthere" shape=circle fillcolor="#102030" width=2];
+                a -> b [dir=none];
+                b -> a [dir=both];
+            }"##,
+        )
+        .unwrap();
+        let checkpoint = dot_to_checkpoint(&graph, Color::WHITE);
+        let snapshot = TabSnapshot::parse(&checkpoint).unwrap();
+        let dot = export_dot("Deps \"v2\"", &checkpoint).unwrap();
+        let (a, b) = (snapshot.nodes[0].id, snapshot.nodes[1].id);
+        assert_eq!(
+            dot,
+            format!(
+                r##"digraph "Deps \"v2\"" {{
+    "{a}" [label="Say \"hi\"\nthere", shape=circle, style=filled, fillcolor="#102030", width=2.00, height=2.00];
+    "{b}" [label="b", shape=box, style=filled, fillcolor="#ffffff", width=2.22, height=1.11];
+    "{a}" -> "{b}" [dir=none];
+    "{b}" -> "{a}" [dir=both];
+}}
+"##
+            )
+        );
+        // ids are stable and the export can be imported again
+        assert_eq!(export_dot("Deps \"v2\"", &checkpoint).unwrap(), dot);
+        let imported = parse_dot(&dot).unwrap();
+        let copy = TabSnapshot::parse(&dot_to_checkpoint(&imported, Color::BLACK)).unwrap();
+        assert_eq!(copy.labels(), snapshot.labels());
+        assert_eq!(copy.nodes[0].text.text, "Say \"hi\"\nthere");
+        assert_eq!(copy.nodes[0].bg_color, snapshot.nodes[0].bg_color);
+        assert_eq!(px(&copy.nodes[0].width), Some(144.));
+    }
+}
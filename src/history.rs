@@ -0,0 +1,405 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::{Doc, Tab};
+use crate::config::HistoryConfig;
+use crate::diff::TabSnapshot;
+use crate::import::remap_ids;
+use crate::utils::ReflectableUuid;
+
+const MS_PER_MINUTE: f64 = 60. * 1000.;
+const MS_PER_DAY: f64 = 24. * 60. * MS_PER_MINUTE;
+const PREVIEW_LABELS: usize = 3;
+const MAX_COMPARED_VERSIONS: usize = 8;
+
+/// Snapshot of a document. Versions are stored per document under [`history_key`], so that
+/// they aren't loaded with the `docs` map.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Version {
+    pub id: ReflectableUuid,
+    /// Set for versions created by the user, automatic versions have no name.
+    pub name: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: f64,
+    pub doc: Doc,
+}
+
+pub fn history_key(doc_id: ReflectableUuid) -> String {
+    format!("history-{}", doc_id.0)
+}
+
+impl Version {
+    /// Only the latest checkpoint of every tab is kept.
+    pub fn new(doc: &Doc, name: Option<String>, timestamp: f64) -> Self {
+        let tabs = doc
+            .tabs
+            .iter()
+            .map(|tab| Tab {
+                checkpoints: tab.checkpoints.back().cloned().into_iter().collect(),
+                ..tab.clone()
+            })
+            .collect();
+        Self {
+            id: ReflectableUuid::generate(),
+            name,
+            timestamp,
+            doc: Doc {
+                tabs,
+                shared: None,
+                ..doc.clone()
+            },
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or("Automatic")
+    }
+
+    /// Number of tabs and nodes, and the first nodes of the active tab.
+    pub fn preview(&self) -> String {
+        let snapshots: Vec<(bool, TabSnapshot)> = self
+            .doc
+            .tabs
+            .iter()
+            .filter_map(|tab| {
+                let checkpoint = tab.checkpoints.back()?;
+                Some((tab.is_active, TabSnapshot::parse(checkpoint).ok()?))
+            })
+            .collect();
+        let nodes: usize = snapshots
+            .iter()
+            .map(|(_, snapshot)| snapshot.nodes.len())
+            .sum();
+        let mut preview = format!(
+            "{} {}, {} {}",
+            self.doc.tabs.len(),
+            plural(self.doc.tabs.len(), "tab"),
+            nodes,
+            plural(nodes, "node")
+        );
+        let active = snapshots
+            .iter()
+            .find(|(is_active, _)| *is_active)
+            .or(snapshots.first());
+        if let Some((_, snapshot)) = active {
+            let labels = snapshot.labels();
+            if !labels.is_empty() {
+                let mut shown: Vec<String> = labels.iter().take(PREVIEW_LABELS).cloned().collect();
+                if labels.len() > PREVIEW_LABELS {
+                    shown.push("...".to_string());
+                }
+                preview.push_str(&format!(": {}", shown.join(", ")));
+            }
+        }
+        preview
+    }
+
+    /// Same content as the document, ignoring earlier checkpoints.
+    fn matches(&self, doc: &Doc) -> bool {
+        let other = Version::new(doc, None, 0.);
+        serde_json::to_value(&self.doc.tabs).ok() == serde_json::to_value(&other.doc.tabs).ok()
+            && self.doc.name == other.doc.name
+            && self.doc.tags == other.doc.tags
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        word.to_string()
+    } else {
+        format!("{}s", word)
+    }
+}
+
+/// Whether an automatic version of the document should be added to its history.
+pub fn auto_version_due(history: &[Version], doc: &Doc, config: &HistoryConfig, now: f64) -> bool {
+    if config.auto_interval == 0 {
+        return false;
+    }
+    match history.last() {
+        Some(last) => {
+            now - last.timestamp >= config.auto_interval as f64 * MS_PER_MINUTE
+                && !last.matches(doc)
+        }
+        None => true,
+    }
+}
+
+/// Removes automatic versions that are too old or too many, returns how many were removed.
+pub fn prune(history: &mut Vec<Version>, config: &HistoryConfig, now: f64) -> usize {
+    let len = history.len();
+    if config.max_age_days > 0 {
+        let max_age = config.max_age_days as f64 * MS_PER_DAY;
+        history.retain(|version| version.name.is_some() || now - version.timestamp <= max_age);
+    }
+    let auto_versions = history
+        .iter()
+        .filter(|version| version.name.is_none())
+        .count();
+    let mut to_remove = auto_versions.saturating_sub(config.max_auto_versions);
+    history.retain(|version| {
+        if version.name.is_none() && to_remove > 0 {
+            to_remove -= 1;
+            return false;
+        }
+        true
+    });
+    len - history.len()
+}
+
+/// Document with the content of the version. Checkpoints of tabs that still exist are kept, so
+/// the restore can be undone tab by tab.
+pub fn restore_version(doc: &Doc, version: &Version) -> Doc {
+    let tabs = version
+        .doc
+        .tabs
+        .iter()
+        .map(|tab| {
+            let mut checkpoints = doc
+                .tabs
+                .iter()
+                .find(|current| current.id == tab.id)
+                .map(|current| current.checkpoints.clone())
+                .unwrap_or_default();
+            checkpoints.extend(tab.checkpoints.iter().cloned());
+            Tab {
+                checkpoints,
+                ..tab.clone()
+            }
+        })
+        .collect();
+    // the kept checkpoints may still show images of the current document
+    let mut inline_images = doc.inline_images.clone();
+    inline_images.extend(version.doc.inline_images.clone());
+    Doc {
+        tabs,
+        name: version.doc.name.clone(),
+        tags: version.doc.tags.clone(),
+        inline_images,
+        ..doc.clone()
+    }
+}
+
+/// New document with the content of the version, next to the original.
+pub fn restore_version_as_copy(version: &Version) -> Doc {
+    let mut doc = remap_ids(&version.doc);
+    doc.name = format!("{} ({})", version.doc.name, version.label());
+    doc
+}
+
+/// Age of a version like "5 min ago".
+pub fn format_age(timestamp: f64, now: f64) -> String {
+    let minutes = ((now - timestamp) / MS_PER_MINUTE).max(0.) as u64;
+    match minutes {
+        0 => "just now".to_string(),
+        1..=59 => format!("{} min ago", minutes),
+        60..=1439 => format!("{} h ago", minutes / 60),
+        _ => {
+            let days = minutes / (24 * 60);
+            format!("{} {} ago", days, plural(days as usize, "day"))
+        }
+    }
+}
+
+/// Checkpoint of a tab that can be picked as a side of the diff view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompareSource {
+    pub label: String,
+    pub checkpoint: String,
+}
+
+/// Checkpoints of a tab newest first, followed by the tab in the newest versions.
+pub fn compare_sources(tab: &Tab, history: &[Version], now: f64) -> Vec<CompareSource> {
+    let checkpoints = tab
+        .checkpoints
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, checkpoint)| CompareSource {
+            label: match i {
+                0 => "Current".to_string(),
+                _ => format!("Checkpoint -{}", i),
+            },
+            checkpoint: checkpoint.clone(),
+        });
+    let mut versions: Vec<&Version> = history.iter().collect();
+    versions.sort_by(|a, b| b.timestamp.total_cmp(&a.timestamp));
+    let versions = versions
+        .into_iter()
+        .filter_map(|version| {
+            let version_tab = version.doc.tabs.iter().find(|t| t.id == tab.id)?;
+            Some(CompareSource {
+                label: format!(
+                    "{} ({})",
+                    version.label(),
+                    format_age(version.timestamp, now)
+                ),
+                checkpoint: version_tab.checkpoints.back()?.clone(),
+            })
+        })
+        .take(MAX_COMPARED_VERSIONS);
+    checkpoints.chain(versions).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{checkpoint, node};
+
+    fn doc(text: &str) -> Doc {
+        let text_checkpoint = |text: &str| {
+            checkpoint(
+                vec![node("3f5c7a9e-1b2d-4c6e-8f0a-1b2c3d4e5f60", text, 0.)],
+                vec![],
+            )
+        };
+        Doc {
+            tabs: vec![Tab {
+                is_active: true,
+                id: ReflectableUuid::generate(),
+                name: "Tab 1".to_string(),
+                checkpoints: vec![text_checkpoint("old"), text_checkpoint(text)].into(),
+            }],
+            id: ReflectableUuid::generate(),
+            name: "Plan".to_string(),
+            tags: vec![],
+            shared: None,
+            inline_images: Default::default(),
+        }
+    }
+
+    fn version(name: Option<&str>, minutes_ago: f64, now: f64) -> Version {
+        Version::new(
+            &doc("text"),
+            name.map(|name| name.to_string()),
+            now - minutes_ago * MS_PER_MINUTE,
+        )
+    }
+
+    #[test]
+    fn test_new_version() {
+        let version = Version::new(&doc("Start"), Some("Draft".to_string()), 0.);
+        assert_eq!(version.doc.tabs[0].checkpoints.len(), 1);
+        assert_eq!(version.label(), "Draft");
+        assert_eq!(version.preview(), "1 tab, 1 node: Start");
+    }
+
+    #[test]
+    fn test_auto_version_due() {
+        let config = HistoryConfig::default();
+        let now = 1_000_000_000.;
+        let current = doc("text");
+        assert!(auto_version_due(&[], &current, &config, now));
+        let recent = Version::new(&current, None, now - 10. * MS_PER_MINUTE);
+        assert!(!auto_version_due(&[recent], &current, &config, now));
+        let old = Version::new(&doc("other"), None, now - 31. * MS_PER_MINUTE);
+        assert!(auto_version_due(&[old], &current, &config, now));
+        // nothing changed since the last version
+        let unchanged = Version::new(&current, None, now - 31. * MS_PER_MINUTE);
+        assert!(!auto_version_due(&[unchanged], &current, &config, now));
+        let disabled = HistoryConfig {
+            auto_interval: 0,
+            ..config
+        };
+        assert!(!auto_version_due(&[], &current, &disabled, now));
+    }
+
+    #[test]
+    fn test_prune() {
+        let now = 100. * MS_PER_DAY;
+        let config = HistoryConfig {
+            auto_interval: 30,
+            max_auto_versions: 2,
+            max_age_days: 7,
+        };
+        let mut history = vec![
+            version(Some("Kickoff"), 60. * 24. * 60., now),
+            version(None, 10. * 24. * 60., now),
+            version(None, 3. * 24. * 60., now),
+            version(None, 60., now),
+            version(None, 10., now),
+        ];
+        assert_eq!(prune(&mut history, &config, now), 2);
+        let ages: Vec<String> = history
+            .iter()
+            .map(|version| {
+                format!(
+                    "{}: {}",
+                    version.label(),
+                    format_age(version.timestamp, now)
+                )
+            })
+            .collect();
+        assert_eq!(
+            ages,
+            vec![
+                "Kickoff: 60 days ago",
+                "Automatic: 1 h ago",
+                "Automatic: 10 min ago"
+            ]
+        );
+        assert_eq!(prune(&mut history, &config, now), 0);
+    }
+
+    #[test]
+    fn test_restore_version() {
+        let current = doc("current");
+        let mut old = current.clone();
+        old.name = "Old plan".to_string();
+        old.tabs[0]
+            .checkpoints
+            .push_back(old.tabs[0].checkpoints[0].clone());
+        let version = Version::new(&old, Some("v1".to_string()), 0.);
+
+        let restored = restore_version(&current, &version);
+        assert_eq!(restored.id, current.id);
+        assert_eq!(restored.name, "Old plan");
+        // the current checkpoints are kept for undo
+        assert_eq!(restored.tabs[0].checkpoints.len(), 3);
+        assert_eq!(
+            restored.tabs[0].checkpoints.back(),
+            old.tabs[0].checkpoints.back()
+        );
+
+        let copy = restore_version_as_copy(&version);
+        assert_ne!(copy.id, current.id);
+        assert_ne!(copy.tabs[0].id, current.tabs[0].id);
+        assert_eq!(copy.name, "Old plan (v1)");
+    }
+
+    #[test]
+    fn test_format_age() {
+        let now = 10. * MS_PER_DAY;
+        assert_eq!(format_age(now, now), "just now");
+        assert_eq!(format_age(now - 5. * MS_PER_MINUTE, now), "5 min ago");
+        assert_eq!(format_age(now - 150. * MS_PER_MINUTE, now), "2 h ago");
+        assert_eq!(format_age(now - MS_PER_DAY, now), "1 day ago");
+        assert_eq!(format_age(now + MS_PER_MINUTE, now), "just now");
+    }
+
+    #[test]
+    fn test_compare_sources() {
+        let now = 10. * MS_PER_DAY;
+        let current = doc("new");
+        let older = Version::new(
+            &current,
+            Some("Draft".to_string()),
+            now - 90. * MS_PER_MINUTE,
+        );
+        let newer = Version::new(&current, None, now - 5. * MS_PER_MINUTE);
+        // versions of other tabs have nothing to compare with
+        let other = version(Some("Other"), 1., now);
+        let sources = compare_sources(&current.tabs[0], &[older, other, newer], now);
+        let labels: Vec<&str> = sources.iter().map(|source| source.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            [
+                "Current",
+                "Checkpoint -1",
+                "Automatic (5 min ago)",
+                "Draft (1 h ago)"
+            ]
+        );
+        assert_eq!(sources[0].checkpoint, current.tabs[0].checkpoints[1]);
+        assert_eq!(sources[1].checkpoint, current.tabs[0].checkpoints[0]);
+    }
+}
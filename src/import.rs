@@ -0,0 +1,692 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::canvas::arrow::components::ArrowMeta;
+use crate::components::{Doc, Tab};
+use crate::config::ImportConfig;
+use crate::ui_plugin::JsonNode;
+use crate::utils::{dedup_tags, ReflectableUuid};
+
+pub type ImportCallback = Box<dyn FnOnce(Result<Doc, ImportError>) + Send>;
+
+/// Content types documents are served with, gists use text/plain.
+const DOCUMENT_CONTENT_TYPES: [&str; 3] =
+    ["application/json", "text/plain", "application/octet-stream"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    InvalidUrl,
+    Network(String),
+    Status { status: u16, status_text: String },
+    TooLarge { limit: usize },
+    ContentType(String),
+    NotUtf8,
+    InvalidJson(String),
+    InvalidDocument(String),
+    File(String),
+    TimedOut { seconds: u64 },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::InvalidUrl => write!(f, "Enter a single http(s) URL"),
+            ImportError::Network(e) => write!(f, "Failed to load document: {}", e),
+            ImportError::Status {
+                status,
+                status_text,
+            } => write!(f, "Server responded with {} {}", status, status_text),
+            ImportError::TooLarge { limit } => {
+                write!(f, "Document is larger than {} KiB", limit / 1024)
+            }
+            ImportError::ContentType(content_type) => write!(
+                f,
+                "Expected a JSON document, got {} (for gists use the \"Raw\" link)",
+                content_type
+            ),
+            ImportError::NotUtf8 => write!(f, "Document is not valid UTF-8"),
+            ImportError::InvalidJson(e) => write!(f, "Document is not valid JSON: {}", e),
+            ImportError::InvalidDocument(e) => write!(f, "Not a Velo document: {}", e),
+            ImportError::File(e) => write!(f, "Failed to read document: {}", e),
+            ImportError::TimedOut { seconds } => {
+                write!(f, "No response after {} seconds", seconds)
+            }
+        }
+    }
+}
+
+/// URL typed into the import modal, `file://` URLs are accepted on native targets.
+pub fn parse_import_url(text: &str) -> Result<String, ImportError> {
+    let text = text.trim();
+    #[cfg(not(target_arch = "wasm32"))]
+    if text.starts_with("file://") {
+        return Ok(text.to_string());
+    }
+    let mut finder = linkify::LinkFinder::new();
+    finder.kinds(&[linkify::LinkKind::Url]);
+    let links: Vec<_> = finder.links(text).collect();
+    match links.as_slice() {
+        [link] if link.start() == 0 && link.end() == text.len() => {
+            let url = link.as_str();
+            if url.starts_with("http://") || url.starts_with("https://") {
+                Ok(url.to_string())
+            } else {
+                Err(ImportError::InvalidUrl)
+            }
+        }
+        _ => Err(ImportError::InvalidUrl),
+    }
+}
+
+fn header<'a>(response: &'a ehttp::Response, name: &str) -> Option<&'a str> {
+    response
+        .headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Checks status, announced size and content type of a response, before its body is read.
+fn check_headers(response: &ehttp::Response, max_size: usize) -> Result<(), ImportError> {
+    if !response.ok {
+        return Err(ImportError::Status {
+            status: response.status,
+            status_text: response.status_text.clone(),
+        });
+    }
+    let content_length =
+        header(response, "content-length").and_then(|value| value.parse::<usize>().ok());
+    if content_length.unwrap_or(0) > max_size {
+        return Err(ImportError::TooLarge { limit: max_size });
+    }
+    if let Some(content_type) = header(response, "content-type") {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        if !DOCUMENT_CONTENT_TYPES.contains(&mime.as_str()) && !mime.ends_with("+json") {
+            return Err(ImportError::ContentType(mime));
+        }
+    }
+    Ok(())
+}
+
+/// Checks status, size and content type of a response and returns its body.
+pub fn check_response(response: &ehttp::Response, max_size: usize) -> Result<&str, ImportError> {
+    check_headers(response, max_size)?;
+    if response.bytes.len() > max_size {
+        return Err(ImportError::TooLarge { limit: max_size });
+    }
+    std::str::from_utf8(&response.bytes).map_err(|_| ImportError::NotUtf8)
+}
+
+/// Requests a URL with a deadline for the whole request, the body is only read if the headers
+/// pass [`check_headers`] and at most one byte past `max_size` is read.
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch_limited(url: &str, config: &ImportConfig) -> Result<ehttp::Response, ImportError> {
+    use std::io::Read;
+
+    let io_error = |e: &std::io::Error| match e.kind() {
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => ImportError::TimedOut {
+            seconds: config.timeout,
+        },
+        _ => ImportError::Network(e.to_string()),
+    };
+    let agent = ureq::AgentBuilder::new()
+        .timeout(std::time::Duration::from_secs(config.timeout))
+        .build();
+    let (ok, response) = match agent.get(url).call() {
+        Ok(response) => (true, response),
+        Err(ureq::Error::Status(_, response)) => (false, response),
+        Err(ureq::Error::Transport(e)) => {
+            return Err(
+                match std::error::Error::source(&e)
+                    .and_then(|source| source.downcast_ref::<std::io::Error>())
+                {
+                    Some(source) => io_error(source),
+                    None => ImportError::Network(e.to_string()),
+                },
+            );
+        }
+    };
+    let mut limited = ehttp::Response {
+        url: response.get_url().to_string(),
+        ok,
+        status: response.status(),
+        status_text: response.status_text().to_string(),
+        headers: response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name)?.to_string();
+                Some((name.to_ascii_lowercase(), value))
+            })
+            .collect(),
+        bytes: vec![],
+    };
+    check_headers(&limited, config.max_size)?;
+    response
+        .into_reader()
+        .take(config.max_size as u64 + 1)
+        .read_to_end(&mut limited.bytes)
+        .map_err(|e| io_error(&e))?;
+    Ok(limited)
+}
+
+pub fn parse_document(json: &str) -> Result<Doc, ImportError> {
+    let doc: Doc = serde_json::from_str(json).map_err(|e| {
+        if e.is_syntax() || e.is_eof() {
+            ImportError::InvalidJson(e.to_string())
+        } else {
+            ImportError::InvalidDocument(e.to_string())
+        }
+    })?;
+    validate_document(&doc).map_err(ImportError::InvalidDocument)?;
+    Ok(doc)
+}
+
+/// Checks what loading the tabs of the document relies on.
+fn validate_document(doc: &Doc) -> Result<(), String> {
+    if doc.tabs.is_empty() {
+        return Err("document has no tabs".to_string());
+    }
+    let mut tab_ids = HashSet::new();
+    for tab in doc.tabs.iter() {
+        if !tab_ids.insert(tab.id) {
+            return Err(format!("duplicate tab id {}", tab.id.0));
+        }
+        for checkpoint in tab.checkpoints.iter() {
+            validate_checkpoint(checkpoint).map_err(|e| format!("tab \"{}\": {}", tab.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_checkpoint(checkpoint: &str) -> Result<(), String> {
+    let json: Value = serde_json::from_str(checkpoint).map_err(|e| e.to_string())?;
+    if !json["images"].is_object() {
+        return Err("missing images".to_string());
+    }
+    let nodes = json["nodes"].as_array().ok_or("missing nodes")?;
+    for node in nodes {
+        serde_json::from_value::<JsonNode>(node.clone())
+            .map_err(|e| format!("invalid node: {}", e))?;
+    }
+    let arrows = json["arrows"].as_array().ok_or("missing arrows")?;
+    for arrow in arrows {
+        serde_json::from_value::<ArrowMeta>(arrow.clone())
+            .map_err(|e| format!("invalid arrow: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_document_file(path: &std::path::Path, max_size: usize) -> Result<Doc, ImportError> {
+    let file_error = |e: std::io::Error| ImportError::File(format!("{}: {}", path.display(), e));
+    let size = std::fs::metadata(path).map_err(file_error)?.len();
+    if size > max_size as u64 {
+        return Err(ImportError::TooLarge { limit: max_size });
+    }
+    let bytes = std::fs::read(path).map_err(file_error)?;
+    parse_document(std::str::from_utf8(&bytes).map_err(|_| ImportError::NotUtf8)?)
+}
+
+/// Loads a document from a URL returned by [`parse_import_url`]. In the browser requests can't
+/// be cancelled, so the timeout is left to the caller there.
+pub fn fetch_document(url: String, config: &ImportConfig, callback: ImportCallback) {
+    let max_size = config.max_size;
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        if let Some(path) = url.strip_prefix("file://") {
+            callback(read_document_file(std::path::Path::new(path), max_size));
+            return;
+        }
+        let config = config.clone();
+        std::thread::spawn(move || {
+            callback(
+                fetch_limited(&url, &config)
+                    .and_then(|response| parse_document(check_response(&response, max_size)?)),
+            )
+        });
+    }
+    #[cfg(target_arch = "wasm32")]
+    ehttp::fetch(ehttp::Request::get(url), move |result| {
+        callback(
+            result
+                .map_err(ImportError::Network)
+                .and_then(|response| parse_document(check_response(&response, max_size)?)),
+        )
+    });
+}
+
+/// Copy of a document that can live next to the original, with new ids for the
+/// document, its tabs and nodes. Node ids are shared across checkpoints, so undo keeps working.
+pub fn remap_ids(doc: &Doc) -> Doc {
+    let mut node_ids: HashMap<String, String> = HashMap::new();
+    let mut remap = |id: &str| {
+        node_ids
+            .entry(id.to_string())
+            .or_insert_with(|| ReflectableUuid::generate().0.to_string())
+            .clone()
+    };
+    let tabs = doc
+        .tabs
+        .iter()
+        .map(|tab| Tab {
+            id: ReflectableUuid::generate(),
+            checkpoints: tab
+                .checkpoints
+                .iter()
+                .map(|checkpoint| remap_checkpoint(checkpoint, &mut remap))
+                .collect(),
+            ..tab.clone()
+        })
+        .collect();
+    Doc {
+        id: ReflectableUuid::generate(),
+        name: doc.name.clone(),
+        tabs,
+        tags: doc.tags.clone(),
+        // the copy hasn't been shared
+        shared: None,
+        inline_images: doc.inline_images.clone(),
+    }
+}
+
+fn remap_checkpoint(checkpoint: &str, remap: &mut impl FnMut(&str) -> String) -> String {
+    let mut json: Value = match serde_json::from_str(checkpoint) {
+        Ok(json) => json,
+        // validated on import
+        Err(_) => return checkpoint.to_string(),
+    };
+    if let Some(images) = json["images"].as_object_mut() {
+        let remapped: serde_json::Map<String, Value> = std::mem::take(images)
+            .into_iter()
+            .map(|(id, image)| (remap(&id), image))
+            .collect();
+        *images = remapped;
+    }
+    if let Some(nodes) = json["nodes"].as_array_mut() {
+        for node in nodes.iter_mut() {
+            if let Some(id) = node["id"].as_str() {
+                let id = remap(id);
+                node["id"] = Value::String(id);
+            }
+        }
+    }
+    if let Some(arrows) = json["arrows"].as_array_mut() {
+        for arrow in arrows.iter_mut() {
+            for end in ["start", "end"] {
+                if let Some(id) = arrow[end]["id"].as_str() {
+                    let id = remap(id);
+                    arrow[end]["id"] = Value::String(id);
+                }
+            }
+        }
+    }
+    if let Some(cards) = json["board"]["cards"].as_array_mut() {
+        for card in cards.iter_mut() {
+            if let Some(id) = card["id"].as_str() {
+                let id = remap(id);
+                card["id"] = Value::String(id);
+            }
+        }
+    }
+    json.to_string()
+}
+
+/// Names of the tabs changed by [`merge_docs`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MergeSummary {
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    pub kept: Vec<String>,
+}
+
+impl fmt::Display for MergeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |names: &[String]| {
+            if names.is_empty() {
+                "none".to_string()
+            } else {
+                names
+                    .iter()
+                    .map(|name| format!("\"{}\"", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        };
+        writeln!(f, "Added tabs: {}", list(&self.added))?;
+        writeln!(f, "Replaced tabs: {}", list(&self.replaced))?;
+        write!(f, "Kept local tabs: {}", list(&self.kept))
+    }
+}
+
+/// Merges an imported document into the local one with the same id. Local tabs are kept in
+/// their order, tabs with the same id are replaced and new tabs are added at the end.
+pub fn merge_docs(local: &Doc, incoming: &Doc) -> (Doc, MergeSummary) {
+    let mut summary = MergeSummary::default();
+    let mut tabs: Vec<Tab> = local
+        .tabs
+        .iter()
+        .map(
+            |tab| match incoming.tabs.iter().find(|incoming| incoming.id == tab.id) {
+                Some(incoming) => {
+                    summary.replaced.push(incoming.name.clone());
+                    Tab {
+                        is_active: tab.is_active,
+                        ..incoming.clone()
+                    }
+                }
+                None => {
+                    summary.kept.push(tab.name.clone());
+                    tab.clone()
+                }
+            },
+        )
+        .collect();
+    for tab in incoming.tabs.iter() {
+        if !local.tabs.iter().any(|local| local.id == tab.id) {
+            summary.added.push(tab.name.clone());
+            tabs.push(Tab {
+                is_active: false,
+                ..tab.clone()
+            });
+        }
+    }
+    let tags = dedup_tags(&[local.tags.clone(), incoming.tags.clone()].concat());
+    let mut inline_images = local.inline_images.clone();
+    inline_images.extend(incoming.inline_images.clone());
+    let doc = Doc {
+        tabs,
+        tags,
+        inline_images,
+        ..local.clone()
+    };
+    (doc, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{http_response, json_response, mock_server, wait};
+
+    const DOCUMENT: &str = r#"{
+        "id": "c9b1d4a6-1b1a-4b5e-9a57-1f6a5d9c3e10",
+        "name": "Imported",
+        "tags": [],
+        "tabs": [{
+            "id": "0e3c2b5e-8d7c-4d1f-a0f4-9f7d7b1e2c33",
+            "name": "Tab 1",
+            "is_active": true,
+            "checkpoints": ["{\"images\": {}, \"nodes\": [], \"arrows\": []}"]
+        }]
+    }"#;
+
+    fn fetch(url: String) -> Result<Doc, ImportError> {
+        let config = ImportConfig {
+            max_size: 2048,
+            timeout: 5,
+        };
+        wait(|callback| fetch_document(url, &config, callback))
+    }
+
+    #[test]
+    fn test_parse_import_url() {
+        assert_eq!(
+            parse_import_url(" https://example.com/velo.json\n"),
+            Ok("https://example.com/velo.json".to_string())
+        );
+        assert_eq!(
+            parse_import_url("file:///tmp/velo.json"),
+            Ok("file:///tmp/velo.json".to_string())
+        );
+        assert_eq!(
+            parse_import_url("see https://example.com/velo.json"),
+            Err(ImportError::InvalidUrl)
+        );
+        assert_eq!(parse_import_url("velo.json"), Err(ImportError::InvalidUrl));
+        assert_eq!(
+            parse_import_url("ftp://example.com/velo.json"),
+            Err(ImportError::InvalidUrl)
+        );
+    }
+
+    #[test]
+    fn test_fetch_document() {
+        let (url, _requests) = mock_server(&http_response(
+            "200 OK",
+            "text/plain; charset=utf-8",
+            DOCUMENT,
+        ));
+        let doc = fetch(url).unwrap();
+        assert_eq!(doc.name, "Imported");
+        assert_eq!(doc.tabs.len(), 1);
+    }
+
+    #[test]
+    fn test_fetch_document_errors() {
+        let (url, _requests) = mock_server(&json_response("404 Not Found", "{}"));
+        assert_eq!(
+            fetch(url).unwrap_err(),
+            ImportError::Status {
+                status: 404,
+                status_text: "Not Found".to_string()
+            }
+        );
+
+        let (url, _requests) = mock_server(&json_response("200 OK", &"x".repeat(4096)));
+        assert_eq!(
+            fetch(url).unwrap_err(),
+            ImportError::TooLarge { limit: 2048 }
+        );
+
+        let (url, _requests) = mock_server(&http_response("200 OK", "text/html", "<html></html>"));
+        assert_eq!(
+            fetch(url).unwrap_err(),
+            ImportError::ContentType("text/html".to_string())
+        );
+
+        let (url, _requests) = mock_server(&json_response("200 OK", "{\"id\": "));
+        assert!(matches!(fetch(url), Err(ImportError::InvalidJson(_))));
+
+        let (url, _requests) = mock_server(&json_response("200 OK", r#"{"tabs": 1}"#));
+        assert!(matches!(fetch(url), Err(ImportError::InvalidDocument(_))));
+
+        // nothing listens on the port anymore
+        let url = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        assert!(matches!(fetch(url), Err(ImportError::Network(_))));
+    }
+
+    #[test]
+    fn test_fetch_document_limits() {
+        // the size isn't announced, so the body is read up to the limit
+        let (url, _requests) = mock_server(&format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{}",
+            "x".repeat(4096)
+        ));
+        assert_eq!(
+            fetch(url).unwrap_err(),
+            ImportError::TooLarge { limit: 2048 }
+        );
+
+        // the connection is accepted by the listener but nothing responds
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let config = ImportConfig {
+            max_size: 2048,
+            timeout: 1,
+        };
+        assert_eq!(
+            wait(|callback| fetch_document(url, &config, callback)).unwrap_err(),
+            ImportError::TimedOut { seconds: 1 }
+        );
+        drop(listener);
+    }
+
+    #[test]
+    fn test_parse_document_schema() {
+        assert!(parse_document(DOCUMENT).is_ok());
+        let no_tabs = r#"{"id": "c9b1d4a6-1b1a-4b5e-9a57-1f6a5d9c3e10", "name": "Empty", "tags": [], "tabs": []}"#;
+        assert_eq!(
+            parse_document(no_tabs).unwrap_err(),
+            ImportError::InvalidDocument("document has no tabs".to_string())
+        );
+        let invalid_checkpoint = DOCUMENT.replace("\\\"nodes\\\": []", "\\\"nodes\\\": [1]");
+        match parse_document(&invalid_checkpoint) {
+            Err(ImportError::InvalidDocument(e)) => {
+                assert!(e.starts_with("tab \"Tab 1\": invalid node"), "{}", e)
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    fn tab(id: &str, name: &str, checkpoint: &str) -> Tab {
+        Tab {
+            is_active: false,
+            id: ReflectableUuid(uuid::Uuid::parse_str(id).unwrap()),
+            name: name.to_string(),
+            checkpoints: vec![checkpoint.to_string()].into(),
+        }
+    }
+
+    const TAB_1: &str = "0e3c2b5e-8d7c-4d1f-a0f4-9f7d7b1e2c33";
+    const TAB_2: &str = "5a0d6c0b-2c1e-4f8e-9b7a-6e4d3c2b1a01";
+    const TAB_3: &str = "7f1e2d3c-4b5a-4968-8776-655443322110";
+    const EMPTY: &str = r#"{"images": {}, "nodes": [], "arrows": []}"#;
+
+    #[test]
+    fn test_remap_ids() {
+        let node = "3f5c7a9e-1b2d-4c6e-8f0a-1b2c3d4e5f60";
+        let checkpoint = serde_json::json!({
+            "images": { node: "aW1hZ2U=" },
+            "nodes": [{ "id": node }],
+            "arrows": [{
+                "start": { "id": node, "pos": "Top" },
+                "end": { "id": node, "pos": "Bottom" }
+            }]
+        })
+        .to_string();
+        let mut doc = parse_document(DOCUMENT).unwrap();
+        doc.tabs[0].checkpoints.push_back(checkpoint.clone());
+        doc.tabs[0].checkpoints.push_back(checkpoint);
+
+        let copy = remap_ids(&doc);
+        assert_ne!(copy.id, doc.id);
+        assert_ne!(copy.tabs[0].id, doc.tabs[0].id);
+        assert_eq!(copy.name, "Imported");
+        assert_eq!(copy.tabs[0].name, "Tab 1");
+        assert_eq!(
+            serde_json::from_str::<Value>(&copy.tabs[0].checkpoints[0]).unwrap(),
+            serde_json::from_str::<Value>(EMPTY).unwrap()
+        );
+        let first: Value = serde_json::from_str(&copy.tabs[0].checkpoints[1]).unwrap();
+        let second: Value = serde_json::from_str(&copy.tabs[0].checkpoints[2]).unwrap();
+        let new_id = first["nodes"][0]["id"].as_str().unwrap();
+        assert_ne!(new_id, node);
+        assert!(first["images"][new_id].is_string());
+        assert_eq!(first["arrows"][0]["start"]["id"], new_id);
+        assert_eq!(first["arrows"][0]["end"]["id"], new_id);
+        assert_eq!(first["arrows"][0]["end"]["pos"], "Bottom");
+        assert_eq!(second["nodes"][0]["id"], new_id);
+    }
+
+    #[test]
+    fn test_remap_board_cards() {
+        let node = "3f5c7a9e-1b2d-4c6e-8f0a-1b2c3d4e5f60";
+        let checkpoint = serde_json::json!({
+            "nodes": [{ "id": node }],
+            "arrows": [],
+            "board": {
+                "columns": [{ "name": "To do", "wip_limit": null }],
+                "lanes": [],
+                "left": 0.0,
+                "bottom": 0.0,
+                "cards": [{ "id": node, "column": 0, "lane": 0 }]
+            }
+        })
+        .to_string();
+        let mut doc = parse_document(DOCUMENT).unwrap();
+        doc.tabs[0].checkpoints.push_back(checkpoint);
+
+        let copy = remap_ids(&doc);
+        let json: Value = serde_json::from_str(&copy.tabs[0].checkpoints[1]).unwrap();
+        let new_id = json["nodes"][0]["id"].as_str().unwrap();
+        assert_ne!(new_id, node);
+        assert_eq!(json["board"]["cards"][0]["id"], new_id);
+        let board: crate::board::Board = serde_json::from_value(json["board"].clone()).unwrap();
+        assert_eq!(board.cards[0].id.0.to_string(), new_id);
+    }
+
+    #[test]
+    fn test_merge_docs() {
+        let mut local = parse_document(DOCUMENT).unwrap();
+        local.tags = vec!["local".to_string()];
+        local.tabs = vec![tab(TAB_1, "Tab 1", EMPTY), tab(TAB_2, "Notes", EMPTY)];
+        local.tabs[1].is_active = true;
+        let mut incoming = local.clone();
+        incoming.name = "Renamed".to_string();
+        incoming.tags = vec!["local".to_string(), "shared".to_string()];
+        incoming.tabs = vec![
+            tab(TAB_3, "Ideas", EMPTY),
+            tab(
+                TAB_2,
+                "Notes v2",
+                r#"{"images": {}, "nodes": [], "arrows": [], "v": 2}"#,
+            ),
+        ];
+        incoming.tabs[0].is_active = true;
+        local
+            .inline_images
+            .insert("image:local".to_string(), "bG9jYWw=".to_string());
+        incoming
+            .inline_images
+            .insert("image:incoming".to_string(), "aW5jb21pbmc=".to_string());
+
+        let (merged, summary) = merge_docs(&local, &incoming);
+        assert_eq!(merged.id, local.id);
+        assert_eq!(merged.name, "Imported");
+        assert_eq!(merged.tags, vec!["local", "shared"]);
+        let names: Vec<&str> = merged.tabs.iter().map(|tab| tab.name.as_str()).collect();
+        assert_eq!(names, vec!["Tab 1", "Notes v2", "Ideas"]);
+        let active: Vec<bool> = merged.tabs.iter().map(|tab| tab.is_active).collect();
+        assert_eq!(active, vec![false, true, false]);
+        assert!(merged.tabs[1].checkpoints[0].contains("\"v\": 2"));
+        assert_eq!(merged.inline_images.len(), 2);
+        assert_eq!(
+            summary,
+            MergeSummary {
+                added: vec!["Ideas".to_string()],
+                replaced: vec!["Notes v2".to_string()],
+                kept: vec!["Tab 1".to_string()],
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "Added tabs: \"Ideas\"\nReplaced tabs: \"Notes v2\"\nKept local tabs: \"Tab 1\""
+        );
+    }
+
+    #[test]
+    fn test_read_document_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("velo.json");
+        std::fs::write(&path, DOCUMENT).unwrap();
+        assert!(read_document_file(&path, 2048).is_ok());
+        assert_eq!(
+            read_document_file(&path, 10).unwrap_err(),
+            ImportError::TooLarge { limit: 10 }
+        );
+        assert!(matches!(
+            read_document_file(&dir.path().join("missing.json"), 2048),
+            Err(ImportError::File(_))
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bevy::prelude::*;
+use bevy_cosmic_edit::{CosmicKeymap, EditorAction, KeyChord, Modifiers};
+
+/// App shortcuts that can be rebound in the `[keymap]` config table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AppAction {
+    SaveTab,
+    SaveDoc,
+    /// Restores the previous checkpoint of the active tab.
+    Undo,
+    /// Adds an image node from the clipboard.
+    PasteImage,
+    CommandPalette,
+}
+
+impl AppAction {
+    pub const ALL: [AppAction; 5] = [
+        AppAction::SaveTab,
+        AppAction::SaveDoc,
+        AppAction::Undo,
+        AppAction::PasteImage,
+        AppAction::CommandPalette,
+    ];
+
+    /// Name used in config files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppAction::SaveTab => "save_tab",
+            AppAction::SaveDoc => "save_doc",
+            AppAction::Undo => "undo",
+            AppAction::PasteImage => "paste_image",
+            AppAction::CommandPalette => "command_palette",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        AppAction::ALL
+            .iter()
+            .find(|action| action.name() == name)
+            .copied()
+    }
+}
+
+/// Key bindings of the app shortcuts, editor shortcuts live in [`CosmicKeymap`].
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Keymap {
+    pub bindings: HashMap<AppAction, KeyChord>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let primary = Modifiers::primary();
+        let bindings = HashMap::from([
+            (AppAction::SaveTab, KeyChord::new(primary, KeyCode::S)),
+            (
+                AppAction::SaveDoc,
+                KeyChord::new(primary.with_shift(), KeyCode::S),
+            ),
+            (AppAction::Undo, KeyChord::new(primary, KeyCode::L)),
+            (AppAction::PasteImage, KeyChord::new(primary, KeyCode::V)),
+            (
+                AppAction::CommandPalette,
+                KeyChord::new(primary, KeyCode::K),
+            ),
+        ]);
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn just_pressed(&self, action: AppAction, keys: &Input<KeyCode>) -> bool {
+        self.bindings
+            .get(&action)
+            .map_or(false, |chord| chord.just_pressed(keys))
+    }
+}
+
+/// Applies `[keymap]` overrides (action name -> chord) on top of the platform defaults.
+/// Invalid entries are skipped and returned as (action name, message) pairs.
+pub fn build_keymaps(
+    overrides: &BTreeMap<String, String>,
+) -> (Keymap, CosmicKeymap, Vec<(String, String)>) {
+    let mut keymap = Keymap::default();
+    let mut cosmic_keymap = CosmicKeymap::default();
+    let mut errors = vec![];
+    for (name, chord) in overrides {
+        let chord = match chord.parse::<KeyChord>() {
+            Ok(chord) => chord,
+            Err(e) => {
+                errors.push((name.clone(), e));
+                continue;
+            }
+        };
+        if let Some(action) = AppAction::from_name(name) {
+            keymap.bindings.insert(action, chord);
+        } else if let Some(action) = EditorAction::from_name(name) {
+            cosmic_keymap.bindings.insert(action, chord);
+        } else {
+            let names: Vec<&str> = AppAction::ALL
+                .iter()
+                .map(|action| action.name())
+                .chain(EditorAction::ALL.iter().map(|action| action.name()))
+                .collect();
+            errors.push((
+                name.clone(),
+                format!(
+                    "unknown action \"{}\", expected one of: {}",
+                    name,
+                    names.join(", ")
+                ),
+            ));
+        }
+    }
+    // app and editor shortcuts run side by side (e.g. paste), so only
+    // conflicts inside the same keymap are reported
+    for action in AppAction::ALL {
+        let chord = keymap.bindings[&action];
+        if let Some(other) = AppAction::ALL
+            .iter()
+            .find(|other| **other != action && keymap.bindings[*other] == chord)
+        {
+            if overrides.contains_key(action.name()) {
+                errors.push((
+                    action.name().to_string(),
+                    format!("{} is already bound to {}", chord, other.name()),
+                ));
+            }
+        }
+    }
+    for action in EditorAction::ALL {
+        let chord = cosmic_keymap.bindings[&action];
+        if let Some(other) = EditorAction::ALL
+            .iter()
+            .find(|other| **other != action && cosmic_keymap.bindings[*other] == chord)
+        {
+            if overrides.contains_key(action.name()) {
+                errors.push((
+                    action.name().to_string(),
+                    format!("{} is already bound to {}", chord, other.name()),
+                ));
+            }
+        }
+    }
+    (keymap, cosmic_keymap, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_keymaps() {
+        let overrides = BTreeMap::from([
+            ("save_tab".to_string(), "Ctrl+Alt+S".to_string()),
+            ("select_all".to_string(), "Super+A".to_string()),
+        ]);
+        let (keymap, cosmic_keymap, errors) = build_keymaps(&overrides);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            keymap.bindings[&AppAction::SaveTab],
+            "Ctrl+Alt+S".parse().unwrap()
+        );
+        assert_eq!(
+            keymap.bindings[&AppAction::SaveDoc],
+            Keymap::default().bindings[&AppAction::SaveDoc]
+        );
+        assert_eq!(
+            cosmic_keymap.bindings[&EditorAction::SelectAll],
+            "Super+A".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_build_keymaps_errors() {
+        let overrides = BTreeMap::from([
+            ("save".to_string(), "Ctrl+S".to_string()),
+            ("undo".to_string(), "Ctrl+Hyper+Z".to_string()),
+            ("copy".to_string(), "Primary+X".to_string()),
+        ]);
+        let (keymap, _, errors) = build_keymaps(&overrides);
+        assert_eq!(keymap, Keymap::default());
+        let names: Vec<&str> = errors.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["save", "undo", "copy"]);
+        assert!(errors[0].1.starts_with("unknown action \"save\""));
+        assert!(errors[2].1.ends_with("is already bound to cut"));
+    }
+}
@@ -1,7 +1,18 @@
+mod board;
 mod canvas;
 mod components;
+mod config;
+mod diff;
+mod dot;
+mod history;
+mod import;
+mod keymap;
 mod resources;
+mod share;
 mod systems;
+mod templates;
+#[cfg(test)]
+mod test_utils;
 mod themes;
 mod ui_plugin;
 mod utils;
@@ -24,7 +35,11 @@ pub struct VeloPlugin;
 impl Plugin for VeloPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_systems((setup_camera, setup_background))
-            .add_startup_system(setup_velo_theme.in_base_set(StartupSet::PreStartup))
+            .add_startup_systems(
+                (setup_config, setup_velo_theme, setup_keymap, setup_share)
+                    .chain()
+                    .in_base_set(StartupSet::PreStartup),
+            )
             .add_plugins(
                 DefaultPlugins
                     .set(WindowPlugin {
@@ -11,10 +11,10 @@ use std::path::PathBuf;
 pub struct AppState {
     pub current_document: Option<ReflectableUuid>,
     pub docs: HashMap<ReflectableUuid, Doc>,
-    pub github_token: Option<String>,
     #[cfg(not(target_arch = "wasm32"))]
     pub search_index: Option<SearchIndexState>,
     pub doc_list_ui: HashSet<ReflectableUuid>,
+    pub tag_filter: HashSet<String>,
 }
 
 #[derive(Resource, Debug)]
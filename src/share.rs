@@ -0,0 +1,836 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::components::{Doc, SharedDoc};
+use crate::config::{Config, HttpShareMethod};
+
+pub type ShareCallback<T> = Box<dyn FnOnce(Result<T, String>) + Send>;
+
+/// Backend that publishes documents, so that they can be opened with a link.
+/// Results are passed to callbacks, which may be called from another thread.
+pub trait ShareProvider: Send + Sync {
+    /// Name stored in [`SharedDoc::provider`].
+    fn name(&self) -> &'static str;
+
+    /// Visibility offered first when sharing, `None` if documents can't be shared publicly.
+    fn default_public(&self) -> Option<bool> {
+        None
+    }
+
+    /// Publishes a document, `public` is ignored by providers without a visibility.
+    fn publish(&self, doc: &Doc, public: bool, callback: ShareCallback<SharedDoc>);
+
+    /// Publishes a new version of a document that was shared by this provider.
+    fn update(
+        &self,
+        shared: &SharedDoc,
+        doc: &Doc,
+        public: bool,
+        callback: ShareCallback<SharedDoc>,
+    );
+
+    fn delete(&self, shared: &SharedDoc, callback: ShareCallback<()>);
+}
+
+/// Share provider picked in the config, sharing is disabled if there is none.
+#[derive(Resource)]
+pub struct Sharing {
+    pub provider: Option<Box<dyn ShareProvider>>,
+    pub viewer_url: String,
+}
+
+impl Sharing {
+    pub fn from_config(config: &Config) -> Self {
+        let share = &config.share;
+        let gist = config.github_access_token().map(|access_token| {
+            let github = share.github.as_ref();
+            Box::new(GistProvider {
+                api_url: github.map_or_else(
+                    || "https://api.github.com".to_string(),
+                    |github| github.api_url.clone(),
+                ),
+                access_token,
+                public: github.map_or(true, |github| github.public),
+            }) as Box<dyn ShareProvider>
+        });
+        let http = share.http.as_ref().map(|http| {
+            Box::new(HttpProvider {
+                url: http.url.clone(),
+                method: http.method,
+                headers: http.headers.clone(),
+            }) as Box<dyn ShareProvider>
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        let folder = share.folder.as_ref().map(|folder| {
+            Box::new(FolderProvider {
+                path: folder.path.clone(),
+                base_url: folder.base_url.clone(),
+            }) as Box<dyn ShareProvider>
+        });
+        #[cfg(target_arch = "wasm32")]
+        let folder = None;
+        // an invalid provider is reported while reading the config
+        let provider = match share.provider.as_deref() {
+            Some("gist") => gist,
+            Some("http") => http,
+            Some("folder") => folder,
+            Some(_) => None,
+            None => gist.or(http).or(folder),
+        };
+        Self {
+            provider,
+            viewer_url: share.viewer_url.clone(),
+        }
+    }
+
+    /// Link that opens the shared document in the viewer, `None` for `file://` URLs that the
+    /// viewer can't load.
+    pub fn viewer_link(&self, shared: &SharedDoc) -> Option<String> {
+        if shared.url.starts_with("file://") {
+            return None;
+        }
+        let encode = |value: &str| -> String {
+            url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+        };
+        Some(
+            self.viewer_url
+                .replace("{url}", &encode(&shared.url))
+                .replace("{id}", &encode(&shared.id)),
+        )
+    }
+}
+
+/// Contents of a shared document.
+pub fn document_json(doc: &Doc) -> Result<String, String> {
+    // where the sharer published it is meaningless to whoever imports the document
+    serde_json::to_string_pretty(&Doc {
+        shared: None,
+        ..doc.clone()
+    })
+    .map_err(|e| format!("Failed to serialize document: {}", e))
+}
+
+fn response_error(server: &str, response: &ehttp::Response) -> String {
+    let message = response
+        .text()
+        .and_then(|text| serde_json::from_str::<Value>(text.as_ref()).ok())
+        .and_then(|json| json["message"].as_str().map(|message| message.to_string()));
+    match message {
+        Some(message) => format!(
+            "{} responded with {} {}: {}",
+            server, response.status, response.status_text, message
+        ),
+        None => format!(
+            "{} responded with {} {}",
+            server, response.status, response.status_text
+        ),
+    }
+}
+
+const GIST_FILE_NAME: &str = "velo.json";
+
+/// Shares documents as GitHub gists.
+pub struct GistProvider {
+    pub api_url: String,
+    pub access_token: String,
+    /// Visibility offered first, see [`crate::config::GithubShareConfig::public`].
+    pub public: bool,
+}
+
+#[derive(Serialize)]
+struct GistFile {
+    content: String,
+}
+
+#[derive(Serialize)]
+struct GistRequest {
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public: Option<bool>,
+    files: std::collections::HashMap<String, GistFile>,
+}
+
+impl GistProvider {
+    fn request(&self, method: &str, path: &str, body: Option<String>) -> ehttp::Request {
+        let url = format!("{}{}", self.api_url.trim_end_matches('/'), path);
+        let mut request = match body {
+            Some(body) => ehttp::Request::post(url, body),
+            None => ehttp::Request::get(url),
+        };
+        request.method = method.to_string();
+        request.headers.insert(
+            "Accept".to_string(),
+            "application/vnd.github.v3+json".to_string(),
+        );
+        request.headers.insert(
+            "Authorization".to_string(),
+            format!("token {}", self.access_token),
+        );
+        request
+    }
+
+    fn body(doc: &Doc, public: Option<bool>) -> Result<String, String> {
+        let mut files = std::collections::HashMap::new();
+        files.insert(
+            GIST_FILE_NAME.to_string(),
+            GistFile {
+                content: document_json(doc)?,
+            },
+        );
+        let body = GistRequest {
+            description: "Velo Document".to_string(),
+            public,
+            files,
+        };
+        serde_json::to_string(&body).map_err(|e| e.to_string())
+    }
+
+    fn parse_response(
+        result: ehttp::Result<ehttp::Response>,
+        public: bool,
+    ) -> Result<SharedDoc, String> {
+        let response = result.map_err(|e| format!("Failed to reach GitHub: {}", e))?;
+        if !response.ok {
+            return Err(response_error("GitHub", &response));
+        }
+        let json: Value = response
+            .text()
+            .and_then(|text| serde_json::from_str(text.as_ref()).ok())
+            .ok_or_else(|| "GitHub responded with invalid JSON".to_string())?;
+        let id = json["id"].as_str();
+        let raw_url = json["files"][GIST_FILE_NAME]["raw_url"].as_str();
+        match (id, raw_url) {
+            (Some(id), Some(raw_url)) => Ok(SharedDoc {
+                provider: "gist".to_string(),
+                id: id.to_string(),
+                url: Self::latest_url(raw_url),
+                public,
+                method: None,
+            }),
+            _ => Err("GitHub response is missing the gist id or file URL".to_string()),
+        }
+    }
+
+    /// The raw URL of a gist file is pinned to a revision, without it the URL always serves the
+    /// latest one, e.g. `https://gist.githubusercontent.com/<owner>/<id>/raw/velo.json`.
+    fn latest_url(raw_url: &str) -> String {
+        match raw_url.split_once("/raw/") {
+            Some((gist_url, file)) => format!(
+                "{}/raw/{}",
+                gist_url,
+                file.rsplit('/').next().unwrap_or(GIST_FILE_NAME)
+            ),
+            None => raw_url.to_string(),
+        }
+    }
+
+    fn parse_delete_response(result: ehttp::Result<ehttp::Response>) -> Result<(), String> {
+        let response = result.map_err(|e| format!("Failed to reach GitHub: {}", e))?;
+        // a gist deleted on github.com is as good as unshared
+        if response.ok || response.status == 404 {
+            Ok(())
+        } else {
+            Err(response_error("GitHub", &response))
+        }
+    }
+}
+
+impl ShareProvider for GistProvider {
+    fn name(&self) -> &'static str {
+        "gist"
+    }
+
+    fn default_public(&self) -> Option<bool> {
+        Some(self.public)
+    }
+
+    fn publish(&self, doc: &Doc, public: bool, callback: ShareCallback<SharedDoc>) {
+        let body = match Self::body(doc, Some(public)) {
+            Ok(body) => body,
+            Err(e) => return callback(Err(e)),
+        };
+        ehttp::fetch(self.request("POST", "/gists", Some(body)), move |result| {
+            callback(Self::parse_response(result, public))
+        });
+    }
+
+    fn update(
+        &self,
+        shared: &SharedDoc,
+        doc: &Doc,
+        public: bool,
+        callback: ShareCallback<SharedDoc>,
+    ) {
+        if shared.public != public {
+            // GitHub can't change the visibility of a gist, so the old one is replaced
+            let delete_request = self.request("DELETE", &format!("/gists/{}", shared.id), None);
+            let old_id = shared.id.clone();
+            return self.publish(
+                doc,
+                public,
+                Box::new(move |result| {
+                    if result.is_ok() {
+                        ehttp::fetch(delete_request, move |result| {
+                            if let Err(e) = Self::parse_delete_response(result) {
+                                error!("Failed to delete gist {}: {}", old_id, e);
+                            }
+                        });
+                    }
+                    callback(result)
+                }),
+            );
+        }
+        let body = match Self::body(doc, None) {
+            Ok(body) => body,
+            Err(e) => return callback(Err(e)),
+        };
+        let request = self.request("PATCH", &format!("/gists/{}", shared.id), Some(body));
+        // links that were already sent keep showing the latest version, documents shared with a
+        // pinned URL get one that isn't
+        let url = Self::latest_url(&shared.url);
+        ehttp::fetch(request, move |result| {
+            callback(Self::parse_response(result, public).map(|shared| SharedDoc { url, ..shared }))
+        });
+    }
+
+    fn delete(&self, shared: &SharedDoc, callback: ShareCallback<()>) {
+        let request = self.request("DELETE", &format!("/gists/{}", shared.id), None);
+        ehttp::fetch(request, move |result| {
+            callback(Self::parse_delete_response(result))
+        });
+    }
+}
+
+/// Shares documents with a generic HTTP endpoint, see [`crate::config::HttpShareConfig`].
+pub struct HttpProvider {
+    pub url: String,
+    pub method: HttpShareMethod,
+    pub headers: BTreeMap<String, String>,
+}
+
+impl HttpProvider {
+    fn request(&self, method: &str, url: String, body: Option<String>) -> ehttp::Request {
+        let mut request = match body {
+            Some(body) => {
+                let mut request = ehttp::Request::post(url, body);
+                request
+                    .headers
+                    .insert("Content-Type".to_string(), "application/json".to_string());
+                request
+            }
+            None => ehttp::Request::get(url),
+        };
+        request.method = method.to_string();
+        for (name, value) in self.headers.iter() {
+            request.headers.insert(name.clone(), value.clone());
+        }
+        request
+    }
+
+    fn put(&self, doc: &Doc, url: String, callback: ShareCallback<SharedDoc>) {
+        let body = match document_json(doc) {
+            Ok(body) => body,
+            Err(e) => return callback(Err(e)),
+        };
+        let id = doc.id.0.to_string();
+        ehttp::fetch(
+            self.request("PUT", url.clone(), Some(body)),
+            move |result| {
+                callback(
+                    result
+                        .map_err(|e| format!("Failed to reach share server: {}", e))
+                        .and_then(|response| {
+                            if response.ok {
+                                Ok(SharedDoc {
+                                    provider: "http".to_string(),
+                                    id,
+                                    url,
+                                    public: false,
+                                    method: None,
+                                })
+                            } else {
+                                Err(response_error("Share server", &response))
+                            }
+                        }),
+                )
+            },
+        );
+    }
+
+    fn post(&self, doc: &Doc, callback: ShareCallback<SharedDoc>) {
+        let body = match document_json(doc) {
+            Ok(body) => body,
+            Err(e) => return callback(Err(e)),
+        };
+        let id = doc.id.0.to_string();
+        let base_url = self.url.clone();
+        ehttp::fetch(
+            self.request("POST", self.url.clone(), Some(body)),
+            move |result| {
+                callback(
+                    result
+                        .map_err(|e| format!("Failed to reach share server: {}", e))
+                        .and_then(|response| {
+                            if !response.ok {
+                                return Err(response_error("Share server", &response));
+                            }
+                            Self::created_url(&base_url, &response)
+                                .map(|url| SharedDoc {
+                                    provider: "http".to_string(),
+                                    id,
+                                    url,
+                                    public: false,
+                                    method: Some("POST".to_string()),
+                                })
+                                .ok_or_else(|| {
+                                    "Share server responded without a document URL".to_string()
+                                })
+                        }),
+                )
+            },
+        );
+    }
+
+    /// URL of a document created with POST, from the `Location` header or the JSON response.
+    fn created_url(base_url: &str, response: &ehttp::Response) -> Option<String> {
+        let location = response
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("location"))
+            .map(|(_, location)| location.clone());
+        let url = location.or_else(|| {
+            response
+                .text()
+                .and_then(|text| serde_json::from_str::<Value>(text.as_ref()).ok())
+                .and_then(|json| json["url"].as_str().map(|url| url.to_string()))
+        })?;
+        if url.starts_with('/') {
+            // relative to the host of the endpoint
+            let origin_end = base_url
+                .find("://")
+                .and_then(|scheme_end| {
+                    base_url[scheme_end + 3..]
+                        .find('/')
+                        .map(|path_start| scheme_end + 3 + path_start)
+                })
+                .unwrap_or(base_url.len());
+            Some(format!("{}{}", &base_url[..origin_end], url))
+        } else {
+            Some(url)
+        }
+    }
+}
+
+impl ShareProvider for HttpProvider {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn publish(&self, doc: &Doc, _public: bool, callback: ShareCallback<SharedDoc>) {
+        match self.method {
+            HttpShareMethod::Put => {
+                let url = format!("{}/{}.json", self.url.trim_end_matches('/'), doc.id.0);
+                self.put(doc, url, callback)
+            }
+            HttpShareMethod::Post => self.post(doc, callback),
+        }
+    }
+
+    fn update(
+        &self,
+        shared: &SharedDoc,
+        doc: &Doc,
+        _public: bool,
+        callback: ShareCallback<SharedDoc>,
+    ) {
+        // the method in the config may have changed since the document was created
+        if shared.method.as_deref() != Some("POST") {
+            return self.put(doc, shared.url.clone(), callback);
+        }
+        // the URL of a posted document isn't known to accept PUT, so it is replaced
+        let delete_request = self.request("DELETE", shared.url.clone(), None);
+        let old_url = shared.url.clone();
+        self.post(
+            doc,
+            Box::new(move |result| {
+                if result.is_ok() {
+                    ehttp::fetch(delete_request, move |result| match result {
+                        Ok(response) if response.ok || response.status == 404 => {}
+                        Ok(response) => error!(
+                            "Failed to delete {}: {}",
+                            old_url,
+                            response_error("Share server", &response)
+                        ),
+                        Err(e) => error!("Failed to delete {}: {}", old_url, e),
+                    });
+                }
+                callback(result)
+            }),
+        );
+    }
+
+    fn delete(&self, shared: &SharedDoc, callback: ShareCallback<()>) {
+        ehttp::fetch(
+            self.request("DELETE", shared.url.clone(), None),
+            move |result| {
+                callback(
+                    result
+                        .map_err(|e| format!("Failed to reach share server: {}", e))
+                        .and_then(|response| {
+                            if response.ok || response.status == 404 {
+                                Ok(())
+                            } else {
+                                Err(response_error("Share server", &response))
+                            }
+                        }),
+                )
+            },
+        );
+    }
+}
+
+/// Shares documents by writing them to a folder.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FolderProvider {
+    pub path: std::path::PathBuf,
+    pub base_url: Option<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FolderProvider {
+    fn write(&self, doc: &Doc) -> Result<SharedDoc, String> {
+        let file_name = format!("{}.json", doc.id.0);
+        let path = self.path.join(&file_name);
+        let contents = document_json(doc)?;
+        std::fs::create_dir_all(&self.path)
+            .and_then(|_| std::fs::write(&path, contents))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let url = match &self.base_url {
+            Some(base_url) => format!("{}/{}", base_url.trim_end_matches('/'), file_name),
+            None => format!(
+                "file://{}",
+                std::fs::canonicalize(&path).unwrap_or(path).display()
+            ),
+        };
+        Ok(SharedDoc {
+            provider: "folder".to_string(),
+            id: doc.id.0.to_string(),
+            url,
+            public: false,
+            method: None,
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ShareProvider for FolderProvider {
+    fn name(&self) -> &'static str {
+        "folder"
+    }
+
+    fn publish(&self, doc: &Doc, _public: bool, callback: ShareCallback<SharedDoc>) {
+        callback(self.write(doc))
+    }
+
+    fn update(
+        &self,
+        _shared: &SharedDoc,
+        doc: &Doc,
+        _public: bool,
+        callback: ShareCallback<SharedDoc>,
+    ) {
+        callback(self.write(doc))
+    }
+
+    fn delete(&self, shared: &SharedDoc, callback: ShareCallback<()>) {
+        let path = self.path.join(format!("{}.json", shared.id));
+        callback(match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("{}: {}", path.display(), e))
+            }
+            _ => Ok(()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ShareConfig;
+    use crate::test_utils::{json_response, mock_server, wait};
+
+    fn gist_response(id: &str, revision: &str) -> String {
+        json_response(
+            "201 Created",
+            &format!(
+                r#"{{"id": "{}", "files": {{"velo.json": {{"raw_url": "https://gist.example.com/owner/{}/raw/{}/velo.json"}}}}}}"#,
+                id, id, revision
+            ),
+        )
+    }
+
+    fn doc(shared: Option<SharedDoc>) -> Doc {
+        Doc {
+            name: "Shared".to_string(),
+            tabs: vec![crate::components::Tab {
+                is_active: true,
+                id: crate::utils::ReflectableUuid::generate(),
+                name: "Tab 1".to_string(),
+                checkpoints: std::collections::VecDeque::new(),
+            }],
+            shared,
+            ..Default::default()
+        }
+    }
+
+    fn gist(id: &str, public: bool) -> SharedDoc {
+        SharedDoc {
+            provider: "gist".to_string(),
+            id: id.to_string(),
+            url: format!("https://gist.example.com/owner/{}/raw/velo.json", id),
+            public,
+            method: None,
+        }
+    }
+
+    fn gist_provider(api_url: String, public: bool) -> GistProvider {
+        GistProvider {
+            api_url,
+            access_token: "token".to_string(),
+            public,
+        }
+    }
+
+    #[test]
+    fn test_gist_publish_secret() {
+        let (url, requests) = mock_server(&gist_response("abc", "1a2b"));
+        let provider = gist_provider(url, true);
+        let shared = wait(|callback| provider.publish(&doc(None), false, callback)).unwrap();
+        let (request_line, body) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /gists HTTP/1.1");
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["public"], Value::Bool(false));
+        assert!(body["files"]["velo.json"]["content"]
+            .as_str()
+            .unwrap()
+            .contains("\"Shared\""));
+        assert_eq!(shared, gist("abc", false));
+    }
+
+    #[test]
+    fn test_gist_update_in_place() {
+        let (url, requests) = mock_server(&gist_response("abc", "3c4d"));
+        let provider = gist_provider(url, true);
+        let doc = doc(Some(gist("abc", true)));
+        let shared =
+            wait(|callback| provider.update(&gist("abc", true), &doc, true, callback)).unwrap();
+        let (request_line, body) = requests.recv().unwrap();
+        assert_eq!(request_line, "PATCH /gists/abc HTTP/1.1");
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["public"], Value::Null);
+        // where the document was shared is not published
+        assert!(!body["files"]["velo.json"]["content"]
+            .as_str()
+            .unwrap()
+            .contains("abc"));
+        // the link stays the same for everyone it was sent to
+        assert_eq!(shared, gist("abc", true));
+    }
+
+    #[test]
+    fn test_gist_update_changed_visibility() {
+        let (url, requests) = mock_server(&gist_response("def", "5e6f"));
+        let provider = gist_provider(url, true);
+        let shared =
+            wait(|callback| provider.update(&gist("abc", true), &doc(None), false, callback))
+                .unwrap();
+        let (request_line, _) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /gists HTTP/1.1");
+        assert_eq!(shared, gist("def", false));
+    }
+
+    #[test]
+    fn test_gist_latest_url() {
+        assert_eq!(
+            GistProvider::latest_url(
+                "https://gist.githubusercontent.com/owner/abc/raw/0123456789abcdef/velo.json"
+            ),
+            "https://gist.githubusercontent.com/owner/abc/raw/velo.json"
+        );
+        assert_eq!(
+            GistProvider::latest_url("https://gist.githubusercontent.com/owner/abc/raw/velo.json"),
+            "https://gist.githubusercontent.com/owner/abc/raw/velo.json"
+        );
+    }
+
+    #[test]
+    fn test_gist_error() {
+        let (url, _requests) = mock_server(&json_response(
+            "401 Unauthorized",
+            r#"{"message": "Bad credentials"}"#,
+        ));
+        let provider = gist_provider(url, true);
+        let error = wait(|callback| provider.publish(&doc(None), true, callback)).unwrap_err();
+        assert!(error.contains("401"), "{}", error);
+        assert!(error.contains("Bad credentials"), "{}", error);
+    }
+
+    #[test]
+    fn test_gist_delete() {
+        let (url, requests) = mock_server("HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n");
+        let provider = gist_provider(url, true);
+        assert_eq!(
+            wait(|callback| provider.delete(&gist("abc", true), callback)),
+            Ok(())
+        );
+        let (request_line, _) = requests.recv().unwrap();
+        assert_eq!(request_line, "DELETE /gists/abc HTTP/1.1");
+
+        let (url, _requests) = mock_server(&json_response(
+            "403 Forbidden",
+            r#"{"message": "Forbidden"}"#,
+        ));
+        let provider = gist_provider(url, true);
+        assert!(wait(|callback| provider.delete(&gist("abc", true), callback)).is_err());
+    }
+
+    #[test]
+    fn test_http_put() {
+        let (url, requests) = mock_server(&json_response("201 Created", ""));
+        let provider = HttpProvider {
+            url: format!("{}/velo/", url),
+            method: HttpShareMethod::Put,
+            headers: BTreeMap::from([("Authorization".to_string(), "Bearer x".to_string())]),
+        };
+        let doc = doc(None);
+        let shared = wait(|callback| provider.publish(&doc, false, callback)).unwrap();
+        let (request_line, body) = requests.recv().unwrap();
+        assert_eq!(
+            request_line,
+            format!("PUT /velo/{}.json HTTP/1.1", doc.id.0)
+        );
+        assert!(body.contains("\"Shared\""));
+        assert_eq!(shared.url, format!("{}/velo/{}.json", url, doc.id.0));
+    }
+
+    #[test]
+    fn test_http_post() {
+        let (url, requests) = mock_server(
+            "HTTP/1.1 201 Created\r\nLocation: /p/xyz\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+        let provider = HttpProvider {
+            url: format!("{}/paste", url),
+            method: HttpShareMethod::Post,
+            headers: BTreeMap::new(),
+        };
+        let shared = wait(|callback| provider.publish(&doc(None), false, callback)).unwrap();
+        let (request_line, _) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /paste HTTP/1.1");
+        assert_eq!(shared.url, format!("{}/p/xyz", url));
+        assert_eq!(shared.method.as_deref(), Some("POST"));
+
+        let (url, _requests) = mock_server(&json_response(
+            "200 OK",
+            r#"{"url": "https://paste.example.com/p/xyz"}"#,
+        ));
+        let provider = HttpProvider {
+            url,
+            method: HttpShareMethod::Post,
+            headers: BTreeMap::new(),
+        };
+        let shared = wait(|callback| provider.publish(&doc(None), false, callback)).unwrap();
+        assert_eq!(shared.url, "https://paste.example.com/p/xyz");
+    }
+
+    #[test]
+    fn test_http_update_posted() {
+        let (url, requests) = mock_server(
+            "HTTP/1.1 201 Created\r\nLocation: /p/new\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+        // documents are updated the way they were created, not with the configured method
+        let provider = HttpProvider {
+            url: format!("{}/paste", url),
+            method: HttpShareMethod::Put,
+            headers: BTreeMap::new(),
+        };
+        let posted = SharedDoc {
+            provider: "http".to_string(),
+            id: "abc".to_string(),
+            url: format!("{}/p/old", url),
+            public: false,
+            method: Some("POST".to_string()),
+        };
+        let shared =
+            wait(|callback| provider.update(&posted, &doc(None), false, callback)).unwrap();
+        let (request_line, _) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /paste HTTP/1.1");
+        assert_eq!(shared.url, format!("{}/p/new", url));
+    }
+
+    #[test]
+    fn test_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = FolderProvider {
+            path: dir.path().join("shared"),
+            base_url: Some("https://intranet.example.com/velo/".to_string()),
+        };
+        let doc = doc(None);
+        let shared = wait(|callback| provider.publish(&doc, false, callback)).unwrap();
+        let path = dir.path().join("shared").join(format!("{}.json", doc.id.0));
+        assert!(path.exists());
+        assert_eq!(
+            shared.url,
+            format!("https://intranet.example.com/velo/{}.json", doc.id.0)
+        );
+        assert_eq!(wait(|callback| provider.delete(&shared, callback)), Ok(()));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_folder_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = FolderProvider {
+            path: dir.path().to_path_buf(),
+            base_url: None,
+        };
+        let shared = wait(|callback| provider.publish(&doc(None), false, callback)).unwrap();
+        assert!(shared.url.starts_with("file://"));
+        let config = crate::config::ImportConfig::default();
+        let imported =
+            wait(|callback| crate::import::fetch_document(shared.url.clone(), &config, callback))
+                .unwrap();
+        assert_eq!(imported.name, "Shared");
+    }
+
+    #[test]
+    fn test_sharing_from_config() {
+        let mut config = Config::default();
+        assert!(Sharing::from_config(&config).provider.is_none());
+        config.github_access_token = Some("token".to_string());
+        config.share = ShareConfig {
+            viewer_url: "https://velo.example.com/#{id}?document={url}".to_string(),
+            ..Default::default()
+        };
+        let sharing = Sharing::from_config(&config);
+        assert_eq!(sharing.provider.as_ref().unwrap().name(), "gist");
+        assert_eq!(
+            sharing.viewer_link(&gist("abc", false)).unwrap(),
+            "https://velo.example.com/#abc?document=https%3A%2F%2Fgist.example.com%2Fabc%2Fraw%2Fvelo.json"
+        );
+        let file = SharedDoc {
+            url: "file:///tmp/velo/abc.json".to_string(),
+            ..gist("abc", false)
+        };
+        assert_eq!(sharing.viewer_link(&file), None);
+        config.share.provider = Some("folder".to_string());
+        config.share.folder = Some(crate::config::FolderShareConfig {
+            path: "/tmp/velo".into(),
+            base_url: None,
+        });
+        let sharing = Sharing::from_config(&config);
+        assert_eq!(sharing.provider.as_ref().unwrap().name(), "folder");
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    components::{EffectsCamera, MainCamera},
+    components::{CanvasBackground, EffectsCamera, MainCamera},
+    config::Config,
+    keymap::build_keymaps,
+    share::Sharing,
     themes::{get_theme_by_name, Theme},
     utils::UserPreferences,
 };
@@ -10,15 +13,36 @@ use bevy::{
 };
 use bevy_pkv::PkvStore;
 
-pub fn setup_velo_theme(mut commands: Commands, pkv: Res<PkvStore>) {
+// exclusive system, so that the config is available to the rest of PreStartup systems
+pub fn setup_config(world: &mut World) {
+    #[cfg(not(target_arch = "wasm32"))]
+    let config = crate::config::read_config();
+    #[cfg(target_arch = "wasm32")]
+    let config = Config::default();
+    world.insert_resource(config);
+}
+
+pub fn setup_keymap(mut commands: Commands, config: Res<Config>) {
+    // invalid entries are already reported while reading the config
+    let (keymap, cosmic_keymap, _) = build_keymaps(&config.keymap);
+    commands.insert_resource(keymap);
+    commands.insert_resource(cosmic_keymap);
+}
+
+pub fn setup_share(mut commands: Commands, config: Res<Config>) {
+    commands.insert_resource(Sharing::from_config(&config));
+}
+
+pub fn setup_velo_theme(mut commands: Commands, pkv: Res<PkvStore>, config: Res<Config>) {
+    let default_theme_name = config.theme.clone().unwrap_or_else(|| "light".to_string());
     let theme_name = if let Ok(user_preferences) = pkv.get::<UserPreferences>("user_preferences") {
         if let Some(theme_name) = user_preferences.theme_name {
             theme_name
         } else {
-            "light".to_string()
+            default_theme_name
         }
     } else {
-        "light".to_string()
+        default_theme_name
     };
     let theme = get_theme_by_name(&theme_name);
     commands.insert_resource(theme);
@@ -32,7 +56,7 @@ pub fn setup_background(mut commands: Commands, asset_server: Res<AssetServer>,
     if let Some(bg_color) = theme.canvas_bg_color {
         sprite_bundle.sprite.color = bg_color;
     }
-    commands.spawn(sprite_bundle);
+    commands.spawn((sprite_bundle, CanvasBackground));
 }
 
 pub fn setup_camera(mut commands: Commands) {
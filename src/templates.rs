@@ -0,0 +1,320 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::canvas::arrow::components::{ArrowConnect, ArrowConnectPos, ArrowMeta, ArrowType};
+use crate::components::{Doc, Tab};
+use crate::import::remap_ids;
+use crate::ui_plugin::{JsonNode, JsonNodeText, NodeType, TextPos};
+use crate::utils::ReflectableUuid;
+
+/// User templates are stored under this key, apart from the `docs` map.
+pub const TEMPLATES_KEY: &str = "templates";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Template {
+    /// `builtin:<name>` for templates shipped with the app, a UUID for user templates.
+    pub id: String,
+    pub name: String,
+    pub doc: Doc,
+}
+
+impl Template {
+    pub fn is_builtin(&self) -> bool {
+        self.id.starts_with("builtin:")
+    }
+}
+
+/// Template with the latest checkpoint of every tab of the document.
+pub fn template_from_doc(doc: &Doc) -> Template {
+    let tabs = doc
+        .tabs
+        .iter()
+        .map(|tab| Tab {
+            checkpoints: tab.checkpoints.back().cloned().into_iter().collect(),
+            ..tab.clone()
+        })
+        .collect();
+    Template {
+        id: ReflectableUuid::generate().0.to_string(),
+        name: doc.name.clone(),
+        doc: Doc {
+            tabs,
+            shared: None,
+            ..doc.clone()
+        },
+    }
+}
+
+/// New document with fresh ids for the document, tabs, nodes and arrows.
+pub fn doc_from_template(template: &Template) -> Doc {
+    let mut doc = remap_ids(&template.doc);
+    for (index, tab) in doc.tabs.iter_mut().enumerate() {
+        tab.is_active = index == 0;
+    }
+    doc
+}
+
+/// Adds a user template, replacing a previous one with the same name.
+pub fn add_user_template(templates: &mut Vec<Template>, template: Template) {
+    templates.retain(|existing| existing.name != template.name);
+    templates.push(template);
+}
+
+struct TemplateBuilder {
+    nodes: Vec<Value>,
+    arrows: Vec<Value>,
+}
+
+impl TemplateBuilder {
+    fn new() -> Self {
+        Self {
+            nodes: vec![],
+            arrows: vec![],
+        }
+    }
+
+    fn node(
+        &mut self,
+        text: &str,
+        position: (f32, f32),
+        size: (f32, f32),
+        color: Color,
+    ) -> ReflectableUuid {
+        let id = ReflectableUuid::generate();
+        self.nodes.push(json!(JsonNode {
+            id: id.0,
+            node_type: NodeType::Rect,
+            left: Val::Px(position.0),
+            bottom: Val::Px(position.1),
+            width: Val::Px(size.0),
+            height: Val::Px(size.1),
+            text: JsonNodeText {
+                text: text.to_string(),
+                pos: TextPos::Center,
+            },
+            bg_color: color,
+            z_index: 0,
+        }));
+        id
+    }
+
+    fn arrow(&mut self, start: ReflectableUuid, end: ReflectableUuid) {
+        self.arrows.push(json!(ArrowMeta {
+            arrow_type: ArrowType::Arrow,
+            start: ArrowConnect {
+                id: start,
+                pos: ArrowConnectPos::Right,
+            },
+            end: ArrowConnect {
+                id: end,
+                pos: ArrowConnectPos::Left,
+            },
+        }));
+    }
+
+    fn build(self, key: &str, name: &str) -> Template {
+        let checkpoint = json!({
+            "images": {},
+            "nodes": self.nodes,
+            "arrows": self.arrows,
+        })
+        .to_string();
+        Template {
+            id: format!("builtin:{}", key),
+            name: name.to_string(),
+            doc: Doc {
+                tabs: vec![Tab {
+                    is_active: true,
+                    id: ReflectableUuid::generate(),
+                    name: "Tab 1".to_string(),
+                    checkpoints: vec![checkpoint].into(),
+                }],
+                id: ReflectableUuid::generate(),
+                name: name.to_string(),
+                tags: vec![],
+                shared: None,
+                inline_images: Default::default(),
+            },
+        }
+    }
+}
+
+fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::rgb_u8(r, g, b)
+}
+
+fn retrospective() -> Template {
+    let mut builder = TemplateBuilder::new();
+    let columns = [
+        ("**What went well**", rgb(200, 230, 201)),
+        ("**What didn't go well**", rgb(255, 205, 210)),
+        ("**Action items**", rgb(187, 222, 251)),
+    ];
+    for (index, (title, color)) in columns.into_iter().enumerate() {
+        let left = 20. + index as f32 * 280.;
+        builder.node(title, (left, 480.), (260., 60.), color);
+        let note = if index == 2 {
+            "- [ ] Owner: action"
+        } else {
+            "Note"
+        };
+        builder.node(note, (left, 340.), (260., 120.), color);
+    }
+    builder.build("retrospective", "Retrospective")
+}
+
+fn swot() -> Template {
+    let mut builder = TemplateBuilder::new();
+    let quadrants = [
+        ("**Strengths**\n- ", (20., 290.), rgb(200, 230, 201)),
+        ("**Weaknesses**\n- ", (350., 290.), rgb(255, 224, 178)),
+        ("**Opportunities**\n- ", (20., 20.), rgb(187, 222, 251)),
+        ("**Threats**\n- ", (350., 20.), rgb(255, 205, 210)),
+    ];
+    for (text, position, color) in quadrants {
+        builder.node(text, position, (310., 250.), color);
+    }
+    builder.build("swot", "SWOT analysis")
+}
+
+fn mind_map() -> Template {
+    let mut builder = TemplateBuilder::new();
+    let center = builder.node(
+        "**Central idea**",
+        (40., 250.),
+        (200., 100.),
+        rgb(255, 236, 179),
+    );
+    for index in 0..4 {
+        let idea = builder.node(
+            &format!("Idea {}", index + 1),
+            (360., 430. - index as f32 * 130.),
+            (180., 80.),
+            rgb(225, 245, 254),
+        );
+        builder.arrow(center, idea);
+    }
+    builder.build("mind_map", "Mind map")
+}
+
+fn kanban() -> Template {
+    let mut builder = TemplateBuilder::new();
+    let columns = ["**To do**", "**In progress**", "**Done**"];
+    for (index, title) in columns.into_iter().enumerate() {
+        let left = 20. + index as f32 * 240.;
+        builder.node(title, (left, 480.), (220., 60.), rgb(207, 216, 220));
+        builder.node("Card", (left, 380.), (220., 80.), rgb(255, 249, 196));
+    }
+    builder.build("kanban", "Kanban columns")
+}
+
+fn user_story_map() -> Template {
+    let mut builder = TemplateBuilder::new();
+    builder.node(
+        "**User journey**",
+        (20., 500.),
+        (640., 50.),
+        rgb(207, 216, 220),
+    );
+    for index in 0..3 {
+        let left = 20. + index as f32 * 220.;
+        builder.node(
+            &format!("**Activity {}**", index + 1),
+            (left, 400.),
+            (200., 70.),
+            rgb(179, 229, 252),
+        );
+        builder.node("User story", (left, 290.), (200., 70.), rgb(255, 249, 196));
+    }
+    builder.node("*Release 1*", (20., 200.), (640., 40.), rgb(236, 239, 241));
+    builder.node("User story", (20., 110.), (200., 70.), rgb(255, 249, 196));
+    builder.build("user_story_map", "User story map")
+}
+
+pub fn builtin_templates() -> Vec<Template> {
+    vec![
+        retrospective(),
+        swot(),
+        mind_map(),
+        kanban(),
+        user_story_map(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::TabSnapshot;
+
+    #[test]
+    fn test_builtin_templates() {
+        let templates = builtin_templates();
+        let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Retrospective",
+                "SWOT analysis",
+                "Mind map",
+                "Kanban columns",
+                "User story map"
+            ]
+        );
+        for template in templates.iter() {
+            assert!(template.is_builtin());
+            // templates are loaded like imported documents
+            let json = serde_json::to_string(&template.doc).unwrap();
+            assert!(
+                crate::import::parse_document(&json).is_ok(),
+                "{}",
+                template.name
+            );
+        }
+        let mind_map = TabSnapshot::parse(&templates[2].doc.tabs[0].checkpoints[0]).unwrap();
+        assert_eq!(mind_map.nodes.len(), 5);
+        assert_eq!(mind_map.arrows.len(), 4);
+    }
+
+    #[test]
+    fn test_doc_from_template() {
+        let template = mind_map();
+        let doc = doc_from_template(&template);
+        assert_ne!(doc.id, template.doc.id);
+        assert_ne!(doc.tabs[0].id, template.doc.tabs[0].id);
+        assert_eq!(doc.name, "Mind map");
+        let original = TabSnapshot::parse(&template.doc.tabs[0].checkpoints[0]).unwrap();
+        let copy = TabSnapshot::parse(&doc.tabs[0].checkpoints[0]).unwrap();
+        assert!(copy
+            .nodes
+            .iter()
+            .all(|node| original.nodes.iter().all(|other| other.id != node.id)));
+        // arrows point at the new nodes
+        for arrow in copy.arrows.iter() {
+            assert!(copy.nodes.iter().any(|node| node.id == arrow.start.id.0));
+            assert!(copy.nodes.iter().any(|node| node.id == arrow.end.id.0));
+        }
+        // two documents from the same template don't share ids
+        assert_ne!(doc_from_template(&template).id, doc.id);
+    }
+
+    #[test]
+    fn test_template_from_doc() {
+        let mut doc = doc_from_template(&kanban());
+        doc.name = "Sprint board".to_string();
+        let checkpoint = doc.tabs[0].checkpoints[0].clone();
+        doc.tabs[0].checkpoints.push_front("{}".to_string());
+        let template = template_from_doc(&doc);
+        assert!(!template.is_builtin());
+        assert_eq!(template.name, "Sprint board");
+        assert_eq!(
+            template.doc.tabs[0].checkpoints,
+            std::collections::VecDeque::from(vec![checkpoint])
+        );
+
+        let mut templates = vec![template_from_doc(&doc)];
+        add_user_template(&mut templates, template.clone());
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].id, template.id);
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+
+use serde_json::{json, Value};
+
+/// Serves a single HTTP response and sends back the request line and body it received.
+pub fn mock_server(response: &str) -> (String, mpsc::Receiver<(String, String)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let response = response.to_string();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        reader.get_mut().write_all(response.as_bytes()).unwrap();
+        let _ = tx.send((
+            request_line.trim().to_string(),
+            String::from_utf8(body).unwrap(),
+        ));
+    });
+    (url, rx)
+}
+
+pub fn http_response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+pub fn json_response(status: &str, body: &str) -> String {
+    http_response(status, "application/json", body)
+}
+
+/// Waits for the result passed to a callback.
+pub fn wait<T: Send + 'static>(run: impl FnOnce(Box<dyn FnOnce(T) + Send>)) -> T {
+    let (tx, rx) = mpsc::channel();
+    run(Box::new(move |result| {
+        tx.send(result).unwrap();
+    }));
+    rx.recv().unwrap()
+}
+
+/// Rectangle node of a checkpoint.
+pub fn node(id: &str, text: &str, left: f32) -> Value {
+    json!({
+        "id": id,
+        "node_type": "Rect",
+        "left": { "Px": left },
+        "bottom": { "Px": 100.0 },
+        "width": { "Px": 100.0 },
+        "height": { "Px": 100.0 },
+        "text": { "text": text, "pos": "Center" },
+        "bg_color": { "Rgba": { "red": 1.0, "green": 1.0, "blue": 1.0, "alpha": 1.0 } },
+        "z_index": 0
+    })
+}
+
+pub fn checkpoint(nodes: Vec<Value>, arrows: Vec<Value>) -> String {
+    json!({ "images": {}, "nodes": nodes, "arrows": arrows }).to_string()
+}
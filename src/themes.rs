@@ -1,12 +1,21 @@
 use bevy::prelude::*;
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::utils::line_of_key;
 use std::path::PathBuf;
 
-#[derive(Resource, Debug)]
+#[derive(Resource, Debug, Clone)]
 pub struct Theme {
     pub add_tab_bg: Color,
     pub arrow_btn_bg: Color,
     pub arrow_connector: Color,
     pub arrow: Color,
+    pub blockquote: Color,
+    /// Background of the columns of a kanban board.
+    pub board_column_bg: Color,
     pub bottom_panel_bg: Color,
     pub btn_border: Color,
     pub canvas_bg_color: Option<Color>,
@@ -17,6 +26,13 @@ pub struct Theme {
     pub code_default_lang: String,
     pub code_theme: String,
     pub del_button: Color,
+    /// Outlines of the diff view, by kind of change.
+    pub diff_added: Color,
+    pub diff_moved: Color,
+    pub diff_recolored: Color,
+    pub diff_removed: Color,
+    pub diff_resized: Color,
+    pub diff_text_changed: Color,
     pub doc_list_bg: Color,
     pub font: Color,
     pub font_name: String,
@@ -26,11 +42,14 @@ pub struct Theme {
     pub inline_code: Color,
     pub left_panel_bg: Color,
     pub link: Color,
+    /// Badge of nodes with markdown that couldn't be fully rendered.
+    pub markdown_warning: Color,
     pub menu_bg: Color,
     pub menu_btn_bg: Color,
     pub menu_btn: Color,
     pub modal_bg: Color,
     pub modal_text_input_bg: Color,
+    pub name: String,
     pub new_tab_btn_bg: Color,
     pub node_bg: Color,
     pub node_border: Color,
@@ -43,8 +62,12 @@ pub struct Theme {
     pub selected_node_border: Color,
     pub shadow: Color,
     pub tab_bg: Color,
+    pub tag_active_bg: Color,
+    pub tag_bg: Color,
     pub text_pos_btn_bg: Color,
     pub tooltip_bg: Color,
+    /// Header of the board columns with more cards than their WIP limit.
+    pub wip_exceeded: Color,
 }
 
 pub fn velo_light() -> Theme {
@@ -53,6 +76,8 @@ pub fn velo_light() -> Theme {
         arrow_btn_bg: Color::rgb(207.0 / 255.0, 216.0 / 255.0, 220.0 / 255.0),
         arrow_connector: Color::BLUE.with_a(0.8),
         arrow: Color::rgb(63.0 / 255.0, 81.0 / 255.0, 181.0 / 255.0),
+        blockquote: Color::rgb(0.4, 0.4, 0.4),
+        board_column_bg: Color::rgba(0., 0., 0., 0.05),
         bottom_panel_bg: Color::rgb(189.0 / 255.0, 189.0 / 255.0, 189.0 / 255.0),
         btn_border: Color::rgb(0.5, 0.5, 0.5),
         canvas_bg_color: None,
@@ -63,6 +88,12 @@ pub fn velo_light() -> Theme {
         code_default_lang: "rs".to_string(),
         code_theme: "Solarized (light)".to_string(),
         del_button: Color::BLACK,
+        diff_added: Color::rgb(0.2, 0.7, 0.3),
+        diff_moved: Color::rgb(0.2, 0.45, 0.9),
+        diff_recolored: Color::rgb(0.6, 0.3, 0.8),
+        diff_removed: Color::rgb(0.85, 0.2, 0.2),
+        diff_resized: Color::rgb(0.95, 0.55, 0.1),
+        diff_text_changed: Color::rgb(0.8, 0.7, 0.1),
         doc_list_bg: Color::rgb(158., 158., 158.),
         font: Color::rgb(0.0, 0.0, 0.0),
         font_name: "Victor Mono".to_string(),
@@ -70,11 +101,13 @@ pub fn velo_light() -> Theme {
         inline_code: Color::GRAY,
         left_panel_bg: Color::rgb(224.0 / 255.0, 224.0 / 255.0, 224.0 / 255.0),
         link: Color::BLUE,
+        markdown_warning: Color::rgb(1., 152.0 / 255.0, 0.),
         menu_bg: Color::rgb(245.0 / 255.0, 245.0 / 255.0, 245.0 / 255.0),
         menu_btn_bg: Color::rgb(0.0 / 255.0, 150.0 / 255.0, 136.0 / 255.0),
         menu_btn: Color::BLACK,
         modal_bg: Color::WHITE,
         modal_text_input_bg: Color::WHITE,
+        name: "light".to_string(),
         new_tab_btn_bg: Color::rgb(189.0 / 255.0, 189.0 / 255.0, 189.0 / 255.0),
         node_bg: Color::rgb(0.98, 0.98, 0.98),
         node_border: Color::rgb(158.0 / 255.0, 157.0 / 255.0, 36.0 / 255.0),
@@ -87,8 +120,11 @@ pub fn velo_light() -> Theme {
         selected_node_border: Color::rgba(33.0 / 255.0, 150.0 / 255.0, 243.0 / 255.0, 1.0),
         shadow: Color::rgba(0., 0., 0., 0.5),
         tab_bg: Color::rgb(0.9, 0.9, 0.9),
+        tag_active_bg: Color::rgba(33.0 / 255.0, 150.0 / 255.0, 243.0 / 255.0, 1.0),
+        tag_bg: Color::rgb(207.0 / 255.0, 216.0 / 255.0, 220.0 / 255.0),
         text_pos_btn_bg: Color::rgb(207.0 / 255.0, 216.0 / 255.0, 220.0 / 255.0),
         tooltip_bg: Color::rgb(1., 1., 1.),
+        wip_exceeded: Color::rgb(229.0 / 255.0, 57.0 / 255.0, 53.0 / 255.0),
         font_size: 14.,
         line_height: 18.,
     }
@@ -102,6 +138,8 @@ pub fn velo_dark() -> Theme {
         arrow_btn_bg: Color::rgb(0.2, 0.2, 0.2),
         arrow_connector: Color::rgb(0.8, 0.8, 0.8),
         arrow: Color::rgb(0.8, 0.8, 0.8),
+        blockquote: Color::rgb(0.7, 0.7, 0.7),
+        board_column_bg: Color::rgba(1., 1., 1., 0.05),
         bottom_panel_bg: Color::rgb(0.1, 0.1, 0.1),
         btn_border: Color::rgb(0.8, 0.8, 0.8),
         canvas_bg_color: Some(Color::rgb(0.3, 0.3, 0.3)),
@@ -112,6 +150,12 @@ pub fn velo_dark() -> Theme {
         code_default_lang: "rs".to_string(),
         code_theme: "base16-ocean.dark".to_string(),
         del_button: Color::BLACK,
+        diff_added: Color::rgb(0.4, 0.8, 0.45),
+        diff_moved: Color::rgb(0.4, 0.6, 1.),
+        diff_recolored: Color::rgb(0.75, 0.5, 0.9),
+        diff_removed: Color::rgb(0.95, 0.4, 0.4),
+        diff_resized: Color::rgb(1., 0.65, 0.3),
+        diff_text_changed: Color::rgb(0.9, 0.8, 0.3),
         doc_list_bg: Color::rgb(0.2, 0.2, 0.2),
         font: Color::WHITE,
         font_name: "Source Code Pro".to_string(),
@@ -119,11 +163,13 @@ pub fn velo_dark() -> Theme {
         inline_code: Color::WHITE,
         left_panel_bg: Color::rgb(224.0 / 255.0, 224.0 / 255.0, 224.0 / 255.0),
         link: Color::BLUE,
+        markdown_warning: Color::rgb(1., 183.0 / 255.0, 77.0 / 255.0),
         menu_bg: Color::rgb(245.0 / 255.0, 245.0 / 255.0, 245.0 / 255.0),
         menu_btn_bg: Color::rgb(0.0 / 255.0, 150.0 / 255.0, 136.0 / 255.0),
         menu_btn: Color::WHITE,
         modal_bg: Color::rgb(0.2, 0.2, 0.2),
         modal_text_input_bg: Color::rgb(0.2, 0.2, 0.2),
+        name: "dark".to_string(),
         new_tab_btn_bg: Color::rgb(0.2, 0.2, 0.2),
         node_bg: Color::BLACK.with_a(0.5),
         node_border: Color::rgb(0.3, 0.3, 0.3),
@@ -136,8 +182,11 @@ pub fn velo_dark() -> Theme {
         selected_node_border: Color::rgb(0.3, 0.3, 0.3),
         shadow: Color::rgb(0.1, 0.1, 0.1),
         tab_bg: Color::rgb(0.2, 0.2, 0.2),
+        tag_active_bg: Color::TEAL,
+        tag_bg: Color::rgb(0.3, 0.3, 0.3),
         text_pos_btn_bg: Color::rgb(0.2, 0.2, 0.2),
         tooltip_bg: Color::rgb(0.2, 0.2, 0.2),
+        wip_exceeded: Color::rgb(239.0 / 255.0, 83.0 / 255.0, 80.0 / 255.0),
     }
 }
 
@@ -145,6 +194,462 @@ pub fn get_theme_by_name(theme_name: &str) -> Theme {
     match theme_name {
         "light" => velo_light(),
         "dark" => velo_dark(),
-        _ => velo_light(),
+        _ => {
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(themes_dir) = themes_dir() {
+                let (themes, errors) = load_user_themes(&themes_dir);
+                for error in errors {
+                    error!("{}", error);
+                }
+                if let Some((_, theme)) = themes.into_iter().find(|(name, _)| name == theme_name) {
+                    return theme;
+                }
+            }
+            warn!(
+                "Theme {} not found, falling back to light theme",
+                theme_name
+            );
+            velo_light()
+        }
+    }
+}
+
+/// Names of the built-in themes followed by the user-defined ones.
+pub fn get_theme_names() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut names = vec!["light".to_string(), "dark".to_string()];
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(themes_dir) = themes_dir() {
+        let (themes, errors) = load_user_themes(&themes_dir);
+        for error in errors {
+            error!("{}", error);
+        }
+        names.extend(themes.into_iter().map(|(name, _)| name));
+    }
+    names
+}
+
+/// Error produced while loading a user-defined theme file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+/// Directory with user-defined themes: `<config dir>/themes/*.toml`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn themes_dir() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", crate::ORG_NAME, crate::APP_NAME)
+        .map(|dirs| dirs.config_dir().join("themes"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct ThemeSource {
+    name: String,
+    base: String,
+    path: PathBuf,
+    source: String,
+    table: toml::Table,
+}
+
+/// Loads every `*.toml` file in `themes_dir` as a theme.
+///
+/// A theme file may set any `Theme` field, everything else is inherited from
+/// `base` (a built-in theme or another user theme, `light` by default).
+/// The theme name is taken from `name` or the file stem.
+/// Files that fail to load are reported in the returned errors and skipped.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_user_themes(themes_dir: &Path) -> (Vec<(String, Theme)>, Vec<ThemeError>) {
+    let mut errors = vec![];
+    let mut sources = vec![];
+    let entries = match std::fs::read_dir(themes_dir) {
+        Ok(entries) => entries,
+        Err(_) => return (vec![], errors),
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    for path in paths {
+        match read_theme_source(&path) {
+            Ok(source) => {
+                if source.name == "light" || source.name == "dark" {
+                    errors.push(ThemeError {
+                        path: source.path.clone(),
+                        line: line_of_key(&source.source, "name"),
+                        message: format!(
+                            "theme name \"{}\" is reserved for a built-in theme",
+                            source.name
+                        ),
+                    });
+                } else if sources.iter().any(|s: &ThemeSource| s.name == source.name) {
+                    errors.push(ThemeError {
+                        path: source.path.clone(),
+                        line: line_of_key(&source.source, "name"),
+                        message: format!("theme \"{}\" is already defined", source.name),
+                    });
+                } else {
+                    sources.push(source);
+                }
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+
+    let code_themes = bevy_markdown::get_code_theme_names();
+    let mut themes = vec![];
+    for source in sources.iter() {
+        match resolve_theme(&source.name, &sources, &code_themes, &mut vec![]) {
+            Ok(theme) => themes.push((source.name.clone(), theme)),
+            Err(error) => errors.push(error),
+        }
+    }
+    (themes, errors)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_theme_source(path: &Path) -> Result<ThemeSource, ThemeError> {
+    let error = |line, message| ThemeError {
+        path: path.to_path_buf(),
+        line,
+        message,
+    };
+    let source = std::fs::read_to_string(path).map_err(|e| error(None, e.to_string()))?;
+    let table = toml::from_str::<toml::Table>(&source)
+        .map_err(|e| error(None, e.to_string().trim_end().to_string()))?;
+    let get_str = |key: &str| -> Result<Option<String>, ThemeError> {
+        match table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::String(s)) => Ok(Some(s.clone())),
+            Some(value) => Err(error(
+                line_of_key(&source, key),
+                format!("`{}` must be a string, found {}", key, value.type_str()),
+            )),
+        }
+    };
+    let name = match get_str("name")? {
+        Some(name) => name,
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    };
+    let base = get_str("base")?.unwrap_or_else(|| "light".to_string());
+    Ok(ThemeSource {
+        name,
+        base,
+        path: path.to_path_buf(),
+        source,
+        table,
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn resolve_theme(
+    name: &str,
+    sources: &[ThemeSource],
+    code_themes: &[String],
+    visiting: &mut Vec<String>,
+) -> Result<Theme, ThemeError> {
+    let source = sources.iter().find(|s| s.name == name).unwrap();
+    let error = |line, message| ThemeError {
+        path: source.path.clone(),
+        line,
+        message,
+    };
+    visiting.push(name.to_string());
+    let mut theme = match source.base.as_str() {
+        "light" => velo_light(),
+        "dark" => velo_dark(),
+        base if visiting.iter().any(|v| v == base) => {
+            return Err(error(
+                line_of_key(&source.source, "base"),
+                format!("base theme \"{}\" inherits from \"{}\" (cycle)", base, name),
+            ));
+        }
+        base if sources.iter().any(|s| s.name == base) => {
+            resolve_theme(base, sources, code_themes, visiting).map_err(|e| {
+                error(
+                    line_of_key(&source.source, "base"),
+                    format!("base theme \"{}\" failed to load: {}", base, e.message),
+                )
+            })?
+        }
+        base => {
+            return Err(error(
+                line_of_key(&source.source, "base"),
+                format!("unknown base theme \"{}\"", base),
+            ));
+        }
+    };
+    for (key, value) in source.table.iter() {
+        if key == "name" || key == "base" {
+            continue;
+        }
+        apply_theme_value(&mut theme, key, value).map_err(|message| {
+            error(
+                line_of_key(&source.source, key),
+                format!("`{}`: {}", key, message),
+            )
+        })?;
+    }
+    theme.name = name.to_string();
+    if !code_themes.contains(&theme.code_theme) {
+        return Err(error(
+            line_of_key(&source.source, "code_theme"),
+            format!(
+                "unknown code_theme \"{}\", expected one of: {}",
+                theme.code_theme,
+                code_themes.join(", ")
+            ),
+        ));
+    }
+    visiting.pop();
+    Ok(theme)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn apply_theme_value(theme: &mut Theme, key: &str, value: &toml::Value) -> Result<(), String> {
+    match key {
+        "add_tab_bg" => theme.add_tab_bg = parse_color(value)?,
+        "arrow_btn_bg" => theme.arrow_btn_bg = parse_color(value)?,
+        "arrow_connector" => theme.arrow_connector = parse_color(value)?,
+        "arrow" => theme.arrow = parse_color(value)?,
+        "blockquote" => theme.blockquote = parse_color(value)?,
+        "board_column_bg" => theme.board_column_bg = parse_color(value)?,
+        "bottom_panel_bg" => theme.bottom_panel_bg = parse_color(value)?,
+        "btn_border" => theme.btn_border = parse_color(value)?,
+        "canvas_bg_color" => theme.canvas_bg_color = parse_optional_color(value)?,
+        "canvas_bg_img" => theme.canvas_bg_img = parse_optional_path(value)?,
+        "celebrate_btn_bg" => theme.celebrate_btn_bg = parse_color(value)?,
+        "celebrate_btn" => theme.celebrate_btn = parse_color(value)?,
+        "clipboard_image_bg" => theme.clipboard_image_bg = parse_color(value)?,
+        "code_default_lang" => theme.code_default_lang = parse_string(value)?,
+        "code_theme" => theme.code_theme = parse_string(value)?,
+        "del_button" => theme.del_button = parse_color(value)?,
+        "diff_added" => theme.diff_added = parse_color(value)?,
+        "diff_moved" => theme.diff_moved = parse_color(value)?,
+        "diff_recolored" => theme.diff_recolored = parse_color(value)?,
+        "diff_removed" => theme.diff_removed = parse_color(value)?,
+        "diff_resized" => theme.diff_resized = parse_color(value)?,
+        "diff_text_changed" => theme.diff_text_changed = parse_color(value)?,
+        "doc_list_bg" => theme.doc_list_bg = parse_color(value)?,
+        "font" => theme.font = parse_color(value)?,
+        "font_name" => theme.font_name = parse_string(value)?,
+        "font_size" => theme.font_size = parse_f32(value)?,
+        "line_height" => theme.line_height = parse_f32(value)?,
+        "front_back_btn_bg" => theme.front_back_btn_bg = parse_color(value)?,
+        "inline_code" => theme.inline_code = parse_color(value)?,
+        "left_panel_bg" => theme.left_panel_bg = parse_color(value)?,
+        "link" => theme.link = parse_color(value)?,
+        "markdown_warning" => theme.markdown_warning = parse_color(value)?,
+        "menu_bg" => theme.menu_bg = parse_color(value)?,
+        "menu_btn_bg" => theme.menu_btn_bg = parse_color(value)?,
+        "menu_btn" => theme.menu_btn = parse_color(value)?,
+        "modal_bg" => theme.modal_bg = parse_color(value)?,
+        "modal_text_input_bg" => theme.modal_text_input_bg = parse_color(value)?,
+        "new_tab_btn_bg" => theme.new_tab_btn_bg = parse_color(value)?,
+        "node_bg" => theme.node_bg = parse_color(value)?,
+        "node_border" => theme.node_border = parse_color(value)?,
+        "node_found_color" => theme.node_found_color = parse_color(value)?,
+        "node_manipulation_bg" => theme.node_manipulation_bg = parse_color(value)?,
+        "node_manipulation" => theme.node_manipulation = parse_color(value)?,
+        "ok_cancel_bg" => theme.ok_cancel_bg = parse_color(value)?,
+        "search_box_bg" => theme.search_box_bg = parse_color(value)?,
+        "search_box_border" => theme.search_box_border = parse_color(value)?,
+        "selected_node_border" => theme.selected_node_border = parse_color(value)?,
+        "shadow" => theme.shadow = parse_color(value)?,
+        "tab_bg" => theme.tab_bg = parse_color(value)?,
+        "tag_active_bg" => theme.tag_active_bg = parse_color(value)?,
+        "tag_bg" => theme.tag_bg = parse_color(value)?,
+        "text_pos_btn_bg" => theme.text_pos_btn_bg = parse_color(value)?,
+        "tooltip_bg" => theme.tooltip_bg = parse_color(value)?,
+        "wip_exceeded" => theme.wip_exceeded = parse_color(value)?,
+        _ => return Err("unknown theme field".to_string()),
+    }
+    Ok(())
+}
+
+/// Parses `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa` (the `#` is optional).
+pub fn parse_hex_color(hex: &str) -> Result<Color, String> {
+    let hex = hex.trim();
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    Color::hex(digits).map_err(|_| format!("invalid hex color \"{}\"", hex))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_color(value: &toml::Value) -> Result<Color, String> {
+    match value {
+        toml::Value::String(s) => parse_hex_color(s),
+        _ => Err(format!(
+            "expected a hex color string like \"#ff8800\", found {}",
+            value.type_str()
+        )),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_optional_color(value: &toml::Value) -> Result<Option<Color>, String> {
+    match value {
+        toml::Value::String(s) if s == "none" => Ok(None),
+        _ => parse_color(value).map(Some),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_optional_path(value: &toml::Value) -> Result<Option<PathBuf>, String> {
+    match value {
+        toml::Value::String(s) if s == "none" => Ok(None),
+        toml::Value::String(s) => Ok(Some(PathBuf::from(s))),
+        _ => Err(format!(
+            "expected a path string, found {}",
+            value.type_str()
+        )),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_string(value: &toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        _ => Err(format!("expected a string, found {}", value.type_str())),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_f32(value: &toml::Value) -> Result<f32, String> {
+    match value {
+        toml::Value::Float(f) => Ok(*f as f32),
+        toml::Value::Integer(i) => Ok(*i as f32),
+        _ => Err(format!("expected a number, found {}", value.type_str())),
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+
+    fn write_theme(dir: &Path, file_name: &str, content: &str) {
+        std::fs::write(dir.join(file_name), content).unwrap();
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#ff0000").unwrap(), Color::rgb(1., 0., 0.));
+        assert_eq!(parse_hex_color("00ff00").unwrap(), Color::rgb(0., 1., 0.));
+        assert_eq!(
+            parse_hex_color("#0000ff00").unwrap(),
+            Color::rgba(0., 0., 1., 0.)
+        );
+        assert!(parse_hex_color("#zzzzzz").is_err());
+    }
+
+    #[test]
+    fn test_load_user_themes_inherits_from_base() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        write_theme(
+            temp_dir.path(),
+            "midnight.toml",
+            r##"
+base = "dark"
+node_bg = "#101020"
+font_size = 16
+canvas_bg_img = "none"
+"##,
+        );
+        write_theme(
+            temp_dir.path(),
+            "midnight_blue.toml",
+            r##"
+name = "Midnight Blue"
+base = "midnight"
+link = "#3366ff"
+"##,
+        );
+        let (themes, errors) = load_user_themes(temp_dir.path());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(themes.len(), 2);
+
+        let (name, midnight) = &themes[0];
+        assert_eq!(name, "midnight");
+        let dark = velo_dark();
+        assert_eq!(midnight.node_bg, parse_hex_color("#101020").unwrap());
+        assert_eq!(midnight.font_size, 16.);
+        assert_eq!(midnight.canvas_bg_img, None);
+        assert_eq!(midnight.code_theme, dark.code_theme);
+        assert_eq!(midnight.arrow, dark.arrow);
+
+        let (name, midnight_blue) = &themes[1];
+        assert_eq!(name, "Midnight Blue");
+        assert_eq!(midnight_blue.name, "Midnight Blue");
+        assert_eq!(midnight_blue.node_bg, midnight.node_bg);
+        assert_eq!(midnight_blue.link, parse_hex_color("#3366ff").unwrap());
+    }
+
+    #[test]
+    fn test_load_user_themes_errors() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        write_theme(temp_dir.path(), "a_syntax.toml", "node_bg = ");
+        write_theme(
+            temp_dir.path(),
+            "b_color.toml",
+            "base = \"light\"\nnode_bg = \"#nothex\"\n",
+        );
+        write_theme(temp_dir.path(), "c_unknown.toml", "\n\nnot_a_field = 1\n");
+        write_theme(
+            temp_dir.path(),
+            "d_code_theme.toml",
+            "code_theme = \"Does not exist\"\n",
+        );
+        write_theme(temp_dir.path(), "e_base.toml", "base = \"missing\"\n");
+        write_theme(temp_dir.path(), "f_cycle.toml", "base = \"f_cycle\"\n");
+        write_theme(temp_dir.path(), "ignored.txt", "node_bg = 1");
+
+        let (themes, errors) = load_user_themes(temp_dir.path());
+        assert!(themes.is_empty());
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+
+        assert!(errors[0].path.ends_with("a_syntax.toml"));
+        assert!(errors[0].message.contains("line 1"), "{}", errors[0]);
+
+        let file_errors: Vec<(Option<usize>, &str)> = errors[1..]
+            .iter()
+            .map(|e| (e.line, e.message.as_str()))
+            .collect();
+        assert_eq!(
+            file_errors[0],
+            (Some(2), "`node_bg`: invalid hex color \"#nothex\"")
+        );
+        assert_eq!(
+            file_errors[1],
+            (Some(3), "`not_a_field`: unknown theme field")
+        );
+        assert_eq!(file_errors[2].0, Some(1));
+        assert!(file_errors[2]
+            .1
+            .starts_with("unknown code_theme \"Does not exist\", expected one of:"));
+        assert_eq!(file_errors[3], (Some(1), "unknown base theme \"missing\""));
+        assert_eq!(
+            file_errors[4],
+            (
+                Some(1),
+                "base theme \"f_cycle\" inherits from \"f_cycle\" (cycle)"
+            )
+        );
+        assert!(errors[1]
+            .to_string()
+            .ends_with("b_color.toml:2: `node_bg`: invalid hex color \"#nothex\""));
     }
 }
@@ -68,6 +68,60 @@ pub use search::*;
 mod canvas_click;
 use canvas_click::*;
 
+#[path = "systems/theme_picker.rs"]
+mod theme_picker;
+use theme_picker::*;
+
+#[path = "systems/command_registry.rs"]
+mod command_registry;
+pub use command_registry::*;
+
+#[path = "systems/command_palette.rs"]
+mod command_palette;
+use command_palette::*;
+
+#[path = "systems/share.rs"]
+mod share;
+use share::*;
+
+#[path = "systems/diff_view.rs"]
+mod diff_view;
+use diff_view::*;
+
+#[path = "systems/history.rs"]
+mod history;
+use history::*;
+
+#[path = "systems/templates.rs"]
+mod templates;
+use templates::*;
+
+#[path = "systems/markdown_warnings.rs"]
+mod markdown_warnings;
+use markdown_warnings::*;
+
+#[path = "systems/markdown_render.rs"]
+mod markdown_render;
+pub use markdown_render::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[path = "systems/dot_files.rs"]
+mod dot_files;
+#[cfg(not(target_arch = "wasm32"))]
+pub use dot_files::*;
+
+#[path = "systems/mind_map.rs"]
+mod mind_map;
+pub use mind_map::*;
+
+#[path = "systems/board.rs"]
+mod board;
+pub use board::*;
+
+#[path = "systems/inline_images.rs"]
+mod inline_images;
+pub use inline_images::*;
+
 #[path = "systems/active_editor_changed.rs"]
 mod active_editor_changed;
 use active_editor_changed::*;
@@ -88,8 +142,22 @@ pub struct UpdateDeleteDocBtnEvent;
 
 #[derive(Resource, Clone)]
 pub struct CommChannels {
-    pub tx: Sender<String>,
-    pub rx: Receiver<String>,
+    pub tx: Sender<ImportResult>,
+    pub rx: Receiver<ImportResult>,
+}
+
+/// Document loaded from a file or URL.
+pub struct ImportResult {
+    /// Import modal waiting for the result, `None` for documents opened with a `?document=` link.
+    pub modal_id: Option<ReflectableUuid>,
+    pub result: Result<crate::components::Doc, crate::import::ImportError>,
+}
+
+/// Import started from a modal, the modal shows its progress until the result arrives.
+#[derive(Resource, Default)]
+pub struct PendingImport {
+    pub modal_id: Option<ReflectableUuid>,
+    pub started_at: f64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Reflect, Default, Debug)]
@@ -124,9 +192,6 @@ pub struct JsonNode {
     pub z_index: i32,
 }
 
-pub const MAX_CHECKPOINTS: i32 = 7;
-pub const MAX_SAVED_DOCS_IN_MEMORY: i32 = 7;
-
 #[derive(Resource, Default)]
 pub struct UiState {
     pub modal_id: Option<ReflectableUuid>,
@@ -148,16 +213,27 @@ pub struct BlinkTimer {
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<UiState>();
+        app.init_resource::<PendingImport>();
         app.init_resource::<AppState>();
 
         app.add_event::<AddRectEvent>();
         app.add_event::<CreateArrowEvent>();
         app.add_event::<RedrawArrowEvent>();
         app.add_event::<SaveStoreEvent>();
+        app.add_event::<HistoryChanged>();
         app.add_event::<UpdateDeleteDocBtnEvent>();
+        app.add_event::<RunCommand>();
+
+        app.init_resource::<CommandRegistry>();
+        app.init_resource::<ShareChannels>();
+        app.init_resource::<MarkdownRenderCache>();
+        app.init_resource::<InlineImages>();
+        app.init_resource::<InlineImageChannels>();
+        app.init_resource::<MindMap>();
+        app.init_resource::<TabBoard>();
 
         #[cfg(not(target_arch = "wasm32"))]
-        app.add_startup_systems((read_native_config, init_search_index).before(init_layout));
+        app.add_startup_system(init_search_index.before(init_layout));
         #[cfg(target_arch = "wasm32")]
         app.add_startup_system(load_from_url.before(init_layout));
         app.add_startup_system(init_layout);
@@ -170,6 +246,8 @@ impl Plugin for UiPlugin {
             resize_entity_end,
             cancel_modal,
             confirm_modal,
+            import_progress,
+            import_conflict_click,
             resize_notificator,
         ));
 
@@ -192,7 +270,7 @@ impl Plugin for UiPlugin {
         );
 
         app.add_systems(
-            (load_tab, remove_load_tab_request)
+            (load_board, load_tab, remove_load_tab_request)
                 .chain()
                 .distributive_run_if(should_load_tab),
         );
@@ -205,17 +283,70 @@ impl Plugin for UiPlugin {
             delete_tab_handler,
             rename_tab_handler,
             mouse_scroll_list,
-            list_item_click,
+            open_doc_handler,
             new_doc_handler,
             rename_doc_handler,
             delete_doc_handler,
             save_doc_handler,
+            tag_doc_handler,
             keyboard_input_system.after(bevy_cosmic_edit::cosmic_edit_bevy_events),
         ));
         app.add_systems((doc_list_del_button_update, doc_list_ui_changed).chain());
+        app.add_system(tag_chip_click.before(doc_list_ui_changed));
+        app.add_systems((change_theme_handler, theme_option_click, apply_theme));
+        app.add_systems((
+            command_button_click,
+            command_palette_handler,
+            command_palette_input,
+            save_tab_handler,
+            restore_checkpoint_handler,
+            diff_view_handler,
+            diff_picker_click,
+            #[cfg(not(target_arch = "wasm32"))]
+            paste_image_handler,
+        ));
 
         #[cfg(not(target_arch = "wasm32"))]
         app.add_systems((search_box_click, search_box_text_changed));
+        app.add_systems((
+            save_version_handler,
+            pending_version_handler.after(save_tab),
+            history_store_handler.after(save_to_store),
+            history_panel_handler,
+            history_button_click,
+            pending_restore_handler.after(save_tab),
+        ));
+        app.add_systems((
+            template_picker_handler,
+            template_option_click,
+            template_delete_click,
+            save_template_handler,
+            pending_template_handler.after(save_tab),
+        ));
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems((dot_file_handler, pending_dot_export_handler.after(save_tab)));
+        app.add_systems((
+            mind_map_mode_handler,
+            mind_map_arrange,
+            mind_map_pending.before(mind_map_keys),
+            mind_map_keys
+                .before(bevy_cosmic_edit::cosmic_edit_bevy_events)
+                .before(keyboard_input_system),
+            mind_map_outline.after(entity_to_edit_changed),
+        ));
+        app.add_systems((
+            board_handler,
+            board_edit_handler,
+            board_drop.after(update_rectangle_position),
+            board_visuals
+                .after(board_edit_handler)
+                .after(board_drop)
+                .after(load_board),
+            pending_board_csv_handler,
+        ));
+        app.add_systems((receive_inline_images, update_inline_images).chain());
+        app.add_system(markdown_warning_badge);
+        app.add_systems((start_markdown_render, finish_markdown_render).chain());
 
         app.add_systems((
             button_generic_handler,
@@ -227,9 +358,12 @@ impl Plugin for UiPlugin {
             #[cfg(target_arch = "wasm32")]
             set_window_property,
             shared_doc_handler,
+            (share_visibility_click, share_request_handler).chain(),
+            share_result_handler,
             #[cfg(not(target_arch = "wasm32"))]
             particles_effect,
             save_to_store.after(save_tab),
+            autosave,
             canvas_click,
             active_editor_changed,
         ));
@@ -248,38 +382,25 @@ impl Plugin for UiPlugin {
 }
 
 #[cfg(target_arch = "wasm32")]
-fn load_from_url(mut commands: Commands) {
-    let (tx, rx) = async_channel::bounded(1);
+fn load_from_url(mut commands: Commands, config: Res<crate::config::Config>) {
+    let (tx, rx) = async_channel::unbounded();
     commands.insert_resource(CommChannels { tx: tx.clone(), rx });
     let href = web_sys::window().unwrap().location().href().unwrap();
     let url = url::Url::parse(href.as_str()).unwrap();
     let query_pairs: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
     if let Some(url) = query_pairs.get("document") {
-        let pool = bevy::tasks::IoTaskPool::get();
-        let mut finder = linkify::LinkFinder::new();
-        finder.kinds(&[linkify::LinkKind::Url]);
-        let links: Vec<_> = finder.links(url).collect();
-        if links.len() == 1 {
-            let url = links.first().unwrap().as_str().to_owned();
-            let cc = tx.clone();
-            let task = pool.spawn(async move {
-                let request = ehttp::Request::get(url);
-                ehttp::fetch(request, move |result| {
-                    let json_string = result.unwrap().text().unwrap();
-                    cc.try_send(json_string).unwrap();
-                });
-            });
-            task.detach();
+        match crate::import::parse_import_url(url) {
+            Ok(url) => crate::import::fetch_document(
+                url,
+                &config.import,
+                Box::new(move |result| {
+                    let _ = tx.try_send(ImportResult {
+                        modal_id: None,
+                        result,
+                    });
+                }),
+            ),
+            Err(e) => error!("Failed to load document from {}: {}", url, e),
         }
     }
 }
-
-#[cfg(not(target_arch = "wasm32"))]
-fn read_native_config(mut app_state: ResMut<AppState>) {
-    use crate::utils::read_config_file;
-
-    let config = read_config_file().unwrap_or_default();
-    if let Some(github_token) = &config.github_access_token {
-        app_state.github_token = Some(github_token.clone());
-    }
-}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_cosmic_edit::CosmicFont;
+use bevy_ui_borders::Outline;
+use serde_json::Value;
+
+use super::ui_helpers::{spawn_modal, BoardBackground, ModalStatus, ModalTop, RawText};
+use super::{
+    close_modal, show_modal_error, style_rect, MainPanel, ModalAction, RunCommand, VeloCommand,
+    VeloNodeContainer,
+};
+use crate::board::{parse_board_spec, Board, BoardCard, BoardColumn, LANE_LABEL_WIDTH};
+use crate::canvas::arrow::events::RedrawArrowEvent;
+use crate::resources::{AppState, FontSystemState, LoadTabRequest};
+use crate::themes::Theme;
+use crate::utils::ReflectableUuid;
+use crate::UiState;
+
+/// Space between a new board and the top left corner of the canvas.
+const BOARD_MARGIN: f32 = 20.;
+/// Below the nodes, even the ones sent to back.
+const BOARD_Z_INDEX: i32 = -1000;
+
+/// Board of the current tab, saved in its checkpoints.
+#[derive(Resource, Default)]
+pub struct TabBoard {
+    pub board: Option<Board>,
+}
+
+/// Columns and lanes confirmed in the board modal, `None` removes the board.
+#[derive(Resource)]
+pub struct BoardEditRequest {
+    pub spec: Option<(Vec<BoardColumn>, Vec<String>)>,
+}
+
+/// File the cards of the board are written to by [`pending_board_csv_handler`].
+#[derive(Resource)]
+pub struct PendingBoardCsv {
+    pub path: PathBuf,
+    /// Export modal, closed when the file is written or showing the error.
+    pub modal_id: ReflectableUuid,
+}
+
+/// Asks for the columns and lanes of the board or the path of the CSV export.
+pub fn board_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    mut ui_state: ResMut<UiState>,
+    tab_board: Res<TabBoard>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        let (action, default_value) = match (command, &tab_board.board) {
+            (VeloCommand::EditBoard, Some(board)) => (ModalAction::EditBoard, board.spec()),
+            (VeloCommand::EditBoard, None) => {
+                (ModalAction::EditBoard, "To do, Doing:3, Done".to_string())
+            }
+            (VeloCommand::ExportBoardCsv, Some(_)) => {
+                (ModalAction::ExportBoardCsv, "./board.csv".to_string())
+            }
+            (VeloCommand::ExportBoardCsv, None) => (
+                ModalAction::Message,
+                "This tab has no board, add one with \"Edit board\"".to_string(),
+            ),
+            _ => continue,
+        };
+        let id = ReflectableUuid::generate();
+        *ui_state = UiState::default();
+        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            windows.single(),
+            id,
+            action,
+            Some(default_value),
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
+    }
+}
+
+/// Confirmed in the board modal, an empty text removes the board.
+pub fn request_board_edit(commands: &mut Commands, text: &str) -> Result<(), String> {
+    let spec = if text.trim().is_empty() {
+        None
+    } else {
+        Some(parse_board_spec(text)?)
+    };
+    commands.insert_resource(BoardEditRequest { spec });
+    Ok(())
+}
+
+pub fn request_board_csv(commands: &mut Commands, path: &str, modal_id: ReflectableUuid) {
+    commands.insert_resource(PendingBoardCsv {
+        path: PathBuf::from(path.trim()),
+        modal_id,
+    });
+}
+
+fn apply_board_layout(
+    board: &Board,
+    containers: &mut Query<(&VeloNodeContainer, &mut Style)>,
+    redraw_arrow: &mut EventWriter<RedrawArrowEvent>,
+) {
+    let sizes: HashMap<ReflectableUuid, (f32, f32)> = containers
+        .iter()
+        .filter_map(|(container, style)| {
+            let rect = style_rect(style)?;
+            Some((container.id, (rect.width, rect.height)))
+        })
+        .collect();
+    let layout = board.layout(&sizes);
+    for (container, mut style) in containers.iter_mut() {
+        if let Some((left, bottom)) = layout.get(&container.id) {
+            if style.position.left != Val::Px(*left) || style.position.bottom != Val::Px(*bottom) {
+                style.position.left = Val::Px(*left);
+                style.position.bottom = Val::Px(*bottom);
+                redraw_arrow.send(RedrawArrowEvent { id: container.id });
+            }
+        }
+    }
+}
+
+fn node_centers(
+    containers: &Query<(&VeloNodeContainer, &mut Style)>,
+) -> HashMap<ReflectableUuid, (f32, f32)> {
+    containers
+        .iter()
+        .filter_map(|(container, style)| Some((container.id, style_rect(style)?.center())))
+        .collect()
+}
+
+pub fn board_edit_handler(
+    mut commands: Commands,
+    request: Option<Res<BoardEditRequest>>,
+    mut tab_board: ResMut<TabBoard>,
+    mut containers: Query<(&VeloNodeContainer, &mut Style)>,
+    main_panel_query: Query<&Node, With<MainPanel>>,
+    mut redraw_arrow: EventWriter<RedrawArrowEvent>,
+) {
+    let request = match request {
+        Some(request) => request,
+        None => return,
+    };
+    commands.remove_resource::<BoardEditRequest>();
+    let (columns, lanes) = match request.spec.clone() {
+        Some(spec) => spec,
+        None => {
+            tab_board.board = None;
+            return;
+        }
+    };
+    if let Some(board) = &mut tab_board.board {
+        board.set_spec(columns, lanes);
+        apply_board_layout(board, &mut containers, &mut redraw_arrow);
+        return;
+    }
+    let mut board = Board::new(columns, lanes, BOARD_MARGIN, 0.);
+    let panel_height = main_panel_query.single().size().y;
+    board.bottom = (panel_height - board.height() - BOARD_MARGIN).max(BOARD_MARGIN);
+    // the nodes of the tab become cards, the ones outside of the board go to the first cell
+    let centers = node_centers(&containers);
+    let mut nodes: Vec<(&ReflectableUuid, &(f32, f32))> = centers.iter().collect();
+    nodes.sort_by(|a, b| b.1 .1.total_cmp(&a.1 .1));
+    for (id, center) in nodes {
+        if !board.place(*id, *center, &centers) {
+            board.cards.push(BoardCard {
+                id: *id,
+                column: 0,
+                lane: 0,
+            });
+        }
+    }
+    apply_board_layout(&board, &mut containers, &mut redraw_arrow);
+    tab_board.board = Some(board);
+}
+
+/// Snaps the node released after dragging into the board cell under it.
+pub fn board_drop(
+    mut tab_board: ResMut<TabBoard>,
+    ui_state: Res<UiState>,
+    mut held: Local<Option<ReflectableUuid>>,
+    mut containers: Query<(&VeloNodeContainer, &mut Style)>,
+    mut redraw_arrow: EventWriter<RedrawArrowEvent>,
+) {
+    let released = match *held {
+        Some(id) if ui_state.hold_entity != Some(id) => id,
+        _ => {
+            *held = ui_state.hold_entity;
+            return;
+        }
+    };
+    *held = ui_state.hold_entity;
+    if tab_board.board.is_none() {
+        return;
+    }
+    let centers = node_centers(&containers);
+    let center = match centers.get(&released) {
+        Some(center) => *center,
+        None => return,
+    };
+    let board = tab_board.board.as_mut().unwrap();
+    board.place(released, center, &centers);
+    board.retain(|id| centers.contains_key(id));
+    apply_board_layout(board, &mut containers, &mut redraw_arrow);
+}
+
+pub fn load_board(
+    request: Res<LoadTabRequest>,
+    app_state: Res<AppState>,
+    mut tab_board: ResMut<TabBoard>,
+) {
+    let checkpoint = app_state
+        .docs
+        .get(&request.doc_id)
+        .and_then(|doc| doc.tabs.iter().find(|tab| tab.id == request.tab_id))
+        .and_then(|tab| tab.checkpoints.back());
+    tab_board.board = checkpoint
+        .and_then(|checkpoint| serde_json::from_str::<Value>(checkpoint).ok())
+        .and_then(|json| serde_json::from_value(json.get("board")?.clone()).ok());
+}
+
+/// Columns and lane names drawn behind the cards.
+pub fn board_visuals(
+    mut commands: Commands,
+    tab_board: Res<TabBoard>,
+    theme: Res<Theme>,
+    backgrounds: Query<Entity, With<BoardBackground>>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+) {
+    if !(tab_board.is_changed() || theme.is_changed()) {
+        return;
+    }
+    for entity in backgrounds.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let board = match &tab_board.board {
+        Some(board) => board,
+        None => return,
+    };
+    let main_panel = main_panel_query.single();
+    let text_style = TextStyle {
+        font_size: 16.,
+        color: theme.font,
+        ..default()
+    };
+    for (index, column) in board.columns.iter().enumerate() {
+        let rect = board.column_rect(index);
+        let exceeded = board.wip_exceeded(index);
+        let count = board.column_count(index);
+        let title = match column.wip_limit {
+            Some(limit) => format!("{} ({}/{})", column.name, count, limit),
+            None => format!("{} ({})", column.name, count),
+        };
+        let border = if exceeded {
+            theme.wip_exceeded
+        } else {
+            theme.node_border
+        };
+        let entity = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            left: Val::Px(rect.left),
+                            bottom: Val::Px(rect.bottom),
+                            ..default()
+                        },
+                        size: Size::new(Val::Px(rect.width), Val::Px(rect.height)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(6.)),
+                        ..default()
+                    },
+                    background_color: theme.board_column_bg.into(),
+                    z_index: ZIndex::Local(BOARD_Z_INDEX),
+                    ..default()
+                },
+                Outline::all(border, Val::Px(if exceeded { 2. } else { 1. })),
+                BoardBackground,
+            ))
+            .with_children(|builder| {
+                builder.spawn(TextBundle::from_section(
+                    title,
+                    TextStyle {
+                        color: if exceeded {
+                            theme.wip_exceeded
+                        } else {
+                            theme.font
+                        },
+                        ..text_style.clone()
+                    },
+                ));
+            })
+            .id();
+        commands.entity(main_panel).add_child(entity);
+    }
+    for (index, lane) in board.lanes.iter().enumerate() {
+        let rect = board.lane_rect(index);
+        let entity = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            left: Val::Px(rect.left),
+                            bottom: Val::Px(rect.bottom),
+                            ..default()
+                        },
+                        size: Size::new(Val::Px(rect.width), Val::Px(rect.height)),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    z_index: ZIndex::Local(BOARD_Z_INDEX + 1),
+                    ..default()
+                },
+                Outline::all(theme.node_border, Val::Px(1.)),
+                BoardBackground,
+            ))
+            .with_children(|builder| {
+                builder.spawn(
+                    TextBundle::from_section(lane.clone(), text_style.clone()).with_style(Style {
+                        max_size: Size::width(Val::Px(LANE_LABEL_WIDTH)),
+                        padding: UiRect::all(Val::Px(6.)),
+                        ..default()
+                    }),
+                );
+            })
+            .id();
+        commands.entity(main_panel).add_child(entity);
+    }
+}
+
+/// Writes the cards of the board, the modal is closed or shows why the export failed.
+pub fn pending_board_csv_handler(
+    mut commands: Commands,
+    pending_csv: Option<Res<PendingBoardCsv>>,
+    tab_board: Res<TabBoard>,
+    text_query: Query<&RawText>,
+    mut ui_state: ResMut<UiState>,
+    query_top: Query<(Entity, &ModalTop), With<ModalTop>>,
+    mut status_query: Query<(&mut Text, &ModalStatus)>,
+) {
+    let pending = match pending_csv {
+        Some(pending) => pending,
+        None => return,
+    };
+    commands.remove_resource::<PendingBoardCsv>();
+    let result = match &tab_board.board {
+        Some(board) => {
+            let texts: HashMap<ReflectableUuid, String> = text_query
+                .iter()
+                .map(|raw_text| (raw_text.id, raw_text.last_text.clone()))
+                .collect();
+            std::fs::write(&pending.path, board.to_csv(&texts))
+                .map_err(|e| format!("Failed to write {}: {}", pending.path.display(), e))
+        }
+        None => Err("The tab has no board".to_string()),
+    };
+    match result {
+        Ok(()) => {
+            info!("Exported board to {}", pending.path.display());
+            close_modal(&mut commands, &query_top, &mut ui_state, pending.modal_id);
+        }
+        Err(e) => {
+            error!("Failed to export board to CSV: {}", e);
+            show_modal_error(&mut status_query, pending.modal_id, e);
+        }
+    }
+}
@@ -7,127 +7,125 @@ use bevy::{prelude::*, window::PrimaryWindow};
 use bevy_cosmic_edit::{CosmicEdit, CosmicFont};
 use bevy_pkv::PkvStore;
 use cosmic_text::Edit;
-use serde::Serialize;
-use serde_json::{json, Value};
+use serde_json::json;
 use uuid::Uuid;
 
 use crate::themes::Theme;
 use crate::{AddRectEvent, JsonNode, JsonNodeText, NodeType, UiState};
 
 use super::ui_helpers::{
-    pos_to_style, spawn_modal, ButtonAction, ChangeColor, DeleteDoc, DocListItemButton,
-    GenericButton, NewDoc, ParticlesEffect, RawText, SaveDoc, TextPosMode, Tooltip, VeloNode,
+    pos_to_style, spawn_modal, ChangeColor, DocListItemButton, GenericButton, RawText, TextPosMode,
+    Tooltip, VeloNode,
 };
-use super::{ExportToFile, ImportFromFile, ImportFromUrl, MainPanel, ShareDoc, VeloNodeContainer};
+use super::{MainPanel, RunCommand, TabBoard, VeloCommand, VeloNodeContainer};
 use crate::canvas::arrow::components::{ArrowMeta, ArrowMode};
 use crate::components::{Doc, EffectsCamera, Tab};
+use crate::config::Config;
 use crate::resources::{AppState, FontSystemState, LoadDocRequest, SaveDocRequest};
 use crate::utils::{get_timestamp, load_doc_to_memory, to_cosmic_text_pos, ReflectableUuid};
 
 pub fn rec_button_handlers(
     mut commands: Commands,
     mut events: EventWriter<AddRectEvent>,
-    mut interaction_query: Query<
-        (&Interaction, &ButtonAction),
-        (Changed<Interaction>, With<ButtonAction>),
-    >,
+    mut run_command: EventReader<RunCommand>,
     mut nodes: Query<(Entity, &VeloNodeContainer, &mut ZIndex), With<VeloNodeContainer>>,
     mut arrows: Query<(Entity, &ArrowMeta, &mut Visibility), (With<ArrowMeta>, Without<Tooltip>)>,
     mut state: ResMut<UiState>,
     windows: Query<&Window, With<PrimaryWindow>>,
     theme: Res<Theme>,
+    mut tab_board: ResMut<TabBoard>,
 ) {
     let window = windows.single();
-    for (interaction, button_action) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => match button_action.button_type {
-                super::ui_helpers::ButtonTypes::AddRec => {
-                    events.send(AddRectEvent {
-                        node: JsonNode {
-                            id: Uuid::new_v4(),
-                            node_type: NodeType::Rect,
-                            left: Val::Px(window.width() / 2. - 200.),
-                            bottom: Val::Px(window.height() / 2.),
-                            width: Val::Px(128.0),
-                            height: Val::Px(128.0),
-                            text: JsonNodeText {
-                                text: "".to_string(),
-                                pos: crate::TextPos::Center,
-                            },
-                            bg_color: theme.node_bg,
-                            z_index: 0,
+    for RunCommand(command) in run_command.iter() {
+        match command {
+            VeloCommand::AddRectangle => {
+                events.send(AddRectEvent {
+                    node: JsonNode {
+                        id: Uuid::new_v4(),
+                        node_type: NodeType::Rect,
+                        left: Val::Px(window.width() / 2. - 200.),
+                        bottom: Val::Px(window.height() / 2.),
+                        width: Val::Px(128.0),
+                        height: Val::Px(128.0),
+                        text: JsonNodeText {
+                            text: "".to_string(),
+                            pos: crate::TextPos::Center,
                         },
-                        image: None,
-                    });
-                }
-                super::ui_helpers::ButtonTypes::AddCircle => {
-                    events.send(AddRectEvent {
-                        node: JsonNode {
-                            id: Uuid::new_v4(),
-                            node_type: NodeType::Circle,
-                            left: Val::Px(window.width() / 2. - 200.),
-                            bottom: Val::Px(window.height() / 2.),
-                            width: Val::Px(128.0),
-                            height: Val::Px(128.0),
-                            text: JsonNodeText {
-                                text: "".to_string(),
-                                pos: crate::TextPos::Center,
-                            },
-                            bg_color: theme.node_bg,
-                            z_index: 0,
+                        bg_color: theme.node_bg,
+                        z_index: 0,
+                    },
+                    image: None,
+                });
+            }
+            VeloCommand::AddCircle => {
+                events.send(AddRectEvent {
+                    node: JsonNode {
+                        id: Uuid::new_v4(),
+                        node_type: NodeType::Circle,
+                        left: Val::Px(window.width() / 2. - 200.),
+                        bottom: Val::Px(window.height() / 2.),
+                        width: Val::Px(128.0),
+                        height: Val::Px(128.0),
+                        text: JsonNodeText {
+                            text: "".to_string(),
+                            pos: crate::TextPos::Center,
                         },
-                        image: None,
-                    });
-                }
-                super::ui_helpers::ButtonTypes::Del => {
-                    if let Some(id) = state.entity_to_edit {
-                        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
-                        *state = UiState::default();
-                        for (entity, node, _) in nodes.iter() {
-                            if node.id == id {
+                        bg_color: theme.node_bg,
+                        z_index: 0,
+                    },
+                    image: None,
+                });
+            }
+            VeloCommand::DeleteNode => {
+                if let Some(id) = state.entity_to_edit {
+                    commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+                    *state = UiState::default();
+                    for (entity, node, _) in nodes.iter() {
+                        if node.id == id {
+                            commands.entity(entity).despawn_recursive();
+                        }
+                    }
+                    if let Some(board) = &mut tab_board.board {
+                        board.retain(|card| *card != id);
+                    }
+                    #[allow(unused)]
+                    for (entity, arrow, mut visibility) in &mut arrows.iter_mut() {
+                        if arrow.start.id == id || arrow.end.id == id {
+                            #[cfg(not(target_arch = "wasm32"))]
+                            {
                                 commands.entity(entity).despawn_recursive();
                             }
-                        }
-                        #[allow(unused)]
-                        for (entity, arrow, mut visibility) in &mut arrows.iter_mut() {
-                            if arrow.start.id == id || arrow.end.id == id {
-                                #[cfg(not(target_arch = "wasm32"))]
-                                {
-                                    commands.entity(entity).despawn_recursive();
-                                }
-                                #[cfg(target_arch = "wasm32")]
-                                {
-                                    *visibility = Visibility::Hidden;
-                                }
+                            #[cfg(target_arch = "wasm32")]
+                            {
+                                *visibility = Visibility::Hidden;
                             }
                         }
                     }
                 }
-                super::ui_helpers::ButtonTypes::Front => {
-                    if let Some(id) = state.entity_to_edit {
-                        for (_, node, mut z_index) in nodes.iter_mut() {
-                            if node.id == id {
-                                if let ZIndex::Local(i) = *z_index {
-                                    *z_index = ZIndex::Local(i + 1);
-                                }
+            }
+            VeloCommand::BringToFront => {
+                if let Some(id) = state.entity_to_edit {
+                    for (_, node, mut z_index) in nodes.iter_mut() {
+                        if node.id == id {
+                            if let ZIndex::Local(i) = *z_index {
+                                *z_index = ZIndex::Local(i + 1);
                             }
                         }
                     }
                 }
-                super::ui_helpers::ButtonTypes::Back => {
-                    if let Some(id) = state.entity_to_edit {
-                        for (_, node, mut z_index) in nodes.iter_mut() {
-                            if node.id == id {
-                                if let ZIndex::Local(i) = *z_index {
-                                    *z_index = ZIndex::Local(i - 1);
-                                }
+            }
+            VeloCommand::SendToBack => {
+                if let Some(id) = state.entity_to_edit {
+                    for (_, node, mut z_index) in nodes.iter_mut() {
+                        if node.id == id {
+                            if let ZIndex::Local(i) = *z_index {
+                                *z_index = ZIndex::Local(i - 1);
                             }
                         }
                     }
                 }
-            },
-            Interaction::Hovered => {}
-            Interaction::None => {}
+            }
+            _ => {}
         }
     }
 }
@@ -215,50 +213,49 @@ pub fn change_arrow_type(
 
 pub fn new_doc_handler(
     mut commands: Commands,
-    mut new_doc_query: Query<&Interaction, (Changed<Interaction>, With<NewDoc>)>,
+    mut run_command: EventReader<RunCommand>,
     mut app_state: ResMut<AppState>,
 ) {
-    for interaction in &mut new_doc_query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                let doc_id = ReflectableUuid::generate();
-                let name = "Untitled".to_string();
-                let tab_id = ReflectableUuid::generate();
-                let mut checkpoints = VecDeque::new();
-                checkpoints.push_back(
-                    json!({
-                        "nodes": [],
-                        "arrows": [],
-                        "images": {},
-                    })
-                    .to_string(),
-                );
-                let tabs = vec![Tab {
-                    id: tab_id,
-                    name: "Tab 1".to_string(),
-                    checkpoints,
-                    is_active: true,
-                }];
-                app_state.docs.insert(
-                    doc_id,
-                    Doc {
-                        id: doc_id,
-                        name: name.clone(),
-                        tabs,
-                        tags: vec![],
-                    },
-                );
-                commands.insert_resource(SaveDocRequest {
-                    doc_id: app_state.current_document.unwrap(),
-                    path: None,
-                });
-                app_state.current_document = Some(doc_id);
-                commands.insert_resource(LoadDocRequest { doc_id });
-                app_state.doc_list_ui.insert(doc_id);
-            }
-            Interaction::Hovered => {}
-            Interaction::None => {}
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::NewDocument {
+            continue;
         }
+        let doc_id = ReflectableUuid::generate();
+        let name = "Untitled".to_string();
+        let tab_id = ReflectableUuid::generate();
+        let mut checkpoints = VecDeque::new();
+        checkpoints.push_back(
+            json!({
+                "nodes": [],
+                "arrows": [],
+                "images": {},
+            })
+            .to_string(),
+        );
+        let tabs = vec![Tab {
+            id: tab_id,
+            name: "Tab 1".to_string(),
+            checkpoints,
+            is_active: true,
+        }];
+        app_state.docs.insert(
+            doc_id,
+            Doc {
+                id: doc_id,
+                name: name.clone(),
+                tabs,
+                tags: vec![],
+                shared: None,
+                inline_images: HashMap::new(),
+            },
+        );
+        commands.insert_resource(SaveDocRequest {
+            doc_id: app_state.current_document.unwrap(),
+            path: None,
+        });
+        app_state.current_document = Some(doc_id);
+        commands.insert_resource(LoadDocRequest { doc_id });
+        app_state.doc_list_ui.insert(doc_id);
     }
 }
 
@@ -295,7 +292,7 @@ pub fn rename_doc_handler(
 
 pub fn delete_doc_handler(
     mut commands: Commands,
-    mut delete_doc_query: Query<&Interaction, (Changed<Interaction>, With<DeleteDoc>)>,
+    mut run_command: EventReader<RunCommand>,
     mut ui_state: ResMut<UiState>,
     mut app_state: ResMut<AppState>,
     main_panel_query: Query<Entity, With<MainPanel>>,
@@ -306,70 +303,104 @@ pub fn delete_doc_handler(
     theme: Res<Theme>,
 ) {
     let window = windows.single();
-    for interaction in &mut delete_doc_query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                if app_state.docs.len() == 1 {
-                    if let Ok(docs) = pkv.get::<HashMap<ReflectableUuid, Doc>>("docs") {
-                        if docs.len() > 1 {
-                            for (id, doc) in docs.iter() {
-                                if app_state.docs.len() != 1 {
-                                    break;
-                                }
-                                app_state.docs.insert(*id, doc.clone());
-                            }
-                        } else {
-                            // do not allow deletion if there is less than two docs
-                            return;
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::DeleteDocument {
+            continue;
+        }
+        if app_state.docs.len() == 1 {
+            if let Ok(docs) = pkv.get::<HashMap<ReflectableUuid, Doc>>("docs") {
+                if docs.len() > 1 {
+                    for (id, doc) in docs.iter() {
+                        if app_state.docs.len() != 1 {
+                            break;
                         }
-                    } else {
-                        // do not allow deletion if there is less than two docs
-                        return;
+                        app_state.docs.insert(*id, doc.clone());
                     }
+                } else {
+                    // do not allow deletion if there is less than two docs
+                    return;
                 }
-                let id = ReflectableUuid::generate();
-                *ui_state = UiState::default();
-                commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
-                ui_state.modal_id = Some(id);
-                let entity = spawn_modal(
-                    &mut commands,
-                    &theme,
-                    &mut cosmic_fonts,
-                    font_system_state.0.clone().unwrap(),
-                    window,
-                    id,
-                    super::ModalAction::DeleteDocument,
-                );
-                commands.entity(main_panel_query.single()).add_child(entity);
+            } else {
+                // do not allow deletion if there is less than two docs
+                return;
             }
-            Interaction::Hovered => {}
-            Interaction::None => {}
         }
+        let id = ReflectableUuid::generate();
+        *ui_state = UiState::default();
+        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            window,
+            id,
+            super::ModalAction::DeleteDocument,
+            None,
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
     }
 }
 
 pub fn save_doc_handler(
     mut commands: Commands,
-    mut save_doc_query: Query<&Interaction, (Changed<Interaction>, With<SaveDoc>)>,
+    mut run_command: EventReader<RunCommand>,
     state: Res<AppState>,
 ) {
-    for interaction in &mut save_doc_query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                commands.insert_resource(SaveDocRequest {
-                    doc_id: state.current_document.unwrap(),
-                    path: None,
-                });
-            }
-            Interaction::Hovered => {}
-            Interaction::None => {}
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::SaveDocument {
+            continue;
         }
+        commands.insert_resource(SaveDocRequest {
+            doc_id: state.current_document.unwrap(),
+            path: None,
+        });
+    }
+}
+
+pub fn tag_doc_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    mut ui_state: ResMut<UiState>,
+    mut app_state: ResMut<AppState>,
+    mut pkv: ResMut<PkvStore>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: ResMut<FontSystemState>,
+    theme: Res<Theme>,
+    config: Res<Config>,
+) {
+    let window = windows.single();
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::EditTags {
+            continue;
+        }
+        let doc_id = app_state.current_document.unwrap();
+        load_doc_to_memory(doc_id, &mut app_state, &mut pkv, config.max_docs_in_memory);
+        let tags = app_state.docs.get(&doc_id).unwrap().tags.join(", ");
+        let id = ReflectableUuid::generate();
+        *ui_state = UiState::default();
+        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            window,
+            id,
+            super::ModalAction::EditTags,
+            Some(tags),
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
     }
 }
 
 pub fn export_to_file(
     mut commands: Commands,
-    mut query: Query<&Interaction, (Changed<Interaction>, With<ExportToFile>)>,
+    mut run_command: EventReader<RunCommand>,
     mut ui_state: ResMut<UiState>,
     main_panel_query: Query<Entity, With<MainPanel>>,
     windows: Query<&Window, With<PrimaryWindow>>,
@@ -378,34 +409,36 @@ pub fn export_to_file(
     theme: Res<Theme>,
 ) {
     let window = windows.single();
-    for interaction in &mut query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                let id = ReflectableUuid::generate();
-                *ui_state = UiState::default();
-                commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
-                ui_state.modal_id = Some(id);
-                let entity = spawn_modal(
-                    &mut commands,
-                    &theme,
-                    &mut cosmic_fonts,
-                    font_system_state.0.clone().unwrap(),
-                    window,
-                    id,
-                    super::ModalAction::SaveToFile,
-                );
-                commands.entity(main_panel_query.single()).add_child(entity);
-            }
-            Interaction::Hovered => {}
-            Interaction::None => {}
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::ExportToFile {
+            continue;
         }
+        let id = ReflectableUuid::generate();
+        *ui_state = UiState::default();
+        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            window,
+            id,
+            super::ModalAction::SaveToFile,
+            None,
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
     }
 }
 
 #[cfg(target_arch = "wasm32")]
-pub fn set_window_property(mut app_state: ResMut<AppState>, mut pkv: ResMut<PkvStore>) {
+pub fn set_window_property(
+    mut app_state: ResMut<AppState>,
+    mut pkv: ResMut<PkvStore>,
+    config: Res<Config>,
+) {
     if let Some(doc_id) = app_state.current_document {
-        load_doc_to_memory(doc_id, &mut app_state, &mut pkv);
+        load_doc_to_memory(doc_id, &mut app_state, &mut pkv, config.max_docs_in_memory);
         let current_doc = app_state.docs.get(&doc_id).unwrap().clone();
         let value = serde_json::to_string_pretty(&current_doc).unwrap();
         let window = wasm_bindgen::JsValue::from(web_sys::window().unwrap());
@@ -415,86 +448,9 @@ pub fn set_window_property(mut app_state: ResMut<AppState>, mut pkv: ResMut<PkvS
     }
 }
 
-#[derive(Serialize)]
-struct GistFile {
-    content: String,
-}
-
-#[derive(Serialize)]
-struct GistCreateRequest {
-    description: String,
-    public: bool,
-    files: std::collections::HashMap<String, GistFile>,
-}
-
-pub fn shared_doc_handler(
-    mut app_state: ResMut<AppState>,
-    mut query: Query<&Interaction, (Changed<Interaction>, With<ShareDoc>)>,
-    mut pkv: ResMut<PkvStore>,
-) {
-    for interaction in &mut query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                if let Some(doc_id) = app_state.current_document {
-                    load_doc_to_memory(doc_id, &mut app_state, &mut pkv);
-                    let current_doc = app_state.docs.get(&doc_id).unwrap().clone();
-                    let contents = serde_json::to_string_pretty(&current_doc).unwrap();
-                    let mut files = std::collections::HashMap::new();
-                    let filename = "velo.json";
-                    let file = GistFile {
-                        content: contents.to_string(),
-                    };
-                    files.insert(filename.to_string(), file);
-
-                    let request = GistCreateRequest {
-                        description: "Velo Document".to_string(),
-                        public: true,
-                        files,
-                    };
-
-                    let mut request = ehttp::Request::post(
-                        "https://api.github.com/gists",
-                        serde_json::to_string_pretty(&request).unwrap(),
-                    );
-                    request.headers.insert(
-                        "Accept".to_string(),
-                        "application/vnd.github.v3+json".to_string(),
-                    );
-                    request.headers.insert(
-                        "Authorization".to_string(),
-                        format!("token {}", app_state.github_token.as_ref().unwrap()),
-                    );
-                    #[cfg(not(target_arch = "wasm32"))]
-                    let mut clipboard = arboard::Clipboard::new().unwrap();
-                    ehttp::fetch(request, move |result| {
-                        let response = result.unwrap();
-                        if response.ok {
-                            let res_json: Value =
-                                serde_json::from_str(response.text().unwrap().as_str()).unwrap();
-                            let files: Value = res_json["files"].clone();
-                            let velo = files["velo.json"].clone();
-                            #[cfg(not(target_arch = "wasm32"))]
-                            clipboard
-                                .set_text(format!(
-                                    "https://staffengineer.github.io/velo?document={}",
-                                    velo["raw_url"].to_string().replace('\"', "")
-                                ))
-                                .unwrap();
-                        } else {
-                            error!("Error sharing document: {}", response.status_text);
-                        }
-                    });
-                }
-            }
-            Interaction::Hovered => {}
-            Interaction::None => {}
-        }
-    }
-}
-
 pub fn import_from_file(
     mut commands: Commands,
-    mut query: Query<&Interaction, (Changed<Interaction>, With<ImportFromFile>)>,
+    mut run_command: EventReader<RunCommand>,
     mut ui_state: ResMut<UiState>,
     main_panel_query: Query<Entity, With<MainPanel>>,
     windows: Query<&Window, With<PrimaryWindow>>,
@@ -503,33 +459,31 @@ pub fn import_from_file(
     theme: Res<Theme>,
 ) {
     let window = windows.single();
-    for interaction in &mut query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                let id = ReflectableUuid::generate();
-                *ui_state = UiState::default();
-                commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
-                ui_state.modal_id = Some(id);
-                let entity = spawn_modal(
-                    &mut commands,
-                    &theme,
-                    &mut cosmic_fonts,
-                    font_system_state.0.clone().unwrap(),
-                    window,
-                    id,
-                    super::ModalAction::LoadFromFile,
-                );
-                commands.entity(main_panel_query.single()).add_child(entity);
-            }
-            Interaction::Hovered => {}
-            Interaction::None => {}
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::ImportFromFile {
+            continue;
         }
+        let id = ReflectableUuid::generate();
+        *ui_state = UiState::default();
+        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            window,
+            id,
+            super::ModalAction::LoadFromFile,
+            None,
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
     }
 }
 
 pub fn import_from_url(
     mut commands: Commands,
-    mut query: Query<&Interaction, (Changed<Interaction>, With<ImportFromUrl>)>,
+    mut run_command: EventReader<RunCommand>,
     mut ui_state: ResMut<UiState>,
     main_panel_query: Query<Entity, With<MainPanel>>,
     windows: Query<&Window, With<PrimaryWindow>>,
@@ -538,27 +492,25 @@ pub fn import_from_url(
     theme: Res<Theme>,
 ) {
     let window = windows.single();
-    for interaction in &mut query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                let id = ReflectableUuid::generate();
-                *ui_state = UiState::default();
-                commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
-                ui_state.modal_id = Some(id);
-                let entity = spawn_modal(
-                    &mut commands,
-                    &theme,
-                    &mut cosmic_fonts,
-                    font_system_state.0.clone().unwrap(),
-                    window,
-                    id,
-                    super::ModalAction::LoadFromUrl,
-                );
-                commands.entity(main_panel_query.single()).add_child(entity);
-            }
-            Interaction::Hovered => {}
-            Interaction::None => {}
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::ImportFromUrl {
+            continue;
         }
+        let id = ReflectableUuid::generate();
+        *ui_state = UiState::default();
+        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            window,
+            id,
+            super::ModalAction::LoadFromUrl,
+            None,
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
     }
 }
 
@@ -599,7 +551,7 @@ pub fn button_generic_handler(
 
 #[cfg(not(target_arch = "wasm32"))]
 pub fn particles_effect(
-    mut query: Query<&Interaction, (Changed<Interaction>, With<ParticlesEffect>)>,
+    mut run_command: EventReader<RunCommand>,
     mut commands: Commands,
     mut effects: ResMut<Assets<bevy_hanabi::EffectAsset>>,
     mut effects_camera: Query<&mut Camera, With<EffectsCamera>>,
@@ -608,83 +560,80 @@ pub fn particles_effect(
     use bevy_hanabi::prelude::*;
     use rand::Rng;
 
-    for interaction in &mut query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                if effects_camera.single_mut().is_active {
-                    effects_camera.single_mut().is_active = false;
-                    for (name, entity) in effects_query.iter_mut() {
-                        if name.as_str() == "effect:2d" {
-                            commands.entity(entity).despawn_recursive();
-                        }
-                    }
-                } else {
-                    effects_camera.single_mut().is_active = true;
-                    let mut gradient = Gradient::new();
-                    let mut rng = rand::thread_rng();
-                    gradient.add_key(
-                        0.0,
-                        Vec4::new(
-                            rng.gen_range(0.0..1.0),
-                            rng.gen_range(0.0..1.0),
-                            rng.gen_range(0.0..1.0),
-                            1.0,
-                        ),
-                    );
-                    gradient.add_key(
-                        1.0,
-                        Vec4::new(
-                            rng.gen_range(0.0..1.0),
-                            rng.gen_range(0.0..1.0),
-                            rng.gen_range(0.0..1.0),
-                            0.0,
-                        ),
-                    );
-
-                    let mut size_gradient1 = Gradient::new();
-                    size_gradient1.add_key(0.0, Vec2::splat(0.008));
-                    size_gradient1.add_key(0.3, Vec2::splat(0.012));
-                    size_gradient1.add_key(1.0, Vec2::splat(0.0));
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::ToggleParticles {
+            continue;
+        }
+        if effects_camera.single_mut().is_active {
+            effects_camera.single_mut().is_active = false;
+            for (name, entity) in effects_query.iter_mut() {
+                if name.as_str() == "effect:2d" {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        } else {
+            effects_camera.single_mut().is_active = true;
+            let mut gradient = Gradient::new();
+            let mut rng = rand::thread_rng();
+            gradient.add_key(
+                0.0,
+                Vec4::new(
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                    1.0,
+                ),
+            );
+            gradient.add_key(
+                1.0,
+                Vec4::new(
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                    0.0,
+                ),
+            );
 
-                    let spawner = Spawner::rate(rng.gen_range(10.0..300.0).into());
-                    let effect = effects.add(
-                        EffectAsset {
-                            name: "Effect".into(),
-                            capacity: 32768,
-                            spawner,
-                            ..Default::default()
-                        }
-                        .init(InitPositionCircleModifier {
-                            center: Vec3::ZERO,
-                            axis: Vec3::Z,
-                            radius: 0.0001,
-                            dimension: ShapeDimension::Surface,
-                        })
-                        .init(InitVelocityCircleModifier {
-                            center: Vec3::ZERO,
-                            axis: Vec3::Z,
-                            speed: Value::Uniform((0.05, 0.1)),
-                        })
-                        .init(InitLifetimeModifier {
-                            lifetime: Value::Uniform((5., 10.)),
-                        })
-                        .render(SizeOverLifetimeModifier {
-                            gradient: size_gradient1,
-                        })
-                        .render(ColorOverLifetimeModifier { gradient }),
-                    );
+            let mut size_gradient1 = Gradient::new();
+            size_gradient1.add_key(0.0, Vec2::splat(0.008));
+            size_gradient1.add_key(0.3, Vec2::splat(0.012));
+            size_gradient1.add_key(1.0, Vec2::splat(0.0));
 
-                    commands
-                        .spawn(ParticleEffectBundle {
-                            effect: ParticleEffect::new(effect),
-                            ..default()
-                        })
-                        .insert(Name::new("effect:2d"))
-                        .insert(RenderLayers::layer(2));
+            let spawner = Spawner::rate(rng.gen_range(10.0..300.0).into());
+            let effect = effects.add(
+                EffectAsset {
+                    name: "Effect".into(),
+                    capacity: 32768,
+                    spawner,
+                    ..Default::default()
                 }
-            }
-            Interaction::Hovered => {}
-            Interaction::None => {}
+                .init(InitPositionCircleModifier {
+                    center: Vec3::ZERO,
+                    axis: Vec3::Z,
+                    radius: 0.0001,
+                    dimension: ShapeDimension::Surface,
+                })
+                .init(InitVelocityCircleModifier {
+                    center: Vec3::ZERO,
+                    axis: Vec3::Z,
+                    speed: Value::Uniform((0.05, 0.1)),
+                })
+                .init(InitLifetimeModifier {
+                    lifetime: Value::Uniform((5., 10.)),
+                })
+                .render(SizeOverLifetimeModifier {
+                    gradient: size_gradient1,
+                })
+                .render(ColorOverLifetimeModifier { gradient }),
+            );
+
+            commands
+                .spawn(ParticleEffectBundle {
+                    effect: ParticleEffect::new(effect),
+                    ..default()
+                })
+                .insert(Name::new("effect:2d"))
+                .insert(RenderLayers::layer(2));
         }
     }
 }
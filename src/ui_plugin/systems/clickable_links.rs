@@ -1,17 +1,36 @@
 use bevy::{prelude::*, window::PrimaryWindow};
-use bevy_cosmic_edit::{get_node_cursor_pos, get_x_offset, get_y_offset, CosmicEdit};
+use bevy_cosmic_edit::{
+    get_node_cursor_pos, get_scaled_y_offset, get_x_offset, scaled_layout_runs, CosmicEdit,
+    CosmicLineScales,
+};
+use bevy_markdown::toggle_task_checkbox;
 use cosmic_text::Edit;
 
-use super::{ui_helpers::BevyMarkdownView, UiState, VeloNode};
+use super::{
+    ui_helpers::{BevyMarkdownView, RenderMarkdown},
+    RawText, UiState, VeloNode,
+};
+use crate::resources::{AppState, SaveDocRequest};
 
+/// Opens clicked links and toggles clicked task list checkboxes of readonly nodes.
 pub fn clickable_links(
+    mut commands: Commands,
     mut windows: Query<&mut Window, With<PrimaryWindow>>,
     mut markdown_text_query: Query<
-        (&Node, &GlobalTransform, &mut CosmicEdit, &BevyMarkdownView),
+        (
+            Entity,
+            &mut RawText,
+            &Node,
+            &GlobalTransform,
+            &mut CosmicEdit,
+            &BevyMarkdownView,
+            Option<&CosmicLineScales>,
+        ),
         With<BevyMarkdownView>,
     >,
     mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<VeloNode>)>,
     ui_state: Res<UiState>,
+    app_state: Res<AppState>,
 ) {
     if ui_state.hold_entity.is_some() {
         return;
@@ -20,20 +39,29 @@ pub fn clickable_links(
     let scale_factor = primary_window.scale_factor() as f32;
     for interaction in &mut interaction_query {
         if *interaction == Interaction::Clicked {
-            for (node, transform, cosmic_edit, bevy_markdown_view) in markdown_text_query.iter_mut()
+            for (
+                entity,
+                mut raw_text,
+                node,
+                transform,
+                cosmic_edit,
+                bevy_markdown_view,
+                line_scales,
+            ) in markdown_text_query.iter_mut()
             {
+                let line_scales = line_scales.map_or(&[][..], |line_scales| &line_scales.0[..]);
                 let size = (node.size().x, node.size().y);
                 if let Some(pos) =
                     get_node_cursor_pos(&primary_window, transform, size, cosmic_edit.is_ui_node)
                 {
-                    let font_size = cosmic_edit.editor.buffer().metrics().font_size;
-                    let line_height = cosmic_edit.editor.buffer().metrics().line_height;
-                    let y_start = get_y_offset(&cosmic_edit.editor) as f32;
+                    let y_start = get_scaled_y_offset(&cosmic_edit.editor, line_scales) as f32;
                     let x_start = get_x_offset(&cosmic_edit.editor) as f32;
-                    for layout_runs in cosmic_edit.editor.buffer().layout_runs() {
-                        let line_offset =
-                            (y_start + (layout_runs.line_y - font_size)) / scale_factor;
-                        if pos.1 < (line_offset + line_height / scale_factor) && pos.1 > line_offset
+                    for (layout_runs, placement) in
+                        scaled_layout_runs(cosmic_edit.editor.buffer(), line_scales)
+                    {
+                        let line_offset = (y_start + placement.top) / scale_factor;
+                        if pos.1 < (line_offset + placement.height / scale_factor)
+                            && pos.1 > line_offset
                         {
                             for glyph in layout_runs.glyphs {
                                 let start = (x_start + glyph.x) / scale_factor;
@@ -49,6 +77,23 @@ pub fn clickable_links(
                                             #[cfg(target_arch = "wasm32")]
                                             open_url_in_new_tab(link.clone().as_str()).unwrap();
                                         }
+                                        // offsets point into the text the view was rendered from
+                                        if let Some(offset) = text_span.checkbox {
+                                            if cosmic_edit.readonly
+                                                && toggle_task_checkbox(
+                                                    &mut raw_text.last_text,
+                                                    offset,
+                                                )
+                                            {
+                                                commands.entity(entity).insert(RenderMarkdown);
+                                                if let Some(doc_id) = app_state.current_document {
+                                                    commands.insert_resource(SaveDocRequest {
+                                                        doc_id,
+                                                        path: None,
+                                                    });
+                                                }
+                                            }
+                                        }
                                     }
                                 }
                             }
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_cosmic_edit::{get_cosmic_text, ActiveEditor, CosmicEdit, CosmicFont};
+use bevy_pkv::PkvStore;
+
+use super::ui_helpers::{
+    spawn_command_palette, spawn_command_palette_item, CommandPalette, CommandPaletteInput,
+    CommandPaletteResults,
+};
+use super::{CommandRegistry, MainPanel, RunCommand, UiState, VeloCommand};
+use crate::keymap::Keymap;
+use crate::resources::{AppState, FontSystemState};
+use crate::share::Sharing;
+use crate::themes::Theme;
+use crate::utils::ReflectableUuid;
+
+const MAX_PALETTE_ITEMS: usize = 10;
+
+struct PaletteItem {
+    command: VeloCommand,
+    title: String,
+    shortcut: Option<String>,
+}
+
+pub fn command_palette_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    palette_query: Query<Entity, With<CommandPalette>>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut ui_state: ResMut<UiState>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    let window = windows.single();
+    for RunCommand(command) in run_command.iter() {
+        if let Ok(palette) = palette_query.get_single() {
+            // the palette is closed by running any command, including its own toggle
+            commands.entity(palette).despawn_recursive();
+            if *command == VeloCommand::CommandPalette {
+                commands.insert_resource(ActiveEditor { entity: None });
+            }
+            return;
+        }
+        if *command == VeloCommand::CommandPalette {
+            // keep the selected node, so that node commands can be run from the palette
+            ui_state.doc_to_edit = None;
+            ui_state.tab_to_edit = None;
+            ui_state.search_box_to_edit = None;
+            let palette = spawn_command_palette(
+                &mut commands,
+                &theme,
+                &mut cosmic_fonts,
+                font_system_state.0.clone().unwrap(),
+                window,
+            );
+            commands
+                .entity(main_panel_query.single())
+                .add_child(palette);
+            return;
+        }
+    }
+}
+
+pub fn command_palette_input(
+    mut commands: Commands,
+    mut palette_query: Query<(Entity, &mut CommandPalette)>,
+    input_query: Query<&CosmicEdit, With<CommandPaletteInput>>,
+    results_query: Query<Entity, With<CommandPaletteResults>>,
+    mut run_command: EventWriter<RunCommand>,
+    keys: Res<Input<KeyCode>>,
+    registry: Res<CommandRegistry>,
+    keymap: Res<Keymap>,
+    app_state: Res<AppState>,
+    sharing: Res<Sharing>,
+    pkv: Res<PkvStore>,
+    theme: Res<Theme>,
+) {
+    let (palette_entity, mut palette) = match palette_query.get_single_mut() {
+        Ok(palette) => palette,
+        Err(_) => return,
+    };
+    if keys.just_pressed(KeyCode::Escape) {
+        commands.entity(palette_entity).despawn_recursive();
+        commands.insert_resource(ActiveEditor { entity: None });
+        return;
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        if let Some(command) = palette.matches.get(palette.selected) {
+            run_command.send(RunCommand(*command));
+        }
+        return;
+    }
+    let query = match input_query.get_single() {
+        Ok(cosmic_edit) => get_cosmic_text(&cosmic_edit.editor).replace('\n', ""),
+        Err(_) => return,
+    };
+    let mut changed = palette.is_added();
+    if query != palette.query {
+        palette.query = query;
+        palette.selected = 0;
+        changed = true;
+    }
+    if keys.just_pressed(KeyCode::Down) && palette.selected + 1 < palette.matches.len() {
+        palette.selected += 1;
+        changed = true;
+    }
+    if keys.just_pressed(KeyCode::Up) && palette.selected > 0 {
+        palette.selected -= 1;
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+    let items = filter_palette_items(
+        palette_items(&registry, &keymap, &app_state, &sharing, &pkv),
+        palette.query.trim(),
+    );
+    palette.matches = items.iter().map(|item| item.command).collect();
+    let results = results_query.single();
+    commands.entity(results).despawn_descendants();
+    for (index, item) in items.into_iter().enumerate() {
+        let row = spawn_command_palette_item(
+            &mut commands,
+            &theme,
+            item.command,
+            item.title,
+            item.shortcut,
+            index == palette.selected,
+        );
+        commands.entity(results).add_child(row);
+    }
+}
+
+fn palette_items(
+    registry: &CommandRegistry,
+    keymap: &Keymap,
+    app_state: &AppState,
+    sharing: &Sharing,
+    pkv: &PkvStore,
+) -> Vec<PaletteItem> {
+    let mut items: Vec<PaletteItem> = registry
+        .iter()
+        .filter(|registered| match registered.command {
+            VeloCommand::ShareDocument => sharing.provider.is_some(),
+            VeloCommand::UnshareDocument => {
+                sharing.provider.is_some()
+                    && app_state
+                        .current_document
+                        .and_then(|doc_id| app_state.docs.get(&doc_id))
+                        .map_or(false, |doc| doc.shared.is_some())
+            }
+            _ => true,
+        })
+        .map(|registered| PaletteItem {
+            command: registered.command,
+            title: registered.title.clone(),
+            shortcut: registered
+                .shortcut
+                .and_then(|action| keymap.bindings.get(&action))
+                .map(|chord| chord.to_string()),
+        })
+        .collect();
+    let mut names = pkv
+        .get::<HashMap<ReflectableUuid, String>>("names")
+        .unwrap_or_default();
+    // documents in memory may have been renamed since they were saved
+    for (id, doc) in app_state.docs.iter() {
+        names.insert(*id, doc.name.clone());
+    }
+    let mut names: Vec<(ReflectableUuid, String)> = names
+        .into_iter()
+        .filter(|(id, _)| Some(*id) != app_state.current_document)
+        .collect();
+    names.sort_by(|a, b| a.1.cmp(&b.1));
+    for (id, name) in names {
+        items.push(PaletteItem {
+            command: VeloCommand::OpenDocument(id),
+            title: format!("Open document: {}", name),
+            shortcut: None,
+        });
+    }
+    if let Some(doc) = app_state
+        .current_document
+        .and_then(|doc_id| app_state.docs.get(&doc_id))
+    {
+        for tab in doc.tabs.iter().filter(|tab| !tab.is_active) {
+            items.push(PaletteItem {
+                command: VeloCommand::SelectTab(tab.id),
+                title: format!("Go to tab: {}", tab.name),
+                shortcut: None,
+            });
+        }
+    }
+    items
+}
+
+fn filter_palette_items(items: Vec<PaletteItem>, query: &str) -> Vec<PaletteItem> {
+    if query.is_empty() {
+        return items.into_iter().take(MAX_PALETTE_ITEMS).collect();
+    }
+    let mut scored: Vec<(i32, PaletteItem)> = items
+        .into_iter()
+        .filter_map(|item| fuzzy_score(query, &item.title).map(|score| (score, item)))
+        .collect();
+    // stable sort keeps registration order for equal scores
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored
+        .into_iter()
+        .take(MAX_PALETTE_ITEMS)
+        .map(|(_, item)| item)
+        .collect()
+}
+
+/// Scores `text` against `query` as a case-insensitive subsequence match, higher is better.
+/// Consecutive characters and characters at word starts are preferred, as are shorter texts.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let index = (position..text.len()).find(|index| text[*index] == query_char)?;
+        score += 1;
+        if index > 0 && previous_match == Some(index - 1) {
+            score += 5;
+        }
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous_match = Some(index);
+        position = index + 1;
+    }
+    Some(score * 1000 - text.len() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("sd", "Save document").is_some());
+        assert!(fuzzy_score("SAVE", "Save document").is_some());
+        assert!(fuzzy_score("save tab", "Save tab").is_some());
+        assert_eq!(fuzzy_score("ds", "Save document"), None);
+        assert_eq!(fuzzy_score("x", "Save document"), None);
+        // word starts beat scattered matches
+        assert!(fuzzy_score("nt", "New tab") > fuzzy_score("nt", "Open document"));
+        // consecutive matches beat word starts
+        assert!(fuzzy_score("tab", "Save tab") > fuzzy_score("tab", "Toggle a bookmark"));
+        // shorter texts win ties
+        assert!(fuzzy_score("tag", "Edit tags") > fuzzy_score("tag", "Edit tags of document"));
+    }
+
+    #[test]
+    fn test_filter_palette_items() {
+        let items = vec![
+            PaletteItem {
+                command: VeloCommand::NewDocument,
+                title: "New document".to_string(),
+                shortcut: None,
+            },
+            PaletteItem {
+                command: VeloCommand::SaveTab,
+                title: "Save tab".to_string(),
+                shortcut: Some("Ctrl+S".to_string()),
+            },
+            PaletteItem {
+                command: VeloCommand::AddTab,
+                title: "New tab".to_string(),
+                shortcut: None,
+            },
+        ];
+        let commands: Vec<VeloCommand> = filter_palette_items(items, "tab")
+            .into_iter()
+            .map(|item| item.command)
+            .collect();
+        assert_eq!(commands, vec![VeloCommand::AddTab, VeloCommand::SaveTab]);
+    }
+}
@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+
+use super::ui_helpers::{ButtonAction, ButtonTypes, CommandButton};
+use crate::keymap::AppAction;
+use crate::utils::ReflectableUuid;
+
+/// Action that can be run from menu buttons, keyboard shortcuts and the command palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VeloCommand {
+    NewDocument,
+    NewFromTemplate,
+    SaveAsTemplate,
+    SaveDocument,
+    SaveTab,
+    RestoreCheckpoint,
+    ShowChanges,
+    SaveVersion,
+    VersionHistory,
+    DeleteDocument,
+    EditTags,
+    ChangeTheme,
+    ExportToFile,
+    ImportFromFile,
+    ImportFromUrl,
+    ImportDot,
+    ExportDot,
+    ShareDocument,
+    UnshareDocument,
+    AddTab,
+    DeleteTab,
+    AddRectangle,
+    AddCircle,
+    DeleteNode,
+    BringToFront,
+    SendToBack,
+    ToggleMindMap,
+    ArrangeMindMap,
+    EditBoard,
+    ExportBoardCsv,
+    ToggleParticles,
+    PasteImage,
+    CommandPalette,
+    OpenDocument(ReflectableUuid),
+    SelectTab(ReflectableUuid),
+}
+
+/// Buttons, keyboard shortcuts and the command palette all run commands by sending this event.
+pub struct RunCommand(pub VeloCommand);
+
+pub struct RegisteredCommand {
+    pub command: VeloCommand,
+    pub title: String,
+    /// Shortcut shown next to the command in the palette.
+    pub shortcut: Option<AppAction>,
+}
+
+/// Commands listed in the command palette.
+#[derive(Resource)]
+pub struct CommandRegistry {
+    commands: Vec<RegisteredCommand>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        let mut registry = Self { commands: vec![] };
+        registry
+            .register(VeloCommand::NewDocument, "New document", None)
+            .register(
+                VeloCommand::NewFromTemplate,
+                "New document from template",
+                None,
+            )
+            .register(
+                VeloCommand::SaveAsTemplate,
+                "Save document as template",
+                None,
+            )
+            .register(
+                VeloCommand::SaveDocument,
+                "Save document",
+                Some(AppAction::SaveDoc),
+            )
+            .register(VeloCommand::SaveTab, "Save tab", Some(AppAction::SaveTab))
+            .register(
+                VeloCommand::RestoreCheckpoint,
+                "Restore previous checkpoint",
+                Some(AppAction::Undo),
+            )
+            .register(VeloCommand::ShowChanges, "Compare changes", None)
+            .register(VeloCommand::SaveVersion, "Save named version", None)
+            .register(VeloCommand::VersionHistory, "Version history", None)
+            .register(VeloCommand::DeleteDocument, "Delete document", None)
+            .register(VeloCommand::EditTags, "Edit tags", None)
+            .register(VeloCommand::ChangeTheme, "Change theme", None);
+        #[cfg(not(target_arch = "wasm32"))]
+        registry
+            .register(VeloCommand::ExportToFile, "Export to file", None)
+            .register(VeloCommand::ImportFromFile, "Import from file", None)
+            .register(VeloCommand::ImportFromUrl, "Import from URL", None)
+            .register(
+                VeloCommand::ImportDot,
+                "Import DOT graph as a new tab",
+                None,
+            )
+            .register(VeloCommand::ExportDot, "Export tab as DOT graph", None)
+            .register(VeloCommand::ExportBoardCsv, "Export board as CSV", None);
+        registry
+            .register(VeloCommand::ShareDocument, "Share document", None)
+            .register(VeloCommand::UnshareDocument, "Unshare document", None)
+            .register(VeloCommand::AddTab, "New tab", None)
+            .register(VeloCommand::DeleteTab, "Delete tab", None)
+            .register(VeloCommand::AddRectangle, "Add rectangle", None)
+            .register(VeloCommand::AddCircle, "Add circle", None)
+            .register(VeloCommand::DeleteNode, "Delete selected node", None)
+            .register(
+                VeloCommand::BringToFront,
+                "Move selected node to front",
+                None,
+            )
+            .register(VeloCommand::SendToBack, "Move selected node to back", None)
+            .register(VeloCommand::ToggleMindMap, "Toggle mind map mode", None)
+            .register(VeloCommand::ArrangeMindMap, "Arrange mind map", None)
+            .register(VeloCommand::EditBoard, "Edit board columns and lanes", None);
+        #[cfg(not(target_arch = "wasm32"))]
+        registry
+            .register(
+                VeloCommand::ToggleParticles,
+                "Toggle particles effect",
+                None,
+            )
+            .register(
+                VeloCommand::PasteImage,
+                "Paste image from clipboard",
+                Some(AppAction::PasteImage),
+            );
+        registry
+    }
+}
+
+impl CommandRegistry {
+    pub fn register(
+        &mut self,
+        command: VeloCommand,
+        title: impl Into<String>,
+        shortcut: Option<AppAction>,
+    ) -> &mut Self {
+        self.commands.push(RegisteredCommand {
+            command,
+            title: title.into(),
+            shortcut,
+        });
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredCommand> {
+        self.commands.iter()
+    }
+}
+
+pub fn command_button_click(
+    mut run_command: EventWriter<RunCommand>,
+    command_buttons: Query<(&Interaction, &CommandButton), Changed<Interaction>>,
+    action_buttons: Query<(&Interaction, &ButtonAction), Changed<Interaction>>,
+) {
+    for (interaction, command_button) in command_buttons.iter() {
+        if *interaction == Interaction::Clicked {
+            run_command.send(RunCommand(command_button.0));
+        }
+    }
+    for (interaction, button_action) in action_buttons.iter() {
+        if *interaction == Interaction::Clicked {
+            let command = match button_action.button_type {
+                ButtonTypes::AddRec => VeloCommand::AddRectangle,
+                ButtonTypes::AddCircle => VeloCommand::AddCircle,
+                ButtonTypes::Del => VeloCommand::DeleteNode,
+                ButtonTypes::Front => VeloCommand::BringToFront,
+                ButtonTypes::Back => VeloCommand::SendToBack,
+            };
+            run_command.send(RunCommand(command));
+        }
+    }
+}
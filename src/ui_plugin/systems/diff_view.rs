@@ -0,0 +1,312 @@
+use bevy::{prelude::*, ui::FocusPolicy, window::PrimaryWindow};
+use bevy_cosmic_edit::CosmicFont;
+use bevy_pkv::PkvStore;
+use bevy_ui_borders::BorderColor;
+
+use super::ui_helpers::{
+    diff_option_color, spawn_diff_picker, spawn_modal, DiffPicker, DiffPickerCancel,
+    DiffPickerCompare, DiffPickerOption, DiffSide, ModalAction,
+};
+use super::{load_history, MainPanel, RunCommand, UiState, VeloCommand};
+use crate::diff::{diff_checkpoints, ChangeKind, TabDiff};
+use crate::history::compare_sources;
+use crate::resources::{AppState, FontSystemState, LoadDocRequest, LoadTabRequest};
+use crate::themes::Theme;
+use crate::utils::{get_timestamp, ReflectableUuid};
+
+/// Outlines and legend of the diff view, removed when it is toggled off or the canvas reloads.
+#[derive(Component)]
+pub struct DiffOverlay;
+
+fn change_color(theme: &Theme, kind: ChangeKind) -> Color {
+    match kind {
+        ChangeKind::Added => theme.diff_added,
+        ChangeKind::Removed => theme.diff_removed,
+        ChangeKind::Moved => theme.diff_moved,
+        ChangeKind::Resized => theme.diff_resized,
+        ChangeKind::Recolored => theme.diff_recolored,
+        ChangeKind::TextChanged => theme.diff_text_changed,
+    }
+}
+
+/// Spawns a message modal to be added to the main panel, unless another modal is open.
+fn spawn_message(
+    commands: &mut Commands,
+    ui_state: &mut UiState,
+    window: &Window,
+    cosmic_fonts: &mut ResMut<Assets<CosmicFont>>,
+    font_system_state: &FontSystemState,
+    theme: &Res<Theme>,
+    message: String,
+) -> Option<Entity> {
+    if ui_state.modal_id.is_some() {
+        return None;
+    }
+    let id = ReflectableUuid::generate();
+    ui_state.modal_id = Some(id);
+    let entity = spawn_modal(
+        commands,
+        theme,
+        cosmic_fonts,
+        font_system_state.0.clone().unwrap(),
+        window,
+        id,
+        ModalAction::Message,
+        Some(message),
+    );
+    Some(entity)
+}
+
+/// Toggles the diff view, the checkpoints to compare are picked in a [`DiffPicker`].
+pub fn diff_view_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    overlay_query: Query<Entity, With<DiffOverlay>>,
+    picker_query: Query<Entity, With<DiffPicker>>,
+    app_state: Res<AppState>,
+    pkv: Res<PkvStore>,
+    load_doc_request: Option<Res<LoadDocRequest>>,
+    load_tab_request: Option<Res<LoadTabRequest>>,
+    mut ui_state: ResMut<UiState>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    // outlines would point at nodes of another checkpoint, and the picker at another tab
+    if load_doc_request.is_some() || load_tab_request.is_some() {
+        for entity in overlay_query.iter().chain(picker_query.iter()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::ShowChanges {
+            continue;
+        }
+        if !overlay_query.is_empty() || !picker_query.is_empty() {
+            for entity in overlay_query.iter().chain(picker_query.iter()) {
+                commands.entity(entity).despawn_recursive();
+            }
+            continue;
+        }
+        let doc_id = match app_state.current_document {
+            Some(doc_id) => doc_id,
+            None => continue,
+        };
+        let active_tab = app_state
+            .docs
+            .get(&doc_id)
+            .and_then(|doc| doc.tabs.iter().find(|tab| tab.is_active));
+        let tab = match active_tab {
+            Some(tab) => tab,
+            None => continue,
+        };
+        let sources = compare_sources(tab, &load_history(&pkv, doc_id), get_timestamp());
+        if sources.len() < 2 {
+            if let Some(entity) = spawn_message(
+                &mut commands,
+                &mut ui_state,
+                windows.single(),
+                &mut cosmic_fonts,
+                &font_system_state,
+                &theme,
+                "No previous checkpoint to compare with".to_string(),
+            ) {
+                commands.entity(main_panel_query.single()).add_child(entity);
+            }
+            continue;
+        }
+        // the last change is shown unless something else is picked
+        let picker = spawn_diff_picker(&mut commands, &theme, sources, 1, 0);
+        commands.entity(main_panel_query.single()).add_child(picker);
+    }
+}
+
+pub fn diff_picker_click(
+    mut commands: Commands,
+    option_query: Query<(&Interaction, &DiffPickerOption), Changed<Interaction>>,
+    mut option_colors: Query<(&DiffPickerOption, &mut BackgroundColor)>,
+    compare_query: Query<&Interaction, (Changed<Interaction>, With<DiffPickerCompare>)>,
+    cancel_query: Query<&Interaction, (Changed<Interaction>, With<DiffPickerCancel>)>,
+    mut picker_query: Query<(Entity, &mut DiffPicker)>,
+    mut ui_state: ResMut<UiState>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    let Ok((picker_entity, mut picker)) = picker_query.get_single_mut() else {
+        return;
+    };
+    for (interaction, option) in option_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match option.side {
+            DiffSide::From => picker.from = option.index,
+            DiffSide::To => picker.to = option.index,
+        }
+        for (other, mut color) in option_colors.iter_mut() {
+            if other.side == option.side {
+                color.0 = diff_option_color(&theme, other.index == option.index);
+            }
+        }
+    }
+    if cancel_query.iter().any(|i| *i == Interaction::Clicked) {
+        commands.entity(picker_entity).despawn_recursive();
+        return;
+    }
+    if !compare_query.iter().any(|i| *i == Interaction::Clicked) {
+        return;
+    }
+    commands.entity(picker_entity).despawn_recursive();
+    let (from, to) = (&picker.sources[picker.from], &picker.sources[picker.to]);
+    let main_panel = main_panel_query.single();
+    let message = match diff_checkpoints(&from.checkpoint, &to.checkpoint) {
+        Ok(diff) if diff.is_empty() => {
+            format!("No changes from {} to {}", from.label, to.label)
+        }
+        Ok(diff) => {
+            let title = format!("Changes from {} to {}", from.label, to.label);
+            for entity in spawn_diff_overlay(&mut commands, &theme, &diff, title) {
+                commands.entity(main_panel).add_child(entity);
+            }
+            return;
+        }
+        Err(e) => format!("Failed to compare checkpoints: {}", e),
+    };
+    if let Some(entity) = spawn_message(
+        &mut commands,
+        &mut ui_state,
+        windows.single(),
+        &mut cosmic_fonts,
+        &font_system_state,
+        &theme,
+        message,
+    ) {
+        commands.entity(main_panel).add_child(entity);
+    }
+}
+
+/// Outlines changed nodes where they are in the newer checkpoint, removed nodes where they
+/// were, and adds a legend with the number of changes.
+fn spawn_diff_overlay(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    diff: &TabDiff,
+    title: String,
+) -> Vec<Entity> {
+    let mut entities = vec![];
+    for node in diff.nodes.iter() {
+        let color = change_color(theme, node.changes[0]);
+        let background = if node.changes[0] == ChangeKind::Removed {
+            color.with_a(0.2)
+        } else {
+            Color::NONE
+        };
+        let outline = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            left: node.position.0,
+                            bottom: node.position.1,
+                            ..default()
+                        },
+                        size: Size::new(node.size.0, node.size.1),
+                        border: UiRect::all(Val::Px(3.)),
+                        ..default()
+                    },
+                    background_color: background.into(),
+                    focus_policy: FocusPolicy::Pass,
+                    z_index: ZIndex::Global(1),
+                    ..default()
+                },
+                BorderColor(color),
+                DiffOverlay,
+            ))
+            .with_children(|builder| {
+                let changes: Vec<String> = node.changes.iter().map(|c| c.to_string()).collect();
+                builder
+                    .spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: UiRect {
+                                left: Val::Px(-3.),
+                                top: Val::Px(-24.),
+                                ..default()
+                            },
+                            padding: UiRect::horizontal(Val::Px(4.)),
+                            ..default()
+                        },
+                        background_color: color.into(),
+                        focus_policy: FocusPolicy::Pass,
+                        ..default()
+                    })
+                    .with_children(|builder| {
+                        builder.spawn(TextBundle::from_section(
+                            changes.join(", "),
+                            TextStyle {
+                                font_size: 14.,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        ));
+                    });
+            })
+            .id();
+        entities.push(outline);
+    }
+
+    let text_style = |color| TextStyle {
+        font_size: 14.,
+        color,
+        ..default()
+    };
+    let mut sections = vec![TextSection::new(
+        format!("{}\n", title),
+        text_style(theme.font),
+    )];
+    for (kind, count) in diff.counts() {
+        sections.push(TextSection::new(
+            format!("{} {}\n", count, kind),
+            text_style(change_color(theme, kind)),
+        ));
+    }
+    for (kind, arrows) in diff.arrow_counts() {
+        sections.push(TextSection::new(
+            format!("{}\n", arrows),
+            text_style(change_color(theme, kind)),
+        ));
+    }
+    let legend = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(20.),
+                        top: Val::Px(20.),
+                        ..default()
+                    },
+                    padding: UiRect::all(Val::Px(8.)),
+                    border: UiRect::all(Val::Px(1.)),
+                    ..default()
+                },
+                background_color: theme.modal_bg.into(),
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+            DiffOverlay,
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_sections(sections));
+        })
+        .id();
+    entities.push(legend);
+    entities
+}
@@ -4,7 +4,7 @@ use bevy::{
 };
 
 use super::ui_helpers::ScrollingList;
-use crate::{themes::Theme, ui_plugin::ui_helpers::DocListItemButton};
+use crate::themes::Theme;
 
 use crate::resources::{AppState, LoadDocRequest, SaveDocRequest};
 
@@ -12,37 +12,33 @@ use std::collections::{HashMap, HashSet};
 
 use bevy_pkv::PkvStore;
 
-use crate::{ui_plugin::ui_helpers::add_list_item, utils::ReflectableUuid};
+use crate::{
+    ui_plugin::ui_helpers::add_list_item,
+    utils::{dedup_tags, ReflectableUuid},
+};
 
 use super::{
-    ui_helpers::{DeleteDoc, DocList, DocListItemContainer},
-    UpdateDeleteDocBtnEvent,
+    ui_helpers::{DeleteDoc, DocList, DocListItemContainer, TagChip},
+    RunCommand, UpdateDeleteDocBtnEvent, VeloCommand,
 };
 
-pub fn list_item_click(
-    mut interaction_query: Query<
-        (&Interaction, &DocListItemButton),
-        (Changed<Interaction>, With<DocListItemButton>),
-    >,
+pub fn open_doc_handler(
+    mut run_command: EventReader<RunCommand>,
     mut state: ResMut<AppState>,
     mut commands: Commands,
 ) {
-    for (interaction, doc_list_item) in &mut interaction_query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                if Some(doc_list_item.id) != state.current_document {
-                    commands.insert_resource(SaveDocRequest {
-                        doc_id: state.current_document.unwrap(),
-                        path: None,
-                    });
-                    state.current_document = Some(doc_list_item.id);
-                    commands.insert_resource(LoadDocRequest {
-                        doc_id: doc_list_item.id,
-                    });
-                }
-            }
-            Interaction::Hovered => {}
-            Interaction::None => {}
+    for RunCommand(command) in run_command.iter() {
+        let doc_id = match command {
+            VeloCommand::OpenDocument(doc_id) => *doc_id,
+            _ => continue,
+        };
+        if Some(doc_id) != state.current_document {
+            commands.insert_resource(SaveDocRequest {
+                doc_id: state.current_document.unwrap(),
+                path: None,
+            });
+            state.current_document = Some(doc_id);
+            commands.insert_resource(LoadDocRequest { doc_id });
         }
     }
 }
@@ -87,10 +83,29 @@ pub fn doc_list_del_button_update(
     }
 }
 
+pub fn tag_chip_click(
+    mut interaction_query: Query<(&Interaction, &TagChip), (Changed<Interaction>, With<TagChip>)>,
+    mut app_state: ResMut<AppState>,
+) {
+    for (interaction, tag_chip) in &mut interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                if !app_state.tag_filter.remove(&tag_chip.tag) {
+                    app_state.tag_filter.insert(tag_chip.tag.clone());
+                }
+            }
+            Interaction::Hovered => {}
+            Interaction::None => {}
+        }
+    }
+}
+
 pub fn doc_list_ui_changed(
     mut commands: Commands,
     app_state: Res<AppState>,
     mut last_doc_list: Local<HashSet<ReflectableUuid>>,
+    mut last_tag_filter: Local<HashSet<String>>,
+    mut last_doc_tags: Local<HashMap<ReflectableUuid, Vec<String>>>,
     mut doc_list_query: Query<Entity, With<DocList>>,
     asset_server: Res<AssetServer>,
     pkv: Res<PkvStore>,
@@ -98,30 +113,71 @@ pub fn doc_list_ui_changed(
     mut event_writer: EventWriter<UpdateDeleteDocBtnEvent>,
     theme: Res<Theme>,
 ) {
-    if app_state.is_changed() && app_state.doc_list_ui != *last_doc_list {
+    if !app_state.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let doc_tags: HashMap<ReflectableUuid, Vec<String>> = app_state
+        .docs
+        .iter()
+        .map(|(id, doc)| (*id, doc.tags.clone()))
+        .collect();
+    if app_state.doc_list_ui != *last_doc_list
+        || app_state.tag_filter != *last_tag_filter
+        || doc_tags != *last_doc_tags
+        || theme.is_changed()
+    {
         // Think about re-using UI elements instead of destroying and re-creating them
         for entity in query_container.iter_mut() {
             commands.entity(entity).despawn_recursive();
         }
         let doc_list = doc_list_query.single_mut();
-        let mut doc_tuples: Vec<(String, ReflectableUuid)> = app_state
+        let stored_tags = pkv
+            .get::<HashMap<ReflectableUuid, Vec<String>>>("tags")
+            .unwrap_or_default();
+        let mut doc_tuples: Vec<(String, ReflectableUuid, Vec<String>)> = app_state
             .doc_list_ui
             .iter()
             .map(|doc_id| {
                 let doc_name = get_doc_name(*doc_id, &pkv, &app_state);
-                (doc_name, *doc_id)
+                let tags = get_doc_tags(*doc_id, &stored_tags, &app_state);
+                (doc_name, *doc_id, tags)
             })
+            .filter(|(_, _, tags)| app_state.tag_filter.iter().all(|t| tags.contains(t)))
             .collect();
         // Sort the tuples alphabetically based on doc_name
-        doc_tuples.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
-        for (doc_name, doc_id) in doc_tuples {
-            let doc_list_item =
-                add_list_item(&mut commands, &theme, &asset_server, doc_id, doc_name);
+        doc_tuples.sort_by(|(name1, _, _), (name2, _, _)| name1.cmp(name2));
+        for (doc_name, doc_id, tags) in doc_tuples {
+            let doc_list_item = add_list_item(
+                &mut commands,
+                &theme,
+                &asset_server,
+                doc_id,
+                doc_name,
+                tags,
+                &app_state.tag_filter,
+            );
             commands.entity(doc_list).add_child(doc_list_item);
         }
         event_writer.send(UpdateDeleteDocBtnEvent);
         *last_doc_list = app_state.doc_list_ui.clone();
+        *last_tag_filter = app_state.tag_filter.clone();
+        *last_doc_tags = doc_tags;
+    }
+}
+
+pub fn get_doc_tags(
+    doc_id: ReflectableUuid,
+    stored_tags: &HashMap<ReflectableUuid, Vec<String>>,
+    app_state: &Res<AppState>,
+) -> Vec<String> {
+    // tags stored before they were normalized may differ in case from the filter
+    if let Some(doc) = app_state.docs.get(&doc_id) {
+        return dedup_tags(&doc.tags);
     }
+    stored_tags
+        .get(&doc_id)
+        .map(|tags| dedup_tags(tags))
+        .unwrap_or_default()
 }
 
 pub fn get_doc_name(
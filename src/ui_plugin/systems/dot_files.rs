@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_cosmic_edit::CosmicFont;
+
+use super::ui_helpers::{spawn_modal, ModalStatus, ModalTop};
+use super::{close_modal, show_modal_error, MainPanel, ModalAction, RunCommand, VeloCommand};
+use crate::components::Tab;
+use crate::dot::{dot_to_checkpoint, export_dot, parse_dot};
+use crate::resources::{AppState, FontSystemState, LoadDocRequest, SaveTabRequest};
+use crate::themes::Theme;
+use crate::utils::ReflectableUuid;
+use crate::UiState;
+
+/// Tab written to a DOT file by [`pending_dot_export_handler`] once the canvas is saved to it.
+#[derive(Resource)]
+pub struct PendingDotExport {
+    pub doc_id: ReflectableUuid,
+    pub tab_id: ReflectableUuid,
+    pub path: PathBuf,
+    /// Export modal, closed when the file is written or showing the error.
+    pub modal_id: ReflectableUuid,
+}
+
+/// Asks for the path of the DOT file to import or export.
+pub fn dot_file_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    mut ui_state: ResMut<UiState>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        let action = match command {
+            VeloCommand::ImportDot => ModalAction::ImportDot,
+            VeloCommand::ExportDot => ModalAction::ExportDot,
+            _ => continue,
+        };
+        let id = ReflectableUuid::generate();
+        *ui_state = UiState::default();
+        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            windows.single(),
+            id,
+            action,
+            None,
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
+    }
+}
+
+/// Adds the graph of the DOT file as a new tab of the current document and opens it.
+pub fn import_dot_tab(
+    commands: &mut Commands,
+    app_state: &mut AppState,
+    theme: &Theme,
+    path: &str,
+) -> Result<(), String> {
+    let path = Path::new(path.trim());
+    let text =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read DOT file: {}", e))?;
+    let graph = parse_dot(&text).map_err(|e| e.to_string())?;
+    let doc_id = match app_state.current_document {
+        Some(doc_id) => doc_id,
+        None => return Err("No document is open".to_string()),
+    };
+    let name = graph
+        .name
+        .clone()
+        .filter(|name| !name.trim().is_empty())
+        .or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "Graph".to_string());
+    let tabs = &mut app_state.docs.get_mut(&doc_id).unwrap().tabs;
+    for tab in tabs.iter_mut() {
+        if tab.is_active {
+            commands.insert_resource(SaveTabRequest {
+                tab_id: tab.id,
+                doc_id,
+            });
+        }
+        tab.is_active = false;
+    }
+    tabs.push(Tab {
+        id: ReflectableUuid::generate(),
+        name,
+        checkpoints: VecDeque::from(vec![dot_to_checkpoint(&graph, theme.node_bg)]),
+        is_active: true,
+    });
+    commands.insert_resource(LoadDocRequest { doc_id });
+    Ok(())
+}
+
+/// Confirmed in the export modal, the active tab is saved first so the file has the canvas as
+/// it is.
+pub fn request_dot_export(
+    commands: &mut Commands,
+    app_state: &AppState,
+    path: &str,
+    modal_id: ReflectableUuid,
+) -> Result<(), String> {
+    let tab = app_state
+        .current_document
+        .and_then(|doc_id| Some((doc_id, app_state.docs.get(&doc_id)?)))
+        .and_then(|(doc_id, doc)| Some((doc_id, doc.tabs.iter().find(|tab| tab.is_active)?)));
+    let (doc_id, tab) = match tab {
+        Some(tab) => tab,
+        None => return Err("No tab is open".to_string()),
+    };
+    commands.insert_resource(SaveTabRequest {
+        doc_id,
+        tab_id: tab.id,
+    });
+    commands.insert_resource(PendingDotExport {
+        doc_id,
+        tab_id: tab.id,
+        path: PathBuf::from(path.trim()),
+        modal_id,
+    });
+    Ok(())
+}
+
+fn write_dot(app_state: &AppState, pending: &PendingDotExport) -> Result<String, String> {
+    let tab = app_state
+        .docs
+        .get(&pending.doc_id)
+        .and_then(|doc| doc.tabs.iter().find(|tab| tab.id == pending.tab_id))
+        .ok_or_else(|| "The tab was deleted".to_string())?;
+    let checkpoint = tab
+        .checkpoints
+        .back()
+        .ok_or_else(|| "The tab is empty".to_string())?;
+    let dot = export_dot(&tab.name, checkpoint)?;
+    std::fs::write(&pending.path, dot)
+        .map_err(|e| format!("Failed to write {}: {}", pending.path.display(), e))?;
+    Ok(tab.name.clone())
+}
+
+/// Writes the tab once it is saved, the modal is closed or shows why the export failed.
+pub fn pending_dot_export_handler(
+    mut commands: Commands,
+    pending_export: Option<Res<PendingDotExport>>,
+    save_tab_request: Option<Res<SaveTabRequest>>,
+    app_state: Res<AppState>,
+    mut ui_state: ResMut<UiState>,
+    query_top: Query<(Entity, &ModalTop), With<ModalTop>>,
+    mut status_query: Query<(&mut Text, &ModalStatus)>,
+) {
+    let pending = match pending_export {
+        Some(pending) if save_tab_request.is_none() => pending,
+        _ => return,
+    };
+    commands.remove_resource::<PendingDotExport>();
+    match write_dot(&app_state, &pending) {
+        Ok(name) => {
+            info!("Exported tab \"{}\" to {}", name, pending.path.display());
+            close_modal(&mut commands, &query_top, &mut ui_state, pending.modal_id);
+        }
+        Err(e) => {
+            error!("Failed to export tab to DOT: {}", e);
+            show_modal_error(&mut status_query, pending.modal_id, e);
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use bevy::prelude::*;
 use bevy_cosmic_edit::{
-    cosmic_edit_set_text, get_cosmic_text, ActiveEditor, CosmicEdit, CosmicFont, CosmicText,
+    get_cosmic_text, ActiveEditor, CosmicEdit, CosmicFont, CosmicImages, CosmicLineScales,
+    CosmicStrikethrough, CosmicText,
 };
-use bevy_markdown::{generate_markdown_lines, BevyMarkdown, BevyMarkdownTheme};
 use cosmic_text::{Cursor, Edit};
 
 use crate::{
@@ -12,7 +12,7 @@ use crate::{
 };
 use bevy_ui_borders::Outline;
 
-use super::{BevyMarkdownView, NodeType, RawText, UiState, VeloNode};
+use super::{MarkdownRenderTask, NodeType, RawText, RenderMarkdown, UiState, VeloNode};
 
 pub fn entity_to_edit_changed(
     ui_state: Res<UiState>,
@@ -55,12 +55,16 @@ pub fn entity_to_edit_changed(
                         let font = cosmic_fonts
                             .get_mut(&cosmic_edit.font_system.clone())
                             .unwrap();
-                        cosmic_edit_set_text(
-                            CosmicText::OneStyle(text),
-                            cosmic_edit.attrs.clone(),
-                            &mut cosmic_edit.editor,
-                            &mut font.0,
-                        );
+                        cosmic_edit.set_text(CosmicText::OneStyle(text), &mut font.0);
+                        // plain text is edited at a single size, without images
+                        commands
+                            .entity(entity)
+                            .insert((
+                                CosmicLineScales::default(),
+                                CosmicImages::default(),
+                                CosmicStrikethrough::default(),
+                            ))
+                            .remove::<MarkdownRenderTask>();
                         commands.insert_resource(ActiveEditor {
                             entity: Some(entity),
                         });
@@ -74,34 +78,8 @@ pub fn entity_to_edit_changed(
                             0,
                             bevy_color_to_cosmic(theme.node_bg),
                         ));
-                        let text = get_cosmic_text(&cosmic_edit.editor);
-                        raw_text.last_text = text.clone();
-                        let markdown_theme = BevyMarkdownTheme {
-                            code_theme: theme.code_theme.clone(),
-                            code_default_lang: theme.code_default_lang.clone(),
-                            link: bevy_color_to_cosmic(theme.link),
-                            inline_code: bevy_color_to_cosmic(theme.inline_code),
-                        };
-                        let markdown_lines = generate_markdown_lines(BevyMarkdown {
-                            text,
-                            markdown_theme,
-                            attrs: cosmic_edit.attrs.clone(),
-                        })
-                        .expect("should handle markdown convertion");
-                        let font = cosmic_fonts
-                            .get_mut(&cosmic_edit.font_system.clone())
-                            .unwrap();
-                        cosmic_edit_set_text(
-                            CosmicText::MultiStyle(markdown_lines.lines),
-                            cosmic_edit.attrs.clone(),
-                            &mut cosmic_edit.editor,
-                            &mut font.0,
-                        );
-                        commands.entity(entity).insert(BevyMarkdownView {
-                            id: raw_text.id,
-                            span_metadata: markdown_lines.span_metadata,
-                        });
-                        cosmic_edit.editor.buffer_mut().set_redraw(true);
+                        raw_text.last_text = get_cosmic_text(&cosmic_edit.editor);
+                        commands.entity(entity).insert(RenderMarkdown);
                     }
                 }
             }
@@ -127,35 +105,8 @@ pub fn entity_to_edit_changed(
                             0,
                             bevy_color_to_cosmic(theme.node_bg),
                         ));
-                        let text = get_cosmic_text(&cosmic_edit.editor);
-                        raw_text.last_text = text.clone();
-                        let markdown_theme = BevyMarkdownTheme {
-                            code_theme: theme.code_theme.clone(),
-                            code_default_lang: theme.code_default_lang.clone(),
-                            link: bevy_color_to_cosmic(theme.link),
-                            inline_code: bevy_color_to_cosmic(theme.inline_code),
-                        };
-                        let markdown_lines = generate_markdown_lines(BevyMarkdown {
-                            text,
-                            markdown_theme,
-                            attrs: cosmic_edit.attrs.clone(),
-                        })
-                        .expect("should handle markdown convertion");
-                        let font = cosmic_fonts
-                            .get_mut(&cosmic_edit.font_system.clone())
-                            .unwrap();
-                        cosmic_edit.attrs = cosmic_edit.attrs.clone();
-                        cosmic_edit_set_text(
-                            CosmicText::MultiStyle(markdown_lines.lines),
-                            cosmic_edit.attrs.clone(),
-                            &mut cosmic_edit.editor,
-                            &mut font.0,
-                        );
-                        commands.entity(entity).insert(BevyMarkdownView {
-                            id: raw_text.id,
-                            span_metadata: markdown_lines.span_metadata,
-                        });
-                        cosmic_edit.editor.buffer_mut().set_redraw(true);
+                        raw_text.last_text = get_cosmic_text(&cosmic_edit.editor);
+                        commands.entity(entity).insert(RenderMarkdown);
                     }
                 }
 
@@ -0,0 +1,323 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_cosmic_edit::CosmicFont;
+use bevy_pkv::PkvStore;
+
+use super::ui_helpers::{
+    spawn_history_panel, spawn_modal, HistoryAction, HistoryButton, HistoryPanel, ModalAction,
+};
+use super::{MainPanel, RunCommand, SaveStoreEvent, UiState, VeloCommand};
+use crate::config::Config;
+use crate::history::{
+    auto_version_due, history_key, prune, restore_version, restore_version_as_copy, Version,
+};
+use crate::resources::{AppState, FontSystemState, LoadDocRequest, SaveTabRequest};
+use crate::themes::Theme;
+use crate::utils::{get_timestamp, ReflectableUuid};
+
+/// Named version requested from the modal, added once the active tab is saved.
+#[derive(Resource)]
+pub struct PendingVersion {
+    pub doc_id: ReflectableUuid,
+    pub name: String,
+}
+
+/// Version restored from the history panel, once the active tab is saved.
+#[derive(Resource)]
+pub struct PendingRestore {
+    pub doc_id: ReflectableUuid,
+    pub version_id: ReflectableUuid,
+    pub action: HistoryAction,
+}
+
+/// Sent when versions of a document are added or removed, to refresh the open panel.
+pub struct HistoryChanged {
+    pub doc_id: ReflectableUuid,
+}
+
+pub fn load_history(pkv: &PkvStore, doc_id: ReflectableUuid) -> Vec<Version> {
+    pkv.get::<Vec<Version>>(&history_key(doc_id))
+        .unwrap_or_default()
+}
+
+fn store_history(pkv: &mut PkvStore, doc_id: ReflectableUuid, history: &[Version]) {
+    if let Err(e) = pkv.set(&history_key(doc_id), &history) {
+        error!("Failed to save version history: {}", e);
+    }
+}
+
+/// Asks for the name of a version of the current document.
+pub fn save_version_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    mut ui_state: ResMut<UiState>,
+    app_state: Res<AppState>,
+    pkv: Res<PkvStore>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::SaveVersion || ui_state.modal_id.is_some() {
+            continue;
+        }
+        let doc_id = match app_state.current_document {
+            Some(doc_id) => doc_id,
+            None => continue,
+        };
+        let named = load_history(&pkv, doc_id)
+            .iter()
+            .filter(|version| version.name.is_some())
+            .count();
+        let id = ReflectableUuid::generate();
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            windows.single(),
+            id,
+            ModalAction::SaveVersion,
+            Some(format!("Version {}", named + 1)),
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
+    }
+}
+
+/// Confirmed in the save version modal, the version is added by [`pending_version_handler`].
+pub fn request_version(commands: &mut Commands, app_state: &AppState, name: &str) {
+    let doc_id = match app_state.current_document {
+        Some(doc_id) => doc_id,
+        None => return,
+    };
+    // the canvas is saved to the active tab first
+    if let Some(tab) = app_state
+        .docs
+        .get(&doc_id)
+        .and_then(|doc| doc.tabs.iter().find(|tab| tab.is_active))
+    {
+        commands.insert_resource(SaveTabRequest {
+            doc_id,
+            tab_id: tab.id,
+        });
+    }
+    let name = name.trim();
+    commands.insert_resource(PendingVersion {
+        doc_id,
+        name: if name.is_empty() {
+            "Unnamed version".to_string()
+        } else {
+            name.to_string()
+        },
+    });
+}
+
+pub fn pending_version_handler(
+    mut commands: Commands,
+    pending_version: Option<Res<PendingVersion>>,
+    save_tab_request: Option<Res<SaveTabRequest>>,
+    app_state: Res<AppState>,
+    mut pkv: ResMut<PkvStore>,
+    mut events: EventWriter<SaveStoreEvent>,
+    mut history_changed: EventWriter<HistoryChanged>,
+    config: Res<Config>,
+) {
+    let pending = match pending_version {
+        Some(pending) if save_tab_request.is_none() => pending,
+        _ => return,
+    };
+    commands.remove_resource::<PendingVersion>();
+    let doc = match app_state.docs.get(&pending.doc_id) {
+        Some(doc) => doc,
+        None => return,
+    };
+    let now = get_timestamp();
+    let mut history = load_history(&pkv, doc.id);
+    history.push(Version::new(doc, Some(pending.name.clone()), now));
+    prune(&mut history, &config.history, now);
+    store_history(&mut pkv, doc.id, &history);
+    history_changed.send(HistoryChanged { doc_id: doc.id });
+    events.send(SaveStoreEvent {
+        doc_id: doc.id,
+        path: None,
+    });
+}
+
+/// Adds automatic versions when documents are saved.
+pub fn history_store_handler(
+    mut events: EventReader<SaveStoreEvent>,
+    mut history_changed: EventWriter<HistoryChanged>,
+    app_state: Res<AppState>,
+    mut pkv: ResMut<PkvStore>,
+    config: Res<Config>,
+) {
+    for event in events.iter() {
+        let doc = match app_state.docs.get(&event.doc_id) {
+            Some(doc) => doc,
+            None => continue,
+        };
+        let now = get_timestamp();
+        let mut history = load_history(&pkv, doc.id);
+        let added = auto_version_due(&history, doc, &config.history, now);
+        if added {
+            history.push(Version::new(doc, None, now));
+        }
+        let pruned = prune(&mut history, &config.history, now);
+        if added || pruned > 0 {
+            store_history(&mut pkv, doc.id, &history);
+            history_changed.send(HistoryChanged { doc_id: doc.id });
+        }
+    }
+}
+
+/// Opens and closes the version history of the current document.
+pub fn history_panel_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    mut history_changed: EventReader<HistoryChanged>,
+    panel_query: Query<(Entity, &HistoryPanel)>,
+    app_state: Res<AppState>,
+    pkv: Res<PkvStore>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    theme: Res<Theme>,
+) {
+    let mut open = None;
+    for RunCommand(command) in run_command.iter() {
+        match command {
+            VeloCommand::VersionHistory => match panel_query.get_single() {
+                Ok(_) => open = Some(false),
+                Err(_) => open = Some(true),
+            },
+            // the panel shows the versions of the current document only
+            VeloCommand::OpenDocument(_) | VeloCommand::NewDocument => {
+                if !panel_query.is_empty() {
+                    open = Some(false);
+                }
+            }
+            _ => {}
+        }
+    }
+    if open.is_none() {
+        for HistoryChanged { doc_id } in history_changed.iter() {
+            if panel_query.iter().any(|(_, panel)| panel.doc_id == *doc_id) {
+                open = Some(true);
+            }
+        }
+    }
+    let open = match open {
+        Some(open) => open,
+        None => return,
+    };
+    for (entity, _) in panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let doc_id = match app_state.current_document {
+        Some(doc_id) if open => doc_id,
+        _ => return,
+    };
+    let panel = spawn_history_panel(
+        &mut commands,
+        &theme,
+        windows.single(),
+        doc_id,
+        &load_history(&pkv, doc_id),
+        get_timestamp(),
+    );
+    commands.entity(main_panel_query.single()).add_child(panel);
+}
+
+/// Deletes a version right away, restoring it waits for [`pending_restore_handler`].
+pub fn history_button_click(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &HistoryButton), Changed<Interaction>>,
+    panel_query: Query<Entity, With<HistoryPanel>>,
+    app_state: Res<AppState>,
+    mut pkv: ResMut<PkvStore>,
+    mut history_changed: EventWriter<HistoryChanged>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        if button.action == HistoryAction::Delete {
+            let mut history = load_history(&pkv, button.doc_id);
+            history.retain(|version| version.id != button.version_id);
+            store_history(&mut pkv, button.doc_id, &history);
+            history_changed.send(HistoryChanged {
+                doc_id: button.doc_id,
+            });
+            continue;
+        }
+        // the canvas is saved to the active tab first, so that its changes aren't lost
+        if let Some(tab) = app_state
+            .docs
+            .get(&button.doc_id)
+            .and_then(|doc| doc.tabs.iter().find(|tab| tab.is_active))
+        {
+            commands.insert_resource(SaveTabRequest {
+                doc_id: button.doc_id,
+                tab_id: tab.id,
+            });
+        }
+        commands.insert_resource(PendingRestore {
+            doc_id: button.doc_id,
+            version_id: button.version_id,
+            action: button.action,
+        });
+        for entity in panel_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn pending_restore_handler(
+    mut commands: Commands,
+    pending_restore: Option<Res<PendingRestore>>,
+    save_tab_request: Option<Res<SaveTabRequest>>,
+    mut app_state: ResMut<AppState>,
+    mut pkv: ResMut<PkvStore>,
+    mut events: EventWriter<SaveStoreEvent>,
+) {
+    let pending = match pending_restore {
+        Some(pending) if save_tab_request.is_none() => pending,
+        _ => return,
+    };
+    commands.remove_resource::<PendingRestore>();
+    let mut history = load_history(&pkv, pending.doc_id);
+    let version = match history
+        .iter()
+        .find(|version| version.id == pending.version_id)
+    {
+        Some(version) => version.clone(),
+        None => return,
+    };
+    let current = match app_state.docs.get(&pending.doc_id) {
+        Some(current) => current,
+        None => return,
+    };
+    let doc = match pending.action {
+        HistoryAction::Restore => {
+            // the current state, with the saved canvas, can be restored in turn
+            let before = Version::new(current, None, get_timestamp());
+            history.push(before);
+            store_history(&mut pkv, pending.doc_id, &history);
+            restore_version(current, &version)
+        }
+        _ => {
+            events.send(SaveStoreEvent {
+                doc_id: pending.doc_id,
+                path: None,
+            });
+            restore_version_as_copy(&version)
+        }
+    };
+    let doc_id = doc.id;
+    app_state.current_document = Some(doc_id);
+    app_state.doc_list_ui.insert(doc_id);
+    app_state.docs.insert(doc_id, doc);
+    commands.insert_resource(LoadDocRequest { doc_id });
+    events.send(SaveStoreEvent { doc_id, path: None });
+}
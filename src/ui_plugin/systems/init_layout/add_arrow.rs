@@ -3,7 +3,7 @@ use bevy_ui_borders::BorderColor;
 
 use crate::{
     themes::Theme,
-    ui_plugin::ui_helpers::{GenericButton, TooltipPosition},
+    ui_plugin::ui_helpers::{GenericButton, ThemedBackground, ThemedBorder, TooltipPosition},
 };
 
 use super::ui_helpers::{get_tooltip, Tooltip};
@@ -35,17 +35,20 @@ pub fn add_arrow(
         ),
     };
     let top = commands
-        .spawn(NodeBundle {
-            style: Style {
-                flex_direction: FlexDirection::Column,
-                align_self: AlignSelf::Stretch,
-                margin: UiRect::all(Val::Px(3.)),
-                size: Size::new(Val::Percent(13.), Val::Percent(100.)),
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_self: AlignSelf::Stretch,
+                    margin: UiRect::all(Val::Px(3.)),
+                    size: Size::new(Val::Percent(13.), Val::Percent(100.)),
+                    ..default()
+                },
+                background_color: theme.shadow.into(),
                 ..default()
             },
-            background_color: theme.shadow.into(),
-            ..default()
-        })
+            ThemedBackground(|theme| theme.shadow),
+        ))
         .id();
     let button = commands
         .spawn((
@@ -71,6 +74,8 @@ pub fn add_arrow(
             BorderColor(theme.btn_border),
             arrow_mode,
             GenericButton,
+            ThemedBackground(|theme| theme.arrow_btn_bg),
+            ThemedBorder(|theme| theme.btn_border),
         ))
         .with_children(|builder| {
             builder.spawn((
@@ -1,23 +1,29 @@
 use bevy::prelude::*;
 use bevy_ui_borders::BorderColor;
 
-use crate::{themes::Theme, ui_plugin::ui_helpers::GenericButton};
+use crate::{
+    themes::Theme,
+    ui_plugin::ui_helpers::{GenericButton, ThemedBackground, ThemedBorder},
+};
 
 use super::ui_helpers::ChangeColor;
 
 pub fn add_color(commands: &mut Commands, theme: &Res<Theme>, color: Color) -> Entity {
     let top = commands
-        .spawn(NodeBundle {
-            style: Style {
-                flex_direction: FlexDirection::Column,
-                align_self: AlignSelf::Stretch,
-                margin: UiRect::all(Val::Px(5.)),
-                size: Size::new(Val::Percent(20.), Val::Percent(100.)),
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_self: AlignSelf::Stretch,
+                    margin: UiRect::all(Val::Px(5.)),
+                    size: Size::new(Val::Percent(20.), Val::Percent(100.)),
+                    ..default()
+                },
+                background_color: theme.shadow.into(),
                 ..default()
             },
-            background_color: theme.shadow.into(),
-            ..default()
-        })
+            ThemedBackground(|theme| theme.shadow),
+        ))
         .id();
     let button = commands
         .spawn((
@@ -42,6 +48,7 @@ pub fn add_color(commands: &mut Commands, theme: &Res<Theme>, color: Color) -> E
             BorderColor(theme.btn_border),
             ChangeColor { color },
             GenericButton,
+            ThemedBorder(|theme| theme.btn_border),
         ))
         .id();
     commands.entity(top).add_child(button);
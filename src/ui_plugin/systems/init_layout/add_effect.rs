@@ -1,6 +1,9 @@
 use bevy::{prelude::*, text::BreakLineOn};
 
-use crate::{themes::Theme, ui_plugin::ui_helpers::GenericButton};
+use crate::{
+    themes::Theme,
+    ui_plugin::ui_helpers::{GenericButton, ThemedBackground, ThemedText},
+};
 
 pub fn add_effect(
     commands: &mut Commands,
@@ -40,6 +43,7 @@ pub fn add_effect(
             },
             component,
             GenericButton,
+            ThemedBackground(|theme| theme.celebrate_btn_bg),
         ))
         .with_children(|builder| {
             let text_style = TextStyle {
@@ -56,7 +60,10 @@ pub fn add_effect(
                 linebreak_behaviour: BreakLineOn::WordBoundary,
             };
 
-            builder.spawn(TextBundle { text, ..default() });
+            builder.spawn((
+                TextBundle { text, ..default() },
+                ThemedText(|theme| theme.celebrate_btn),
+            ));
         })
         .id();
     commands.entity(top).add_child(button);
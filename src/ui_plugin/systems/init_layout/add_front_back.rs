@@ -3,7 +3,7 @@ use bevy_ui_borders::BorderColor;
 
 use crate::{
     themes::Theme,
-    ui_plugin::ui_helpers::{GenericButton, TooltipPosition},
+    ui_plugin::ui_helpers::{GenericButton, ThemedBackground, ThemedBorder, TooltipPosition},
 };
 
 use super::ui_helpers::{get_tooltip, ButtonAction, ButtonTypes, Tooltip};
@@ -20,17 +20,20 @@ pub fn add_front_back(
         (asset_server.load("back.png"), "Move to back")
     };
     let top = commands
-        .spawn(NodeBundle {
-            style: Style {
-                flex_direction: FlexDirection::Column,
-                align_self: AlignSelf::Stretch,
-                margin: UiRect::all(Val::Px(5.)),
-                size: Size::new(Val::Percent(15.), Val::Percent(100.)),
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_self: AlignSelf::Stretch,
+                    margin: UiRect::all(Val::Px(5.)),
+                    size: Size::new(Val::Percent(15.), Val::Percent(100.)),
+                    ..default()
+                },
+                background_color: theme.shadow.into(),
                 ..default()
             },
-            background_color: theme.shadow.into(),
-            ..default()
-        })
+            ThemedBackground(|theme| theme.shadow),
+        ))
         .id();
     let button = commands
         .spawn((
@@ -56,6 +59,8 @@ pub fn add_front_back(
             BorderColor(theme.btn_border),
             button_action,
             GenericButton,
+            ThemedBackground(|theme| theme.front_back_btn_bg),
+            ThemedBorder(|theme| theme.btn_border),
         ))
         .with_children(|builder| {
             builder.spawn((
@@ -11,7 +11,7 @@ use bevy_pkv::PkvStore;
 
 use super::ui_helpers::ScrollingList;
 use crate::resources::{AppState, LoadDocRequest};
-use crate::ui_plugin::ui_helpers::DocList;
+use crate::ui_plugin::ui_helpers::{DocList, ThemedBackground};
 use crate::utils::ReflectableUuid;
 use crate::{
     components::{Doc, Tab},
@@ -30,16 +30,19 @@ pub fn add_list(
     }
 
     let top = commands
-        .spawn(NodeBundle {
-            style: Style {
-                flex_direction: FlexDirection::Column,
-                size: Size::new(Val::Percent(80.), Val::Percent(80.)),
-                overflow: Overflow::Hidden,
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    size: Size::new(Val::Percent(80.), Val::Percent(80.)),
+                    overflow: Overflow::Hidden,
+                    ..default()
+                },
+                background_color: theme.doc_list_bg.into(),
                 ..default()
             },
-            background_color: theme.doc_list_bg.into(),
-            ..default()
-        })
+            ThemedBackground(|theme| theme.doc_list_bg),
+        ))
         .id();
     let node = commands
         .spawn((
@@ -78,6 +81,8 @@ pub fn add_list(
                 name: "Untitled".to_string(),
                 tabs,
                 tags: vec![],
+                shared: None,
+                inline_images: HashMap::new(),
             },
         );
         app_state.current_document = Some(doc_id);
@@ -3,7 +3,10 @@ use bevy_ui_borders::BorderColor;
 
 use crate::{
     themes::Theme,
-    ui_plugin::ui_helpers::{get_tooltip, GenericButton, Tooltip, TooltipPosition},
+    ui_plugin::ui_helpers::{
+        get_tooltip, GenericButton, ThemedBackground, ThemedBorder, ThemedText, Tooltip,
+        TooltipPosition,
+    },
 };
 
 pub fn add_menu_button(
@@ -22,6 +25,9 @@ pub fn add_menu_button(
         "Import From URL" => "\u{e902}",
         "Save Document to window.velo object" => "\u{e866}",
         "Share Document (copy URL to clipboard)" => "\u{e80d}",
+        "Unshare Document" => "\u{e16f}",
+        "Edit Tags" => "\u{e54e}",
+        "Change Theme" => "\u{e40a}",
         _ => panic!("Unknown menu button tooltip label: {}", label),
     };
     match label.as_str() {
@@ -60,6 +66,7 @@ pub fn add_menu_button(
                     },
                     component,
                     GenericButton,
+                    ThemedBackground(|theme| theme.new_tab_btn_bg),
                 ))
                 .with_children(|builder| {
                     let text_style = TextStyle {
@@ -76,7 +83,10 @@ pub fn add_menu_button(
                         linebreak_behaviour: BreakLineOn::WordBoundary,
                     };
 
-                    builder.spawn(TextBundle { text, ..default() });
+                    builder.spawn((
+                        TextBundle { text, ..default() },
+                        ThemedText(|theme| theme.menu_btn),
+                    ));
                 })
                 .id();
             commands.entity(top).add_child(button);
@@ -103,6 +113,8 @@ pub fn add_menu_button(
                         ..default()
                     },
                     BorderColor(theme.btn_border),
+                    ThemedBackground(|theme| theme.shadow),
+                    ThemedBorder(|theme| theme.btn_border),
                 ))
                 .id();
             let button = commands
@@ -126,6 +138,7 @@ pub fn add_menu_button(
                     },
                     component,
                     GenericButton,
+                    ThemedBackground(|theme| theme.menu_btn_bg),
                 ))
                 .with_children(|builder| {
                     builder.spawn((
@@ -153,11 +166,14 @@ pub fn add_menu_button(
                         ..default()
                     };
 
-                    builder.spawn(TextBundle {
-                        text,
-                        style: text_bundle_style,
-                        ..default()
-                    });
+                    builder.spawn((
+                        TextBundle {
+                            text,
+                            style: text_bundle_style,
+                            ..default()
+                        },
+                        ThemedText(|theme| theme.menu_btn),
+                    ));
                 })
                 .id();
             commands.entity(top).add_child(button);
@@ -9,7 +9,8 @@ use crate::{
     themes::Theme,
     ui_plugin::{
         ui_helpers::{
-            get_tooltip, GenericButton, SearchButton, SearchText, Tooltip, TooltipPosition,
+            get_tooltip, GenericButton, SearchButton, SearchText, ThemedBackground, ThemedBorder,
+            Tooltip, TooltipPosition,
         },
         TextPos,
     },
@@ -25,16 +26,19 @@ pub fn add_search_box(
 ) -> Entity {
     let id = ReflectableUuid::generate();
     let root = commands
-        .spawn((NodeBundle {
-            background_color: theme.search_box_bg.into(),
-            style: Style {
-                size: Size::new(Val::Percent(80.), Val::Percent(8.)),
-                flex_direction: FlexDirection::Column,
-                margin: UiRect::all(Val::Px(5.)),
+        .spawn((
+            NodeBundle {
+                background_color: theme.search_box_bg.into(),
+                style: Style {
+                    size: Size::new(Val::Percent(80.), Val::Percent(8.)),
+                    flex_direction: FlexDirection::Column,
+                    margin: UiRect::all(Val::Px(5.)),
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        },))
+            ThemedBackground(|theme| theme.search_box_bg),
+        ))
         .id();
     let mut attrs = cosmic_text::Attrs::new();
     attrs = attrs.family(cosmic_text::Family::Name(theme.font_name.as_str()));
@@ -59,6 +63,7 @@ pub fn add_search_box(
     commands
         .entity(cosmic_edit)
         .insert(BorderColor(theme.search_box_border))
+        .insert(ThemedBorder(|theme| theme.search_box_border))
         .insert(SearchButton { id })
         .insert(GenericButton);
     commands.entity(cosmic_edit).insert(SearchText { id });
@@ -1,7 +1,10 @@
 use bevy::prelude::*;
 use bevy_ui_borders::BorderColor;
 
-use crate::{themes::Theme, ui_plugin::ui_helpers::GenericButton};
+use crate::{
+    themes::Theme,
+    ui_plugin::ui_helpers::{GenericButton, ThemedBackground, ThemedBorder},
+};
 
 use super::ui_helpers::TextPosMode;
 
@@ -16,17 +19,20 @@ pub fn add_text_pos(
         crate::TextPos::TopLeft => arrow_server.load("text-left-top.png"),
     };
     let top = commands
-        .spawn(NodeBundle {
-            style: Style {
-                flex_direction: FlexDirection::Column,
-                align_self: AlignSelf::Stretch,
-                margin: UiRect::all(Val::Px(5.)),
-                size: Size::new(Val::Percent(15.), Val::Percent(100.)),
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_self: AlignSelf::Stretch,
+                    margin: UiRect::all(Val::Px(5.)),
+                    size: Size::new(Val::Percent(15.), Val::Percent(100.)),
+                    ..default()
+                },
+                background_color: theme.shadow.into(),
                 ..default()
             },
-            background_color: theme.shadow.into(),
-            ..default()
-        })
+            ThemedBackground(|theme| theme.shadow),
+        ))
         .id();
     let button = commands
         .spawn((
@@ -52,6 +58,8 @@ pub fn add_text_pos(
             BorderColor(theme.btn_border),
             text_pos_mode,
             GenericButton,
+            ThemedBackground(|theme| theme.text_pos_btn_bg),
+            ThemedBorder(|theme| theme.btn_border),
         ))
         .id();
     commands.entity(top).add_child(button);
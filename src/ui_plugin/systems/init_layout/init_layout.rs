@@ -8,12 +8,14 @@ use std::time::Duration;
 use bevy_pkv::PkvStore;
 
 use super::ui_helpers::{
-    self, AddTab, BottomPanel, ButtonAction, LeftPanel, LeftPanelControls, LeftPanelExplorer,
-    MainPanel, Menu, NewDoc, ParticlesEffect, Root, SaveDoc, TextPosMode,
+    self, BottomPanel, ButtonAction, CommandButton, LeftPanel, LeftPanelControls,
+    LeftPanelExplorer, MainPanel, Menu, Root, TextPosMode, ThemedBackground, ThemedBorder,
 };
-use super::{CommChannels, ExportToFile, ImportFromFile, ImportFromUrl, ShareDoc};
+use super::{CommChannels, VeloCommand};
 use crate::canvas::arrow::components::{ArrowMode, ArrowType};
+use crate::config::Config;
 use crate::resources::{AppState, FontSystemState};
+use crate::share::Sharing;
 use crate::themes::Theme;
 use crate::{BlinkTimer, TextPos};
 
@@ -63,6 +65,8 @@ pub fn init_layout(
     windows: Query<&Window, With<PrimaryWindow>>,
     mut fonts: ResMut<Assets<Font>>,
     theme: Res<Theme>,
+    config: Res<Config>,
+    sharing: Res<Sharing>,
 ) {
     // font setup
     let font_bytes_regular = include_bytes!("../../../../assets/fonts/VictorMono-Regular.ttf");
@@ -80,7 +84,7 @@ pub fn init_layout(
     };
     fonts.set_untracked(text_style.font, font);
     let cosmic_font_config = CosmicFontConfig {
-        fonts_dir_path: None,
+        fonts_dir_path: config.fonts_dir.clone(),
         load_system_fonts: true,
         font_bytes: Some(vec![
             font_bytes_regular,
@@ -98,7 +102,7 @@ pub fn init_layout(
     let primary_window: &Window = windows.single();
     #[cfg(not(target_arch = "wasm32"))]
     {
-        let (tx, rx) = async_channel::bounded(1);
+        let (tx, rx) = async_channel::unbounded();
         commands.insert_resource(CommChannels { tx, rx });
     }
     let icon_font = asset_server.load("fonts/MaterialIcons-Regular.ttf");
@@ -128,6 +132,8 @@ pub fn init_layout(
             },
             BottomPanel,
             BorderColor(theme.btn_border),
+            ThemedBackground(|theme| theme.bottom_panel_bg),
+            ThemedBorder(|theme| theme.btn_border),
         ))
         .id();
     let add_tab = add_menu_button(
@@ -135,7 +141,7 @@ pub fn init_layout(
         &theme,
         "New Tab".to_string(),
         &icon_font,
-        AddTab,
+        CommandButton(VeloCommand::AddTab),
     );
     commands.entity(bottom_panel).add_child(add_tab);
 
@@ -180,6 +186,8 @@ pub fn init_layout(
                 ..default()
             },
             BorderColor(theme.btn_border),
+            ThemedBackground(|theme| theme.menu_bg),
+            ThemedBorder(|theme| theme.btn_border),
             Menu,
         ))
         .id();
@@ -188,14 +196,28 @@ pub fn init_layout(
         &theme,
         "New Document".to_string(),
         &icon_font,
-        NewDoc,
+        CommandButton(VeloCommand::NewDocument),
     );
     let save_doc = add_menu_button(
         &mut commands,
         &theme,
         "Save Document".to_string(),
         &icon_font,
-        SaveDoc,
+        CommandButton(VeloCommand::SaveDocument),
+    );
+    let tag_doc = add_menu_button(
+        &mut commands,
+        &theme,
+        "Edit Tags".to_string(),
+        &icon_font,
+        CommandButton(VeloCommand::EditTags),
+    );
+    let change_theme = add_menu_button(
+        &mut commands,
+        &theme,
+        "Change Theme".to_string(),
+        &icon_font,
+        CommandButton(VeloCommand::ChangeTheme),
     );
     #[cfg(not(target_arch = "wasm32"))]
     let export_file = add_menu_button(
@@ -203,7 +225,7 @@ pub fn init_layout(
         &theme,
         "Export To File".to_string(),
         &icon_font,
-        ExportToFile,
+        CommandButton(VeloCommand::ExportToFile),
     );
     #[cfg(not(target_arch = "wasm32"))]
     let import_file = add_menu_button(
@@ -211,7 +233,7 @@ pub fn init_layout(
         &theme,
         "Import From File".to_string(),
         &icon_font,
-        ImportFromFile,
+        CommandButton(VeloCommand::ImportFromFile),
     );
     #[cfg(not(target_arch = "wasm32"))]
     let import_url = add_menu_button(
@@ -219,7 +241,7 @@ pub fn init_layout(
         &theme,
         "Import From URL".to_string(),
         &icon_font,
-        ImportFromUrl,
+        CommandButton(VeloCommand::ImportFromUrl),
     );
     #[cfg(target_arch = "wasm32")]
     let set_window_prop = add_menu_button(
@@ -231,21 +253,31 @@ pub fn init_layout(
     );
     commands.entity(menu).add_child(new_doc);
     commands.entity(menu).add_child(save_doc);
+    commands.entity(menu).add_child(tag_doc);
+    commands.entity(menu).add_child(change_theme);
     #[cfg(not(target_arch = "wasm32"))]
     commands.entity(menu).add_child(export_file);
     #[cfg(not(target_arch = "wasm32"))]
     commands.entity(menu).add_child(import_file);
     #[cfg(not(target_arch = "wasm32"))]
     commands.entity(menu).add_child(import_url);
-    if app_state.github_token.is_some() {
+    if sharing.provider.is_some() {
         let share_doc = add_menu_button(
             &mut commands,
             &theme,
             "Share Document (copy URL to clipboard)".to_string(),
             &icon_font,
-            ShareDoc,
+            CommandButton(VeloCommand::ShareDocument),
+        );
+        let unshare_doc = add_menu_button(
+            &mut commands,
+            &theme,
+            "Unshare Document".to_string(),
+            &icon_font,
+            CommandButton(VeloCommand::UnshareDocument),
         );
         commands.entity(menu).add_child(share_doc);
+        commands.entity(menu).add_child(unshare_doc);
     }
     #[cfg(target_arch = "wasm32")]
     commands.entity(menu).add_child(set_window_prop);
@@ -276,6 +308,8 @@ pub fn init_layout(
                 ..default()
             },
             BorderColor(theme.btn_border),
+            ThemedBackground(|theme| theme.left_panel_bg),
+            ThemedBorder(|theme| theme.btn_border),
             LeftPanel,
         ))
         .id();
@@ -559,7 +593,12 @@ pub fn init_layout(
         .id();
     #[cfg(not(target_arch = "wasm32"))]
     {
-        let effect1 = add_effect(&mut commands, &theme, &icon_font, ParticlesEffect);
+        let effect1 = add_effect(
+            &mut commands,
+            &theme,
+            &icon_font,
+            CommandButton(VeloCommand::ToggleParticles),
+        );
         commands.entity(effects).add_child(effect1);
     }
 
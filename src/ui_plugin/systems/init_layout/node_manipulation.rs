@@ -2,7 +2,10 @@ use bevy::{prelude::*, text::BreakLineOn};
 
 use crate::{
     themes::Theme,
-    ui_plugin::ui_helpers::{get_tooltip, ButtonAction, GenericButton, Tooltip, TooltipPosition},
+    ui_plugin::ui_helpers::{
+        get_tooltip, ButtonAction, GenericButton, ThemedBackground, ThemedText, Tooltip,
+        TooltipPosition,
+    },
 };
 
 pub fn node_manipulation(
@@ -26,17 +29,20 @@ pub fn node_manipulation(
         })
         .id();
     let top_new_rec = commands
-        .spawn(NodeBundle {
-            background_color: theme.shadow.into(),
-            style: Style {
-                flex_direction: FlexDirection::Column,
-                align_self: AlignSelf::Stretch,
-                margin: UiRect::all(Val::Px(5.)),
-                size: Size::new(Val::Percent(23.), Val::Percent(100.)),
+        .spawn((
+            NodeBundle {
+                background_color: theme.shadow.into(),
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_self: AlignSelf::Stretch,
+                    margin: UiRect::all(Val::Px(5.)),
+                    size: Size::new(Val::Percent(23.), Val::Percent(100.)),
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        })
+            ThemedBackground(|theme| theme.shadow),
+        ))
         .id();
     let new_rec = commands
         .spawn((
@@ -59,6 +65,7 @@ pub fn node_manipulation(
             },
             create_rec_component,
             GenericButton,
+            ThemedBackground(|theme| theme.node_manipulation_bg),
         ))
         .with_children(|builder| {
             builder.spawn((
@@ -91,11 +98,14 @@ pub fn node_manipulation(
                 ..default()
             };
 
-            builder.spawn(TextBundle {
-                text,
-                style: text_bundle_style,
-                ..default()
-            });
+            builder.spawn((
+                TextBundle {
+                    text,
+                    style: text_bundle_style,
+                    ..default()
+                },
+                ThemedText(|theme| theme.node_manipulation),
+            ));
         })
         .id();
     // let top_new_circle = commands
@@ -172,17 +182,20 @@ pub fn node_manipulation(
     //     })
     //     .id();
     let top_del = commands
-        .spawn(NodeBundle {
-            background_color: theme.shadow.into(),
-            style: Style {
-                flex_direction: FlexDirection::Column,
-                margin: UiRect::all(Val::Px(5.)),
-                align_self: AlignSelf::Stretch,
-                size: Size::new(Val::Percent(23.), Val::Percent(100.)),
+        .spawn((
+            NodeBundle {
+                background_color: theme.shadow.into(),
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    margin: UiRect::all(Val::Px(5.)),
+                    align_self: AlignSelf::Stretch,
+                    size: Size::new(Val::Percent(23.), Val::Percent(100.)),
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        })
+            ThemedBackground(|theme| theme.shadow),
+        ))
         .id();
     let del_rec = commands
         .spawn((
@@ -205,6 +218,7 @@ pub fn node_manipulation(
             },
             delete_component,
             GenericButton,
+            ThemedBackground(|theme| theme.node_manipulation_bg),
         ))
         .with_children(|builder| {
             builder.spawn((
@@ -237,11 +251,14 @@ pub fn node_manipulation(
                 ..default()
             };
 
-            builder.spawn(TextBundle {
-                text,
-                style: text_bundle_style,
-                ..default()
-            });
+            builder.spawn((
+                TextBundle {
+                    text,
+                    style: text_bundle_style,
+                    ..default()
+                },
+                ThemedText(|theme| theme.node_manipulation),
+            ));
         })
         .id();
     // commands.entity(top_new_circle).add_child(new_circle);
@@ -0,0 +1,279 @@
+use async_channel::{Receiver, Sender};
+use base64::{engine::general_purpose, Engine};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::tasks::AsyncComputeTaskPool;
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use bevy_cosmic_edit::{CosmicEdit, CosmicImage, CosmicImages};
+use std::collections::HashMap;
+
+use super::ui_helpers::BevyMarkdownView;
+use crate::utils::ReflectableUuid;
+
+/// Sources of images stored in the document, e.g. pasted screenshots, start with this prefix.
+pub const EMBEDDED_IMAGE_PREFIX: &str = "image:";
+/// Largest image (bytes) downloaded or read from a file.
+const MAX_IMAGE_SIZE: usize = 10 * 1024 * 1024;
+/// Seconds to wait for an image download.
+#[cfg(not(target_arch = "wasm32"))]
+const FETCH_TIMEOUT: u64 = 30;
+
+pub enum InlineImage {
+    Loading,
+    Loaded {
+        handle: Handle<Image>,
+        /// Base64 encoded file, saved with the tab so the document doesn't depend on the source.
+        data: String,
+    },
+    Failed,
+}
+
+/// Images referenced with `![alt](src)` in node text, by source.
+#[derive(Resource, Default)]
+pub struct InlineImages {
+    pub images: HashMap<String, InlineImage>,
+}
+
+impl InlineImages {
+    /// Adds a base64 encoded image saved with a document.
+    pub fn insert_encoded(&mut self, images: &mut Assets<Image>, src: &str, data: &str) {
+        if matches!(self.images.get(src), Some(InlineImage::Loaded { .. })) {
+            return;
+        }
+        let image = match general_purpose::STANDARD.decode(data.as_bytes()) {
+            Ok(bytes) => load_inline_image(images, &bytes),
+            Err(_) => InlineImage::Failed,
+        };
+        self.images.insert(src.to_string(), image);
+    }
+
+    /// Stores the image in the document and returns its source.
+    pub fn embed(&mut self, images: &mut Assets<Image>, bytes: &[u8]) -> String {
+        let src = embedded_src();
+        self.images
+            .insert(src.clone(), load_inline_image(images, bytes));
+        src
+    }
+
+    /// Reads a local file picked by the user in the background and stores it in the document,
+    /// returns the source it will be shown from. Paths in node text are never read, so opening
+    /// a document can't load files from the disk.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn embed_file(&mut self, path: std::path::PathBuf, tx: Sender<FetchedImage>) -> String {
+        let src = embedded_src();
+        self.images.insert(src.clone(), InlineImage::Loading);
+        let fetched_src = src.clone();
+        AsyncComputeTaskPool::get()
+            .spawn(async move {
+                let result = read_image_file(&path);
+                let _ = tx.try_send(FetchedImage {
+                    src: fetched_src,
+                    result,
+                });
+            })
+            .detach();
+        src
+    }
+}
+
+fn embedded_src() -> String {
+    format!("{}{}", EMBEDDED_IMAGE_PREFIX, ReflectableUuid::generate().0)
+}
+
+fn too_large() -> String {
+    format!("image is larger than {} KiB", MAX_IMAGE_SIZE / 1024)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_image_file(path: &std::path::Path) -> Result<Vec<u8>, String> {
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_IMAGE_SIZE as u64 {
+        return Err(too_large());
+    }
+    std::fs::read(path).map_err(|e| e.to_string())
+}
+
+fn load_inline_image(images: &mut Assets<Image>, bytes: &[u8]) -> InlineImage {
+    let img = match image::load_from_memory(bytes) {
+        Ok(img) => img.to_rgba8(),
+        Err(e) => {
+            warn!("Failed to decode image: {}", e);
+            return InlineImage::Failed;
+        }
+    };
+    let size = Extent3d {
+        width: img.width(),
+        height: img.height(),
+        ..Default::default()
+    };
+    let image = Image::new(
+        size,
+        TextureDimension::D2,
+        img.into_raw(),
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    InlineImage::Loaded {
+        handle: images.add(image),
+        data: general_purpose::STANDARD.encode(bytes),
+    }
+}
+
+pub struct FetchedImage {
+    pub src: String,
+    pub result: Result<Vec<u8>, String>,
+}
+
+#[derive(Resource, Clone)]
+pub struct InlineImageChannels {
+    pub tx: Sender<FetchedImage>,
+    pub rx: Receiver<FetchedImage>,
+}
+
+impl Default for InlineImageChannels {
+    fn default() -> Self {
+        let (tx, rx) = async_channel::unbounded();
+        Self { tx, rx }
+    }
+}
+
+/// Sources that are downloaded, other sources in node text only show embedded images. In the
+/// browser relative URLs are loaded from the site.
+fn is_remote(src: &str) -> bool {
+    !src.starts_with(EMBEDDED_IMAGE_PREFIX)
+        && (cfg!(target_arch = "wasm32")
+            || src.starts_with("http://")
+            || src.starts_with("https://"))
+}
+
+/// Downloads at most [`MAX_IMAGE_SIZE`] bytes with a deadline for the whole request.
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch_limited(url: &str) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+
+    let response = ureq::AgentBuilder::new()
+        .timeout(std::time::Duration::from_secs(FETCH_TIMEOUT))
+        .build()
+        .get(url)
+        .call()
+        .map_err(|e| e.to_string())?;
+    let content_length = response
+        .header("content-length")
+        .and_then(|value| value.parse::<usize>().ok());
+    if content_length.unwrap_or(0) > MAX_IMAGE_SIZE {
+        return Err(too_large());
+    }
+    let mut bytes = vec![];
+    response
+        .into_reader()
+        .take(MAX_IMAGE_SIZE as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    if bytes.len() > MAX_IMAGE_SIZE {
+        return Err(too_large());
+    }
+    Ok(bytes)
+}
+
+/// Downloads the image in the background, the result is sent to the channel.
+fn fetch_inline_image(src: String, tx: Sender<FetchedImage>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    AsyncComputeTaskPool::get()
+        .spawn(async move {
+            let result = fetch_limited(&src);
+            let _ = tx.try_send(FetchedImage { src, result });
+        })
+        .detach();
+    // the browser reads the whole response, only the announced size is checked before
+    #[cfg(target_arch = "wasm32")]
+    ehttp::fetch(ehttp::Request::get(src.clone()), move |result| {
+        let result = result.and_then(|response| {
+            let content_length = response
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.parse::<usize>().ok());
+            if !response.ok {
+                Err(format!("{} {}", response.status, response.status_text))
+            } else if content_length.unwrap_or(response.bytes.len()) > MAX_IMAGE_SIZE
+                || response.bytes.len() > MAX_IMAGE_SIZE
+            {
+                Err(too_large())
+            } else {
+                Ok(response.bytes)
+            }
+        });
+        let _ = tx.try_send(FetchedImage { src, result });
+    });
+}
+
+pub fn update_inline_images(
+    mut commands: Commands,
+    mut inline_images: ResMut<InlineImages>,
+    channels: Res<InlineImageChannels>,
+    markdown_view_query: Query<(Entity, &CosmicEdit, Ref<BevyMarkdownView>)>,
+) {
+    let store_changed = inline_images.is_changed();
+    for (entity, cosmic_edit, view) in markdown_view_query.iter() {
+        // nodes being edited show the markdown source
+        if !cosmic_edit.readonly || !(store_changed || view.is_changed()) {
+            continue;
+        }
+        // downloads that failed are tried again when the node is rendered again
+        let retry = view.is_changed();
+        let mut cosmic_images = vec![];
+        for (line, image) in view.images.iter() {
+            match inline_images.images.get(&image.src) {
+                Some(InlineImage::Loaded { handle, .. }) => cosmic_images.push(CosmicImage {
+                    line: *line,
+                    image: handle.clone(),
+                }),
+                Some(InlineImage::Failed) if retry && is_remote(&image.src) => {
+                    inline_images
+                        .images
+                        .insert(image.src.clone(), InlineImage::Loading);
+                    fetch_inline_image(image.src.clone(), channels.tx.clone());
+                }
+                Some(_) => {}
+                // embedded images come with the document, local files are only read when the
+                // user picks them
+                None if !is_remote(&image.src) => {
+                    if !image.src.starts_with(EMBEDDED_IMAGE_PREFIX) {
+                        warn!(
+                            "Image {} is not loaded, paste the file path to embed it",
+                            image.src
+                        );
+                    }
+                    inline_images
+                        .images
+                        .insert(image.src.clone(), InlineImage::Failed);
+                }
+                None => {
+                    inline_images
+                        .images
+                        .insert(image.src.clone(), InlineImage::Loading);
+                    fetch_inline_image(image.src.clone(), channels.tx.clone());
+                }
+            }
+        }
+        commands.entity(entity).insert(CosmicImages(cosmic_images));
+    }
+}
+
+pub fn receive_inline_images(
+    channels: Res<InlineImageChannels>,
+    mut inline_images: ResMut<InlineImages>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    while let Ok(FetchedImage { src, result }) = channels.rx.try_recv() {
+        let image = match result {
+            Ok(bytes) => load_inline_image(&mut images, &bytes),
+            Err(e) => {
+                warn!("Failed to load image {}: {}", src, e);
+                InlineImage::Failed
+            }
+        };
+        inline_images.images.insert(src, image);
+    }
+}
@@ -1,5 +1,6 @@
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
 use bevy::{
-    prelude::*,
     render::render_resource::{Extent3d, TextureDimension, TextureFormat},
     window::PrimaryWindow,
 };
@@ -7,65 +8,67 @@ use bevy::{
 #[cfg(not(target_arch = "wasm32"))]
 use image::*;
 
+#[cfg(not(target_arch = "wasm32"))]
 use std::convert::TryInto;
+#[cfg(not(target_arch = "wasm32"))]
 use uuid::Uuid;
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::AddRectEvent;
 use crate::{
-    resources::{LoadTabRequest, SaveTabRequest},
+    keymap::{AppAction, Keymap},
     themes::Theme,
-    AddRectEvent, BlinkTimer, UiState,
+    BlinkTimer, UiState,
 };
+use bevy_cosmic_edit::{ActiveEditor, CosmicEdit};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy_cosmic_edit::{EditKind, EditSnapshot};
+#[cfg(not(target_arch = "wasm32"))]
+use cosmic_text::Edit;
 
 use super::ui_helpers::{get_sections, EditableText};
-use crate::resources::{AppState, SaveDocRequest};
+#[cfg(not(target_arch = "wasm32"))]
+use super::{InlineImageChannels, InlineImages, RawText};
+use super::{RunCommand, VeloCommand};
+use crate::resources::AppState;
 
 pub fn keyboard_input_system(
     mut commands: Commands,
-    mut images: ResMut<Assets<Image>>,
     mut app_state: ResMut<AppState>,
     mut ui_state: ResMut<UiState>,
     mut char_evr: EventReader<ReceivedCharacter>,
-    mut events: EventWriter<AddRectEvent>,
+    mut run_command: EventWriter<RunCommand>,
     input: Res<Input<KeyCode>>,
-    windows: Query<&Window, With<PrimaryWindow>>,
+    keymap: Res<Keymap>,
     mut deleting: Local<bool>,
     mut editable_text_query: Query<(&mut Text, &EditableText), With<EditableText>>,
     mut blink_timer: ResMut<BlinkTimer>,
     time: Res<Time>,
     theme: Res<Theme>,
+    active_editor: Res<ActiveEditor>,
+    cosmic_edit_query: Query<&CosmicEdit>,
 ) {
-    let primary_window = windows.single();
-    let scale_factor = primary_window.scale_factor();
-    let command = input.any_pressed([KeyCode::RWin, KeyCode::LWin]);
-    let shift = input.any_pressed([KeyCode::RShift, KeyCode::LShift]);
     blink_timer.timer.tick(time.delta());
-    if command && input.just_pressed(KeyCode::V) {
-        #[cfg(not(target_arch = "wasm32"))]
-        insert_from_clipboard(&mut images, &mut events, scale_factor, &theme);
-    } else if command && shift && input.just_pressed(KeyCode::S) {
-        commands.insert_resource(SaveDocRequest {
-            doc_id: app_state.current_document.unwrap(),
-            path: None,
-        });
-    } else if command && input.just_pressed(KeyCode::S) {
-        if let Some(current_doc) = app_state.docs.get(&app_state.current_document.unwrap()) {
-            if let Some(active_tab) = current_doc.tabs.iter().find(|t| t.is_active) {
-                commands.insert_resource(SaveTabRequest {
-                    doc_id: app_state.current_document.unwrap(),
-                    tab_id: active_tab.id,
-                });
-            }
-        }
-    } else if command && input.just_pressed(KeyCode::L) {
-        if let Some(current_doc) = app_state.docs.get(&app_state.current_document.unwrap()) {
-            if let Some(active_tab) = current_doc.tabs.iter().find(|t| t.is_active) {
-                commands.insert_resource(LoadTabRequest {
-                    doc_id: app_state.current_document.unwrap(),
-                    tab_id: active_tab.id,
-                    drop_last_checkpoint: true,
-                });
-            }
-        }
+    // keys belong to the input method while text is being composed
+    if active_editor
+        .entity
+        .and_then(|entity| cosmic_edit_query.get(entity).ok())
+        .map_or(false, |cosmic_edit| cosmic_edit.preedit.is_some())
+    {
+        return;
+    }
+    let shortcut = AppAction::ALL
+        .into_iter()
+        .find(|action| keymap.just_pressed(*action, &input));
+    if let Some(action) = shortcut {
+        let command = match action {
+            AppAction::SaveTab => VeloCommand::SaveTab,
+            AppAction::SaveDoc => VeloCommand::SaveDocument,
+            AppAction::Undo => VeloCommand::RestoreCheckpoint,
+            AppAction::PasteImage => VeloCommand::PasteImage,
+            AppAction::CommandPalette => VeloCommand::CommandPalette,
+        };
+        run_command.send(RunCommand(command));
     } else {
         if ui_state.doc_to_edit.is_some() || ui_state.tab_to_edit.is_some() {
             blink_timer.timer.unpause();
@@ -143,7 +146,7 @@ fn get_text_val(
         for ev in char_evr.iter() {
             if deleting {
                 str.pop();
-            } else {
+            } else if !ev.char.is_control() {
                 str = format!("{}{}", str, ev.char);
             }
         }
@@ -151,6 +154,92 @@ fn get_text_val(
     (str, deleting)
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+pub fn paste_image_handler(
+    mut run_command: EventReader<RunCommand>,
+    mut images: ResMut<Assets<Image>>,
+    mut events: EventWriter<AddRectEvent>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    theme: Res<Theme>,
+    active_editor: Res<ActiveEditor>,
+    mut node_text_query: Query<&mut CosmicEdit, With<RawText>>,
+    mut inline_images: ResMut<InlineImages>,
+    channels: Res<InlineImageChannels>,
+    time: Res<Time>,
+) {
+    let scale_factor = windows.single().scale_factor();
+    for RunCommand(command) in run_command.iter() {
+        if *command == VeloCommand::PasteImage {
+            // pasting while editing a node adds the image to its text
+            match active_editor
+                .entity
+                .and_then(|entity| node_text_query.get_mut(entity).ok())
+            {
+                Some(mut cosmic_edit) => insert_inline_image(
+                    &mut images,
+                    &mut inline_images,
+                    &channels,
+                    &mut cosmic_edit,
+                    time.elapsed_seconds_f64(),
+                ),
+                None => insert_from_clipboard(&mut images, &mut events, scale_factor, &theme),
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn clipboard_image() -> Option<RgbaImage> {
+    let image = arboard::Clipboard::new().ok()?.get_image().ok()?;
+    ImageBuffer::from_raw(
+        image.width.try_into().unwrap(),
+        image.height.try_into().unwrap(),
+        image.bytes.into_owned(),
+    )
+}
+
+/// Path of a file copied to the clipboard, e.g. from a file manager.
+#[cfg(not(target_arch = "wasm32"))]
+fn clipboard_file() -> Option<std::path::PathBuf> {
+    let text = arboard::Clipboard::new().ok()?.get_text().ok()?;
+    let text = text.trim();
+    let path = std::path::PathBuf::from(text.strip_prefix("file://").unwrap_or(text));
+    path.is_file().then_some(path)
+}
+
+/// Stores the clipboard image, or the image file whose path is in the clipboard, in the
+/// document and references it at the cursor.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn insert_inline_image(
+    images: &mut ResMut<Assets<Image>>,
+    inline_images: &mut ResMut<InlineImages>,
+    channels: &InlineImageChannels,
+    cosmic_edit: &mut CosmicEdit,
+    now: f64,
+) {
+    let src = if let Some(image) = clipboard_image() {
+        let mut image_data: Vec<u8> = Vec::new();
+        if let Err(e) = DynamicImage::ImageRgba8(image).write_to(
+            &mut std::io::Cursor::new(&mut image_data),
+            ImageOutputFormat::Png,
+        ) {
+            error!("Failed to encode pasted image: {}", e);
+            return;
+        }
+        inline_images.embed(images, &image_data)
+    } else if let Some(path) = clipboard_file() {
+        inline_images.embed_file(path, channels.tx.clone())
+    } else {
+        return;
+    };
+    let before = EditSnapshot::of(&cosmic_edit.editor);
+    cosmic_edit
+        .editor
+        .insert_string(&format!("![image]({})", src), None);
+    cosmic_edit.record_edit(EditKind::Paste, before, now);
+    cosmic_edit.editor.buffer_mut().set_redraw(true);
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn insert_from_clipboard(
     images: &mut ResMut<Assets<Image>>,
@@ -160,45 +249,37 @@ pub fn insert_from_clipboard(
 ) {
     use crate::JsonNode;
 
-    if let Ok(mut clipboard) = arboard::Clipboard::new() {
-        if let Ok(image) = clipboard.get_image() {
-            let image: RgbaImage = ImageBuffer::from_raw(
-                image.width.try_into().unwrap(),
-                image.height.try_into().unwrap(),
-                image.bytes.into_owned(),
-            )
-            .unwrap();
-            let width = image.width();
-            let height = image.height();
-            let size: Extent3d = Extent3d {
-                width,
-                height,
-                ..Default::default()
-            };
-            let image = Image::new(
-                size,
-                TextureDimension::D2,
-                image.to_vec(),
-                TextureFormat::Rgba8UnormSrgb,
-            );
-            let image = images.add(image);
-            events.send(AddRectEvent {
-                node: JsonNode {
-                    id: Uuid::new_v4(),
-                    node_type: crate::NodeType::Rect,
-                    left: Val::Px(0.0),
-                    bottom: Val::Px(0.0),
-                    width: Val::Px(width as f32 / scale_factor as f32),
-                    height: Val::Px(height as f32 / scale_factor as f32),
-                    text: crate::JsonNodeText {
-                        text: "".to_string(),
-                        pos: crate::TextPos::Center,
-                    },
-                    bg_color: theme.clipboard_image_bg,
-                    z_index: 0,
+    if let Some(image) = clipboard_image() {
+        let width = image.width();
+        let height = image.height();
+        let size: Extent3d = Extent3d {
+            width,
+            height,
+            ..Default::default()
+        };
+        let image = Image::new(
+            size,
+            TextureDimension::D2,
+            image.to_vec(),
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        let image = images.add(image);
+        events.send(AddRectEvent {
+            node: JsonNode {
+                id: Uuid::new_v4(),
+                node_type: crate::NodeType::Rect,
+                left: Val::Px(0.0),
+                bottom: Val::Px(0.0),
+                width: Val::Px(width as f32 / scale_factor as f32),
+                height: Val::Px(height as f32 / scale_factor as f32),
+                text: crate::JsonNodeText {
+                    text: "".to_string(),
+                    pos: crate::TextPos::Center,
                 },
-                image: Some(image),
-            });
-        }
+                bg_color: theme.clipboard_image_bg,
+                z_index: 0,
+            },
+            image: Some(image),
+        });
     }
 }
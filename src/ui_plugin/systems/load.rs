@@ -8,7 +8,7 @@ use bevy_cosmic_edit::CosmicFont;
 
 use super::{
     ui_helpers::{add_tab, spawn_node, BottomPanel, NodeMeta, TabContainer},
-    DeleteDoc, DeleteTab, MainPanel, VeloNodeContainer,
+    DeleteDoc, DeleteTab, InlineImages, MainPanel, VeloNodeContainer,
 };
 use crate::{
     canvas::arrow::components::ArrowMeta,
@@ -17,6 +17,7 @@ use crate::{
 };
 use crate::{canvas::arrow::events::CreateArrowEvent, utils::load_doc_to_memory};
 
+use crate::config::Config;
 use crate::resources::{AppState, LoadDocRequest};
 use crate::utils::ReflectableUuid;
 use crate::{JsonNode, UiState};
@@ -50,6 +51,7 @@ pub fn load_doc(
     mut tabs_query: Query<Entity, With<TabContainer>>,
     mut delete_doc: Query<(&mut Visibility, &DeleteDoc), With<DeleteDoc>>,
     theme: Res<Theme>,
+    config: Res<Config>,
 ) {
     let bottom_panel = bottom_panel.single_mut();
     let doc_id = request.doc_id;
@@ -60,7 +62,7 @@ pub fn load_doc(
             *visibility = Visibility::Hidden;
         }
     }
-    load_doc_to_memory(doc_id, &mut app_state, &mut pkv);
+    load_doc_to_memory(doc_id, &mut app_state, &mut pkv, config.max_docs_in_memory);
 
     let mut tabs = vec![];
     for entity in tabs_query.iter_mut() {
@@ -103,6 +105,7 @@ pub fn load_tab(
     font_system_state: ResMut<FontSystemState>,
     mut windows: Query<&mut Window, With<PrimaryWindow>>,
     theme: Res<Theme>,
+    mut inline_images: ResMut<InlineImages>,
 ) {
     *ui_state = UiState::default();
     commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
@@ -131,7 +134,9 @@ pub fn load_tab(
             *visibility = Visibility::Hidden;
         }
     }
-    for tab in app_state.docs.get_mut(&doc_id).unwrap().tabs.iter_mut() {
+    let doc = app_state.docs.get_mut(&doc_id).unwrap();
+    let doc_inline_images = &doc.inline_images;
+    for tab in doc.tabs.iter_mut() {
         if tab.id == request.tab_id {
             if tab.checkpoints.is_empty() {
                 break;
@@ -143,6 +148,17 @@ pub fn load_tab(
                 tab.checkpoints.back().unwrap().clone()
             };
             let mut json: Value = serde_json::from_str(&json).unwrap();
+            for (src, data) in doc_inline_images.iter() {
+                inline_images.insert_encoded(&mut res_images, src, data);
+            }
+            // checkpoints saved before images were stored with the document
+            if let Some(json_inline_images) = json["inline_images"].as_object() {
+                for (src, data) in json_inline_images.iter() {
+                    if let Some(data) = data.as_str() {
+                        inline_images.insert_encoded(&mut res_images, src, data);
+                    }
+                }
+            }
             let images = json["images"].as_object().unwrap();
             let nodes = json["nodes"].as_array().unwrap();
             for node in nodes.iter() {
@@ -0,0 +1,150 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+    window::PrimaryWindow,
+};
+use bevy_cosmic_edit::{CosmicEdit, CosmicFont, CosmicLineScales, CosmicStrikethrough, CosmicText};
+use bevy_markdown::{
+    generate_markdown_lines_cached, BevyMarkdown, BevyMarkdownLines, BevyMarkdownTheme,
+    MarkdownCache,
+};
+use futures_lite::future;
+
+use super::ui_helpers::{BevyMarkdownView, RawText, RenderMarkdown};
+use crate::{
+    themes::Theme,
+    utils::{bevy_color_to_cosmic, ReflectableUuid},
+};
+
+/// Rendered node texts, shared with the render tasks.
+#[derive(Resource, Clone, Default)]
+pub struct MarkdownRenderCache(pub Arc<Mutex<MarkdownCache>>);
+
+/// Node text being rendered on the async compute pool, replacing it cancels the render.
+#[derive(Component)]
+pub struct MarkdownRenderTask(Task<BevyMarkdownLines>);
+
+pub fn markdown_theme(theme: &Theme) -> BevyMarkdownTheme {
+    BevyMarkdownTheme {
+        code_theme: theme.code_theme.clone(),
+        code_default_lang: theme.code_default_lang.clone(),
+        link: bevy_color_to_cosmic(theme.link),
+        inline_code: bevy_color_to_cosmic(theme.inline_code),
+        blockquote: bevy_color_to_cosmic(theme.blockquote),
+    }
+}
+
+fn apply_markdown_lines(
+    commands: &mut Commands,
+    entity: Entity,
+    id: ReflectableUuid,
+    cosmic_edit: &mut CosmicEdit,
+    cosmic_fonts: &mut Assets<CosmicFont>,
+    markdown_lines: BevyMarkdownLines,
+) {
+    let font = cosmic_fonts
+        .get_mut(&cosmic_edit.font_system.clone())
+        .unwrap();
+    cosmic_edit.set_text(CosmicText::MultiStyle(markdown_lines.lines), &mut font.0);
+    let strikethrough = markdown_lines
+        .span_metadata
+        .iter()
+        .enumerate()
+        .filter(|(_, metadata)| metadata.strikethrough)
+        .map(|(i, _)| i)
+        .collect();
+    commands.entity(entity).insert((
+        BevyMarkdownView {
+            id,
+            span_metadata: markdown_lines.span_metadata,
+            images: markdown_lines.images,
+            diagnostics: markdown_lines
+                .diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.to_string())
+                .collect(),
+        },
+        CosmicLineScales(markdown_lines.line_scales),
+        CosmicStrikethrough(strikethrough),
+    ));
+    cosmic_edit.editor.buffer_mut().set_redraw(true);
+}
+
+/// Shows cached renders right away, other texts are rendered in the background so opening a tab
+/// with many nodes doesn't stall frames.
+pub fn start_markdown_render(
+    mut commands: Commands,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    theme: Res<Theme>,
+    cache: Res<MarkdownRenderCache>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    mut render_query: Query<(Entity, &RawText, &mut CosmicEdit), With<RenderMarkdown>>,
+) {
+    let scale_factor = windows.single().scale_factor() as f32;
+    for (entity, raw_text, mut cosmic_edit) in render_query.iter_mut() {
+        commands.entity(entity).remove::<RenderMarkdown>();
+        // nodes being edited show the markdown source
+        if !cosmic_edit.readonly {
+            continue;
+        }
+        let bevy_markdown = BevyMarkdown {
+            text: raw_text.last_text.clone(),
+            markdown_theme: markdown_theme(&theme),
+            attrs: cosmic_edit.attrs.clone(),
+            // the buffer metrics are scaled to physical pixels
+            font_size: cosmic_edit.editor.buffer().metrics().font_size / scale_factor,
+        };
+        let cached = cache
+            .0
+            .lock()
+            .ok()
+            .and_then(|mut cache| cache.get(&bevy_markdown).cloned());
+        match cached {
+            Some(markdown_lines) => {
+                commands.entity(entity).remove::<MarkdownRenderTask>();
+                apply_markdown_lines(
+                    &mut commands,
+                    entity,
+                    raw_text.id,
+                    &mut cosmic_edit,
+                    &mut cosmic_fonts,
+                    markdown_lines,
+                );
+            }
+            None => {
+                let cache = cache.0.clone();
+                let task = AsyncComputeTaskPool::get()
+                    .spawn(async move { generate_markdown_lines_cached(bevy_markdown, &cache) });
+                commands.entity(entity).insert(MarkdownRenderTask(task));
+            }
+        }
+    }
+}
+
+pub fn finish_markdown_render(
+    mut commands: Commands,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    mut task_query: Query<(Entity, &RawText, &mut CosmicEdit, &mut MarkdownRenderTask)>,
+) {
+    for (entity, raw_text, mut cosmic_edit, mut task) in task_query.iter_mut() {
+        let markdown_lines = match future::block_on(future::poll_once(&mut task.0)) {
+            Some(markdown_lines) => markdown_lines,
+            None => continue,
+        };
+        commands.entity(entity).remove::<MarkdownRenderTask>();
+        // the node went back to editing while it was rendered
+        if !cosmic_edit.readonly {
+            continue;
+        }
+        apply_markdown_lines(
+            &mut commands,
+            entity,
+            raw_text.id,
+            &mut cosmic_edit,
+            &mut cosmic_fonts,
+            markdown_lines,
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use bevy::prelude::*;
+
+use super::ui_helpers::{markdown_warning_display, BevyMarkdownView, MarkdownWarning, Tooltip};
+
+/// Shows the warning badge of nodes whose markdown has diagnostics after the text changed.
+pub fn markdown_warning_badge(
+    markdown_view_query: Query<&BevyMarkdownView, Changed<BevyMarkdownView>>,
+    mut badge_query: Query<(&MarkdownWarning, &mut Style, &Children)>,
+    mut tooltip_query: Query<&mut Text, With<Tooltip>>,
+) {
+    for markdown_view in markdown_view_query.iter() {
+        for (badge, mut style, children) in badge_query.iter_mut() {
+            if badge.id != markdown_view.id {
+                continue;
+            }
+            style.display = markdown_warning_display(&markdown_view.diagnostics);
+            for child in children.iter() {
+                if let Ok(mut text) = tooltip_query.get_mut(*child) {
+                    text.sections[0].value = markdown_view.diagnostics.join("; ");
+                }
+            }
+        }
+        for diagnostic in markdown_view.diagnostics.iter() {
+            warn!("Node {}: {}", markdown_view.id.0, diagnostic);
+        }
+    }
+}
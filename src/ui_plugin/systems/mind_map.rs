@@ -0,0 +1,629 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_cosmic_edit::{ActiveEditor, CosmicEdit};
+use bevy_ui_borders::Outline;
+use uuid::Uuid;
+
+use super::ui_helpers::{CommandPalette, VeloNode};
+use super::{
+    AddRectEvent, JsonNode, JsonNodeText, NodeType, RunCommand, TextPos, UiState, VeloCommand,
+    VeloNodeContainer,
+};
+use crate::canvas::arrow::components::{ArrowConnect, ArrowConnectPos, ArrowMeta};
+use crate::canvas::arrow::events::{CreateArrowEvent, RedrawArrowEvent};
+use crate::themes::Theme;
+use crate::utils::ReflectableUuid;
+
+/// Size of the nodes added in mind-map mode.
+const NODE_WIDTH: f32 = 160.;
+const NODE_HEIGHT: f32 = 64.;
+/// Space between a node and its children, and between siblings.
+const CHILD_GAP: f32 = 60.;
+const SIBLING_GAP: f32 = 20.;
+
+/// Keyboard driven editing of a tree of nodes: Tab adds a child of the selected node, Enter a
+/// sibling while no node is edited, and arrow keys move the selection along arrows.
+#[derive(Resource, Default)]
+pub struct MindMap {
+    pub enabled: bool,
+    /// Node selected with the keyboard, after Escape leaves editing.
+    pub selected: Option<ReflectableUuid>,
+    /// Arrows to nodes that are still being spawned.
+    pending_arrows: Vec<CreateArrowEvent>,
+    /// Nodes moved by the layout, their arrows are redrawn once the new positions are applied.
+    moved: Vec<ReflectableUuid>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NodeRect {
+    pub left: f32,
+    pub bottom: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl NodeRect {
+    pub fn center(&self) -> (f32, f32) {
+        (self.left + self.width / 2., self.bottom + self.height / 2.)
+    }
+}
+
+/// Position and size of a node container, positioned in pixels.
+pub fn style_rect(style: &Style) -> Option<NodeRect> {
+    match (style.position, style.size) {
+        (
+            UiRect {
+                left: Val::Px(left),
+                bottom: Val::Px(bottom),
+                ..
+            },
+            Size {
+                width: Val::Px(width),
+                height: Val::Px(height),
+            },
+        ) => Some(NodeRect {
+            left,
+            bottom,
+            width,
+            height,
+        }),
+        _ => None,
+    }
+}
+
+/// Parent of the node, the start of the first arrow pointing at it.
+fn parent_of(
+    id: ReflectableUuid,
+    edges: &[(ReflectableUuid, ReflectableUuid)],
+) -> Option<ReflectableUuid> {
+    edges
+        .iter()
+        .find(|(start, end)| *end == id && *start != id)
+        .map(|(start, _)| *start)
+}
+
+fn tree_root(id: ReflectableUuid, edges: &[(ReflectableUuid, ReflectableUuid)]) -> ReflectableUuid {
+    let mut root = id;
+    let mut visited = HashSet::from([id]);
+    while let Some(parent) = parent_of(root, edges) {
+        if !visited.insert(parent) {
+            break;
+        }
+        root = parent;
+    }
+    root
+}
+
+/// Children of every node of the tree, from the top one down, nodes reached twice are kept
+/// under their first parent.
+fn tree_children(
+    root: ReflectableUuid,
+    rects: &HashMap<ReflectableUuid, NodeRect>,
+    edges: &[(ReflectableUuid, ReflectableUuid)],
+) -> HashMap<ReflectableUuid, Vec<ReflectableUuid>> {
+    let mut children: HashMap<ReflectableUuid, Vec<ReflectableUuid>> = HashMap::new();
+    let mut visited = HashSet::from([root]);
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        let mut node_children: Vec<ReflectableUuid> = edges
+            .iter()
+            .filter(|(start, end)| *start == node && rects.contains_key(end))
+            .map(|(_, end)| *end)
+            .filter(|end| visited.insert(*end))
+            .collect();
+        node_children.sort_by(|a, b| {
+            let center = |id| rects.get(id).map_or(0., |rect: &NodeRect| rect.center().1);
+            center(b).total_cmp(&center(a))
+        });
+        stack.extend(node_children.iter().cloned());
+        children.insert(node, node_children);
+    }
+    children
+}
+
+fn subtree_height(
+    node: ReflectableUuid,
+    rects: &HashMap<ReflectableUuid, NodeRect>,
+    children: &HashMap<ReflectableUuid, Vec<ReflectableUuid>>,
+    heights: &mut HashMap<ReflectableUuid, f32>,
+) -> f32 {
+    let node_children = children
+        .get(&node)
+        .map_or(&[][..], |children| &children[..]);
+    let children_height = node_children
+        .iter()
+        .map(|child| subtree_height(*child, rects, children, heights))
+        .sum::<f32>()
+        + SIBLING_GAP * node_children.len().saturating_sub(1) as f32;
+    let height = rects
+        .get(&node)
+        .map_or(0., |rect| rect.height)
+        .max(children_height);
+    heights.insert(node, height);
+    height
+}
+
+/// Positions of the descendants of `root` in a balanced tree growing to the right, each node
+/// is centered next to its children. The root keeps its position, nodes that aren't positioned
+/// in pixels are left out.
+pub fn mind_map_layout(
+    root: ReflectableUuid,
+    rects: &HashMap<ReflectableUuid, NodeRect>,
+    edges: &[(ReflectableUuid, ReflectableUuid)],
+) -> HashMap<ReflectableUuid, NodeRect> {
+    let mut layout = HashMap::new();
+    let root_rect = match rects.get(&root) {
+        Some(rect) => *rect,
+        None => return layout,
+    };
+    let children = tree_children(root, rects, edges);
+    let mut heights = HashMap::new();
+    subtree_height(root, rects, &children, &mut heights);
+    let mut stack = vec![(root, root_rect)];
+    while let Some((node, rect)) = stack.pop() {
+        let node_children: Vec<(ReflectableUuid, NodeRect, f32)> = children
+            .get(&node)
+            .into_iter()
+            .flatten()
+            .filter_map(|child| Some((*child, *rects.get(child)?, *heights.get(child)?)))
+            .collect();
+        let total = node_children
+            .iter()
+            .map(|(_, _, height)| height)
+            .sum::<f32>()
+            + SIBLING_GAP * node_children.len().saturating_sub(1) as f32;
+        let mut top = rect.center().1 + total / 2.;
+        for (child, child_rect, height) in node_children {
+            let center = top - height / 2.;
+            let child_rect = NodeRect {
+                left: rect.left + rect.width + CHILD_GAP,
+                bottom: center - child_rect.height / 2.,
+                ..child_rect
+            };
+            layout.insert(child, child_rect);
+            stack.push((child, child_rect));
+            top -= height + SIBLING_GAP;
+        }
+    }
+    layout
+}
+
+/// Node connected to the current one by an arrow, or one of its siblings, closest in the
+/// direction, e.g. `(-1., 0.)` for left.
+pub fn mind_map_navigate(
+    current: ReflectableUuid,
+    direction: (f32, f32),
+    rects: &HashMap<ReflectableUuid, NodeRect>,
+    edges: &[(ReflectableUuid, ReflectableUuid)],
+) -> Option<ReflectableUuid> {
+    let parent = parent_of(current, edges);
+    let (x, y) = rects.get(&current)?.center();
+    edges
+        .iter()
+        .filter_map(|(start, end)| {
+            if *end == current {
+                Some(*start)
+            } else if *start == current || Some(*start) == parent {
+                Some(*end)
+            } else {
+                None
+            }
+        })
+        .filter(|id| *id != current)
+        .filter_map(|id| {
+            let (other_x, other_y) = rects.get(&id)?.center();
+            let (dx, dy) = (other_x - x, other_y - y);
+            let along = dx * direction.0 + dy * direction.1;
+            let across = (dx * direction.1 - dy * direction.0).abs();
+            // nodes out of line count as further away
+            (along > 0.).then_some((along + 2. * across, id))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, id)| id)
+}
+
+pub fn mind_map_mode_handler(
+    mut run_command: EventReader<RunCommand>,
+    mut mind_map: ResMut<MindMap>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        if *command == VeloCommand::ToggleMindMap {
+            mind_map.enabled = !mind_map.enabled;
+            mind_map.selected = None;
+            info!(
+                "Mind map mode {}",
+                if mind_map.enabled { "on" } else { "off" }
+            );
+        }
+    }
+}
+
+/// Lays out the tree of the selected or edited node, the root keeps its position.
+pub fn mind_map_arrange(
+    mut run_command: EventReader<RunCommand>,
+    mut mind_map: ResMut<MindMap>,
+    ui_state: Res<UiState>,
+    mut containers: Query<(&VeloNodeContainer, &mut Style)>,
+    arrows: Query<(&ArrowMeta, &Visibility)>,
+) {
+    if !run_command
+        .iter()
+        .any(|RunCommand(command)| *command == VeloCommand::ArrangeMindMap)
+    {
+        return;
+    }
+    let current = match ui_state.entity_to_edit.or(mind_map.selected) {
+        Some(id) => id,
+        None => return,
+    };
+    let rects: HashMap<ReflectableUuid, NodeRect> = containers
+        .iter()
+        .filter_map(|(container, style)| Some((container.id, style_rect(style)?)))
+        .collect();
+    let edges: Vec<(ReflectableUuid, ReflectableUuid)> = arrows
+        .iter()
+        .filter(|(_, visibility)| **visibility != Visibility::Hidden)
+        .map(|(arrow, _)| (arrow.start.id, arrow.end.id))
+        .collect();
+    let layout = mind_map_layout(tree_root(current, &edges), &rects, &edges);
+    apply_layout(&layout, &rects, &mut containers, &mut mind_map);
+}
+
+/// Moves the nodes to their position in the layout, their arrows are redrawn afterwards.
+fn apply_layout(
+    layout: &HashMap<ReflectableUuid, NodeRect>,
+    rects: &HashMap<ReflectableUuid, NodeRect>,
+    containers: &mut Query<(&VeloNodeContainer, &mut Style)>,
+    mind_map: &mut MindMap,
+) {
+    for (container, mut style) in containers.iter_mut() {
+        if let Some(rect) = layout.get(&container.id) {
+            if rects.get(&container.id) != Some(rect) {
+                style.position.left = Val::Px(rect.left);
+                style.position.bottom = Val::Px(rect.bottom);
+                mind_map.moved.push(container.id);
+            }
+        }
+    }
+}
+
+pub fn mind_map_keys(
+    mut commands: Commands,
+    mut input: ResMut<Input<KeyCode>>,
+    mut char_events: ResMut<Events<ReceivedCharacter>>,
+    mut mind_map: ResMut<MindMap>,
+    mut ui_state: ResMut<UiState>,
+    mut events: EventWriter<AddRectEvent>,
+    mut containers: Query<(&VeloNodeContainer, &mut Style)>,
+    arrows: Query<(&ArrowMeta, &Visibility)>,
+    palette_query: Query<(), With<CommandPalette>>,
+    theme: Res<Theme>,
+    active_editor: Res<ActiveEditor>,
+    cosmic_edit_query: Query<&CosmicEdit>,
+) {
+    // keys belong to the input method while text is being composed
+    let composing = active_editor
+        .entity
+        .and_then(|entity| cosmic_edit_query.get(entity).ok())
+        .map_or(false, |cosmic_edit| cosmic_edit.preedit.is_some());
+    if !mind_map.enabled
+        || composing
+        || !palette_query.is_empty()
+        || ui_state.modal_id.is_some()
+        || ui_state.tab_to_edit.is_some()
+        || ui_state.doc_to_edit.is_some()
+        || ui_state.search_box_to_edit.is_some()
+    {
+        return;
+    }
+    let (current, editing) = match (ui_state.entity_to_edit, mind_map.selected) {
+        (Some(id), _) => (id, true),
+        (None, Some(id)) => (id, false),
+        (None, None) => return,
+    };
+    let mut rects: HashMap<ReflectableUuid, NodeRect> = containers
+        .iter()
+        .filter_map(|(container, style)| Some((container.id, style_rect(style)?)))
+        .collect();
+    let current_rect = match rects.get(&current) {
+        Some(rect) => *rect,
+        None => {
+            mind_map.selected = None;
+            return;
+        }
+    };
+    let mut edges: Vec<(ReflectableUuid, ReflectableUuid)> = arrows
+        .iter()
+        .filter(|(_, visibility)| **visibility != Visibility::Hidden)
+        .map(|(arrow, _)| (arrow.start.id, arrow.end.id))
+        .collect();
+    let shift = input.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+
+    let parent = if input.just_pressed(KeyCode::Tab) && !shift {
+        input.reset(KeyCode::Tab);
+        Some(current)
+    } else if input.just_pressed(KeyCode::Return) && !shift && !editing {
+        // while a node is edited Enter goes to its text
+        input.reset(KeyCode::Return);
+        Some(parent_of(current, &edges).unwrap_or(current))
+    } else {
+        None
+    };
+    if let Some(parent) = parent {
+        // the key shouldn't be typed into the edited node
+        char_events.clear();
+        let id = ReflectableUuid(Uuid::new_v4());
+        // new children go below their siblings, new siblings right below the current node
+        let bottom = if parent == current {
+            f32::MIN
+        } else {
+            current_rect.center().1 - 0.5 - NODE_HEIGHT / 2.
+        };
+        rects.insert(
+            id,
+            NodeRect {
+                left: 0.,
+                bottom,
+                width: NODE_WIDTH,
+                height: NODE_HEIGHT,
+            },
+        );
+        edges.push((parent, id));
+        // the children of the parent are balanced around it, the rest of the map stays in place
+        let layout = mind_map_layout(parent, &rects, &edges);
+        let rect = match layout.get(&id) {
+            Some(rect) => *rect,
+            None => return,
+        };
+        apply_layout(&layout, &rects, &mut containers, &mut mind_map);
+        events.send(AddRectEvent {
+            node: JsonNode {
+                id: id.0,
+                node_type: NodeType::Rect,
+                left: Val::Px(rect.left),
+                bottom: Val::Px(rect.bottom),
+                width: Val::Px(rect.width),
+                height: Val::Px(rect.height),
+                text: JsonNodeText {
+                    text: "".to_string(),
+                    pos: TextPos::Center,
+                },
+                bg_color: theme.node_bg,
+                z_index: 0,
+            },
+            image: None,
+        });
+        let arrow_type = ui_state.arrow_type;
+        mind_map.pending_arrows.push(CreateArrowEvent {
+            arrow_type,
+            start: ArrowConnect {
+                id: parent,
+                pos: ArrowConnectPos::Right,
+            },
+            end: ArrowConnect {
+                id,
+                pos: ArrowConnectPos::Left,
+            },
+        });
+        mind_map.selected = None;
+        return;
+    }
+
+    if editing {
+        if input.just_pressed(KeyCode::Escape) {
+            ui_state.entity_to_edit = None;
+            commands.insert_resource(ActiveEditor { entity: None });
+            mind_map.selected = Some(current);
+        }
+        return;
+    }
+    if input.just_pressed(KeyCode::F2) {
+        *ui_state = UiState::default();
+        ui_state.entity_to_edit = Some(current);
+        mind_map.selected = None;
+        return;
+    }
+    let direction = if input.just_pressed(KeyCode::Left) {
+        (-1., 0.)
+    } else if input.just_pressed(KeyCode::Right) {
+        (1., 0.)
+    } else if input.just_pressed(KeyCode::Up) {
+        (0., 1.)
+    } else if input.just_pressed(KeyCode::Down) {
+        (0., -1.)
+    } else {
+        return;
+    };
+    if let Some(next) = mind_map_navigate(current, direction, &rects, &edges) {
+        mind_map.selected = Some(next);
+    }
+}
+
+/// Connects nodes added in the previous frame, once their arrow markers exist.
+pub fn mind_map_pending(
+    mut mind_map: ResMut<MindMap>,
+    markers: Query<&ArrowConnect>,
+    mut create_arrow: EventWriter<CreateArrowEvent>,
+    mut redraw_arrow: EventWriter<RedrawArrowEvent>,
+) {
+    if mind_map.moved.is_empty() && mind_map.pending_arrows.is_empty() {
+        return;
+    }
+    for id in std::mem::take(&mut mind_map.moved) {
+        redraw_arrow.send(RedrawArrowEvent { id });
+    }
+    let pending = std::mem::take(&mut mind_map.pending_arrows);
+    for arrow in pending {
+        if markers.iter().any(|marker| *marker == arrow.start)
+            && markers.iter().any(|marker| *marker == arrow.end)
+        {
+            create_arrow.send(arrow);
+        } else {
+            mind_map.pending_arrows.push(arrow);
+        }
+    }
+}
+
+/// Highlights the node selected with the keyboard.
+pub fn mind_map_outline(
+    mind_map: Res<MindMap>,
+    ui_state: Res<UiState>,
+    theme: Res<Theme>,
+    mut velo_node_query: Query<(&mut Outline, &VeloNode)>,
+) {
+    if !(mind_map.is_changed() || ui_state.is_changed()) || ui_state.entity_to_edit.is_some() {
+        return;
+    }
+    for (mut outline, node) in velo_node_query.iter_mut() {
+        if Some(node.id) == mind_map.selected {
+            outline.color = theme.selected_node_border;
+            outline.thickness = UiRect::all(Val::Px(2.));
+        } else {
+            outline.color = match node.node_type {
+                NodeType::Rect => theme.node_border,
+                NodeType::Circle => theme.node_border.with_a(0.),
+            };
+            outline.thickness = UiRect::all(Val::Px(1.));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(left: f32, bottom: f32) -> NodeRect {
+        NodeRect {
+            left,
+            bottom,
+            width: 100.,
+            height: 40.,
+        }
+    }
+
+    fn ids(count: usize) -> Vec<ReflectableUuid> {
+        (0..count).map(|_| ReflectableUuid::generate()).collect()
+    }
+
+    #[test]
+    fn test_mind_map_layout() {
+        let id = ids(5);
+        // root with children 1 (top) and 2, 3 is a child of 1, 4 is new and below 2
+        let rects = HashMap::from([
+            (id[0], rect(0., 500.)),
+            (id[1], rect(300., 900.)),
+            (id[2], rect(300., 100.)),
+            (id[3], rect(600., 0.)),
+            (id[4], rect(0., f32::MIN)),
+        ]);
+        let edges = vec![
+            (id[0], id[1]),
+            (id[0], id[2]),
+            (id[1], id[3]),
+            (id[0], id[4]),
+        ];
+        assert_eq!(tree_root(id[3], &edges), id[0]);
+        let layout = mind_map_layout(id[0], &rects, &edges);
+        assert!(!layout.contains_key(&id[0]));
+        // children are right of their parent
+        assert_eq!(layout[&id[1]].left, 100. + CHILD_GAP);
+        assert_eq!(layout[&id[3]].left, 200. + 2. * CHILD_GAP);
+        // stacked from the top, without overlapping, and centered on the root
+        let centers: Vec<f32> = [1, 2, 4]
+            .iter()
+            .map(|i| layout[&id[*i]].center().1)
+            .collect();
+        assert!(centers[0] > centers[1] && centers[1] > centers[2]);
+        assert_eq!(centers[1], 520.);
+        assert_eq!(centers[0] - centers[1], 40. + SIBLING_GAP);
+        assert_eq!(layout[&id[3]].center().1, centers[0]);
+    }
+
+    #[test]
+    fn test_mind_map_layout_cycle() {
+        let id = ids(3);
+        let rects = HashMap::from([
+            (id[0], rect(0., 0.)),
+            (id[1], rect(0., 0.)),
+            (id[2], rect(0., 0.)),
+        ]);
+        let edges = vec![(id[0], id[1]), (id[1], id[2]), (id[2], id[0])];
+        let root = tree_root(id[1], &edges);
+        let layout = mind_map_layout(root, &rects, &edges);
+        assert_eq!(layout.len(), 2);
+    }
+
+    #[test]
+    fn test_mind_map_layout_subtree() {
+        let id = ids(5);
+        // 4 is a new child of 1, added below its sibling 3
+        let rects = HashMap::from([
+            (id[0], rect(0., 500.)),
+            (id[1], rect(300., 900.)),
+            (id[2], rect(300., 100.)),
+            (id[3], rect(600., 950.)),
+            (id[4], rect(0., f32::MIN)),
+        ]);
+        let edges = vec![
+            (id[0], id[1]),
+            (id[0], id[2]),
+            (id[1], id[3]),
+            (id[1], id[4]),
+        ];
+        let layout = mind_map_layout(id[1], &rects, &edges);
+        // only the children of the parent move
+        assert_eq!(layout.len(), 2);
+        assert_eq!(layout[&id[4]].left, 400. + CHILD_GAP);
+        assert!(layout[&id[3]].center().1 > layout[&id[4]].center().1);
+        let middle = (layout[&id[3]].center().1 + layout[&id[4]].center().1) / 2.;
+        assert_eq!(middle, rects[&id[1]].center().1);
+    }
+
+    #[test]
+    fn test_mind_map_layout_missing_rect() {
+        let id = ids(3);
+        // 1 isn't positioned in pixels
+        let rects = HashMap::from([(id[0], rect(0., 0.)), (id[2], rect(0., 0.))]);
+        let edges = vec![(id[0], id[1]), (id[1], id[2]), (id[0], id[2])];
+        let layout = mind_map_layout(id[0], &rects, &edges);
+        assert!(!layout.contains_key(&id[1]));
+        assert_eq!(layout[&id[2]].left, 100. + CHILD_GAP);
+        assert!(mind_map_layout(id[1], &rects, &edges).is_empty());
+    }
+
+    #[test]
+    fn test_mind_map_navigate() {
+        let id = ids(5);
+        let rects = HashMap::from([
+            (id[0], rect(0., 500.)),
+            (id[1], rect(300., 560.)),
+            (id[2], rect(300., 440.)),
+            (id[3], rect(600., 560.)),
+            // not connected
+            (id[4], rect(300., 620.)),
+        ]);
+        let edges = vec![(id[0], id[1]), (id[0], id[2]), (id[1], id[3])];
+        assert_eq!(
+            mind_map_navigate(id[1], (-1., 0.), &rects, &edges),
+            Some(id[0])
+        );
+        assert_eq!(
+            mind_map_navigate(id[1], (1., 0.), &rects, &edges),
+            Some(id[3])
+        );
+        assert_eq!(
+            mind_map_navigate(id[1], (0., -1.), &rects, &edges),
+            Some(id[2])
+        );
+        assert_eq!(
+            mind_map_navigate(id[2], (0., 1.), &rects, &edges),
+            Some(id[1])
+        );
+        assert_eq!(mind_map_navigate(id[1], (0., 1.), &rects, &edges), None);
+        assert_eq!(
+            mind_map_navigate(id[0], (1., 0.), &rects, &edges),
+            Some(id[1])
+        );
+    }
+}
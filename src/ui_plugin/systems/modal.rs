@@ -1,20 +1,38 @@
 use std::collections::HashMap;
-use std::fs::canonicalize;
 use std::path::PathBuf;
 
-use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
 use bevy::tasks::IoTaskPool;
-use bevy_cosmic_edit::{get_cosmic_text, ActiveEditor, CosmicEdit};
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_cosmic_edit::{get_cosmic_text, ActiveEditor, CosmicEdit, CosmicFont};
 use bevy_pkv::PkvStore;
-use linkify::{LinkFinder, LinkKind};
 
-use super::ui_helpers::{ModalCancel, ModalConfirm, ModalTop};
-use super::{CommChannels, EditableText, ModalAction, TabContainer};
+use super::ui_helpers::{
+    spawn_modal, ConflictResolution, ImportConflictButton, ImportConflictDoc, ModalCancel,
+    ModalConfirm, ModalStatus, ModalTop,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use super::{import_dot_tab, request_dot_export};
+use super::{
+    load_history, request_board_csv, request_board_edit, request_version, CommChannels,
+    EditableText, ImportResult, MainPanel, ModalAction, PendingImport, SaveStoreEvent,
+    ShareRequest, TabContainer,
+};
 use crate::components::Doc;
-use crate::resources::{AppState, LoadDocRequest, LoadTabRequest, SaveDocRequest};
-use crate::utils::ReflectableUuid;
+use crate::config::{Config, ImportConfig};
+use crate::diff::diff_docs;
+use crate::history::{history_key, Version};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::import::read_document_file;
+use crate::import::{fetch_document, merge_docs, parse_import_url, remap_ids, ImportError};
+use crate::resources::{AppState, FontSystemState, LoadDocRequest, LoadTabRequest, SaveDocRequest};
+use crate::themes::Theme;
+use crate::utils::{load_doc_to_memory, parse_tags, ReflectableUuid};
 use crate::UiState;
 
+/// Lines of the document diff shown in the import conflict modal.
+const MAX_CONFLICT_LINES: usize = 5;
+
 pub fn cancel_modal(
     mut commands: Commands,
     mut interaction_query: Query<
@@ -22,6 +40,7 @@ pub fn cancel_modal(
         (Changed<Interaction>, With<ModalCancel>),
     >,
     mut state: ResMut<UiState>,
+    mut pending_import: ResMut<PendingImport>,
     query: Query<(Entity, &ModalTop), With<ModalTop>>,
 ) {
     for (interaction, path_modal_cancel) in interaction_query.iter_mut() {
@@ -30,6 +49,9 @@ pub fn cancel_modal(
                 if path_modal_cancel.id == path_modal_top.id {
                     commands.entity(entity).despawn_recursive();
                     state.modal_id = None;
+                    if pending_import.modal_id == Some(path_modal_cancel.id) {
+                        pending_import.modal_id = None;
+                    }
                 }
             }
         }
@@ -120,33 +142,338 @@ fn delete_tab(
     });
 }
 
+/// Starts loading a document for an import modal, which stays open until the result arrives.
+fn start_import(
+    action: &ModalAction,
+    text: &str,
+    modal_id: ReflectableUuid,
+    comm_channels: &CommChannels,
+    config: &ImportConfig,
+    pending_import: &mut PendingImport,
+    time: &Time,
+) {
+    pending_import.modal_id = Some(modal_id);
+    pending_import.started_at = time.elapsed_seconds_f64();
+    let tx = comm_channels.tx.clone();
+    let send = move |result| {
+        let _ = tx.try_send(ImportResult {
+            modal_id: Some(modal_id),
+            result,
+        });
+    };
+    match action {
+        #[cfg(not(target_arch = "wasm32"))]
+        ModalAction::LoadFromFile => send(read_document_file(
+            std::path::Path::new(text.trim()),
+            config.max_size,
+        )),
+        _ => match parse_import_url(text) {
+            Ok(url) => fetch_document(url, config, Box::new(send)),
+            Err(e) => send(Err(e)),
+        },
+    }
+}
+
+pub fn import_progress(
+    mut pending_import: ResMut<PendingImport>,
+    mut status_query: Query<(&mut Text, &ModalStatus)>,
+    config: Res<Config>,
+    time: Res<Time>,
+) {
+    let modal_id = match pending_import.modal_id {
+        Some(modal_id) => modal_id,
+        None => return,
+    };
+    let elapsed = time.elapsed_seconds_f64() - pending_import.started_at;
+    let status = if elapsed > config.import.timeout as f64 {
+        // a late result is ignored
+        pending_import.modal_id = None;
+        ImportError::TimedOut {
+            seconds: config.import.timeout,
+        }
+        .to_string()
+    } else {
+        format!(
+            "Loading{} {}s",
+            ".".repeat((elapsed * 2.) as usize % 4),
+            elapsed as u64
+        )
+    };
+    for (mut text, modal_status) in status_query.iter_mut() {
+        if modal_status.id == modal_id && text.sections[0].value != status {
+            text.sections[0].value = status.clone();
+        }
+    }
+}
+
+fn edit_tags(app_state: &mut ResMut<AppState>, commands: &mut Commands, text: &str) {
+    let doc_id = app_state.current_document.unwrap();
+    if let Some(doc) = app_state.docs.get_mut(&doc_id) {
+        doc.tags = parse_tags(text);
+    }
+    commands.insert_resource(SaveDocRequest { doc_id, path: None });
+}
+
+/// Imports the DOT file and returns whether it was imported, errors are shown in the modal.
+#[cfg(not(target_arch = "wasm32"))]
+fn import_dot(
+    commands: &mut Commands,
+    app_state: &mut AppState,
+    theme: &Theme,
+    path: &str,
+    modal_id: ReflectableUuid,
+    status_query: &mut Query<(&mut Text, &ModalStatus)>,
+) -> bool {
+    match import_dot_tab(commands, app_state, theme, path) {
+        Ok(()) => true,
+        Err(e) => {
+            error!("Failed to import DOT file: {}", e);
+            show_modal_error(status_query, modal_id, e);
+            false
+        }
+    }
+}
+
+/// Changes the board of the tab and returns whether the columns were valid, errors are shown in
+/// the modal.
+fn edit_board(
+    commands: &mut Commands,
+    text: &str,
+    modal_id: ReflectableUuid,
+    status_query: &mut Query<(&mut Text, &ModalStatus)>,
+) -> bool {
+    match request_board_edit(commands, text) {
+        Ok(()) => true,
+        Err(e) => {
+            show_modal_error(status_query, modal_id, e);
+            false
+        }
+    }
+}
+
+/// Closes a modal that was kept open until its work finished.
+pub fn close_modal(
+    commands: &mut Commands,
+    query_top: &Query<(Entity, &ModalTop), With<ModalTop>>,
+    ui_state: &mut UiState,
+    modal_id: ReflectableUuid,
+) {
+    for (entity, modal_top) in query_top.iter() {
+        if modal_top.id == modal_id {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    if ui_state.modal_id == Some(modal_id) {
+        ui_state.modal_id = None;
+        commands.insert_resource(ActiveEditor { entity: None });
+    }
+}
+
+pub fn show_modal_error(
+    status_query: &mut Query<(&mut Text, &ModalStatus)>,
+    modal_id: ReflectableUuid,
+    error: String,
+) {
+    for (mut text, status) in status_query.iter_mut() {
+        if status.id == modal_id {
+            text.sections[0].value = error.clone();
+        }
+    }
+}
+
 pub fn load_doc_handler(
     mut commands: Commands,
     mut app_state: ResMut<AppState>,
+    mut ui_state: ResMut<UiState>,
+    mut pending_import: ResMut<PendingImport>,
     comm_channels: Res<CommChannels>,
     pkv: Res<PkvStore>,
+    query_top: Query<(Entity, &ModalTop), With<ModalTop>>,
+    mut status_query: Query<(&mut Text, &ModalStatus)>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
 ) {
-    if comm_channels.rx.is_empty() {
-        return;
+    while let Ok(ImportResult { modal_id, result }) = comm_channels.rx.try_recv() {
+        if let Some(modal_id) = modal_id {
+            if pending_import.modal_id != Some(modal_id) {
+                // the modal was closed or the import timed out
+                continue;
+            }
+            pending_import.modal_id = None;
+        }
+        let import_document = match result {
+            Ok(import_document) => import_document,
+            Err(e) => {
+                error!("Failed to import document: {}", e);
+                for (mut text, status) in status_query.iter_mut() {
+                    if Some(status.id) == modal_id {
+                        text.sections[0].value = e.to_string();
+                    }
+                }
+                continue;
+            }
+        };
+        if let Some(modal_id) = modal_id {
+            for (entity, modal_top) in query_top.iter() {
+                if modal_top.id == modal_id {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+            if ui_state.modal_id == Some(modal_id) {
+                ui_state.modal_id = None;
+                commands.insert_resource(ActiveEditor { entity: None });
+            }
+        }
+        let local = app_state
+            .docs
+            .get(&import_document.id)
+            .cloned()
+            .or_else(|| {
+                pkv.get::<HashMap<ReflectableUuid, Doc>>("docs")
+                    .ok()
+                    .and_then(|mut docs| docs.remove(&import_document.id))
+            });
+        let local = match local {
+            Some(local) => local,
+            None => {
+                open_imported_doc(&mut commands, &mut app_state, import_document);
+                continue;
+            }
+        };
+        let changes = match diff_docs(&local, &import_document) {
+            Ok(diff) => diff.to_string(),
+            Err(e) => format!("Failed to compare documents: {}", e),
+        };
+        let mut lines: Vec<&str> = changes.lines().take(MAX_CONFLICT_LINES + 1).collect();
+        if lines.len() > MAX_CONFLICT_LINES {
+            lines[MAX_CONFLICT_LINES] = "...";
+        }
+        let id = ReflectableUuid::generate();
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            windows.single(),
+            id,
+            ModalAction::ImportConflict,
+            Some(format!(
+                "Document \"{}\" already exists. Changes in the import:\n{}",
+                local.name,
+                lines.join("\n")
+            )),
+        );
+        commands
+            .entity(entity)
+            .insert(ImportConflictDoc(import_document));
+        commands.entity(main_panel_query.single()).add_child(entity);
+    }
+}
+
+fn open_imported_doc(commands: &mut Commands, app_state: &mut AppState, doc: Doc) {
+    // a document replaced by the import isn't saved, it would overwrite the import
+    if let Some(current_document) = app_state.current_document.filter(|id| *id != doc.id) {
+        commands.insert_resource(SaveDocRequest {
+            doc_id: current_document,
+            path: None,
+        });
     }
-    let r = comm_channels
-        .rx
-        .try_recv()
-        .expect("Failed to receive document string");
-    let import_document: Doc = serde_json::from_str(&r).expect("Failed to deserialize document");
-    if let Ok(docs) = pkv.get::<HashMap<ReflectableUuid, Doc>>("docs") {
-        if docs.contains_key(&import_document.id) {
-            return;
+    app_state.current_document = Some(doc.id);
+    app_state.doc_list_ui.insert(doc.id);
+    commands.insert_resource(LoadDocRequest { doc_id: doc.id });
+    app_state.docs.insert(doc.id, doc);
+}
+
+pub fn import_conflict_click(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &ImportConflictButton), Changed<Interaction>>,
+    query_top: Query<(Entity, &ImportConflictDoc, &ModalTop)>,
+    mut app_state: ResMut<AppState>,
+    mut ui_state: ResMut<UiState>,
+    mut pkv: ResMut<PkvStore>,
+    mut events: EventWriter<SaveStoreEvent>,
+    config: Res<Config>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        for (entity, ImportConflictDoc(incoming), modal_top) in query_top.iter() {
+            if modal_top.id != button.id {
+                continue;
+            }
+            commands.entity(entity).despawn_recursive();
+            ui_state.modal_id = None;
+            let mut summary = None;
+            let doc = match button.resolution {
+                ConflictResolution::Overwrite => {
+                    load_doc_to_memory(
+                        incoming.id,
+                        &mut app_state,
+                        &mut pkv,
+                        config.max_docs_in_memory,
+                    );
+                    // shared documents are published again at the same place
+                    let shared = app_state
+                        .docs
+                        .get(&incoming.id)
+                        .and_then(|local| local.shared.clone());
+                    Doc {
+                        shared,
+                        ..incoming.clone()
+                    }
+                }
+                ConflictResolution::ImportAsNew => Doc {
+                    name: format!("{} (imported)", incoming.name),
+                    ..remap_ids(incoming)
+                },
+                ConflictResolution::Merge => {
+                    load_doc_to_memory(
+                        incoming.id,
+                        &mut app_state,
+                        &mut pkv,
+                        config.max_docs_in_memory,
+                    );
+                    match app_state.docs.get(&incoming.id) {
+                        Some(local) => {
+                            let (merged, merge_summary) = merge_docs(local, incoming);
+                            summary = Some(merge_summary);
+                            merged
+                        }
+                        None => incoming.clone(),
+                    }
+                }
+            };
+            let doc_id = doc.id;
+            open_imported_doc(&mut commands, &mut app_state, doc);
+            // the previous version is replaced in storage right away
+            events.send(SaveStoreEvent { doc_id, path: None });
+            if let Some(summary) = summary {
+                let id = ReflectableUuid::generate();
+                ui_state.modal_id = Some(id);
+                let entity = spawn_modal(
+                    &mut commands,
+                    &theme,
+                    &mut cosmic_fonts,
+                    font_system_state.0.clone().unwrap(),
+                    windows.single(),
+                    id,
+                    ModalAction::Message,
+                    Some(summary.to_string()),
+                );
+                commands.entity(main_panel_query.single()).add_child(entity);
+            }
         }
     }
-    app_state.current_document = Some(import_document.id);
-    app_state.doc_list_ui.insert(import_document.id);
-    app_state
-        .docs
-        .insert(import_document.id, import_document.clone());
-    commands.insert_resource(LoadDocRequest {
-        doc_id: import_document.id,
-    });
 }
 
 pub fn confirm_modal(
@@ -163,10 +490,16 @@ pub fn confirm_modal(
     input: Res<Input<KeyCode>>,
     mut query_path: Query<(&CosmicEdit, &EditableText), With<EditableText>>,
     comm_channels: Res<CommChannels>,
+    mut pending_import: ResMut<PendingImport>,
+    config: Res<Config>,
+    time: Res<Time>,
+    theme: Res<Theme>,
+    mut status_query: Query<(&mut Text, &ModalStatus)>,
 ) {
     for (interaction, path_modal_confirm) in interaction_query.iter_mut() {
         if let Interaction::Clicked = interaction {
             for (entity, path_modal_top) in query_top.iter() {
+                let mut keep_open = false;
                 if path_modal_confirm.id == path_modal_top.id {
                     for (editor, editable_text) in query_path.iter_mut() {
                         let text = get_cosmic_text(&editor.editor);
@@ -179,35 +512,69 @@ pub fn confirm_modal(
                                     });
                                     break;
                                 }
-                                ModalAction::LoadFromFile => {
-                                    if let Ok(path) = canonicalize(PathBuf::from(text.trim())) {
-                                        let json = std::fs::read_to_string(path)
-                                            .expect("Error reading document from file");
-                                        let cc = comm_channels.tx.clone();
-                                        cc.try_send(json).unwrap()
-                                    }
+                                ModalAction::LoadFromFile | ModalAction::LoadFromUrl => {
+                                    start_import(
+                                        &path_modal_confirm.action,
+                                        &text,
+                                        path_modal_top.id,
+                                        &comm_channels,
+                                        &config.import,
+                                        &mut pending_import,
+                                        &time,
+                                    );
+                                    keep_open = true;
                                 }
-                                ModalAction::LoadFromUrl => {
-                                    let pool = IoTaskPool::get();
-                                    let url = text.trim();
-                                    let mut finder = LinkFinder::new();
-                                    finder.kinds(&[LinkKind::Url]);
-                                    let links: Vec<_> = finder.links(url).collect();
-                                    if links.len() == 1 {
-                                        let url = links.first().unwrap().as_str().to_owned();
-                                        let cc = comm_channels.tx.clone();
-                                        let task = pool.spawn(async move {
-                                            let request = ehttp::Request::get(url);
-                                            ehttp::fetch(request, move |result| {
-                                                let json_string = result.unwrap().text().unwrap();
-                                                cc.try_send(json_string).unwrap();
-                                            });
-                                        });
-                                        task.detach();
+                                #[cfg(not(target_arch = "wasm32"))]
+                                ModalAction::ImportDot => {
+                                    keep_open = !import_dot(
+                                        &mut commands,
+                                        &mut app_state,
+                                        &theme,
+                                        &text,
+                                        path_modal_top.id,
+                                        &mut status_query,
+                                    );
+                                }
+                                #[cfg(not(target_arch = "wasm32"))]
+                                ModalAction::ExportDot => {
+                                    // closed once the file is written
+                                    keep_open = true;
+                                    if let Err(e) = request_dot_export(
+                                        &mut commands,
+                                        &app_state,
+                                        &text,
+                                        path_modal_top.id,
+                                    ) {
+                                        show_modal_error(&mut status_query, path_modal_top.id, e);
                                     }
                                 }
+                                #[cfg(target_arch = "wasm32")]
+                                ModalAction::ImportDot | ModalAction::ExportDot => {}
+                                ModalAction::EditBoard => {
+                                    keep_open = !edit_board(
+                                        &mut commands,
+                                        &text,
+                                        path_modal_top.id,
+                                        &mut status_query,
+                                    );
+                                }
+                                ModalAction::ExportBoardCsv => {
+                                    // closed once the file is written
+                                    keep_open = true;
+                                    request_board_csv(&mut commands, &text, path_modal_top.id);
+                                }
+                                ModalAction::EditTags => {
+                                    edit_tags(&mut app_state, &mut commands, &text);
+                                }
+                                ModalAction::SaveVersion => {
+                                    request_version(&mut commands, &app_state, &text);
+                                }
                                 ModalAction::DeleteDocument => {}
                                 ModalAction::DeleteTab => {}
+                                ModalAction::Message => {}
+                                ModalAction::ImportConflict => {}
+                                ModalAction::ShareDocument { .. } => {}
+                                ModalAction::UnshareDocument => {}
                             }
                         }
                     }
@@ -215,6 +582,19 @@ pub fn confirm_modal(
                         ModalAction::SaveToFile => {}
                         ModalAction::LoadFromFile => {}
                         ModalAction::LoadFromUrl => {}
+                        ModalAction::ImportDot => {}
+                        ModalAction::ExportDot => {}
+                        ModalAction::EditBoard => {}
+                        ModalAction::ExportBoardCsv => {}
+                        ModalAction::EditTags => {}
+                        ModalAction::Message => {}
+                        ModalAction::ImportConflict => {}
+                        ModalAction::SaveVersion => {}
+                        // only closed by its buttons
+                        ModalAction::ShareDocument { .. } => {}
+                        ModalAction::UnshareDocument => {
+                            commands.insert_resource(ShareRequest::Unshare);
+                        }
                         ModalAction::DeleteDocument => {
                             delete_doc(&mut app_state, &mut commands, &mut pkv);
                         }
@@ -223,6 +603,9 @@ pub fn confirm_modal(
                         }
                     }
                 }
+                if keep_open {
+                    continue;
+                }
                 commands.entity(entity).despawn_recursive();
                 ui_state.modal_id = None;
                 commands.insert_resource(ActiveEditor { entity: None });
@@ -231,6 +614,7 @@ pub fn confirm_modal(
     }
     if input.just_pressed(KeyCode::Return) {
         for (entity, path_modal_top) in query_top.iter() {
+            let mut keep_open = false;
             if Some(path_modal_top.id) == ui_state.modal_id {
                 for (editor, editable_text) in query_path.iter_mut() {
                     let text = get_cosmic_text(&editor.editor);
@@ -243,35 +627,69 @@ pub fn confirm_modal(
                                 });
                                 break;
                             }
-                            ModalAction::LoadFromFile => {
-                                if let Ok(path) = canonicalize(PathBuf::from(text.trim())) {
-                                    let json = std::fs::read_to_string(path)
-                                        .expect("Error reading document from file");
-                                    let cc = comm_channels.tx.clone();
-                                    cc.try_send(json).unwrap()
-                                }
+                            ModalAction::LoadFromFile | ModalAction::LoadFromUrl => {
+                                start_import(
+                                    &path_modal_top.action,
+                                    &text,
+                                    path_modal_top.id,
+                                    &comm_channels,
+                                    &config.import,
+                                    &mut pending_import,
+                                    &time,
+                                );
+                                keep_open = true;
                             }
-                            ModalAction::LoadFromUrl => {
-                                let pool = IoTaskPool::get();
-                                let url = text.trim();
-                                let mut finder = LinkFinder::new();
-                                finder.kinds(&[LinkKind::Url]);
-                                let links: Vec<_> = finder.links(url).collect();
-                                if links.len() == 1 {
-                                    let url = links.first().unwrap().as_str().to_owned();
-                                    let cc = comm_channels.tx.clone();
-                                    let task = pool.spawn(async move {
-                                        let request = ehttp::Request::get(url);
-                                        ehttp::fetch(request, move |result| {
-                                            let json_string = result.unwrap().text().unwrap();
-                                            cc.try_send(json_string).unwrap();
-                                        });
-                                    });
-                                    task.detach();
+                            #[cfg(not(target_arch = "wasm32"))]
+                            ModalAction::ImportDot => {
+                                keep_open = !import_dot(
+                                    &mut commands,
+                                    &mut app_state,
+                                    &theme,
+                                    &text,
+                                    path_modal_top.id,
+                                    &mut status_query,
+                                );
+                            }
+                            #[cfg(not(target_arch = "wasm32"))]
+                            ModalAction::ExportDot => {
+                                // closed once the file is written
+                                keep_open = true;
+                                if let Err(e) = request_dot_export(
+                                    &mut commands,
+                                    &app_state,
+                                    &text,
+                                    path_modal_top.id,
+                                ) {
+                                    show_modal_error(&mut status_query, path_modal_top.id, e);
                                 }
                             }
+                            #[cfg(target_arch = "wasm32")]
+                            ModalAction::ImportDot | ModalAction::ExportDot => {}
+                            ModalAction::EditBoard => {
+                                keep_open = !edit_board(
+                                    &mut commands,
+                                    &text,
+                                    path_modal_top.id,
+                                    &mut status_query,
+                                );
+                            }
+                            ModalAction::ExportBoardCsv => {
+                                // closed once the file is written
+                                keep_open = true;
+                                request_board_csv(&mut commands, &text, path_modal_top.id);
+                            }
+                            ModalAction::EditTags => {
+                                edit_tags(&mut app_state, &mut commands, &text);
+                            }
+                            ModalAction::SaveVersion => {
+                                request_version(&mut commands, &app_state, &text);
+                            }
                             ModalAction::DeleteDocument => {}
                             ModalAction::DeleteTab => {}
+                            ModalAction::Message => {}
+                            ModalAction::ImportConflict => {}
+                            ModalAction::ShareDocument { .. } => {}
+                            ModalAction::UnshareDocument => {}
                         }
                     }
                 }
@@ -279,6 +697,21 @@ pub fn confirm_modal(
                     ModalAction::SaveToFile => {}
                     ModalAction::LoadFromFile => {}
                     ModalAction::LoadFromUrl => {}
+                    ModalAction::ImportDot => {}
+                    ModalAction::ExportDot => {}
+                    ModalAction::EditBoard => {}
+                    ModalAction::ExportBoardCsv => {}
+                    ModalAction::EditTags => {}
+                    ModalAction::Message => {}
+                    // only closed by its buttons
+                    ModalAction::ImportConflict => keep_open = true,
+                    ModalAction::SaveVersion => {}
+                    ModalAction::ShareDocument { public } => {
+                        commands.insert_resource(ShareRequest::Share { public });
+                    }
+                    ModalAction::UnshareDocument => {
+                        commands.insert_resource(ShareRequest::Unshare);
+                    }
                     ModalAction::DeleteDocument => {
                         delete_doc(&mut app_state, &mut commands, &mut pkv);
                     }
@@ -287,6 +720,9 @@ pub fn confirm_modal(
                     }
                 }
             }
+            if keep_open {
+                continue;
+            }
             commands.entity(entity).despawn_recursive();
             ui_state.modal_id = None;
             commands.insert_resource(ActiveEditor { entity: None });
@@ -314,6 +750,10 @@ fn remove_from_storage(
             pkv.set("names", &names).unwrap();
         }
     }
+    if !load_history(pkv, id_to_remove).is_empty() {
+        pkv.set(&history_key(id_to_remove), &Vec::<Version>::new())
+            .unwrap();
+    }
     if let Ok(last_saved) = pkv.get::<ReflectableUuid>("last_saved") {
         if last_saved == id_to_remove {
             pkv.set("last_saved", &new_id).unwrap();
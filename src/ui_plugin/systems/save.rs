@@ -6,16 +6,20 @@ use bevy_pkv::PkvStore;
 use image::*;
 
 use serde_json::json;
-use std::{collections::HashMap, io::Cursor};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Cursor,
+};
 
 use super::ui_helpers::VeloNode;
-use super::{RawText, SaveStoreEvent, VeloNodeContainer};
+use super::{InlineImage, InlineImages, RawText, SaveStoreEvent, TabBoard, VeloNodeContainer};
 use crate::canvas::arrow::components::ArrowMeta;
 use crate::components::Doc;
+use crate::config::Config;
 use crate::resources::SaveDocRequest;
 use crate::resources::{AppState, SaveTabRequest};
-use crate::utils::{load_doc_to_memory, ReflectableUuid};
-use crate::{ui_plugin::ui_helpers::style_to_pos, JsonNode, JsonNodeText, MAX_CHECKPOINTS};
+use crate::utils::{dedup_tags, load_doc_to_memory, ReflectableUuid};
+use crate::{ui_plugin::ui_helpers::style_to_pos, JsonNode, JsonNodeText};
 
 pub fn should_save_doc(request: Option<Res<SaveDocRequest>>) -> bool {
     request.is_some()
@@ -39,10 +43,11 @@ pub fn save_doc(
     mut pkv: ResMut<PkvStore>,
     mut commands: Commands,
     mut events: EventWriter<SaveStoreEvent>,
+    config: Res<Config>,
 ) {
     let doc_id = request.doc_id;
 
-    load_doc_to_memory(doc_id, &mut app_state, &mut pkv);
+    load_doc_to_memory(doc_id, &mut app_state, &mut pkv, config.max_docs_in_memory);
 
     for tab in app_state.docs.get_mut(&doc_id).unwrap().tabs.iter() {
         if tab.is_active {
@@ -74,18 +79,14 @@ pub fn save_to_store(
             docs.insert(doc_id, app_state.docs.get(&doc_id).unwrap().clone());
             pkv.set("docs", &docs).unwrap();
         }
+        let doc_tags = dedup_tags(&app_state.docs.get(&doc_id).unwrap().tags);
+        app_state.docs.get_mut(&doc_id).unwrap().tags = doc_tags.clone();
         if let Ok(mut tags) = pkv.get::<HashMap<ReflectableUuid, Vec<String>>>("tags") {
-            let doc = app_state.docs.get(&doc_id).unwrap();
-            if let Some(tags) = tags.get_mut(&doc_id) {
-                tags.append(&mut doc.tags.clone());
-            } else {
-                tags.insert(doc.id, doc.tags.clone());
-            }
+            tags.insert(doc_id, doc_tags.clone());
             pkv.set("tags", &tags).unwrap();
         } else {
-            let doc = app_state.docs.get(&doc_id).unwrap();
             let mut tags = HashMap::new();
-            tags.insert(doc.id, doc.tags.clone());
+            tags.insert(doc_id, doc_tags.clone());
             pkv.set("tags", &tags).unwrap();
         }
         if let Ok(mut names) = pkv.get::<HashMap<ReflectableUuid, String>>("names") {
@@ -117,6 +118,7 @@ pub fn save_to_store(
                 pool.spawn(async move {
                     let _ = super::clear_tabs_index(&index, &tabs_to_delete);
                     let _ = super::update_search_index(&index, &node_updates);
+                    let _ = super::update_doc_tags_index(&index, &doc_id.0, &doc_tags);
                 })
                 .detach();
             }
@@ -135,6 +137,9 @@ pub fn save_tab(
     request: Res<SaveTabRequest>,
     mut app_state: ResMut<AppState>,
     text_query: Query<(&RawText, &CosmicEdit), With<RawText>>,
+    config: Res<Config>,
+    inline_images: Res<InlineImages>,
+    tab_board: Res<TabBoard>,
 ) {
     #[cfg(not(target_arch = "wasm32"))]
     if let Some(index) = &mut app_state.search_index {
@@ -158,6 +163,9 @@ pub fn save_tab(
             }
         }
     }
+    if let Some(board) = &tab_board.board {
+        json["board"] = json!(board);
+    }
 
     let json_nodes = json["nodes"].as_array_mut().unwrap();
     for (node, _, z_index, parent, test_pos_style) in node_query.iter() {
@@ -212,19 +220,64 @@ pub fn save_tab(
     }
 
     let doc_id = request.doc_id;
+    let doc = app_state.docs.get_mut(&doc_id).unwrap();
 
-    for tab in &mut app_state.docs.get_mut(&doc_id).unwrap().tabs {
-        if request.tab_id == tab.id {
-            if (tab.checkpoints.len() as i32) > MAX_CHECKPOINTS {
-                tab.checkpoints.pop_front();
+    // inline images are kept by source, the ones used by the tab are stored with the document
+    for (src, image) in inline_images.images.iter() {
+        if let InlineImage::Loaded { data, .. } = image {
+            if text_query
+                .iter()
+                .any(|(raw_text, _)| raw_text.last_text.contains(src.as_str()))
+            {
+                doc.inline_images.insert(src.clone(), data.clone());
             }
-            if let Some(last) = tab.checkpoints.back() {
-                if last == &json.to_string() {
-                    break;
-                }
-            }
-            tab.checkpoints.push_back(json.to_string());
-            break;
+        }
+    }
+
+    if let Some(tab) = doc.tabs.iter_mut().find(|tab| tab.id == request.tab_id) {
+        push_checkpoint(
+            &mut tab.checkpoints,
+            json.to_string(),
+            config.checkpoint_depth,
+        );
+    }
+    // images no checkpoint refers to anymore are dropped
+    let tabs = &doc.tabs;
+    doc.inline_images.retain(|src, _| {
+        tabs.iter().any(|tab| {
+            tab.checkpoints
+                .iter()
+                .any(|json| json.contains(src.as_str()))
+        })
+    });
+}
+
+/// Adds a checkpoint unless it's the same as the last one, keeping at most `depth` of them.
+fn push_checkpoint(checkpoints: &mut VecDeque<String>, checkpoint: String, depth: usize) {
+    if checkpoints.back() == Some(&checkpoint) {
+        return;
+    }
+    while checkpoints.len() >= depth.max(1) {
+        checkpoints.pop_front();
+    }
+    checkpoints.push_back(checkpoint);
+}
+
+pub fn autosave(
+    mut commands: Commands,
+    config: Res<Config>,
+    app_state: Res<AppState>,
+    time: Res<Time>,
+    mut elapsed: Local<f32>,
+) {
+    if let Some(interval) = config.autosave_interval {
+        *elapsed += time.delta_seconds();
+        if *elapsed < interval as f32 {
+            return;
+        }
+        *elapsed = 0.;
+        if let Some(doc_id) = app_state.current_document {
+            commands.insert_resource(SaveDocRequest { doc_id, path: None });
         }
     }
 }
@@ -257,6 +310,8 @@ mod tests {
                     name: "Test tab".to_string(),
                     checkpoints: std::collections::VecDeque::new(),
                 }],
+                shared: None,
+                inline_images: HashMap::new(),
             },
         );
         let request = SaveDocRequest {
@@ -268,6 +323,7 @@ mod tests {
         PkvStore::new("test", "test").clear().unwrap();
         app.insert_resource(PkvStore::new("test", "test"));
         app.insert_resource(app_state);
+        app.init_resource::<Config>();
 
         // Run systems
         app.update();
@@ -314,6 +370,8 @@ mod tests {
                     name: "Test tab".to_string(),
                     checkpoints: std::collections::VecDeque::new(),
                 }],
+                shared: None,
+                inline_images: HashMap::new(),
             },
         );
         let request = SaveDocRequest {
@@ -329,6 +387,7 @@ mod tests {
         app.add_event::<SaveStoreEvent>();
         app.insert_resource(pkv);
         app.insert_resource(app_state);
+        app.init_resource::<Config>();
 
         // Run systems
         app.update();
@@ -353,7 +412,8 @@ mod tests {
     }
 
     #[test]
-    /// the PKV store already has tags for the document being saved.
+    /// the PKV store already has tags for the document being saved,
+    /// stored tags are replaced by the deduplicated document tags.
     fn test_save_doc3() {
         // Setup
         let mut app = App::new();
@@ -363,19 +423,24 @@ mod tests {
         let doc_id = ReflectableUuid::generate();
         let tab_id = ReflectableUuid::generate();
         let mut app_state = AppState::default();
-        let existing_tags = vec!["test_tag_2".to_string(), "test_tag_1".to_string()];
         app_state.docs.insert(
             doc_id,
             Doc {
                 id: doc_id,
                 name: "test_doc".to_string(),
-                tags: vec!["test_tag_1".to_string()],
+                tags: vec![
+                    "test_tag_1".to_string(),
+                    "test_tag_3".to_string(),
+                    "#Test_Tag_1".to_string(),
+                ],
                 tabs: vec![crate::components::Tab {
                     id: tab_id,
                     is_active: true,
                     name: "Test tab".to_string(),
                     checkpoints: std::collections::VecDeque::new(),
                 }],
+                shared: None,
+                inline_images: HashMap::new(),
             },
         );
         let request = SaveDocRequest {
@@ -391,6 +456,7 @@ mod tests {
         app.add_event::<SaveStoreEvent>();
         app.insert_resource(pkv);
         app.insert_resource(app_state);
+        app.init_resource::<Config>();
 
         // Run systems
         app.update();
@@ -403,7 +469,13 @@ mod tests {
         assert!(saved_docs.get(&doc_id).unwrap().tabs[0].is_active);
         // Check that the tags were saved to the PKV store
         let saved_tags: HashMap<ReflectableUuid, Vec<String>> = pkv.get("tags").unwrap();
-        let expected_tags = existing_tags;
+        let expected_tags = vec!["test_tag_1".to_string(), "test_tag_3".to_string()];
+        assert_eq!(saved_tags.get(&doc_id).unwrap(), &expected_tags);
+        // Saving again should not pile up duplicates
+        app.insert_resource(SaveDocRequest { doc_id, path: None });
+        app.update();
+        let pkv = app.world.resource::<PkvStore>();
+        let saved_tags: HashMap<ReflectableUuid, Vec<String>> = pkv.get("tags").unwrap();
         assert_eq!(saved_tags.get(&doc_id).unwrap(), &expected_tags);
         // Check that the name was saved to the PKV store
         let saved_names: HashMap<ReflectableUuid, String> = pkv.get("names").unwrap();
@@ -416,4 +488,17 @@ mod tests {
         assert_eq!(saved_doc.name, "test_doc");
         assert!(saved_doc.tabs[0].is_active);
     }
+
+    #[test]
+    fn test_push_checkpoint() {
+        let mut checkpoints = VecDeque::new();
+        for i in 0..10 {
+            push_checkpoint(&mut checkpoints, i.to_string(), 3);
+            assert!(checkpoints.len() <= 3);
+        }
+        assert_eq!(checkpoints, ["7", "8", "9"]);
+        // saving the same canvas again doesn't drop an older checkpoint
+        push_checkpoint(&mut checkpoints, "9".to_string(), 3);
+        assert_eq!(checkpoints, ["7", "8", "9"]);
+    }
 }
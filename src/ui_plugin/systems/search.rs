@@ -14,16 +14,20 @@ use tantivy::collector::TopDocs;
 use tantivy::query::BooleanQuery;
 use tantivy::query::FuzzyTermQuery;
 use tantivy::query::Occur;
+use tantivy::query::Query as TantivyQuery;
+use tantivy::query::TermQuery;
 use tantivy::ReloadPolicy;
 
 use tantivy::schema::*;
 use tantivy::Index;
 use uuid::Uuid;
 
+use crate::components::Doc;
+use crate::config::{Config, SearchConfig};
 use crate::resources::AppState;
 use crate::themes::Theme;
 use crate::ui_plugin::NodeType;
-use crate::utils::ReflectableUuid;
+use crate::utils::{normalize_tag, ReflectableUuid};
 use crate::APP_NAME;
 use crate::ORG_NAME;
 
@@ -32,6 +36,8 @@ use super::ui_helpers::SearchText;
 use super::ui_helpers::VeloNode;
 use super::UiState;
 
+const SEARCH_INDEX_DIR: &str = "search_index_v2";
+
 pub struct SearchIndexState {
     pub index: Index,
     pub tabs_to_delete: HashSet<Uuid>,
@@ -88,15 +94,26 @@ pub fn search_box_text_changed(
     pkv: Res<PkvStore>,
     mut velo_node_query: Query<(&mut Outline, &VeloNode, Entity), With<VeloNode>>,
     theme: Res<Theme>,
+    config: Res<Config>,
 ) {
     let str = get_cosmic_text(&text_query.single().editor);
     if str != *previous_search_text {
         if !str.is_empty() {
             if let Some(index) = &app_state.search_index {
                 let index = &index.index;
-                let result = fuzzy_search(index, str.as_str());
-                match result {
-                    Ok(docs) => {
+                let (tags, text) = split_tag_query(str.as_str());
+                let result = if text.is_empty() {
+                    Ok(vec![])
+                } else {
+                    fuzzy_search(index, text.as_str(), &config.search)
+                };
+                let tagged_docs = if tags.is_empty() {
+                    Ok(None)
+                } else {
+                    search_by_tags(index, &tags).map(Some)
+                };
+                match (result, tagged_docs) {
+                    (Ok(docs), Ok(tagged_docs)) => {
                         let node_ids: HashSet<ReflectableUuid> = docs
                             .clone()
                             .into_iter()
@@ -106,13 +123,23 @@ pub fn search_box_text_changed(
                             .map(|l| ReflectableUuid(l.node_id))
                             .collect();
                         highlight_search_match_nodes(&node_ids, &mut velo_node_query, &theme);
-                        let doc_ids: HashSet<ReflectableUuid> = docs
-                            .into_iter()
-                            .map(|location| ReflectableUuid(location.doc_id))
-                            .collect();
+                        let doc_ids: HashSet<ReflectableUuid> = match tagged_docs {
+                            Some(tagged_docs) if text.is_empty() => {
+                                tagged_docs.into_iter().map(ReflectableUuid).collect()
+                            }
+                            Some(tagged_docs) => docs
+                                .into_iter()
+                                .filter(|location| tagged_docs.contains(&location.doc_id))
+                                .map(|location| ReflectableUuid(location.doc_id))
+                                .collect(),
+                            None => docs
+                                .into_iter()
+                                .map(|location| ReflectableUuid(location.doc_id))
+                                .collect(),
+                        };
                         app_state.doc_list_ui = doc_ids;
                     }
-                    Err(e) => info!("Error searching index {:?}", e),
+                    (Err(e), _) | (_, Err(e)) => info!("Error searching index {:?}", e),
                 }
             }
         } else if let Ok(names) = pkv.get::<HashMap<ReflectableUuid, String>>("names") {
@@ -127,20 +154,107 @@ pub fn search_box_text_changed(
     }
 }
 
-pub fn init_search_index(mut app_state: ResMut<AppState>) {
+pub fn init_search_index(mut app_state: ResMut<AppState>, pkv: Res<PkvStore>) {
     let dirs = directories::ProjectDirs::from("", ORG_NAME, APP_NAME);
-    let path = match dirs.as_ref() {
+    let data_dir = match dirs.as_ref() {
         Some(dirs) => dirs.data_dir(),
         None => Path::new("."),
+    };
+    // the index lives in a versioned directory so that indexes created
+    // with an older schema are rebuilt instead of failing on missing fields
+    let path = data_dir.join(SEARCH_INDEX_DIR);
+    if let Err(e) = std::fs::create_dir_all(&path) {
+        error!(
+            "Unable to create search index directory {:?}: {:?}",
+            path, e
+        );
+    }
+    let index = initialize_search_index(path);
+    remove_legacy_search_index(data_dir);
+    let is_empty = index
+        .reader()
+        .map_or(true, |reader| reader.searcher().num_docs() == 0);
+    if is_empty {
+        if let Ok(docs) = pkv.get::<HashMap<ReflectableUuid, Doc>>("docs") {
+            let index = index.clone();
+            bevy::tasks::IoTaskPool::get()
+                .spawn(async move {
+                    if let Err(e) = reindex_docs(&index, &docs) {
+                        error!("Unable to rebuild search index: {:?}", e);
+                    }
+                })
+                .detach();
+        }
     }
-    .to_path_buf();
     app_state.search_index = Some(SearchIndexState {
-        index: initialize_search_index(path),
+        index,
         node_updates: HashMap::new(),
         tabs_to_delete: HashSet::new(),
     });
 }
 
+/// Removes the files of the index kept directly in the data directory before it moved to
+/// [`SEARCH_INDEX_DIR`]. Only files listed by tantivy are removed.
+pub fn remove_legacy_search_index(data_dir: &Path) {
+    let managed_path = data_dir.join(".managed.json");
+    let Ok(managed) = std::fs::read_to_string(&managed_path) else {
+        return;
+    };
+    let files: Vec<PathBuf> = serde_json::from_str(&managed).unwrap_or_default();
+    let index_files = [".tantivy-meta.lock", ".tantivy-writer.lock", "meta.json"];
+    for file in files
+        .iter()
+        .map(|file| data_dir.join(file))
+        .chain(index_files.iter().map(|file| data_dir.join(file)))
+    {
+        if file.is_file() {
+            if let Err(e) = std::fs::remove_file(&file) {
+                error!("Unable to remove old search index file {:?}: {:?}", file, e);
+            }
+        }
+    }
+    let _ = std::fs::remove_file(managed_path);
+}
+
+/// Indexes the nodes of the last checkpoint of every tab and the tags of the given documents.
+pub fn reindex_docs(index: &Index, docs: &HashMap<ReflectableUuid, Doc>) -> tantivy::Result<()> {
+    let mut node_search_locations = HashMap::new();
+    for doc in docs.values() {
+        for tab in doc.tabs.iter() {
+            let Some(json) = tab
+                .checkpoints
+                .back()
+                .and_then(|checkpoint| serde_json::from_str::<serde_json::Value>(checkpoint).ok())
+            else {
+                continue;
+            };
+            for node in json["nodes"].as_array().into_iter().flatten() {
+                let (Some(node_id), Some(text)) = (
+                    node["id"].as_str().and_then(|id| Uuid::parse_str(id).ok()),
+                    node["text"]["text"].as_str(),
+                ) else {
+                    continue;
+                };
+                node_search_locations.insert(
+                    NodeSearchLocation {
+                        doc_id: doc.id.0,
+                        tab_id: tab.id.0,
+                        node_id,
+                    },
+                    text.to_string(),
+                );
+            }
+        }
+    }
+    update_search_index(index, &node_search_locations)?;
+    for doc in docs.values() {
+        if !doc.tags.is_empty() {
+            update_doc_tags_index(index, &doc.id.0, &doc.tags)?;
+        }
+    }
+    Ok(())
+}
+
 pub fn initialize_search_index(dir: PathBuf) -> tantivy::Index {
     Index::open_in_dir(dir.clone()).unwrap_or_else(|_| {
         let mut schema_builder = Schema::builder();
@@ -149,6 +263,8 @@ pub fn initialize_search_index(dir: PathBuf) -> tantivy::Index {
         schema_builder.add_text_field("doc_id", STRING | STORED);
         schema_builder.add_text_field("tab_id", STRING | STORED);
         schema_builder.add_text_field("node_id", STRING | STORED);
+        schema_builder.add_text_field("doc_meta_id", STRING);
+        schema_builder.add_facet_field("tags", FacetOptions::default());
         let schema = schema_builder.build();
         Index::create_in_dir(dir, schema).unwrap()
     })
@@ -193,6 +309,82 @@ pub fn update_search_index(
 
 const MAX_SEARCH_RESULTS: usize = 1000;
 
+/// Document tags are indexed as a separate "meta" document per velo document
+/// so that re-tagging does not require re-indexing every node.
+pub fn update_doc_tags_index(index: &Index, doc_id: &Uuid, tags: &[String]) -> tantivy::Result<()> {
+    let schema = index.schema();
+    let tags_field = schema.get_field("tags").unwrap();
+    let doc_meta_id_field = schema.get_field("doc_meta_id").unwrap();
+    let doc_id_field = schema.get_field("doc_id").unwrap();
+    let mut index_writer = index.writer(50_000_000)?;
+
+    let term = tantivy::Term::from_field_text(doc_meta_id_field, &doc_id.to_string());
+    index_writer.delete_term(term);
+
+    if !tags.is_empty() {
+        let mut document = tantivy::Document::new();
+        document.add_text(doc_meta_id_field, &doc_id.to_string());
+        document.add_text(doc_id_field, &doc_id.to_string());
+        for tag in tags {
+            document.add_facet(tags_field, Facet::from_path(vec![normalize_tag(tag)]));
+        }
+        index_writer.add_document(document)?;
+    }
+
+    index_writer.commit()?;
+
+    Ok(())
+}
+
+/// Returns ids of documents tagged with all of the given tags.
+pub fn search_by_tags(index: &Index, tags: &[String]) -> tantivy::Result<HashSet<Uuid>> {
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::OnCommit)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let tags_field = schema.get_field("tags").unwrap();
+    let doc_id_field = schema.get_field("doc_id").unwrap();
+
+    let queries: Vec<(Occur, Box<dyn TantivyQuery>)> = tags
+        .iter()
+        .map(|tag| {
+            let facet = Facet::from_path(vec![normalize_tag(tag)]);
+            let term = Term::from_facet(tags_field, &facet);
+            let query: Box<dyn TantivyQuery> =
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+            (Occur::Must, query)
+        })
+        .collect();
+    let query = BooleanQuery::new(queries);
+
+    let top_docs = searcher.search(&query, &(TopDocs::with_limit(MAX_SEARCH_RESULTS)))?;
+    let mut ids = HashSet::new();
+    for (_, doc_address) in top_docs {
+        let doc = searcher.doc(doc_address)?;
+        if let Some(doc_id) = doc.get_first(doc_id_field).and_then(|v| v.as_text()) {
+            if let Ok(doc_id) = Uuid::parse_str(doc_id) {
+                ids.insert(doc_id);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// Splits search box input into `#tag` filters and the remaining text query.
+pub fn split_tag_query(query: &str) -> (Vec<String>, String) {
+    let mut tags = vec![];
+    let mut words = vec![];
+    for word in query.split_whitespace() {
+        match word.strip_prefix('#') {
+            Some(tag) if !tag.is_empty() => tags.push(normalize_tag(tag)),
+            _ => words.push(word),
+        }
+    }
+    (tags, words.join(" "))
+}
+
 pub fn clear_tabs_index(index: &Index, tab_ids: &HashSet<Uuid>) -> tantivy::Result<()> {
     let mut index_writer = index.writer(50_000_000)?;
 
@@ -223,7 +415,11 @@ pub fn clear_doc_index(index: &Index, doc_id: &Uuid) -> tantivy::Result<()> {
     Ok(())
 }
 
-pub fn fuzzy_search(index: &Index, query: &str) -> tantivy::Result<Vec<NodeSearchLocation>> {
+pub fn fuzzy_search(
+    index: &Index,
+    query: &str,
+    search_config: &SearchConfig,
+) -> tantivy::Result<Vec<NodeSearchLocation>> {
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::OnCommit)
@@ -239,17 +435,17 @@ pub fn fuzzy_search(index: &Index, query: &str) -> tantivy::Result<Vec<NodeSearc
     let node_id_field = schema.get_field("node_id").unwrap();
 
     let text_term = Term::from_field_text(text_field, normalized_query.as_str());
-    let query1 = FuzzyTermQuery::new(text_term, 2, true);
+    let query1 = FuzzyTermQuery::new(text_term, search_config.fuzzy_distance, true);
 
     let full_text_term = Term::from_field_text(full_text_field, normalized_query.as_str());
-    let query2 = FuzzyTermQuery::new(full_text_term, 2, true);
+    let query2 = FuzzyTermQuery::new(full_text_term, search_config.fuzzy_distance, true);
     let query = BooleanQuery::new(vec![
         (Occur::Should, Box::new(query1)),
         (Occur::Should, Box::new(query2)),
     ]);
 
     let top_docs = searcher
-        .search(&query, &(TopDocs::with_limit(MAX_SEARCH_RESULTS)))
+        .search(&query, &(TopDocs::with_limit(search_config.max_results)))
         .unwrap();
     let ids: Vec<NodeSearchLocation> = top_docs
         .iter()
@@ -333,7 +529,7 @@ mod tests {
         update_search_index(&index, &node_search_locations).unwrap();
         // Perform fuzzy search and assert the results
         let query = "appla";
-        let result = fuzzy_search(&index, query).unwrap();
+        let result = fuzzy_search(&index, query, &SearchConfig::default()).unwrap();
 
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].doc_id, id1);
@@ -343,6 +539,107 @@ mod tests {
             .expect("Failed to remove temporary directory");
     }
 
+    #[test]
+    fn test_search_by_tags() {
+        let temp_dir = TempDir::new().expect("Failed to create temporary directory");
+        let index = initialize_search_index(temp_dir.path().to_path_buf());
+        let doc_id1 = Uuid::new_v4();
+        let doc_id2 = Uuid::new_v4();
+        update_doc_tags_index(&index, &doc_id1, &["work".to_string(), "Retro".to_string()])
+            .unwrap();
+        update_doc_tags_index(&index, &doc_id2, &["work".to_string()]).unwrap();
+
+        let result = search_by_tags(&index, &["work".to_string()]).unwrap();
+        assert_eq!(result.len(), 2);
+        let result = search_by_tags(&index, &["work".to_string(), "retro".to_string()]).unwrap();
+        assert_eq!(result, HashSet::from([doc_id1]));
+
+        // re-tagging replaces previous tags
+        update_doc_tags_index(&index, &doc_id1, &["home".to_string()]).unwrap();
+        let result = search_by_tags(&index, &["retro".to_string()]).unwrap();
+        assert!(result.is_empty());
+
+        // tag documents never show up in text search
+        let result = fuzzy_search(&index, "work", &SearchConfig::default()).unwrap();
+        assert!(result.is_empty());
+
+        temp_dir
+            .close()
+            .expect("Failed to remove temporary directory");
+    }
+
+    #[test]
+    fn test_reindex_docs() {
+        let temp_dir = TempDir::new().expect("Failed to create temporary directory");
+        let index = initialize_search_index(temp_dir.path().to_path_buf());
+        let doc_id = ReflectableUuid::generate();
+        let node_id = Uuid::new_v4();
+        let checkpoint = serde_json::json!({
+            "nodes": [{ "id": node_id, "text": { "text": "apple pie" } }],
+            "arrows": [],
+        });
+        let doc = Doc {
+            id: doc_id,
+            tags: vec!["work".to_string()],
+            tabs: vec![crate::components::Tab {
+                id: ReflectableUuid::generate(),
+                is_active: true,
+                name: "Tab 1".to_string(),
+                checkpoints: std::collections::VecDeque::from([checkpoint.to_string()]),
+            }],
+            ..Default::default()
+        };
+        reindex_docs(&index, &HashMap::from([(doc_id, doc)])).unwrap();
+
+        let result = fuzzy_search(&index, "apple", &SearchConfig::default()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].doc_id, doc_id.0);
+        assert_eq!(result[0].node_id, node_id);
+        let result = search_by_tags(&index, &["work".to_string()]).unwrap();
+        assert_eq!(result, HashSet::from([doc_id.0]));
+
+        temp_dir
+            .close()
+            .expect("Failed to remove temporary directory");
+    }
+
+    #[test]
+    fn test_remove_legacy_search_index() {
+        let temp_dir = TempDir::new().expect("Failed to create temporary directory");
+        let index = initialize_search_index(temp_dir.path().to_path_buf());
+        let mut node_search_locations = HashMap::new();
+        node_search_locations.insert(
+            NodeSearchLocation {
+                doc_id: Uuid::new_v4(),
+                tab_id: Uuid::new_v4(),
+                node_id: Uuid::new_v4(),
+            },
+            "apple".to_string(),
+        );
+        update_search_index(&index, &node_search_locations).unwrap();
+        drop(index);
+        let other_file = temp_dir.path().join("bevy_pkv.redb");
+        std::fs::write(&other_file, "").unwrap();
+
+        remove_legacy_search_index(temp_dir.path());
+        let remaining: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(remaining, vec![other_file]);
+
+        temp_dir
+            .close()
+            .expect("Failed to remove temporary directory");
+    }
+
+    #[test]
+    fn test_split_tag_query() {
+        let (tags, text) = split_tag_query("#work apple #Retro pie #");
+        assert_eq!(tags, vec!["work".to_string(), "retro".to_string()]);
+        assert_eq!(text, "apple pie #");
+    }
+
     #[test]
     fn test_clear_tab() {
         // Create a temporary directory for the index
@@ -380,7 +677,7 @@ mod tests {
 
         // Perform a search and assert that the tab is not found
         let query = "example";
-        let result = fuzzy_search(&index, query).unwrap();
+        let result = fuzzy_search(&index, query, &SearchConfig::default()).unwrap();
 
         assert_eq!(result.len(), 0);
 
@@ -424,7 +721,7 @@ mod tests {
 
         // Perform a search and assert that the document is not found
         let query = "example";
-        let result = fuzzy_search(&index, query).unwrap();
+        let result = fuzzy_search(&index, query, &SearchConfig::default()).unwrap();
 
         assert_eq!(result.len(), 0);
 
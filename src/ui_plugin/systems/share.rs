@@ -0,0 +1,310 @@
+use async_channel::{Receiver, Sender};
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_cosmic_edit::CosmicFont;
+use bevy_pkv::PkvStore;
+
+use super::ui_helpers::{spawn_modal, ModalAction, ModalTop, ShareVisibilityButton};
+use super::{MainPanel, RunCommand, UiState, VeloCommand};
+use crate::components::SharedDoc;
+use crate::config::Config;
+use crate::resources::{AppState, FontSystemState, SaveDocRequest};
+use crate::share::{ShareCallback, Sharing};
+use crate::themes::Theme;
+use crate::utils::{load_doc_to_memory, ReflectableUuid};
+
+/// Results of share provider requests, sent from their callbacks.
+pub enum ShareResult {
+    Shared {
+        doc_id: ReflectableUuid,
+        result: Result<SharedDoc, String>,
+    },
+    Unshared {
+        doc_id: ReflectableUuid,
+        shared_id: String,
+        result: Result<(), String>,
+    },
+}
+
+#[derive(Resource, Clone)]
+pub struct ShareChannels {
+    pub tx: Sender<ShareResult>,
+    pub rx: Receiver<ShareResult>,
+}
+
+impl Default for ShareChannels {
+    fn default() -> Self {
+        let (tx, rx) = async_channel::unbounded();
+        Self { tx, rx }
+    }
+}
+
+/// Share or unshare of the current document, once the choice is made in a modal.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShareRequest {
+    Share { public: bool },
+    Unshare,
+}
+
+pub fn shared_doc_handler(
+    mut commands: Commands,
+    mut app_state: ResMut<AppState>,
+    mut ui_state: ResMut<UiState>,
+    mut run_command: EventReader<RunCommand>,
+    mut pkv: ResMut<PkvStore>,
+    config: Res<Config>,
+    sharing: Res<Sharing>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::ShareDocument && *command != VeloCommand::UnshareDocument {
+            continue;
+        }
+        let (modal_action, message) = match &sharing.provider {
+            None => {
+                warn!("Sharing requires a share provider in the config");
+                let message = "Sharing requires a share provider, e.g. a GitHub access token, \
+                    in the config file";
+                (ModalAction::Message, Some(message.to_string()))
+            }
+            Some(provider) => {
+                let doc_id = match app_state.current_document {
+                    Some(doc_id) => doc_id,
+                    None => continue,
+                };
+                load_doc_to_memory(doc_id, &mut app_state, &mut pkv, config.max_docs_in_memory);
+                let shared = app_state
+                    .docs
+                    .get(&doc_id)
+                    .and_then(|doc| doc.shared.as_ref())
+                    .filter(|shared| shared.provider == provider.name());
+                let modal_action = if *command == VeloCommand::UnshareDocument {
+                    if shared.is_none() {
+                        // nothing to confirm, the reason is shown as the result
+                        commands.insert_resource(ShareRequest::Unshare);
+                        continue;
+                    }
+                    ModalAction::UnshareDocument
+                } else {
+                    match provider.default_public() {
+                        Some(public) => ModalAction::ShareDocument { public },
+                        None => {
+                            commands.insert_resource(ShareRequest::Share { public: false });
+                            continue;
+                        }
+                    }
+                };
+                (modal_action, None)
+            }
+        };
+        let id = ReflectableUuid::generate();
+        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            windows.single(),
+            id,
+            modal_action,
+            message,
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
+    }
+}
+
+pub fn share_visibility_click(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &ShareVisibilityButton), Changed<Interaction>>,
+    query_top: Query<(Entity, &ModalTop)>,
+    mut ui_state: ResMut<UiState>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        for (entity, modal_top) in query_top.iter() {
+            if modal_top.id != button.id {
+                continue;
+            }
+            commands.entity(entity).despawn_recursive();
+            ui_state.modal_id = None;
+            commands.insert_resource(ShareRequest::Share {
+                public: button.public,
+            });
+        }
+    }
+}
+
+pub fn share_request_handler(
+    mut commands: Commands,
+    request: Option<Res<ShareRequest>>,
+    mut app_state: ResMut<AppState>,
+    mut pkv: ResMut<PkvStore>,
+    config: Res<Config>,
+    sharing: Res<Sharing>,
+    share_channels: Res<ShareChannels>,
+) {
+    let request = match request {
+        Some(request) => *request,
+        None => return,
+    };
+    commands.remove_resource::<ShareRequest>();
+    let provider = match &sharing.provider {
+        Some(provider) => provider,
+        None => return,
+    };
+    let doc_id = match app_state.current_document {
+        Some(doc_id) => doc_id,
+        None => return,
+    };
+    load_doc_to_memory(doc_id, &mut app_state, &mut pkv, config.max_docs_in_memory);
+    let doc = match app_state.docs.get(&doc_id) {
+        Some(doc) => doc,
+        None => return,
+    };
+    let tx = share_channels.tx.clone();
+    // documents shared by another provider are published again
+    let shared = doc
+        .shared
+        .as_ref()
+        .filter(|shared| shared.provider == provider.name());
+    match request {
+        ShareRequest::Unshare => {
+            let shared = match (shared, &doc.shared) {
+                (Some(shared), _) => shared,
+                (None, Some(other)) => {
+                    let _ = tx.try_send(ShareResult::Unshared {
+                        doc_id,
+                        shared_id: other.id.clone(),
+                        result: Err(format!(
+                            "Document was shared with the \"{}\" provider, which is not in use",
+                            other.provider
+                        )),
+                    });
+                    return;
+                }
+                (None, None) => {
+                    let _ = tx.try_send(ShareResult::Unshared {
+                        doc_id,
+                        shared_id: "".to_string(),
+                        result: Err("Document is not shared".to_string()),
+                    });
+                    return;
+                }
+            };
+            let shared_id = shared.id.clone();
+            provider.delete(
+                shared,
+                Box::new(move |result| {
+                    let _ = tx.try_send(ShareResult::Unshared {
+                        doc_id,
+                        shared_id,
+                        result,
+                    });
+                }),
+            );
+        }
+        ShareRequest::Share { public } => {
+            let callback: ShareCallback<SharedDoc> = Box::new(move |result| {
+                let _ = tx.try_send(ShareResult::Shared { doc_id, result });
+            });
+            match shared {
+                Some(shared) => provider.update(shared, doc, public, callback),
+                None => provider.publish(doc, public, callback),
+            }
+        }
+    }
+}
+
+pub fn share_result_handler(
+    mut commands: Commands,
+    mut app_state: ResMut<AppState>,
+    mut ui_state: ResMut<UiState>,
+    share_channels: Res<ShareChannels>,
+    sharing: Res<Sharing>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    while let Ok(share_result) = share_channels.rx.try_recv() {
+        let message = match share_result {
+            ShareResult::Shared {
+                doc_id,
+                result: Ok(shared),
+            } => {
+                let message = match sharing.viewer_link(&shared) {
+                    Some(link) => copy_to_clipboard(link),
+                    // documents in a local folder can't be opened by the viewer
+                    None => format!("Document shared: {}", shared.url),
+                };
+                if let Some(doc) = app_state.docs.get_mut(&doc_id) {
+                    doc.shared = Some(shared);
+                    commands.insert_resource(SaveDocRequest { doc_id, path: None });
+                }
+                message
+            }
+            ShareResult::Shared { result: Err(e), .. } => {
+                error!("Failed to share document: {}", e);
+                format!("Failed to share document: {}", e)
+            }
+            ShareResult::Unshared {
+                doc_id,
+                shared_id,
+                result: Ok(()),
+            } => {
+                if let Some(doc) = app_state.docs.get_mut(&doc_id) {
+                    if doc.shared.as_ref().map(|shared| &shared.id) == Some(&shared_id) {
+                        doc.shared = None;
+                        commands.insert_resource(SaveDocRequest { doc_id, path: None });
+                    }
+                }
+                "Document is no longer shared".to_string()
+            }
+            ShareResult::Unshared { result: Err(e), .. } => {
+                error!("Failed to unshare document: {}", e);
+                format!("Failed to unshare document: {}", e)
+            }
+        };
+        if ui_state.modal_id.is_some() {
+            // don't stack modals, the result is still in the log
+            info!("{}", message);
+            continue;
+        }
+        let id = ReflectableUuid::generate();
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            windows.single(),
+            id,
+            ModalAction::Message,
+            Some(message),
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_to_clipboard(link: String) -> String {
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(link.clone())) {
+        Ok(()) => format!("Link copied to clipboard: {}", link),
+        Err(e) => {
+            error!("Failed to copy link to clipboard: {}", e);
+            format!("Document shared: {}", link)
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn copy_to_clipboard(link: String) -> String {
+    format!("Document shared: {}", link)
+}
@@ -5,8 +5,8 @@ use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy_cosmic_edit::CosmicFont;
 
-use super::ui_helpers::{spawn_modal, AddTab, DeleteTab, TabButton};
-use super::MainPanel;
+use super::ui_helpers::{spawn_modal, TabButton};
+use super::{MainPanel, RunCommand, VeloCommand};
 use crate::components::Tab;
 use crate::resources::{AppState, FontSystemState, LoadDocRequest, LoadTabRequest, SaveTabRequest};
 use crate::themes::Theme;
@@ -15,89 +15,122 @@ use crate::UiState;
 
 pub fn select_tab_handler(
     mut commands: Commands,
-    mut interaction_query: Query<
-        (&Interaction, &TabButton),
-        (Changed<Interaction>, With<TabButton>),
-    >,
+    mut run_command: EventReader<RunCommand>,
     mut state: ResMut<AppState>,
 ) {
-    for (interaction, selected_tab) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                let current_document = state.current_document.unwrap();
-                for tab in state
-                    .docs
-                    .get_mut(&current_document)
-                    .unwrap()
-                    .tabs
-                    .iter_mut()
-                {
-                    if tab.is_active && tab.id == selected_tab.id {
-                        return;
-                    }
-                    if tab.is_active {
-                        commands.insert_resource(SaveTabRequest {
-                            tab_id: tab.id,
-                            doc_id: current_document,
-                        });
-                    }
-                }
-                for tab in state
-                    .docs
-                    .get_mut(&current_document)
-                    .unwrap()
-                    .tabs
-                    .iter_mut()
-                {
-                    tab.is_active = tab.id == selected_tab.id;
-                }
+    for RunCommand(command) in run_command.iter() {
+        let selected_tab_id = match command {
+            VeloCommand::SelectTab(tab_id) => *tab_id,
+            _ => continue,
+        };
+        let current_document = state.current_document.unwrap();
+        for tab in state
+            .docs
+            .get_mut(&current_document)
+            .unwrap()
+            .tabs
+            .iter_mut()
+        {
+            if tab.is_active && tab.id == selected_tab_id {
+                return;
+            }
+            if tab.is_active {
+                commands.insert_resource(SaveTabRequest {
+                    tab_id: tab.id,
+                    doc_id: current_document,
+                });
+            }
+        }
+        for tab in state
+            .docs
+            .get_mut(&current_document)
+            .unwrap()
+            .tabs
+            .iter_mut()
+        {
+            tab.is_active = tab.id == selected_tab_id;
+        }
+
+        commands.insert_resource(LoadTabRequest {
+            doc_id: current_document,
+            tab_id: selected_tab_id,
+            drop_last_checkpoint: false,
+        });
+    }
+}
+
+pub fn save_tab_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    app_state: Res<AppState>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::SaveTab {
+            continue;
+        }
+        if let Some(current_doc) = app_state.docs.get(&app_state.current_document.unwrap()) {
+            if let Some(active_tab) = current_doc.tabs.iter().find(|t| t.is_active) {
+                commands.insert_resource(SaveTabRequest {
+                    doc_id: app_state.current_document.unwrap(),
+                    tab_id: active_tab.id,
+                });
+            }
+        }
+    }
+}
 
+pub fn restore_checkpoint_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    app_state: Res<AppState>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::RestoreCheckpoint {
+            continue;
+        }
+        if let Some(current_doc) = app_state.docs.get(&app_state.current_document.unwrap()) {
+            if let Some(active_tab) = current_doc.tabs.iter().find(|t| t.is_active) {
                 commands.insert_resource(LoadTabRequest {
-                    doc_id: current_document,
-                    tab_id: selected_tab.id,
-                    drop_last_checkpoint: false,
+                    doc_id: app_state.current_document.unwrap(),
+                    tab_id: active_tab.id,
+                    drop_last_checkpoint: true,
                 });
             }
-            Interaction::Hovered => {}
-            Interaction::None => {}
         }
     }
 }
 
 pub fn add_tab_handler(
     mut commands: Commands,
-    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<AddTab>)>,
+    mut run_command: EventReader<RunCommand>,
     mut app_state: ResMut<AppState>,
 ) {
-    for interaction in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                let tab_id = ReflectableUuid::generate();
-                let current_document = app_state.current_document.unwrap();
-                let tabs = &mut app_state.docs.get_mut(&current_document).unwrap().tabs;
-                for tab in tabs.iter_mut() {
-                    if tab.is_active {
-                        commands.insert_resource(SaveTabRequest {
-                            tab_id: tab.id,
-                            doc_id: current_document,
-                        });
-                    }
-                    tab.is_active = false;
-                }
-                let tabs_len = tabs.len();
-                tabs.push(Tab {
-                    id: tab_id,
-                    name: "Tab ".to_string() + &(tabs_len + 1).to_string(),
-                    checkpoints: VecDeque::new(),
-                    is_active: true,
-                });
-                commands.insert_resource(LoadDocRequest {
-                    doc_id: app_state.current_document.unwrap(),
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::AddTab {
+            continue;
+        }
+        let tab_id = ReflectableUuid::generate();
+        let current_document = app_state.current_document.unwrap();
+        let tabs = &mut app_state.docs.get_mut(&current_document).unwrap().tabs;
+        for tab in tabs.iter_mut() {
+            if tab.is_active {
+                commands.insert_resource(SaveTabRequest {
+                    tab_id: tab.id,
+                    doc_id: current_document,
                 });
             }
-            Interaction::Hovered => {}
-            Interaction::None => {}
+            tab.is_active = false;
         }
+        let tabs_len = tabs.len();
+        tabs.push(Tab {
+            id: tab_id,
+            name: "Tab ".to_string() + &(tabs_len + 1).to_string(),
+            checkpoints: VecDeque::new(),
+            is_active: true,
+        });
+        commands.insert_resource(LoadDocRequest {
+            doc_id: app_state.current_document.unwrap(),
+        });
     }
 }
 
@@ -144,7 +177,7 @@ pub fn rename_tab_handler(
 
 pub fn delete_tab_handler(
     mut commands: Commands,
-    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<DeleteTab>)>,
+    mut run_command: EventReader<RunCommand>,
     mut app_state: ResMut<AppState>,
     mut ui_state: ResMut<UiState>,
     main_panel_query: Query<Entity, With<MainPanel>>,
@@ -154,36 +187,34 @@ pub fn delete_tab_handler(
     theme: Res<Theme>,
 ) {
     let window = windows.single();
-    for interaction in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                let id = ReflectableUuid::generate();
-                *ui_state = UiState::default();
-                commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
-                let current_document = app_state.current_document.unwrap();
-                let tabs_len = app_state
-                    .docs
-                    .get_mut(&current_document)
-                    .unwrap()
-                    .tabs
-                    .len();
-                if tabs_len < 2 {
-                    return;
-                }
-                ui_state.modal_id = Some(id);
-                let entity = spawn_modal(
-                    &mut commands,
-                    &theme,
-                    &mut cosmic_fonts,
-                    font_system_state.0.clone().unwrap(),
-                    window,
-                    id,
-                    super::ModalAction::DeleteTab,
-                );
-                commands.entity(main_panel_query.single()).add_child(entity);
-            }
-            Interaction::Hovered => {}
-            Interaction::None => {}
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::DeleteTab {
+            continue;
+        }
+        let id = ReflectableUuid::generate();
+        *ui_state = UiState::default();
+        commands.insert_resource(bevy_cosmic_edit::ActiveEditor { entity: None });
+        let current_document = app_state.current_document.unwrap();
+        let tabs_len = app_state
+            .docs
+            .get_mut(&current_document)
+            .unwrap()
+            .tabs
+            .len();
+        if tabs_len < 2 {
+            return;
         }
+        ui_state.modal_id = Some(id);
+        let entity = spawn_modal(
+            &mut commands,
+            &theme,
+            &mut cosmic_fonts,
+            font_system_state.0.clone().unwrap(),
+            window,
+            id,
+            super::ModalAction::DeleteTab,
+            None,
+        );
+        commands.entity(main_panel_query.single()).add_child(entity);
     }
 }
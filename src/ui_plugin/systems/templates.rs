@@ -0,0 +1,192 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_cosmic_edit::CosmicFont;
+use bevy_pkv::PkvStore;
+
+use super::ui_helpers::{
+    spawn_modal, spawn_template_picker, ModalAction, TemplateDeleteButton, TemplateOption,
+    TemplatePicker,
+};
+use super::{MainPanel, RunCommand, UiState, VeloCommand};
+use crate::resources::{AppState, FontSystemState, LoadDocRequest, SaveDocRequest, SaveTabRequest};
+use crate::templates::{
+    add_user_template, builtin_templates, doc_from_template, template_from_doc, Template,
+    TEMPLATES_KEY,
+};
+use crate::themes::Theme;
+use crate::utils::ReflectableUuid;
+
+/// Template requested for the current document, saved once the active tab is saved.
+#[derive(Resource)]
+pub struct PendingTemplate {
+    pub doc_id: ReflectableUuid,
+}
+
+fn load_user_templates(pkv: &PkvStore) -> Vec<Template> {
+    pkv.get::<Vec<Template>>(TEMPLATES_KEY).unwrap_or_default()
+}
+
+fn store_user_templates(pkv: &mut PkvStore, templates: &[Template]) {
+    if let Err(e) = pkv.set(TEMPLATES_KEY, &templates) {
+        error!("Failed to save templates: {}", e);
+    }
+}
+
+/// Built-in templates followed by the ones saved by the user.
+fn all_templates(pkv: &PkvStore) -> Vec<Template> {
+    let mut templates = builtin_templates();
+    templates.extend(load_user_templates(pkv));
+    templates
+}
+
+pub fn template_picker_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    picker_query: Query<Entity, With<TemplatePicker>>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    pkv: Res<PkvStore>,
+    theme: Res<Theme>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::NewFromTemplate {
+            continue;
+        }
+        if let Ok(picker) = picker_query.get_single() {
+            commands.entity(picker).despawn_recursive();
+            return;
+        }
+        let picker = spawn_template_picker(&mut commands, &theme, &all_templates(&pkv));
+        commands.entity(main_panel_query.single()).add_child(picker);
+    }
+}
+
+pub fn template_option_click(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &TemplateOption), Changed<Interaction>>,
+    picker_query: Query<Entity, With<TemplatePicker>>,
+    mut app_state: ResMut<AppState>,
+    pkv: Res<PkvStore>,
+) {
+    for (interaction, option) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        for picker in picker_query.iter() {
+            commands.entity(picker).despawn_recursive();
+        }
+        let template = match all_templates(&pkv)
+            .into_iter()
+            .find(|template| template.id == option.id)
+        {
+            Some(template) => template,
+            None => continue,
+        };
+        let doc = doc_from_template(&template);
+        let doc_id = doc.id;
+        app_state.docs.insert(doc_id, doc);
+        if let Some(current_document) = app_state.current_document {
+            commands.insert_resource(SaveDocRequest {
+                doc_id: current_document,
+                path: None,
+            });
+        }
+        app_state.current_document = Some(doc_id);
+        commands.insert_resource(LoadDocRequest { doc_id });
+        app_state.doc_list_ui.insert(doc_id);
+    }
+}
+
+pub fn template_delete_click(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &TemplateDeleteButton), Changed<Interaction>>,
+    picker_query: Query<Entity, With<TemplatePicker>>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    mut pkv: ResMut<PkvStore>,
+    theme: Res<Theme>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let mut templates = load_user_templates(&pkv);
+        templates.retain(|template| template.id != button.id);
+        store_user_templates(&mut pkv, &templates);
+        for picker in picker_query.iter() {
+            commands.entity(picker).despawn_recursive();
+        }
+        let picker = spawn_template_picker(&mut commands, &theme, &all_templates(&pkv));
+        commands.entity(main_panel_query.single()).add_child(picker);
+    }
+}
+
+/// Saves the active tab first, the template is added by [`pending_template_handler`].
+pub fn save_template_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    app_state: Res<AppState>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::SaveAsTemplate {
+            continue;
+        }
+        let doc_id = match app_state.current_document {
+            Some(doc_id) => doc_id,
+            None => continue,
+        };
+        if let Some(tab) = app_state
+            .docs
+            .get(&doc_id)
+            .and_then(|doc| doc.tabs.iter().find(|tab| tab.is_active))
+        {
+            commands.insert_resource(SaveTabRequest {
+                doc_id,
+                tab_id: tab.id,
+            });
+        }
+        commands.insert_resource(PendingTemplate { doc_id });
+    }
+}
+
+pub fn pending_template_handler(
+    mut commands: Commands,
+    pending_template: Option<Res<PendingTemplate>>,
+    save_tab_request: Option<Res<SaveTabRequest>>,
+    app_state: Res<AppState>,
+    mut pkv: ResMut<PkvStore>,
+    mut ui_state: ResMut<UiState>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    font_system_state: Res<FontSystemState>,
+    theme: Res<Theme>,
+) {
+    let pending = match pending_template {
+        Some(pending) if save_tab_request.is_none() => pending,
+        _ => return,
+    };
+    commands.remove_resource::<PendingTemplate>();
+    let doc = match app_state.docs.get(&pending.doc_id) {
+        Some(doc) => doc,
+        None => return,
+    };
+    let template = template_from_doc(doc);
+    let message = format!("Saved template \"{}\"", template.name);
+    let mut templates = load_user_templates(&pkv);
+    add_user_template(&mut templates, template);
+    store_user_templates(&mut pkv, &templates);
+    if ui_state.modal_id.is_some() {
+        return;
+    }
+    let id = ReflectableUuid::generate();
+    ui_state.modal_id = Some(id);
+    let entity = spawn_modal(
+        &mut commands,
+        &theme,
+        &mut cosmic_fonts,
+        font_system_state.0.clone().unwrap(),
+        windows.single(),
+        id,
+        ModalAction::Message,
+        Some(message),
+    );
+    commands.entity(main_panel_query.single()).add_child(entity);
+}
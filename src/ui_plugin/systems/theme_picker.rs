@@ -0,0 +1,144 @@
+use bevy::{prelude::*, render::texture::DEFAULT_IMAGE_HANDLE};
+use bevy_cosmic_edit::{cosmic_edit_set_text, get_cosmic_text, CosmicEdit, CosmicFont, CosmicText};
+use bevy_pkv::PkvStore;
+use bevy_ui_borders::BorderColor;
+use cosmic_text::AttrsOwned;
+
+use super::ui_helpers::{
+    spawn_theme_picker, SearchText, ThemeOption, ThemePicker, ThemedBackground, ThemedBorder,
+    ThemedText, Tooltip,
+};
+use super::{MainPanel, RunCommand, VeloCommand};
+use crate::components::CanvasBackground;
+use crate::resources::{AppState, LoadDocRequest, SaveTabRequest};
+use crate::themes::{get_theme_by_name, get_theme_names, Theme};
+use crate::utils::{bevy_color_to_cosmic, UserPreferences};
+
+pub fn change_theme_handler(
+    mut commands: Commands,
+    mut run_command: EventReader<RunCommand>,
+    picker_query: Query<Entity, With<ThemePicker>>,
+    main_panel_query: Query<Entity, With<MainPanel>>,
+    theme: Res<Theme>,
+) {
+    for RunCommand(command) in run_command.iter() {
+        if *command != VeloCommand::ChangeTheme {
+            continue;
+        }
+        if let Ok(picker) = picker_query.get_single() {
+            commands.entity(picker).despawn_recursive();
+            return;
+        }
+        let picker = spawn_theme_picker(&mut commands, &theme, get_theme_names());
+        commands.entity(main_panel_query.single()).add_child(picker);
+    }
+}
+
+pub fn theme_option_click(
+    mut commands: Commands,
+    mut interaction_query: Query<(&Interaction, &ThemeOption), Changed<Interaction>>,
+    picker_query: Query<Entity, With<ThemePicker>>,
+    app_state: Res<AppState>,
+    mut pkv: ResMut<PkvStore>,
+    theme: Res<Theme>,
+) {
+    for (interaction, theme_option) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => {
+                for picker in picker_query.iter() {
+                    commands.entity(picker).despawn_recursive();
+                }
+                if theme_option.name == theme.name {
+                    return;
+                }
+                commands.insert_resource(get_theme_by_name(&theme_option.name));
+                let user_preferences = UserPreferences {
+                    theme_name: Some(theme_option.name.clone()),
+                };
+                if let Err(e) = pkv.set("user_preferences", &user_preferences) {
+                    error!("Unable to save user preferences: {:?}", e);
+                }
+                // nodes, arrows and tabs bake theme colors into their text and shapes,
+                // so the current document is saved and loaded again to re-create them
+                if let Some(doc_id) = app_state.current_document {
+                    for tab in app_state.docs.get(&doc_id).unwrap().tabs.iter() {
+                        if tab.is_active {
+                            commands.insert_resource(SaveTabRequest {
+                                doc_id,
+                                tab_id: tab.id,
+                            });
+                        }
+                    }
+                    commands.insert_resource(LoadDocRequest { doc_id });
+                }
+            }
+            Interaction::Hovered => {}
+            Interaction::None => {}
+        }
+    }
+}
+
+pub fn apply_theme(
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+    mut backgrounds: Query<(&ThemedBackground, &mut BackgroundColor)>,
+    mut borders: Query<(&ThemedBorder, &mut BorderColor)>,
+    mut texts: Query<(&ThemedText, &mut Text)>,
+    mut tooltips: Query<
+        (&mut BackgroundColor, &mut Text),
+        (
+            With<Tooltip>,
+            Without<ThemedBackground>,
+            Without<ThemedText>,
+        ),
+    >,
+    mut search_text: Query<&mut CosmicEdit, With<SearchText>>,
+    mut cosmic_fonts: ResMut<Assets<CosmicFont>>,
+    mut canvas_background: Query<(&mut Sprite, &mut Handle<Image>), With<CanvasBackground>>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    for (themed, mut background_color) in backgrounds.iter_mut() {
+        background_color.0 = themed.0(&theme);
+    }
+    for (themed, mut border_color) in borders.iter_mut() {
+        border_color.0 = themed.0(&theme);
+    }
+    for (themed, mut text) in texts.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.color = themed.0(&theme);
+        }
+    }
+    for (mut background_color, mut text) in tooltips.iter_mut() {
+        background_color.0 = theme.tooltip_bg;
+        for section in text.sections.iter_mut() {
+            section.style.color = theme.font;
+        }
+    }
+    for mut cosmic_edit in search_text.iter_mut() {
+        cosmic_edit.bg = theme.search_box_bg;
+        let attrs = AttrsOwned::new(
+            cosmic_edit
+                .attrs
+                .as_attrs()
+                .color(bevy_color_to_cosmic(theme.font)),
+        );
+        cosmic_edit.attrs = attrs.clone();
+        let text = get_cosmic_text(&cosmic_edit.editor);
+        let font_system = cosmic_fonts.get_mut(&cosmic_edit.font_system).unwrap();
+        cosmic_edit_set_text(
+            CosmicText::OneStyle(text),
+            attrs,
+            &mut cosmic_edit.editor,
+            &mut font_system.0,
+        );
+    }
+    for (mut sprite, mut texture) in canvas_background.iter_mut() {
+        sprite.color = theme.canvas_bg_color.unwrap_or(Color::WHITE);
+        *texture = match theme.canvas_bg_img.clone() {
+            Some(bg_img) => asset_server.load(bg_img),
+            None => DEFAULT_IMAGE_HANDLE.typed(),
+        };
+    }
+}
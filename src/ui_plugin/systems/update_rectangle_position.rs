@@ -1,6 +1,7 @@
 use bevy::{prelude::*, window::PrimaryWindow};
 
 use crate::canvas::arrow::events::RedrawArrowEvent;
+use crate::config::Config;
 
 use super::{LeftPanel, UiState, VeloNodeContainer};
 
@@ -11,6 +12,7 @@ pub fn update_rectangle_position(
     mut query: Query<(&Style, &LeftPanel), Without<VeloNodeContainer>>,
     mut events: EventWriter<RedrawArrowEvent>,
     windows: Query<&Window, With<PrimaryWindow>>,
+    config: Res<Config>,
 ) {
     let primary_window = windows.single();
     for event in cursor_moved_events.iter() {
@@ -19,13 +21,22 @@ pub fn update_rectangle_position(
                 let size = query.single_mut().0.size;
                 if let (Val::Percent(x), Val::Px(element_width)) = (size.width, style.size.width) {
                     let width = (primary_window.width() * x) / 100.;
-                    style.position.left = Val::Px(event.position.x - width - element_width / 2.);
+                    let left = event.position.x - width - element_width / 2.;
+                    style.position.left = Val::Px(snap_to_grid(left, config.grid_size));
                 }
                 if let Val::Px(element_height) = style.size.height {
-                    style.position.bottom = Val::Px(event.position.y - element_height / 2.);
+                    let bottom = event.position.y - element_height / 2.;
+                    style.position.bottom = Val::Px(snap_to_grid(bottom, config.grid_size));
                 }
                 events.send(RedrawArrowEvent { id: top.id });
             }
         }
     }
 }
+
+fn snap_to_grid(value: f32, grid_size: Option<f32>) -> f32 {
+    match grid_size {
+        Some(grid_size) => (value / grid_size).round() * grid_size,
+        None => value,
+    }
+}
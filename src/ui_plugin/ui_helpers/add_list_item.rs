@@ -8,9 +8,14 @@ use bevy::{
     prelude::*,
 };
 
-use crate::{themes::Theme, utils::ReflectableUuid};
+use std::collections::HashSet;
 
-use super::{DeleteDoc, DocListItemButton, DocListItemContainer, EditableText, GenericButton};
+use crate::{themes::Theme, ui_plugin::VeloCommand, utils::ReflectableUuid};
+
+use super::{
+    CommandButton, DeleteDoc, DocListItemButton, DocListItemContainer, EditableText, GenericButton,
+    TagChip,
+};
 
 pub fn add_list_item(
     commands: &mut Commands,
@@ -18,6 +23,8 @@ pub fn add_list_item(
     asset_server: &Res<AssetServer>,
     id: ReflectableUuid,
     name: String,
+    tags: Vec<String>,
+    active_tags: &HashSet<String>,
 ) -> Entity {
     let icon_font = asset_server.load("fonts/MaterialIcons-Regular.ttf");
     let root = commands
@@ -45,12 +52,15 @@ pub fn add_list_item(
                 style: Style {
                     size: Size::new(Val::Percent(90.), Val::Percent(100.)),
                     justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
                     padding: UiRect::all(Val::Px(3.)),
                     ..default()
                 },
                 ..default()
             },
             DocListItemButton { id },
+            CommandButton(VeloCommand::OpenDocument(id)),
             GenericButton,
         ))
         .id();
@@ -107,6 +117,7 @@ pub fn add_list_item(
                 ..default()
             },
             DeleteDoc { id },
+            CommandButton(VeloCommand::DeleteDocument),
             GenericButton,
         ))
         .id();
@@ -130,8 +141,70 @@ pub fn add_list_item(
         ))
         .id();
     commands.entity(doc_button).add_child(doc_label);
+    if !tags.is_empty() {
+        let tag_chips = commands
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_wrap: FlexWrap::Wrap,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        for tag in tags {
+            let tag_chip = add_tag_chip(commands, theme, tag.clone(), active_tags.contains(&tag));
+            commands.entity(tag_chips).add_child(tag_chip);
+        }
+        commands.entity(doc_button).add_child(tag_chips);
+    }
     commands.entity(del_button).add_child(del_label);
     commands.entity(root).add_child(doc_button);
     commands.entity(root).add_child(del_button);
     root
 }
+
+fn add_tag_chip(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    tag: String,
+    is_active: bool,
+) -> Entity {
+    let background_color = if is_active {
+        theme.tag_active_bg
+    } else {
+        theme.tag_bg
+    };
+    commands
+        .spawn((
+            ButtonBundle {
+                background_color: background_color.into(),
+                style: Style {
+                    margin: UiRect::all(Val::Px(2.)),
+                    padding: UiRect {
+                        left: Val::Px(4.),
+                        right: Val::Px(4.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ..default()
+            },
+            TagChip { tag: tag.clone() },
+            GenericButton,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                TextBundle::from_section(
+                    format!("#{}", tag),
+                    TextStyle {
+                        font_size: 12.,
+                        color: theme.font,
+                        ..default()
+                    },
+                ),
+                Label,
+            ));
+        })
+        .id()
+}
@@ -1,8 +1,8 @@
 use bevy::prelude::*;
 
-use crate::{themes::Theme, utils::ReflectableUuid};
+use crate::{themes::Theme, ui_plugin::VeloCommand, utils::ReflectableUuid};
 
-use super::{DeleteTab, EditableText, GenericButton, TabButton, TabContainer};
+use super::{CommandButton, DeleteTab, EditableText, GenericButton, TabButton, TabContainer};
 
 pub fn add_tab(
     commands: &mut Commands,
@@ -49,6 +49,7 @@ pub fn add_tab(
             },
             GenericButton,
             TabButton { id },
+            CommandButton(VeloCommand::SelectTab(id)),
         ))
         .id();
     let tab_label = commands
@@ -109,6 +110,7 @@ pub fn add_tab(
             },
             GenericButton,
             DeleteTab { id },
+            CommandButton(VeloCommand::DeleteTab),
         ))
         .id();
     let del_label = commands
@@ -1,6 +1,12 @@
-use crate::{ui_plugin::NodeType, utils::ReflectableUuid};
+use crate::{
+    components::Doc,
+    history::CompareSource,
+    themes::Theme,
+    ui_plugin::{NodeType, VeloCommand},
+    utils::ReflectableUuid,
+};
 use bevy::prelude::*;
-use bevy_markdown::TextSpanMetadata;
+use bevy_markdown::{MarkdownImage, TextSpanMetadata};
 
 use crate::TextPos;
 
@@ -13,9 +19,6 @@ pub struct Root;
 #[derive(Component)]
 pub struct Menu;
 
-#[derive(Component)]
-pub struct AddTab;
-
 #[derive(Component)]
 pub struct DeleteTab {
     pub id: ReflectableUuid,
@@ -25,31 +28,52 @@ pub struct DeleteTab {
 pub struct Tooltip;
 
 #[derive(Component)]
-pub struct NewDoc;
+pub struct DocList;
 
 #[derive(Component)]
-pub struct ParticlesEffect;
+pub struct SetWindowProperty;
 
 #[derive(Component)]
-pub struct DocList;
+pub struct TagChip {
+    pub tag: String,
+}
 
 #[derive(Component)]
-pub struct SaveDoc;
+pub struct ThemePicker;
 
+/// Sends [`VeloCommand`] when clicked.
 #[derive(Component)]
-pub struct ExportToFile;
+pub struct CommandButton(pub VeloCommand);
 
 #[derive(Component)]
-pub struct SetWindowProperty;
+pub struct CommandPalette {
+    pub query: String,
+    pub matches: Vec<VeloCommand>,
+    pub selected: usize,
+}
 
 #[derive(Component)]
-pub struct ImportFromFile;
+pub struct CommandPaletteInput;
 
 #[derive(Component)]
-pub struct ImportFromUrl;
+pub struct CommandPaletteResults;
 
 #[derive(Component)]
-pub struct ShareDoc;
+pub struct ThemeOption {
+    pub name: String,
+}
+
+/// Theme field used as background color, re-applied on theme switch.
+#[derive(Component, Clone, Copy)]
+pub struct ThemedBackground(pub fn(&Theme) -> Color);
+
+/// Theme field used as border color, re-applied on theme switch.
+#[derive(Component, Clone, Copy)]
+pub struct ThemedBorder(pub fn(&Theme) -> Color);
+
+/// Theme field used as text color, re-applied on theme switch.
+#[derive(Component, Clone, Copy)]
+pub struct ThemedText(pub fn(&Theme) -> Color);
 
 #[derive(Component)]
 pub struct DeleteDoc {
@@ -155,6 +179,19 @@ pub struct RawText {
 pub struct BevyMarkdownView {
     pub id: ReflectableUuid,
     pub span_metadata: Vec<TextSpanMetadata>,
+    pub images: Vec<(usize, MarkdownImage)>,
+    /// Parts of the text that couldn't be rendered as markdown.
+    pub diagnostics: Vec<String>,
+}
+
+/// Renders the markdown of a readonly node from `RawText::last_text`.
+#[derive(Component, Default)]
+pub struct RenderMarkdown;
+
+/// Badge shown on nodes with markdown diagnostics, its tooltip lists them.
+#[derive(Component)]
+pub struct MarkdownWarning {
+    pub id: ReflectableUuid,
 }
 
 #[derive(Component, Copy, Clone, Debug, Default)]
@@ -177,8 +214,25 @@ pub enum ModalAction {
     SaveToFile,
     LoadFromFile,
     LoadFromUrl,
+    /// Adds the graph in a Graphviz DOT file as a new tab.
+    ImportDot,
+    ExportDot,
+    /// Edits the columns and lanes of the board of the tab, see [`crate::board::parse_board_spec`].
+    EditBoard,
+    ExportBoardCsv,
     DeleteDocument,
     DeleteTab,
+    EditTags,
+    /// Shows the default value as a message.
+    Message,
+    /// Asks how to import a document that already exists, see [`ConflictResolution`].
+    ImportConflict,
+    SaveVersion,
+    /// Asks for the visibility of the shared document, `public` is picked on Return.
+    ShareDocument {
+        public: bool,
+    },
+    UnshareDocument,
 }
 
 impl std::fmt::Display for ModalAction {
@@ -189,10 +243,30 @@ impl std::fmt::Display for ModalAction {
             ModalAction::LoadFromFile => write!(f, "Load from file:"),
             ModalAction::LoadFromUrl => write!(f, "Load from URL:"),
             ModalAction::SaveToFile => write!(f, "Save to file:"),
+            ModalAction::ImportDot => write!(f, "Import DOT file:"),
+            ModalAction::ExportDot => write!(f, "Export tab to DOT file:"),
+            ModalAction::EditBoard => write!(f, "Board columns | lanes:"),
+            ModalAction::ExportBoardCsv => write!(f, "Export board to CSV file:"),
+            ModalAction::EditTags => write!(f, "Tags:"),
+            ModalAction::Message => write!(f, "message"),
+            ModalAction::ImportConflict => write!(f, "import document"),
+            ModalAction::SaveVersion => write!(f, "Name:"),
+            ModalAction::ShareDocument { .. } => write!(f, "Share document as:"),
+            ModalAction::UnshareDocument => write!(f, "unshare document"),
         }
     }
 }
 
+/// Column or lane of the board of the tab, drawn behind the nodes.
+#[derive(Component)]
+pub struct BoardBackground;
+
+/// Progress or error of the import started from a modal.
+#[derive(Component)]
+pub struct ModalStatus {
+    pub id: ReflectableUuid,
+}
+
 #[derive(Component)]
 pub struct ModalConfirm {
     pub id: ReflectableUuid,
@@ -203,3 +277,95 @@ pub struct ModalConfirm {
 pub struct ModalCancel {
     pub id: ReflectableUuid,
 }
+
+/// Imported document waiting for the choice in an import conflict modal.
+#[derive(Component)]
+pub struct ImportConflictDoc(pub Doc);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Overwrite,
+    ImportAsNew,
+    Merge,
+}
+
+impl std::fmt::Display for ConflictResolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConflictResolution::Overwrite => write!(f, "Overwrite"),
+            ConflictResolution::ImportAsNew => write!(f, "Import as new"),
+            ConflictResolution::Merge => write!(f, "Merge"),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct HistoryPanel {
+    pub doc_id: ReflectableUuid,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryAction {
+    Restore,
+    RestoreAsCopy,
+    Delete,
+}
+
+#[derive(Component)]
+pub struct HistoryButton {
+    pub doc_id: ReflectableUuid,
+    pub version_id: ReflectableUuid,
+    pub action: HistoryAction,
+}
+
+#[derive(Component)]
+pub struct TemplatePicker;
+
+/// Picks the two checkpoints compared by the diff view.
+#[derive(Component)]
+pub struct DiffPicker {
+    pub sources: Vec<CompareSource>,
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffSide {
+    From,
+    To,
+}
+
+#[derive(Component)]
+pub struct DiffPickerOption {
+    pub side: DiffSide,
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct DiffPickerCompare;
+
+#[derive(Component)]
+pub struct DiffPickerCancel;
+
+#[derive(Component)]
+pub struct TemplateOption {
+    pub id: String,
+}
+
+/// Removes a user template, built-in templates have no delete button.
+#[derive(Component)]
+pub struct TemplateDeleteButton {
+    pub id: String,
+}
+
+#[derive(Component)]
+pub struct ImportConflictButton {
+    pub id: ReflectableUuid,
+    pub resolution: ConflictResolution,
+}
+
+#[derive(Component)]
+pub struct ShareVisibilityButton {
+    pub id: ReflectableUuid,
+    pub public: bool,
+}
@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use bevy_cosmic_edit::{
+    spawn_cosmic_edit, ActiveEditor, CosmicEditMeta, CosmicFont, CosmicMetrics, CosmicNode,
+    CosmicText,
+};
+use bevy_ui_borders::BorderColor;
+use cosmic_text::AttrsOwned;
+
+use super::{
+    CommandButton, CommandPalette, CommandPaletteInput, CommandPaletteResults, GenericButton,
+};
+use crate::{
+    themes::Theme,
+    ui_plugin::{TextPos, VeloCommand},
+    utils::{bevy_color_to_cosmic, to_cosmic_text_pos},
+};
+
+pub fn spawn_command_palette(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    cosmic_fonts: &mut ResMut<Assets<CosmicFont>>,
+    cosmic_font_handle: Handle<CosmicFont>,
+    window: &Window,
+) -> Entity {
+    let width = 500.;
+    let input_height = 35.;
+    let palette = commands
+        .spawn((
+            NodeBundle {
+                z_index: ZIndex::Global(1),
+                background_color: theme.modal_bg.into(),
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(window.width() / 2. - width / 2.),
+                        top: Val::Px(10.),
+                        ..default()
+                    },
+                    size: Size::new(Val::Px(width), Val::Auto),
+                    border: UiRect::all(Val::Px(1.)),
+                    padding: UiRect::all(Val::Px(5.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+            CommandPalette {
+                query: "".to_string(),
+                matches: vec![],
+                selected: 0,
+            },
+        ))
+        .id();
+    let input = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Px(input_height)),
+                    border: UiRect::all(Val::Px(1.)),
+                    margin: UiRect::all(Val::Px(2.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+        ))
+        .id();
+    let mut attrs = cosmic_text::Attrs::new();
+    attrs = attrs.family(cosmic_text::Family::Name(theme.font_name.as_str()));
+    attrs = attrs.color(bevy_color_to_cosmic(theme.font));
+    let cosmic_edit_meta = CosmicEditMeta {
+        text: CosmicText::OneStyle("".to_string()),
+        attrs: AttrsOwned::new(attrs),
+        font_system_handle: cosmic_font_handle,
+        text_pos: to_cosmic_text_pos(TextPos::TopLeft),
+        size: Some((width - 14., input_height - 2.)),
+        metrics: CosmicMetrics {
+            font_size: theme.font_size,
+            line_height: theme.line_height,
+            scale_factor: window.scale_factor() as f32,
+        },
+        bg: theme.modal_text_input_bg,
+        node: CosmicNode::Ui,
+        readonly: false,
+        bg_image: None,
+    };
+    let cosmic_edit = spawn_cosmic_edit(commands, cosmic_fonts, cosmic_edit_meta);
+    commands.entity(cosmic_edit).insert(CommandPaletteInput);
+    commands.insert_resource(ActiveEditor {
+        entity: Some(cosmic_edit),
+    });
+    let results = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    size: Size::new(Val::Percent(100.), Val::Auto),
+                    ..default()
+                },
+                ..default()
+            },
+            CommandPaletteResults,
+        ))
+        .id();
+    commands.entity(input).add_child(cosmic_edit);
+    commands.entity(palette).add_child(input);
+    commands.entity(palette).add_child(results);
+    palette
+}
+
+pub fn spawn_command_palette_item(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    command: VeloCommand,
+    title: String,
+    shortcut: Option<String>,
+    is_selected: bool,
+) -> Entity {
+    let background_color = if is_selected {
+        theme.tag_active_bg
+    } else {
+        theme.ok_cancel_bg
+    };
+    commands
+        .spawn((
+            ButtonBundle {
+                background_color: background_color.into(),
+                style: Style {
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    size: Size::new(Val::Percent(100.), Val::Auto),
+                    border: UiRect::all(Val::Px(1.)),
+                    padding: UiRect::all(Val::Px(5.)),
+                    margin: UiRect::all(Val::Px(2.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+            GenericButton,
+            CommandButton(command),
+        ))
+        .with_children(|builder| {
+            let text_style = TextStyle {
+                font_size: 18.0,
+                color: theme.font,
+                ..default()
+            };
+            builder.spawn(TextBundle::from_section(title, text_style.clone()));
+            if let Some(shortcut) = shortcut {
+                builder.spawn(TextBundle::from_section(
+                    shortcut,
+                    TextStyle {
+                        font_size: 14.0,
+                        ..text_style
+                    },
+                ));
+            }
+        })
+        .id()
+}
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+use bevy_ui_borders::BorderColor;
+
+use super::{
+    DiffPicker, DiffPickerCancel, DiffPickerCompare, DiffPickerOption, DiffSide, GenericButton,
+};
+use crate::{history::CompareSource, themes::Theme};
+
+/// Background of an option, the picked one on each side is highlighted.
+pub fn diff_option_color(theme: &Theme, picked: bool) -> Color {
+    if picked {
+        theme.tag_active_bg
+    } else {
+        theme.ok_cancel_bg
+    }
+}
+
+/// Two lists of checkpoints and versions of the active tab, the diff view shows the changes
+/// from the one picked on the left to the one picked on the right.
+pub fn spawn_diff_picker(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    sources: Vec<CompareSource>,
+    from: usize,
+    to: usize,
+) -> Entity {
+    let text_style = TextStyle {
+        font_size: 16.0,
+        color: theme.font,
+        ..default()
+    };
+    let button_style = Style {
+        justify_content: JustifyContent::Start,
+        align_items: AlignItems::Center,
+        border: UiRect::all(Val::Px(1.)),
+        padding: UiRect::all(Val::Px(5.)),
+        margin: UiRect::all(Val::Px(2.)),
+        ..default()
+    };
+    let columns = commands
+        .spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::vertical(Val::Px(5.)),
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    for (side, title, picked) in [(DiffSide::From, "From", from), (DiffSide::To, "To", to)] {
+        let column = commands
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    margin: UiRect::horizontal(Val::Px(5.)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|builder| {
+                builder.spawn(TextBundle::from_section(title, text_style.clone()));
+                for (index, source) in sources.iter().enumerate() {
+                    builder
+                        .spawn((
+                            ButtonBundle {
+                                background_color: diff_option_color(theme, index == picked).into(),
+                                style: button_style.clone(),
+                                ..default()
+                            },
+                            BorderColor(theme.btn_border),
+                            GenericButton,
+                            DiffPickerOption { side, index },
+                        ))
+                        .with_children(|builder| {
+                            builder.spawn(TextBundle::from_section(
+                                source.label.clone(),
+                                text_style.clone(),
+                            ));
+                        });
+                }
+            })
+            .id();
+        commands.entity(columns).add_child(column);
+    }
+    let buttons = commands
+        .spawn(NodeBundle {
+            style: Style {
+                justify_content: JustifyContent::SpaceAround,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|builder| {
+            builder
+                .spawn((
+                    ButtonBundle {
+                        background_color: theme.ok_cancel_bg.into(),
+                        style: button_style.clone(),
+                        ..default()
+                    },
+                    BorderColor(theme.btn_border),
+                    GenericButton,
+                    DiffPickerCompare,
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section("Compare", text_style.clone()));
+                });
+            builder
+                .spawn((
+                    ButtonBundle {
+                        background_color: theme.ok_cancel_bg.into(),
+                        style: button_style.clone(),
+                        ..default()
+                    },
+                    BorderColor(theme.btn_border),
+                    GenericButton,
+                    DiffPickerCancel,
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section("Cancel", text_style.clone()));
+                });
+        })
+        .id();
+    commands
+        .spawn((
+            NodeBundle {
+                z_index: ZIndex::Global(1),
+                background_color: theme.modal_bg.into(),
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(10.),
+                        top: Val::Px(10.),
+                        ..default()
+                    },
+                    border: UiRect::all(Val::Px(1.)),
+                    padding: UiRect::all(Val::Px(5.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+            DiffPicker { sources, from, to },
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                "Compare changes",
+                TextStyle {
+                    font_size: 18.0,
+                    ..text_style.clone()
+                },
+            ));
+        })
+        .push_children(&[columns, buttons])
+        .id()
+}
@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use bevy_ui_borders::BorderColor;
+
+use super::{CommandButton, GenericButton, HistoryAction, HistoryButton, HistoryPanel};
+use crate::{
+    history::{format_age, Version},
+    themes::Theme,
+    ui_plugin::VeloCommand,
+    utils::ReflectableUuid,
+};
+
+const MAX_PANEL_VERSIONS: usize = 15;
+
+/// Side panel with the versions of a document, newest first.
+pub fn spawn_history_panel(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    window: &Window,
+    doc_id: ReflectableUuid,
+    history: &[Version],
+    now: f64,
+) -> Entity {
+    let width = 320.;
+    let text_style = TextStyle {
+        font_size: 16.0,
+        color: theme.font,
+        ..default()
+    };
+    let panel = commands
+        .spawn((
+            NodeBundle {
+                z_index: ZIndex::Global(1),
+                background_color: theme.modal_bg.into(),
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(10.),
+                        top: Val::Px(10.),
+                        ..default()
+                    },
+                    size: Size::new(Val::Px(width), Val::Auto),
+                    max_size: Size::new(Val::Undefined, Val::Px(window.height() - 20.)),
+                    overflow: Overflow::Hidden,
+                    border: UiRect::all(Val::Px(1.)),
+                    padding: UiRect::all(Val::Px(5.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+            HistoryPanel { doc_id },
+        ))
+        .with_children(|builder| {
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        justify_content: JustifyContent::SpaceBetween,
+                        align_items: AlignItems::Center,
+                        size: Size::new(Val::Percent(100.), Val::Auto),
+                        margin: UiRect::bottom(Val::Px(5.)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "Version history",
+                        TextStyle {
+                            font_size: 18.0,
+                            ..text_style.clone()
+                        },
+                    ));
+                    spawn_panel_button(
+                        builder,
+                        theme,
+                        "Save version",
+                        CommandButton(VeloCommand::SaveVersion),
+                    );
+                });
+            if history.is_empty() {
+                builder.spawn(TextBundle::from_section(
+                    "No versions yet",
+                    text_style.clone(),
+                ));
+            }
+        })
+        .id();
+    for version in history.iter().rev().take(MAX_PANEL_VERSIONS) {
+        let row = commands
+            .spawn((
+                NodeBundle {
+                    background_color: theme.ok_cancel_bg.into(),
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        size: Size::new(Val::Percent(100.), Val::Auto),
+                        border: UiRect::all(Val::Px(1.)),
+                        padding: UiRect::all(Val::Px(5.)),
+                        margin: UiRect::all(Val::Px(2.)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                BorderColor(theme.btn_border),
+            ))
+            .with_children(|builder| {
+                builder.spawn(TextBundle::from_section(
+                    format!(
+                        "{} - {}",
+                        version.label(),
+                        format_age(version.timestamp, now)
+                    ),
+                    text_style.clone(),
+                ));
+                builder.spawn(
+                    TextBundle::from_section(
+                        version.preview(),
+                        TextStyle {
+                            font_size: 13.0,
+                            ..text_style.clone()
+                        },
+                    )
+                    .with_style(Style {
+                        max_size: Size::new(Val::Px(width - 30.), Val::Undefined),
+                        ..default()
+                    }),
+                );
+                builder
+                    .spawn(NodeBundle {
+                        style: Style {
+                            justify_content: JustifyContent::FlexEnd,
+                            size: Size::new(Val::Percent(100.), Val::Auto),
+                            margin: UiRect::top(Val::Px(3.)),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|builder| {
+                        for (label, action) in [
+                            ("Restore", HistoryAction::Restore),
+                            ("Copy", HistoryAction::RestoreAsCopy),
+                            ("Delete", HistoryAction::Delete),
+                        ] {
+                            spawn_panel_button(
+                                builder,
+                                theme,
+                                label,
+                                HistoryButton {
+                                    doc_id,
+                                    version_id: version.id,
+                                    action,
+                                },
+                            );
+                        }
+                    });
+            })
+            .id();
+        commands.entity(panel).add_child(row);
+    }
+    panel
+}
+
+fn spawn_panel_button(
+    builder: &mut ChildBuilder,
+    theme: &Res<Theme>,
+    label: &str,
+    action: impl Component,
+) {
+    builder
+        .spawn((
+            ButtonBundle {
+                background_color: theme.ok_cancel_bg.into(),
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    border: UiRect::all(Val::Px(1.)),
+                    padding: UiRect::horizontal(Val::Px(5.)),
+                    margin: UiRect::left(Val::Px(3.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+            GenericButton,
+            action,
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 14.0,
+                    color: theme.font,
+                    ..default()
+                },
+            ));
+        });
+}
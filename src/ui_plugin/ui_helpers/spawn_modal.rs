@@ -8,8 +8,8 @@ use bevy::prelude::*;
 use cosmic_text::AttrsOwned;
 
 use super::{
-    add_rectangle_txt, EditableText, GenericButton, ModalAction, ModalCancel, ModalConfirm,
-    ModalTop,
+    add_rectangle_txt, ConflictResolution, EditableText, GenericButton, ImportConflictButton,
+    ModalAction, ModalCancel, ModalConfirm, ModalStatus, ModalTop, ShareVisibilityButton,
 };
 use crate::{
     themes::Theme,
@@ -25,15 +25,24 @@ pub fn spawn_modal(
     window: &Window,
     id: ReflectableUuid,
     modal_action: ModalAction,
+    default_value: Option<String>,
 ) -> Entity {
-    let width = 350.;
+    let width = match modal_action {
+        // room for the three import choices
+        ModalAction::ImportConflict => 450.,
+        _ => 350.,
+    };
     let height = 250.;
-    let default_value = match modal_action {
+    let default_value = default_value.unwrap_or_else(|| match modal_action {
         ModalAction::SaveToFile => "./velo.json".to_string(),
         ModalAction::LoadFromFile => "./velo.json".to_string(),
         ModalAction::LoadFromUrl => "https://gist..".to_string(),
+        ModalAction::ImportDot | ModalAction::ExportDot => "./graph.dot".to_string(),
+        ModalAction::ShareDocument { .. } => "Public gists are listed on the GitHub profile, \
+            secret ones are only visible with the link."
+            .to_string(),
         _ => "".to_string(),
-    };
+    });
     let top = commands
         .spawn((
             NodeBundle {
@@ -74,78 +83,65 @@ pub fn spawn_modal(
         })
         .id();
 
-    let ok_button = commands
-        .spawn((
-            ButtonBundle {
-                background_color: theme.ok_cancel_bg.into(),
-                style: Style {
-                    justify_content: JustifyContent::Center,
-                    border: UiRect::all(Val::Px(1.)),
-                    align_items: AlignItems::Center,
-                    padding: UiRect::all(Val::Px(5.)),
-                    ..default()
-                },
-                ..default()
-            },
-            BorderColor(theme.btn_border),
-            GenericButton,
-            ModalConfirm {
-                id,
-                action: modal_action.clone(),
-            },
-        ))
-        .with_children(|builder| {
-            let text_style = TextStyle {
-                font_size: 18.0,
-                color: theme.font,
-                ..default()
-            };
-
-            builder.spawn(
-                TextBundle::from_section(" Ok ", text_style).with_style(Style {
-                    position_type: PositionType::Relative,
-                    ..default()
-                }),
-            );
-        })
-        .id();
-    let cancel_button = commands
-        .spawn((
-            ButtonBundle {
-                background_color: theme.ok_cancel_bg.into(),
-                style: Style {
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    border: UiRect::all(Val::Px(1.)),
-                    padding: UiRect::all(Val::Px(5.)),
-                    ..default()
+    match modal_action {
+        ModalAction::ImportConflict => {
+            for resolution in [
+                ConflictResolution::Overwrite,
+                ConflictResolution::ImportAsNew,
+                ConflictResolution::Merge,
+            ] {
+                let button = spawn_modal_button(
+                    commands,
+                    theme,
+                    resolution.to_string(),
+                    ImportConflictButton { id, resolution },
+                );
+                commands.entity(modal_static).add_child(button);
+            }
+        }
+        ModalAction::ShareDocument { public } => {
+            // the default visibility comes first
+            for public in [public, !public] {
+                let label = if public { "Public gist" } else { "Secret gist" };
+                let button = spawn_modal_button(
+                    commands,
+                    theme,
+                    label.to_string(),
+                    ShareVisibilityButton { id, public },
+                );
+                commands.entity(modal_static).add_child(button);
+            }
+        }
+        _ => {
+            let ok_button = spawn_modal_button(
+                commands,
+                theme,
+                " Ok ".to_string(),
+                ModalConfirm {
+                    id,
+                    action: modal_action.clone(),
                 },
-                ..default()
-            },
-            GenericButton,
-            BorderColor(theme.btn_border),
-            ModalCancel { id },
-        ))
-        .with_children(|builder| {
-            let text_style = TextStyle {
-                font_size: 18.0,
-                color: theme.font,
-                ..default()
-            };
-
-            builder.spawn(
-                TextBundle::from_section("Cancel", text_style).with_style(Style {
-                    position_type: PositionType::Relative,
-                    ..default()
-                }),
             );
-        })
-        .id();
-    commands.entity(modal_static).add_child(ok_button);
-    commands.entity(modal_static).add_child(cancel_button);
+            commands.entity(modal_static).add_child(ok_button);
+        }
+    }
+    // messages only need to be acknowledged
+    if modal_action != ModalAction::Message {
+        let cancel_button =
+            spawn_modal_button(commands, theme, "Cancel".to_string(), ModalCancel { id });
+        commands.entity(modal_static).add_child(cancel_button);
+    }
 
     let modal_dynamic = match modal_action {
-        ModalAction::SaveToFile | ModalAction::LoadFromFile | ModalAction::LoadFromUrl => {
+        ModalAction::SaveToFile
+        | ModalAction::LoadFromFile
+        | ModalAction::LoadFromUrl
+        | ModalAction::ImportDot
+        | ModalAction::ExportDot
+        | ModalAction::EditBoard
+        | ModalAction::ExportBoardCsv
+        | ModalAction::EditTags
+        | ModalAction::SaveVersion => {
             let top = commands
                 .spawn(NodeBundle {
                     style: Style {
@@ -232,7 +228,7 @@ pub fn spawn_modal(
             commands.entity(top).add_child(button);
             top
         }
-        ModalAction::DeleteDocument | ModalAction::DeleteTab => {
+        ModalAction::DeleteDocument | ModalAction::DeleteTab | ModalAction::UnshareDocument => {
             let top = commands
                 .spawn(NodeBundle {
                     style: Style {
@@ -267,6 +263,31 @@ pub fn spawn_modal(
             commands.entity(top).add_child(node);
             top
         }
+        ModalAction::Message | ModalAction::ImportConflict | ModalAction::ShareDocument { .. } => {
+            let top = commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        padding: UiRect::all(Val::Px(10.)),
+                        size: Size {
+                            width: Val::Percent(100.),
+                            height: Val::Percent(70.),
+                        },
+                        ..default()
+                    },
+                    ..default()
+                })
+                .id();
+            let message = commands
+                .spawn(add_rectangle_txt(theme, default_value).with_style(Style {
+                    max_size: Size::new(Val::Px(width - 20.), Val::Undefined),
+                    ..default()
+                }))
+                .id();
+            commands.entity(top).add_child(message);
+            top
+        }
     };
     let modal = commands
         .spawn((
@@ -293,7 +314,79 @@ pub fn spawn_modal(
         ))
         .id();
     commands.entity(modal).add_child(modal_dynamic);
+    if matches!(
+        modal_action,
+        ModalAction::LoadFromFile
+            | ModalAction::LoadFromUrl
+            | ModalAction::ImportDot
+            | ModalAction::EditBoard
+    ) {
+        let status = commands
+            .spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        color: theme.font,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(20.),
+                        top: Val::Percent(55.),
+                        ..default()
+                    },
+                    max_size: Size::new(Val::Px(width - 40.), Val::Undefined),
+                    ..default()
+                }),
+                ModalStatus { id },
+            ))
+            .id();
+        commands.entity(modal).add_child(status);
+    }
     commands.entity(modal).add_child(modal_static);
     commands.entity(top).add_child(modal);
     top
 }
+
+fn spawn_modal_button(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    label: String,
+    action: impl Component,
+) -> Entity {
+    commands
+        .spawn((
+            ButtonBundle {
+                background_color: theme.ok_cancel_bg.into(),
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    border: UiRect::all(Val::Px(1.)),
+                    padding: UiRect::all(Val::Px(5.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+            GenericButton,
+            action,
+        ))
+        .with_children(|builder| {
+            let text_style = TextStyle {
+                font_size: 18.0,
+                color: theme.font,
+                ..default()
+            };
+
+            builder.spawn(
+                TextBundle::from_section(label, text_style).with_style(Style {
+                    position_type: PositionType::Relative,
+                    ..default()
+                }),
+            );
+        })
+        .id()
+}
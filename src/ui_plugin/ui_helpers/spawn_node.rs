@@ -2,7 +2,6 @@ use bevy_cosmic_edit::{
     spawn_cosmic_edit, ActiveEditor, CosmicEditMeta, CosmicFont, CosmicMetrics, CosmicNode,
     CosmicText,
 };
-use bevy_markdown::{generate_markdown_lines, BevyMarkdown, BevyMarkdownTheme};
 use bevy_ui_borders::{BorderColor, Outline};
 
 use bevy::prelude::*;
@@ -13,14 +12,69 @@ use crate::ui_plugin::NodeType;
 use crate::TextPos;
 
 use super::{
-    create_arrow_marker, create_rectangle_btn, create_resize_marker, BevyMarkdownView, RawText,
-    ResizeMarker, VeloNode, VeloNodeContainer,
+    create_arrow_marker, create_rectangle_btn, create_resize_marker, get_tooltip, GenericButton,
+    MarkdownWarning, RawText, RenderMarkdown, ResizeMarker, Tooltip, TooltipPosition, VeloNode,
+    VeloNodeContainer,
 };
 use crate::canvas::arrow::components::{ArrowConnect, ArrowConnectPos};
 use crate::utils::{
     bevy_color_to_cosmic, convert_from_val_px, to_cosmic_text_pos, ReflectableUuid,
 };
 
+/// Hidden until the markdown of the node has diagnostics, hovering it shows them.
+pub fn spawn_markdown_warning(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    id: ReflectableUuid,
+) -> Entity {
+    commands
+        .spawn((
+            ButtonBundle {
+                z_index: ZIndex::Local(1),
+                background_color: theme.markdown_warning.into(),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(-8.),
+                        top: Val::Px(-8.),
+                        ..default()
+                    },
+                    size: Size::new(Val::Px(16.), Val::Px(16.)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    display: Display::None,
+                    ..default()
+                },
+                ..default()
+            },
+            GenericButton,
+            MarkdownWarning { id },
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                "!",
+                TextStyle {
+                    font_size: 14.,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ));
+            builder.spawn((
+                get_tooltip(theme, String::new(), 14., TooltipPosition::Bottom),
+                Tooltip,
+            ));
+        })
+        .id()
+}
+
+pub fn markdown_warning_display(diagnostics: &[String]) -> Display {
+    if diagnostics.is_empty() {
+        Display::None
+    } else {
+        Display::Flex
+    }
+}
+
 #[derive(Clone)]
 pub struct NodeMeta {
     pub id: ReflectableUuid,
@@ -92,30 +146,9 @@ pub fn spawn_node(
     let mut attrs = cosmic_text::Attrs::new();
     attrs = attrs.family(cosmic_text::Family::Name(theme.font_name.as_str()));
     attrs = attrs.color(bevy_color_to_cosmic(theme.font));
-    let (text, span_metadata) = match item_meta.is_active {
-        true => (CosmicText::OneStyle(item_meta.text.clone()), vec![]),
-        false => {
-            let markdown_theme = BevyMarkdownTheme {
-                code_theme: theme.code_theme.clone(),
-                code_default_lang: theme.code_default_lang.clone(),
-                link: bevy_color_to_cosmic(theme.link),
-                inline_code: bevy_color_to_cosmic(theme.inline_code),
-            };
-            let markdown_lines = generate_markdown_lines(BevyMarkdown {
-                text: item_meta.text.clone(),
-                attrs: AttrsOwned::new(attrs),
-                markdown_theme,
-            })
-            .expect("should handle markdown convertion");
-            (
-                CosmicText::MultiStyle(markdown_lines.lines),
-                markdown_lines.span_metadata,
-            )
-        }
-    };
-
+    // readonly nodes show the markdown source until it's rendered
     let cosmic_edit_meta = CosmicEditMeta {
-        text,
+        text: CosmicText::OneStyle(item_meta.text.clone()),
         font_system_handle: cosmic_font_handle,
         text_pos: to_cosmic_text_pos(item_meta.text_pos.clone()),
         size: Some((
@@ -140,18 +173,15 @@ pub fn spawn_node(
     });
     commands.entity(button).add_child(cosmic_edit);
 
-    match item_meta.is_active {
-        true => {
-            commands.insert_resource(ActiveEditor {
-                entity: Some(cosmic_edit),
-            });
-        }
-        false => {
-            commands.entity(cosmic_edit).insert(BevyMarkdownView {
-                id: item_meta.id,
-                span_metadata,
-            });
-        }
+    let markdown_warning = spawn_markdown_warning(commands, theme, item_meta.id);
+    commands.entity(button).add_child(markdown_warning);
+
+    if item_meta.is_active {
+        commands.insert_resource(ActiveEditor {
+            entity: Some(cosmic_edit),
+        });
+    } else {
+        commands.entity(cosmic_edit).insert(RenderMarkdown);
     }
 
     let arrow_marker1 = commands
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy_ui_borders::BorderColor;
+
+use super::{GenericButton, TemplateDeleteButton, TemplateOption, TemplatePicker};
+use crate::{templates::Template, themes::Theme};
+
+/// List of built-in and user templates, user templates can be deleted.
+pub fn spawn_template_picker(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    templates: &[Template],
+) -> Entity {
+    let text_style = TextStyle {
+        font_size: 18.0,
+        color: theme.font,
+        ..default()
+    };
+    let picker = commands
+        .spawn((
+            NodeBundle {
+                z_index: ZIndex::Global(1),
+                background_color: theme.modal_bg.into(),
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(10.),
+                        top: Val::Px(10.),
+                        ..default()
+                    },
+                    border: UiRect::all(Val::Px(1.)),
+                    padding: UiRect::all(Val::Px(5.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+            TemplatePicker,
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                "New document from template",
+                text_style.clone(),
+            ));
+        })
+        .id();
+    for template in templates {
+        let row = commands
+            .spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|builder| {
+                builder
+                    .spawn((
+                        ButtonBundle {
+                            background_color: theme.ok_cancel_bg.into(),
+                            style: Style {
+                                justify_content: JustifyContent::Start,
+                                align_items: AlignItems::Center,
+                                flex_grow: 1.,
+                                border: UiRect::all(Val::Px(1.)),
+                                padding: UiRect::all(Val::Px(5.)),
+                                margin: UiRect::all(Val::Px(2.)),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        BorderColor(theme.btn_border),
+                        GenericButton,
+                        TemplateOption {
+                            id: template.id.clone(),
+                        },
+                    ))
+                    .with_children(|builder| {
+                        builder.spawn(TextBundle::from_section(
+                            template.name.clone(),
+                            text_style.clone(),
+                        ));
+                    });
+                if template.is_builtin() {
+                    return;
+                }
+                builder
+                    .spawn((
+                        ButtonBundle {
+                            background_color: theme.ok_cancel_bg.into(),
+                            style: Style {
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                border: UiRect::all(Val::Px(1.)),
+                                padding: UiRect::all(Val::Px(5.)),
+                                margin: UiRect::all(Val::Px(2.)),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        BorderColor(theme.btn_border),
+                        GenericButton,
+                        TemplateDeleteButton {
+                            id: template.id.clone(),
+                        },
+                    ))
+                    .with_children(|builder| {
+                        builder.spawn(TextBundle::from_section(
+                            "Delete",
+                            TextStyle {
+                                font_size: 14.0,
+                                ..text_style.clone()
+                            },
+                        ));
+                    });
+            })
+            .id();
+        commands.entity(picker).add_child(row);
+    }
+    picker
+}
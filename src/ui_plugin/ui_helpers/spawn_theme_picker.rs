@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use bevy_ui_borders::BorderColor;
+
+use super::{GenericButton, ThemeOption, ThemePicker};
+use crate::themes::Theme;
+
+pub fn spawn_theme_picker(
+    commands: &mut Commands,
+    theme: &Res<Theme>,
+    theme_names: Vec<String>,
+) -> Entity {
+    let picker = commands
+        .spawn((
+            NodeBundle {
+                z_index: ZIndex::Global(1),
+                background_color: theme.modal_bg.into(),
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(10.),
+                        top: Val::Px(10.),
+                        ..default()
+                    },
+                    border: UiRect::all(Val::Px(1.)),
+                    padding: UiRect::all(Val::Px(5.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BorderColor(theme.btn_border),
+            ThemePicker,
+        ))
+        .id();
+    for name in theme_names {
+        let background_color = if name == theme.name {
+            theme.tag_active_bg
+        } else {
+            theme.ok_cancel_bg
+        };
+        let option = commands
+            .spawn((
+                ButtonBundle {
+                    background_color: background_color.into(),
+                    style: Style {
+                        justify_content: JustifyContent::Start,
+                        align_items: AlignItems::Center,
+                        border: UiRect::all(Val::Px(1.)),
+                        padding: UiRect::all(Val::Px(5.)),
+                        margin: UiRect::all(Val::Px(2.)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                BorderColor(theme.btn_border),
+                GenericButton,
+                ThemeOption { name: name.clone() },
+            ))
+            .with_children(|builder| {
+                let text_style = TextStyle {
+                    font_size: 18.0,
+                    color: theme.font,
+                    ..default()
+                };
+                builder.spawn(TextBundle::from_section(name, text_style));
+            })
+            .id();
+        commands.entity(picker).add_child(option);
+    }
+    picker
+}
@@ -14,6 +14,26 @@ pub use spawn_node::*;
 mod spawn_modal;
 pub use spawn_modal::*;
 
+#[path = "spawn_theme_picker.rs"]
+mod spawn_theme_picker;
+pub use spawn_theme_picker::*;
+
+#[path = "spawn_command_palette.rs"]
+mod spawn_command_palette;
+pub use spawn_command_palette::*;
+
+#[path = "spawn_template_picker.rs"]
+mod spawn_template_picker;
+pub use spawn_template_picker::*;
+
+#[path = "spawn_diff_picker.rs"]
+mod spawn_diff_picker;
+pub use spawn_diff_picker::*;
+
+#[path = "spawn_history_panel.rs"]
+mod spawn_history_panel;
+pub use spawn_history_panel::*;
+
 #[path = "add_tab.rs"]
 mod add_tab;
 pub use add_tab::*;
@@ -7,12 +7,11 @@ use crate::resources::AppState;
 use crate::ui_plugin::TextPos;
 
 use std::collections::HashMap;
-use std::{fs, path::PathBuf};
 use uuid::Uuid;
 
 use bevy_pkv::PkvStore;
 
-use crate::{components::Doc, ui_plugin::MAX_SAVED_DOCS_IN_MEMORY};
+use crate::components::Doc;
 
 #[derive(Clone, Reflect, Default, Debug, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[reflect_value]
@@ -47,6 +46,7 @@ pub fn load_doc_to_memory(
     doc_id: ReflectableUuid,
     app_state: &mut ResMut<AppState>,
     pkv: &mut ResMut<PkvStore>,
+    max_docs_in_memory: usize,
 ) {
     if app_state.docs.contains_key(&doc_id) {
         return;
@@ -54,8 +54,11 @@ pub fn load_doc_to_memory(
     if let Ok(docs) = pkv.get::<HashMap<ReflectableUuid, Doc>>("docs") {
         if docs.contains_key(&doc_id) {
             let keys = app_state.docs.keys().cloned().collect::<Vec<_>>();
-            while (app_state.docs.len() as i32) >= MAX_SAVED_DOCS_IN_MEMORY {
-                app_state.docs.remove(&keys[0]);
+            for key in keys {
+                if app_state.docs.len() < max_docs_in_memory {
+                    break;
+                }
+                app_state.docs.remove(&key);
             }
             app_state
                 .docs
@@ -66,24 +69,40 @@ pub fn load_doc_to_memory(
     }
 }
 
-#[derive(Debug, Default)]
-pub struct Config {
-    pub github_access_token: Option<String>,
+/// 1-based line of the first `key = ...` assignment in a TOML document.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn line_of_key(source: &str, key: &str) -> Option<usize> {
+    source
+        .lines()
+        .position(|line| {
+            line.trim_start()
+                .strip_prefix(key)
+                .map_or(false, |rest| rest.trim_start().starts_with('='))
+        })
+        .map(|index| index + 1)
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub fn read_config_file() -> Option<Config> {
-    let home_dir = std::env::var("HOME").ok()?;
-    let config_file_path = PathBuf::from(&home_dir).join(".velo.toml");
-    let config_str = fs::read_to_string(config_file_path).ok()?;
-    let config_value: toml::Value = toml::from_str(&config_str).ok()?;
-    let mut config = Config::default();
-    if let Some(token) = config_value.get("github_access_token") {
-        if let Some(token_str) = token.as_str() {
-            config.github_access_token = Some(token_str.to_owned());
+/// Tags are compared without case, so `Work`, `#work` and `work` are the same tag.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').trim().to_lowercase()
+}
+
+/// Normalizes tags and removes empty and duplicated ones while keeping the original order.
+pub fn dedup_tags(tags: &[String]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = normalize_tag(tag);
+        if !tag.is_empty() && !result.contains(&tag) {
+            result.push(tag);
         }
     }
-    Some(config)
+    result
+}
+
+/// Parses comma separated tags as typed in the tag editor.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let tags: Vec<String> = input.split(',').map(|tag| tag.to_string()).collect();
+    dedup_tags(&tags)
 }
 
 pub fn convert_from_val_px(x: Val) -> f32 {